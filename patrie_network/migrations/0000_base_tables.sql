@@ -0,0 +1,38 @@
+-- The tables every later migration builds on. They predate the migrations
+-- folder, so existing databases already have them: IF NOT EXISTS keeps this a
+-- no-op there, and a fresh database (sandbox, tests) gets them first.
+CREATE TABLE IF NOT EXISTS tenants (
+    id                       UUID PRIMARY KEY,
+    unit_deposit_account_id  TEXT  -- Where the monthly invoice is pulled from
+);
+
+-- The Store: what each tenant is subscribed to
+CREATE TABLE IF NOT EXISTS subscription_settings (
+    tenant_id                  UUID PRIMARY KEY REFERENCES tenants(id),
+    base_fee_retail            DOUBLE PRECISION,
+    health_active              BOOLEAN DEFAULT FALSE,
+    health_cost_wholesale      DOUBLE PRECISION,
+    retirement_active          BOOLEAN DEFAULT FALSE,
+    retirement_cost_wholesale  DOUBLE PRECISION,
+    crime_active               BOOLEAN DEFAULT FALSE,
+    crime_cost_wholesale       DOUBLE PRECISION
+);
+
+-- The explorer index (core/explorer_indexer.rs). Timestamps are the block's
+-- commit time in UTC, so a backfill lands in the right billing month.
+CREATE TABLE IF NOT EXISTS chain_blocks (
+    block_height  BIGINT PRIMARY KEY,
+    block_hash    TEXT NOT NULL,
+    timestamp     TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS chain_transactions (
+    tx_hash            TEXT PRIMARY KEY,
+    block_height       BIGINT NOT NULL REFERENCES chain_blocks(block_height),
+    sender_account_id  TEXT NOT NULL,
+    command_type       TEXT NOT NULL,
+    payload            JSONB,
+    timestamp          TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_chain_transactions_time ON chain_transactions (timestamp);
@@ -0,0 +1,21 @@
+-- Monthly platform invoices.
+-- One row per (tenant, billing period). The row is created BEFORE any money
+-- moves, so a second run of the same period always finds it and never
+-- charges the tenant twice.
+CREATE TABLE IF NOT EXISTS invoices (
+    id               UUID PRIMARY KEY,
+    tenant_id        UUID NOT NULL REFERENCES tenants(id),
+    period_start     DATE NOT NULL,  -- Always the 1st of the month
+    status           TEXT NOT NULL DEFAULT 'draft'
+                     CHECK (status IN ('draft', 'finalized', 'payment_pending', 'paid', 'failed')),
+    total_cents      BIGINT NOT NULL DEFAULT 0,
+    idempotency_key  TEXT NOT NULL UNIQUE, -- Sent to Unit with the book payment
+    unit_payment_id  TEXT,
+    failure_reason   TEXT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    UNIQUE (tenant_id, period_start)
+);
+
+CREATE INDEX IF NOT EXISTS idx_invoices_status ON invoices (status);
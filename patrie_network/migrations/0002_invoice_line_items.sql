@@ -0,0 +1,13 @@
+-- Itemized lines of an invoice (the "Legal Receipt").
+-- Written in the same transaction that finalizes the invoice.
+CREATE TABLE IF NOT EXISTS invoice_line_items (
+    id            UUID PRIMARY KEY,
+    invoice_id    UUID NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    position      INT NOT NULL,
+    category      TEXT NOT NULL
+                  CHECK (category IN ('platform_fee', 'pass_through_premium', 'admin_fee')),
+    description   TEXT NOT NULL,
+    amount_cents  BIGINT NOT NULL,
+
+    UNIQUE (invoice_id, position)
+);
@@ -0,0 +1,73 @@
+-- Versioned price books.
+-- The book in force on a date is the one with the latest effective_from <= date.
+-- Invoices record which book priced them, so old invoices keep their old prices.
+CREATE TABLE IF NOT EXISTS price_books (
+    id              UUID PRIMARY KEY,
+    version         INT NOT NULL UNIQUE,
+    effective_from  DATE NOT NULL UNIQUE,
+    notes           TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One price per key. tier = NULL means "all tiers"; a tier-specific row wins.
+CREATE TABLE IF NOT EXISTS price_book_entries (
+    price_book_id   UUID NOT NULL REFERENCES price_books(id) ON DELETE CASCADE,
+    price_key       TEXT NOT NULL,  -- e.g. 'base_platform_fee', 'admin_fee_health'
+    tier            TEXT,           -- 'starter' | 'professional' | 'enterprise' | NULL
+    amount_cents    BIGINT NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_price_book_entries_key
+    ON price_book_entries (price_book_id, price_key, COALESCE(tier, ''));
+
+-- Negotiated prices for a single tenant (replace the price book value)
+CREATE TABLE IF NOT EXISTS tenant_price_overrides (
+    id               UUID PRIMARY KEY,
+    tenant_id        UUID NOT NULL REFERENCES tenants(id),
+    price_key        TEXT NOT NULL,
+    amount_cents     BIGINT NOT NULL,
+    effective_from   DATE NOT NULL,
+    effective_until  DATE,          -- NULL = open ended
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Discounts & coupons. Applied to retained revenue only (never to pass-through premiums).
+CREATE TABLE IF NOT EXISTS tenant_discounts (
+    id                UUID PRIMARY KEY,
+    tenant_id         UUID NOT NULL REFERENCES tenants(id),
+    code              TEXT NOT NULL,  -- Coupon code or "NEGOTIATED"
+    description       TEXT NOT NULL,
+    percent_off_bps   INT CHECK (percent_off_bps BETWEEN 0 AND 10000), -- 1500 = 15%
+    amount_off_cents  BIGINT CHECK (amount_off_cents >= 0),
+    applies_to        TEXT,           -- price_key, or NULL for all retained fees
+    valid_from        DATE NOT NULL,
+    valid_until       DATE,           -- NULL = open ended
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CHECK (percent_off_bps IS NOT NULL OR amount_off_cents IS NOT NULL)
+);
+
+-- The tier now drives pricing, so it is stored with the subscription
+ALTER TABLE subscription_settings
+    ADD COLUMN IF NOT EXISTS tier TEXT NOT NULL DEFAULT 'enterprise';
+
+ALTER TABLE invoices
+    ADD COLUMN IF NOT EXISTS price_book_id UUID REFERENCES price_books(id);
+
+-- Discounts show up as their own (negative) lines
+ALTER TABLE invoice_line_items DROP CONSTRAINT IF EXISTS invoice_line_items_category_check;
+ALTER TABLE invoice_line_items ADD CONSTRAINT invoice_line_items_category_check
+    CHECK (category IN ('platform_fee', 'pass_through_premium', 'admin_fee', 'discount'));
+
+-- Version 1: The prices that used to be hard-coded in tiers.rs
+INSERT INTO price_books (id, version, effective_from, notes)
+VALUES ('00000000-0000-0000-0000-000000000001', 1, '1970-01-01', 'Initial prices (migrated from tiers.rs)')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO price_book_entries (price_book_id, price_key, tier, amount_cents) VALUES
+    ('00000000-0000-0000-0000-000000000001', 'base_platform_fee', NULL, 250000),
+    ('00000000-0000-0000-0000-000000000001', 'admin_fee_health',  NULL,   5000),
+    ('00000000-0000-0000-0000-000000000001', 'admin_fee_401k',    NULL,   5000),
+    ('00000000-0000-0000-0000-000000000001', 'admin_fee_crime',   NULL,   5000),
+    ('00000000-0000-0000-0000-000000000001', 'bridge_exit_fee',   NULL,   1000)
+ON CONFLICT DO NOTHING;
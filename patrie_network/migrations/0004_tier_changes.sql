@@ -0,0 +1,27 @@
+-- Gusto linkage needed to toggle benefits when a tenant changes tier
+ALTER TABLE tenants
+    ADD COLUMN IF NOT EXISTS gusto_company_uuid TEXT,
+    ADD COLUMN IF NOT EXISTS gusto_access_token TEXT;
+
+-- Company benefit UUIDs returned by Gusto (needed to deactivate on downgrade)
+ALTER TABLE subscription_settings
+    ADD COLUMN IF NOT EXISTS health_benefit_id TEXT,
+    ADD COLUMN IF NOT EXISTS retirement_benefit_id TEXT;
+
+-- One-off charges (+) and credits (-) waiting for the next invoice.
+-- Mid-cycle tier changes land here as prorations.
+CREATE TABLE IF NOT EXISTS billing_adjustments (
+    id                  UUID PRIMARY KEY,
+    tenant_id           UUID NOT NULL REFERENCES tenants(id),
+    description         TEXT NOT NULL,
+    amount_cents        BIGINT NOT NULL,
+    applied_invoice_id  UUID REFERENCES invoices(id), -- NULL = not billed yet
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_billing_adjustments_pending
+    ON billing_adjustments (tenant_id) WHERE applied_invoice_id IS NULL;
+
+ALTER TABLE invoice_line_items DROP CONSTRAINT IF EXISTS invoice_line_items_category_check;
+ALTER TABLE invoice_line_items ADD CONSTRAINT invoice_line_items_category_check
+    CHECK (category IN ('platform_fee', 'pass_through_premium', 'admin_fee', 'discount', 'adjustment'));
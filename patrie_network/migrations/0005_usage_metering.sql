@@ -0,0 +1,61 @@
+-- Which Iroha domain belongs to which tenant (set at onboarding)
+ALTER TABLE tenants
+    ADD COLUMN IF NOT EXISTS iroha_domain TEXT UNIQUE;
+
+-- One row per instruction inside an indexed transaction.
+-- Lets us meter mints and bridge withdrawals per domain.
+CREATE TABLE IF NOT EXISTS chain_instructions (
+    tx_hash                 TEXT NOT NULL,
+    position                INT NOT NULL,
+    kind                    TEXT NOT NULL,  -- 'Mint' | 'Burn' | 'Transfer' | 'Register' | ...
+    source_account_id       TEXT,
+    destination_account_id  TEXT,
+    quantity                DOUBLE PRECISION,
+
+    PRIMARY KEY (tx_hash, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_chain_instructions_kind ON chain_instructions (kind);
+
+-- Included quotas and overage rates, versioned with the price book.
+-- Overage is charged per started block: 1,000 extra tx at $5 / 1,000.
+CREATE TABLE IF NOT EXISTS price_book_usage_rates (
+    price_book_id             UUID NOT NULL REFERENCES price_books(id) ON DELETE CASCADE,
+    metric                    TEXT NOT NULL
+                              CHECK (metric IN ('transactions_submitted', 'units_minted', 'active_accounts', 'bridge_withdrawals')),
+    tier                      TEXT,           -- NULL = all tiers; tier-specific row wins
+    included_quantity         BIGINT NOT NULL,
+    overage_block_size        BIGINT NOT NULL CHECK (overage_block_size > 0),
+    overage_cents_per_block   BIGINT NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_price_book_usage_rates_key
+    ON price_book_usage_rates (price_book_id, metric, COALESCE(tier, ''));
+
+-- What we measured (and billed) for each tenant and period
+CREATE TABLE IF NOT EXISTS tenant_usage (
+    tenant_id     UUID NOT NULL REFERENCES tenants(id),
+    period_start  DATE NOT NULL,
+    metric        TEXT NOT NULL,
+    quantity      BIGINT NOT NULL,
+    measured_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (tenant_id, period_start, metric)
+);
+
+ALTER TABLE invoice_line_items DROP CONSTRAINT IF EXISTS invoice_line_items_category_check;
+ALTER TABLE invoice_line_items ADD CONSTRAINT invoice_line_items_category_check
+    CHECK (category IN ('platform_fee', 'pass_through_premium', 'admin_fee', 'discount', 'adjustment', 'usage_overage'));
+
+-- Version 1 quotas
+INSERT INTO price_book_usage_rates
+    (price_book_id, metric, tier, included_quantity, overage_block_size, overage_cents_per_block)
+VALUES
+    ('00000000-0000-0000-0000-000000000001', 'transactions_submitted', 'starter',       10000,   1000,  500),
+    ('00000000-0000-0000-0000-000000000001', 'transactions_submitted', NULL,           100000,   1000,  300),
+    ('00000000-0000-0000-0000-000000000001', 'units_minted',           'starter',     1000000, 100000, 1000),
+    ('00000000-0000-0000-0000-000000000001', 'units_minted',           NULL,         10000000, 100000,  500),
+    ('00000000-0000-0000-0000-000000000001', 'active_accounts',        'starter',          50,     10, 2000),
+    ('00000000-0000-0000-0000-000000000001', 'active_accounts',        NULL,              500,     10, 1500),
+    ('00000000-0000-0000-0000-000000000001', 'bridge_withdrawals',     NULL,               10,      1, 1000)
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,40 @@
+-- Tenant lifecycle: suspended tenants cannot write through the API
+ALTER TABLE tenants
+    ADD COLUMN IF NOT EXISTS status TEXT NOT NULL DEFAULT 'active'
+        CHECK (status IN ('active', 'suspended')),
+    ADD COLUMN IF NOT EXISTS admin_email TEXT;
+
+-- One dunning case per failed invoice
+-- open -> (retries) -> recovered
+--      -> grace -> suspended -> recovered (reinstated once paid)
+CREATE TABLE IF NOT EXISTS dunning_cases (
+    id               UUID PRIMARY KEY,
+    invoice_id       UUID NOT NULL UNIQUE REFERENCES invoices(id),
+    tenant_id        UUID NOT NULL REFERENCES tenants(id),
+    status           TEXT NOT NULL DEFAULT 'open'
+                     CHECK (status IN ('open', 'grace', 'suspended', 'recovered')),
+    attempts         INT NOT NULL DEFAULT 0,
+    next_attempt_at  TIMESTAMPTZ,
+    grace_ends_at    TIMESTAMPTZ,
+    last_error       TEXT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at       TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_dunning_cases_status ON dunning_cases (status);
+
+-- Outbox for tenant notifications (emails are sent from here)
+CREATE TABLE IF NOT EXISTS notifications (
+    id          UUID PRIMARY KEY,
+    tenant_id   UUID NOT NULL REFERENCES tenants(id),
+    recipient   TEXT,           -- NULL if the tenant has no admin email on file
+    kind        TEXT NOT NULL,  -- e.g. 'dunning_retry_scheduled'
+    subject     TEXT NOT NULL,
+    body        TEXT NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    sent_at     TIMESTAMPTZ
+);
+
+-- Each dunning retry is a separate payment attempt with its own Unit idempotency key
+ALTER TABLE invoices
+    ADD COLUMN IF NOT EXISTS payment_attempt INT NOT NULL DEFAULT 0;
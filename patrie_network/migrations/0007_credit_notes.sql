@@ -0,0 +1,56 @@
+-- Who did what to billing data, and why
+CREATE TABLE IF NOT EXISTS audit_log (
+    id           UUID PRIMARY KEY,
+    actor        TEXT NOT NULL,  -- Operator email, or 'system'
+    action       TEXT NOT NULL,  -- e.g. 'credit_note.issued'
+    tenant_id    UUID REFERENCES tenants(id),
+    entity_type  TEXT NOT NULL,
+    entity_id    UUID NOT NULL,
+    reason       TEXT,
+    details      JSONB NOT NULL DEFAULT '{}',
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_tenant ON audit_log (tenant_id, created_at DESC);
+
+-- Corrections to an issued invoice. Resolved as a refund to the tenant's
+-- Unit deposit account, or as credit on their account for future invoices.
+CREATE TABLE IF NOT EXISTS credit_notes (
+    id               UUID PRIMARY KEY,
+    invoice_id       UUID NOT NULL REFERENCES invoices(id),
+    tenant_id        UUID NOT NULL REFERENCES tenants(id),
+    resolution       TEXT NOT NULL CHECK (resolution IN ('refund', 'account_credit')),
+    status           TEXT NOT NULL
+                     CHECK (status IN ('issued', 'refund_pending', 'refunded', 'refund_failed')),
+    total_cents      BIGINT NOT NULL CHECK (total_cents > 0),
+    reason           TEXT NOT NULL,
+    operator         TEXT NOT NULL,
+    unit_payment_id  TEXT,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Each credit is tied to the invoice line it corrects
+CREATE TABLE IF NOT EXISTS credit_note_lines (
+    credit_note_id         UUID NOT NULL REFERENCES credit_notes(id) ON DELETE CASCADE,
+    invoice_line_item_id   UUID NOT NULL REFERENCES invoice_line_items(id),
+    amount_cents           BIGINT NOT NULL CHECK (amount_cents > 0),
+
+    PRIMARY KEY (credit_note_id, invoice_line_item_id)
+);
+
+-- Account credit ledger: + granted (credit note), - consumed (invoice)
+CREATE TABLE IF NOT EXISTS tenant_credits (
+    id              UUID PRIMARY KEY,
+    tenant_id       UUID NOT NULL REFERENCES tenants(id),
+    amount_cents    BIGINT NOT NULL,
+    credit_note_id  UUID REFERENCES credit_notes(id),
+    invoice_id      UUID REFERENCES invoices(id),
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_tenant_credits_tenant ON tenant_credits (tenant_id);
+
+ALTER TABLE invoice_line_items DROP CONSTRAINT IF EXISTS invoice_line_items_category_check;
+ALTER TABLE invoice_line_items ADD CONSTRAINT invoice_line_items_category_check
+    CHECK (category IN ('platform_fee', 'pass_through_premium', 'admin_fee', 'discount',
+                        'adjustment', 'usage_overage', 'account_credit'));
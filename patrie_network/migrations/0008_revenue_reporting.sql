@@ -0,0 +1,16 @@
+-- The tier each invoice was billed at (tier mix reporting must not
+-- depend on the tenant's CURRENT tier)
+ALTER TABLE invoices
+    ADD COLUMN IF NOT EXISTS tier TEXT;
+
+UPDATE invoices i
+SET tier = s.tier
+FROM subscription_settings s
+WHERE s.tenant_id = i.tenant_id AND i.tier IS NULL;
+
+-- Which asset moved, so on-chain commissions can be summed in USD only
+ALTER TABLE chain_instructions
+    ADD COLUMN IF NOT EXISTS asset_definition_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_chain_instructions_destination
+    ON chain_instructions (destination_account_id) WHERE kind = 'Transfer';
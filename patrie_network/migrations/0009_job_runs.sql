@@ -0,0 +1,21 @@
+-- Every execution of a scheduled (or manually triggered) background job.
+-- 'skipped' = another replica held the job's advisory lock.
+CREATE TABLE IF NOT EXISTS job_runs (
+    id            UUID PRIMARY KEY,
+    job_name      TEXT NOT NULL,
+    trigger       TEXT NOT NULL CHECK (trigger IN ('schedule', 'manual')),
+    triggered_by  TEXT,           -- Operator for manual runs
+    status        TEXT NOT NULL CHECK (status IN ('running', 'succeeded', 'failed', 'skipped')),
+    error         TEXT,
+    started_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at   TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs (job_name, started_at DESC);
+
+-- Month-end revenue snapshots written by the report generation job
+CREATE TABLE IF NOT EXISTS revenue_reports (
+    period_start  DATE PRIMARY KEY,
+    report        JSONB NOT NULL,
+    generated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
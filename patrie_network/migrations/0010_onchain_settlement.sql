@@ -0,0 +1,11 @@
+-- Tenants can opt in to pay invoices from their on-chain treasury
+-- (e.g. 'treasury@tesla_supply_chain') instead of their Unit deposit account.
+ALTER TABLE subscription_settings
+    ADD COLUMN IF NOT EXISTS onchain_settlement BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS treasury_account_id TEXT;
+
+-- How an invoice was paid: Unit book payment or on-chain transfer
+ALTER TABLE invoices
+    ADD COLUMN IF NOT EXISTS payment_method TEXT
+        CHECK (payment_method IN ('unit', 'onchain', 'account_credit')),
+    ADD COLUMN IF NOT EXISTS onchain_tx_hash TEXT;
@@ -0,0 +1,31 @@
+-- Enterprise contracts: a fixed term, billed monthly, quarterly or annually
+-- upfront, with an optional committed monthly usage spend (trued up per cycle).
+CREATE TABLE IF NOT EXISTS contracts (
+    id                        UUID PRIMARY KEY,
+    tenant_id                 UUID NOT NULL REFERENCES tenants(id),
+    status                    TEXT NOT NULL DEFAULT 'active'
+                              CHECK (status IN ('active', 'renewed', 'expired', 'cancelled')),
+    term_start                DATE NOT NULL,  -- Always the 1st of a month
+    term_end                  DATE NOT NULL,  -- Exclusive
+    billing_frequency         TEXT NOT NULL CHECK (billing_frequency IN ('monthly', 'quarterly', 'annual')),
+    committed_monthly_cents   BIGINT NOT NULL DEFAULT 0 CHECK (committed_monthly_cents >= 0),
+    auto_renew                BOOLEAN NOT NULL DEFAULT TRUE,
+    renewal_notice_days       INT NOT NULL DEFAULT 60,
+    notice_sent_at            TIMESTAMPTZ,
+    renewed_from              UUID REFERENCES contracts(id),
+    created_at                TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CHECK (term_end > term_start)
+);
+
+CREATE INDEX IF NOT EXISTS idx_contracts_tenant ON contracts (tenant_id, term_start);
+
+-- How many months a line pays for (3 = a quarter billed upfront).
+-- Revenue reporting spreads the amount over those months.
+ALTER TABLE invoice_line_items
+    ADD COLUMN IF NOT EXISTS service_months INT NOT NULL DEFAULT 1 CHECK (service_months >= 1);
+
+ALTER TABLE invoice_line_items DROP CONSTRAINT IF EXISTS invoice_line_items_category_check;
+ALTER TABLE invoice_line_items ADD CONSTRAINT invoice_line_items_category_check
+    CHECK (category IN ('platform_fee', 'pass_through_premium', 'admin_fee', 'discount',
+                        'adjustment', 'usage_overage', 'account_credit', 'commitment_true_up'));
@@ -0,0 +1,46 @@
+-- Which on-chain account mirrors a Unit deposit account.
+-- Money landing in the Unit account mints `usd`; money leaving burns it.
+CREATE TABLE IF NOT EXISTS fiat_account_links (
+    unit_account_id     TEXT PRIMARY KEY,
+    tenant_id           UUID NOT NULL REFERENCES tenants(id),
+    owner_type          TEXT NOT NULL CHECK (owner_type IN ('tenant', 'user')),
+    onchain_account_id  TEXT NOT NULL,  -- e.g. 'treasury@tesla_supply_chain' or 'elon@tesla_supply_chain'
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_fiat_account_links_tenant ON fiat_account_links (tenant_id);
+
+-- Every webhook event Unit sends us, stored BEFORE we act on it.
+-- The id is Unit's event id, so redeliveries are no-ops.
+CREATE TABLE IF NOT EXISTS unit_webhook_events (
+    id            TEXT PRIMARY KEY,
+    event_type    TEXT NOT NULL,  -- e.g. 'transaction.created'
+    payload       JSONB NOT NULL,
+    status        TEXT NOT NULL DEFAULT 'received'
+                  CHECK (status IN ('received', 'processing', 'processed', 'ignored', 'failed')),
+    attempts      INT NOT NULL DEFAULT 0,
+    error         TEXT,
+    received_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    processed_at  TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_unit_webhook_events_pending
+    ON unit_webhook_events (received_at) WHERE status IN ('received', 'processing', 'failed');
+
+-- What each fiat movement did on the ledger. One entry per Unit transaction.
+CREATE TABLE IF NOT EXISTS fiat_ledger_entries (
+    id                   UUID PRIMARY KEY,
+    unit_transaction_id  TEXT NOT NULL UNIQUE,
+    unit_event_id        TEXT NOT NULL REFERENCES unit_webhook_events(id),
+    transaction_type     TEXT NOT NULL,  -- Unit's type, e.g. 'receivedAchTransaction'
+    unit_payment_id      TEXT,           -- The payment behind it, if any (links ACH returns)
+    action               TEXT NOT NULL CHECK (action IN ('mint', 'burn')),
+    onchain_account_id   TEXT NOT NULL,
+    amount_cents         BIGINT NOT NULL CHECK (amount_cents > 0),
+    reverses_entry_id    UUID REFERENCES fiat_ledger_entries(id),  -- Set on returns & reversals
+    tx_hash              TEXT NOT NULL,
+    created_at           TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_fiat_ledger_entries_account ON fiat_ledger_entries (onchain_account_id, created_at DESC);
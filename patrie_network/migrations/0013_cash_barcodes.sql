@@ -0,0 +1,31 @@
+-- Cash deposit barcodes we asked Unit for. A user shows the barcode at a
+-- retailer's till; the cash lands in their Unit account and is minted on-chain.
+CREATE TABLE IF NOT EXISTS cash_deposit_barcodes (
+    id                   UUID PRIMARY KEY,
+    tenant_id            UUID NOT NULL REFERENCES tenants(id),
+    unit_customer_id     TEXT NOT NULL,
+    unit_account_id      TEXT NOT NULL REFERENCES fiat_account_links(unit_account_id),
+    retailer             TEXT NOT NULL,  -- e.g. 'walmart', 'green_dot'
+    barcode_number       TEXT NOT NULL UNIQUE,
+    barcode_url          TEXT NOT NULL,
+    status               TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'used', 'expired')),
+    expires_at           TIMESTAMPTZ NOT NULL,
+    amount_cents         BIGINT,         -- Cash deposited (once used)
+    unit_transaction_id  TEXT,
+    created_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    used_at              TIMESTAMPTZ
+);
+
+-- At most one live barcode per account: a deposit then maps to exactly one barcode
+CREATE UNIQUE INDEX IF NOT EXISTS idx_cash_barcodes_one_active
+    ON cash_deposit_barcodes (unit_account_id) WHERE status = 'active';
+CREATE INDEX IF NOT EXISTS idx_cash_barcodes_customer ON cash_deposit_barcodes (unit_customer_id, created_at DESC);
+
+-- Per-user cash limits. Users without a row get CASH_DAILY_LIMIT_CENTS / CASH_MONTHLY_LIMIT_CENTS.
+CREATE TABLE IF NOT EXISTS cash_deposit_limits (
+    unit_customer_id     TEXT PRIMARY KEY,
+    tenant_id            UUID NOT NULL REFERENCES tenants(id),
+    daily_limit_cents    BIGINT NOT NULL CHECK (daily_limit_cents >= 0),
+    monthly_limit_cents  BIGINT NOT NULL CHECK (monthly_limit_cents >= 0),
+    updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,55 @@
+-- Which Unit account a fiat movement happened on (drill-down for reconciliation)
+ALTER TABLE fiat_ledger_entries ADD COLUMN IF NOT EXISTS unit_account_id TEXT;
+
+UPDATE fiat_ledger_entries e
+SET unit_account_id = ev.payload -> 'relationships' -> 'account' -> 'data' ->> 'id'
+FROM unit_webhook_events ev
+WHERE ev.id = e.unit_event_id AND e.unit_account_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_fiat_ledger_entries_unit_account ON fiat_ledger_entries (unit_account_id, created_at);
+
+-- Proof of reserves: on-chain supply of a fiat-backed asset vs. the Unit
+-- balances backing it, signed, with a Merkle root over every holder's balance.
+CREATE TABLE IF NOT EXISTS reserve_reports (
+    id                    UUID PRIMARY KEY,
+    asset_definition_id   TEXT NOT NULL,  -- e.g. 'usd#bank'
+    as_of                 TIMESTAMPTZ NOT NULL,
+    onchain_supply_cents  BIGINT NOT NULL,
+    reserve_cents         BIGINT NOT NULL,  -- Sum of backing Unit balances
+    difference_cents      BIGINT NOT NULL,  -- supply - reserves (> 0 = under-backed)
+    status                TEXT NOT NULL CHECK (status IN ('balanced', 'discrepancy')),
+    holder_count          INT NOT NULL,
+    merkle_root           TEXT NOT NULL,    -- hex
+    signed_payload        TEXT NOT NULL,    -- Exact JSON bytes that were signed (JSONB would reorder keys)
+    signature             TEXT NOT NULL,    -- hex ed25519
+    public_key            TEXT NOT NULL,    -- hex ed25519
+    created_at            TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_reserve_reports_asset ON reserve_reports (asset_definition_id, as_of DESC);
+
+-- The Merkle leaves, in tree order, so any holder can get an inclusion proof
+CREATE TABLE IF NOT EXISTS reserve_report_leaves (
+    report_id      UUID NOT NULL REFERENCES reserve_reports(id),
+    leaf_index     INT NOT NULL,
+    account_id     TEXT NOT NULL,
+    balance_cents  BIGINT NOT NULL,
+    PRIMARY KEY (report_id, leaf_index)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_reserve_report_leaves_account ON reserve_report_leaves (report_id, account_id);
+
+-- What did not match, down to the single deposit or mint
+CREATE TABLE IF NOT EXISTS reserve_discrepancies (
+    id                   UUID PRIMARY KEY,
+    report_id            UUID NOT NULL REFERENCES reserve_reports(id),
+    kind                 TEXT NOT NULL CHECK (kind IN
+                         ('unminted_deposit', 'unburned_withdrawal', 'unbacked_mint', 'unbacked_burn', 'balance_drift', 'unit_unavailable')),
+    unit_account_id      TEXT,
+    unit_transaction_id  TEXT,
+    fiat_entry_id        UUID REFERENCES fiat_ledger_entries(id),
+    amount_cents         BIGINT NOT NULL,
+    detail               TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_reserve_discrepancies_report ON reserve_discrepancies (report_id);
@@ -0,0 +1,47 @@
+-- A user's own external bank account, registered with Unit as an ACH
+-- counterparty. Usable once the user confirms the two micro-deposits.
+CREATE TABLE IF NOT EXISTS ach_counterparties (
+    id                       UUID PRIMARY KEY,
+    tenant_id                UUID NOT NULL REFERENCES tenants(id),
+    unit_customer_id         TEXT NOT NULL,
+    unit_counterparty_id     TEXT NOT NULL UNIQUE,
+    name                     TEXT NOT NULL,
+    routing_number           TEXT NOT NULL,
+    account_last4            TEXT NOT NULL,  -- Never the full account number
+    account_type             TEXT NOT NULL,  -- 'Checking' | 'Savings'
+    status                   TEXT NOT NULL DEFAULT 'pending_verification'
+                             CHECK (status IN ('pending_verification', 'verified', 'failed')),
+    micro_deposit_1_cents    BIGINT NOT NULL,
+    micro_deposit_2_cents    BIGINT NOT NULL,
+    verification_attempts    INTEGER NOT NULL DEFAULT 0,
+    created_at               TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    verified_at              TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_ach_counterparties_customer ON ach_counterparties (tenant_id, unit_customer_id);
+
+-- ACH payments a user originated. 'credit' pushes money out to the
+-- counterparty, 'debit' pulls it in. Status mirrors Unit's payment status.
+CREATE TABLE IF NOT EXISTS ach_payments (
+    id                   UUID PRIMARY KEY,
+    tenant_id            UUID NOT NULL REFERENCES tenants(id),
+    unit_customer_id     TEXT NOT NULL,
+    unit_account_id      TEXT NOT NULL REFERENCES fiat_account_links(unit_account_id),
+    counterparty_id      UUID NOT NULL REFERENCES ach_counterparties(id),
+    direction            TEXT NOT NULL CHECK (direction IN ('credit', 'debit')),
+    amount_cents         BIGINT NOT NULL CHECK (amount_cents > 0),
+    description          TEXT NOT NULL,
+    unit_payment_id      TEXT UNIQUE,    -- NULL until Unit accepted it
+    status               TEXT NOT NULL DEFAULT 'submitting'
+                         CHECK (status IN ('submitting', 'pending', 'clearing', 'sent', 'returned', 'rejected', 'canceled')),
+    reason               TEXT,           -- Rejection / return reason from Unit
+    burn_tx_hash         TEXT,           -- Outgoing: USD burned before we asked Unit to send
+    refund_tx_hash       TEXT,           -- Outgoing: USD minted back when Unit never sent it
+    created_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    settled_at           TIMESTAMPTZ,
+    returned_at          TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_ach_payments_customer ON ach_payments (tenant_id, unit_customer_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_ach_payments_open ON ach_payments (status) WHERE status IN ('submitting', 'pending', 'clearing');
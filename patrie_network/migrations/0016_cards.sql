@@ -0,0 +1,41 @@
+-- Virtual debit cards on users' Unit deposit accounts. The card spends the
+-- same money the user holds on-chain as `usd`.
+CREATE TABLE IF NOT EXISTS cards (
+    id                   UUID PRIMARY KEY,
+    tenant_id            UUID NOT NULL REFERENCES tenants(id),
+    unit_card_id         TEXT NOT NULL UNIQUE,
+    unit_customer_id     TEXT NOT NULL,
+    unit_account_id      TEXT NOT NULL REFERENCES fiat_account_links(unit_account_id),
+    onchain_account_id   TEXT NOT NULL,
+    last4                TEXT NOT NULL,
+    expiration_date      TEXT NOT NULL,  -- "2028-05"
+    status               TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'frozen', 'closed')),
+    limits               JSONB NOT NULL DEFAULT '{}',  -- Unit card limits, in cents
+    created_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    closed_at            TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_cards_onchain_account ON cards (onchain_account_id);
+
+-- One row per card authorization. While 'held', `held_cents` of the user's
+-- `usd` sits in the card hold account; the capture burns it, a cancel gives
+-- it back. `version` numbers the on-chain hold adjustments (receipt keys).
+CREATE TABLE IF NOT EXISTS card_holds (
+    unit_authorization_id  TEXT PRIMARY KEY,
+    card_id                UUID NOT NULL REFERENCES cards(id),
+    onchain_account_id     TEXT NOT NULL,
+    merchant_name          TEXT,
+    authorized_cents       BIGINT NOT NULL,  -- Latest amount from Unit
+    held_cents             BIGINT NOT NULL DEFAULT 0,
+    status                 TEXT NOT NULL DEFAULT 'held' CHECK (status IN ('held', 'captured', 'released', 'declined')),
+    version                INTEGER NOT NULL DEFAULT 0,
+    captured_cents         BIGINT,
+    unit_transaction_id    TEXT,             -- The purchase that captured it
+    last_tx_hash           TEXT,
+    created_at             TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at             TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_card_holds_card ON card_holds (card_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_card_holds_open ON card_holds (updated_at) WHERE status = 'held';
@@ -0,0 +1,18 @@
+-- Monthly statements, one per on-chain account and month. Generated once the
+-- month is over and never changed afterwards: `content` is exactly what the
+-- PDF / CSV renderings show.
+CREATE TABLE IF NOT EXISTS account_statements (
+    id                      UUID PRIMARY KEY,
+    onchain_account_id      TEXT NOT NULL,
+    tenant_id               UUID REFERENCES tenants(id),
+    period                  DATE NOT NULL,  -- First day of the month
+    asset_definition_id     TEXT NOT NULL,
+    opening_balance_cents   BIGINT NOT NULL, -- Wallet (on-chain) balances
+    closing_balance_cents   BIGINT NOT NULL,
+    content                 JSONB NOT NULL,
+    generated_at            TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    UNIQUE (onchain_account_id, period)
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_statements_tenant ON account_statements (tenant_id, period);
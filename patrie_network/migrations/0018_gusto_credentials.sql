@@ -0,0 +1,20 @@
+-- Per-company Gusto OAuth tokens, encrypted at rest (AES-256-GCM, GUSTO_TOKEN_KEY).
+-- Access tokens expire after ~2 hours; the refresh token rotates on every refresh.
+CREATE TABLE IF NOT EXISTS gusto_credentials (
+    company_uuid        TEXT PRIMARY KEY,
+    access_token_enc    TEXT,         -- hex(nonce || ciphertext), NULL once revoked
+    refresh_token_enc   TEXT,         -- NULL for imported legacy tokens (cannot refresh)
+    expires_at          TIMESTAMPTZ,  -- NULL = unknown, use until Gusto answers 401
+    refreshed_at        TIMESTAMPTZ,
+    revoked_at          TIMESTAMPTZ,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Plaintext tokens in `tenants.gusto_access_token` are moved into
+-- gusto_credentials (encrypted) at startup and then cleared.
+
+-- Tenant lifecycle: offboarded tenants have their Gusto access revoked
+ALTER TABLE tenants DROP CONSTRAINT IF EXISTS tenants_status_check;
+ALTER TABLE tenants ADD CONSTRAINT tenants_status_check
+    CHECK (status IN ('active', 'suspended', 'offboarded'));
+ALTER TABLE tenants ADD COLUMN IF NOT EXISTS offboarded_at TIMESTAMPTZ;
@@ -0,0 +1,31 @@
+-- Every webhook event Gusto sends us, stored BEFORE we act on it.
+-- The id is Gusto's event uuid, so redeliveries are no-ops.
+CREATE TABLE IF NOT EXISTS gusto_webhook_events (
+    id            TEXT PRIMARY KEY,
+    event_type    TEXT NOT NULL,  -- e.g. 'payroll.processed'
+    company_uuid  TEXT,           -- resource_uuid when resource_type = 'Company'
+    entity_type   TEXT,           -- e.g. 'Payroll', 'Employee'
+    entity_uuid   TEXT,
+    payload       JSONB NOT NULL,
+    status        TEXT NOT NULL DEFAULT 'received'
+                  CHECK (status IN ('received', 'processing', 'processed', 'ignored', 'failed')),
+    attempts      INT NOT NULL DEFAULT 0,
+    error         TEXT,
+    received_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    processed_at  TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_gusto_webhook_events_pending
+    ON gusto_webhook_events (received_at) WHERE status IN ('received', 'processing', 'failed');
+CREATE INDEX IF NOT EXISTS idx_gusto_webhook_events_company ON gusto_webhook_events (company_uuid, received_at DESC);
+
+-- What Gusto last told us about the tenant's company
+ALTER TABLE tenants
+    ADD COLUMN IF NOT EXISTS gusto_company_status TEXT NOT NULL DEFAULT 'active'
+        CHECK (gusto_company_status IN ('active', 'deactivated')),
+    ADD COLUMN IF NOT EXISTS gusto_last_payroll_uuid TEXT,
+    ADD COLUMN IF NOT EXISTS gusto_last_payroll_at TIMESTAMPTZ;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tenants_gusto_company ON tenants (gusto_company_uuid)
+    WHERE gusto_company_uuid IS NOT NULL;
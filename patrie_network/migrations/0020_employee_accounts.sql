@@ -0,0 +1,19 @@
+-- Which on-chain account mirrors a Gusto employee (in the tenant's domain).
+-- pending -> active -> terminated (-> active again on rehire)
+CREATE TABLE IF NOT EXISTS gusto_employee_accounts (
+    employee_uuid       TEXT PRIMARY KEY,
+    tenant_id           UUID NOT NULL REFERENCES tenants(id),
+    company_uuid        TEXT NOT NULL,
+    onchain_account_id  TEXT NOT NULL UNIQUE,  -- e.g. 'ada_lovelace_1a2b3c4d@tesla_supply_chain'
+    first_name          TEXT NOT NULL,
+    last_name           TEXT NOT NULL,
+    email               TEXT,
+    status              TEXT NOT NULL DEFAULT 'pending'
+                        CHECK (status IN ('pending', 'active', 'terminated')),
+    register_tx_hash    TEXT,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    synced_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    terminated_at       TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_gusto_employee_accounts_tenant ON gusto_employee_accounts (tenant_id, status);
@@ -0,0 +1,54 @@
+-- How a tenant's Gusto payrolls are mirrored on chain
+CREATE TABLE IF NOT EXISTS payroll_settings (
+    tenant_id            UUID PRIMARY KEY REFERENCES tenants(id),
+    disburse_net_pay     BOOLEAN NOT NULL DEFAULT FALSE,  -- Pay net pay in `usd` from the treasury
+    treasury_account_id  TEXT,                            -- Required to disburse, e.g. 'treasury@tesla_supply_chain'
+    instant_offramp      BOOLEAN NOT NULL DEFAULT FALSE,  -- Move the fiat to employees' own Unit accounts too
+    updated_at           TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One row per processed Gusto payroll; tx_hash is the single ledger
+-- transaction that recorded every payslip (and paid every disbursement)
+CREATE TABLE IF NOT EXISTS payroll_runs (
+    payroll_uuid      TEXT PRIMARY KEY,
+    tenant_id         UUID NOT NULL REFERENCES tenants(id),
+    company_uuid      TEXT NOT NULL,
+    pay_period_start  DATE NOT NULL,
+    pay_period_end    DATE NOT NULL,
+    check_date        DATE NOT NULL,
+    employee_count    INT NOT NULL,
+    gross_cents       BIGINT NOT NULL,
+    net_cents         BIGINT NOT NULL,
+    disbursed_cents   BIGINT NOT NULL DEFAULT 0,
+    status            TEXT NOT NULL DEFAULT 'recording'
+                      CHECK (status IN ('recording', 'recorded')),
+    tx_hash           TEXT,
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    recorded_at       TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_payroll_runs_tenant ON payroll_runs (tenant_id, check_date DESC);
+
+CREATE TABLE IF NOT EXISTS payroll_payslips (
+    id                          UUID PRIMARY KEY,
+    payroll_uuid                TEXT NOT NULL REFERENCES payroll_runs(payroll_uuid),
+    employee_uuid               TEXT NOT NULL,
+    onchain_account_id          TEXT NOT NULL,
+    gross_cents                 BIGINT NOT NULL,
+    net_cents                   BIGINT NOT NULL,
+    employee_taxes_cents        BIGINT NOT NULL,
+    employer_taxes_cents        BIGINT NOT NULL,
+    benefit_deductions_cents    BIGINT NOT NULL,
+    other_deductions_cents      BIGINT NOT NULL,
+    disbursed_cents             BIGINT NOT NULL DEFAULT 0,
+    -- Instant off-ramp: book payment tenant Unit account -> employee Unit account
+    offramp_from_unit_account   TEXT,
+    offramp_to_unit_account     TEXT,
+    offramp_status              TEXT CHECK (offramp_status IN ('submitting', 'sent', 'failed')),
+    offramp_payment_id          TEXT UNIQUE,
+    offramp_error               TEXT,
+    UNIQUE (payroll_uuid, employee_uuid)
+);
+
+CREATE INDEX IF NOT EXISTS idx_payroll_payslips_submitting
+    ON payroll_payslips (offramp_from_unit_account, offramp_to_unit_account) WHERE offramp_status = 'submitting';
@@ -0,0 +1,61 @@
+-- Every benefit Gusto supports (GET /v1/benefits), cached. Gusto's ids are
+-- looked up here by name, never hard-coded.
+CREATE TABLE IF NOT EXISTS gusto_benefit_catalog (
+    benefit_type  TEXT PRIMARY KEY,  -- The id a company benefit is created with
+    name          TEXT NOT NULL,     -- e.g. 'Medical Insurance'
+    description   TEXT,
+    pretax        BOOLEAN NOT NULL DEFAULT FALSE,
+    posttax       BOOLEAN NOT NULL DEFAULT FALSE,
+    healthcare    BOOLEAN NOT NULL DEFAULT FALSE,
+    retirement    BOOLEAN NOT NULL DEFAULT FALSE,
+    fetched_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Our benefit products and the Gusto benefit behind each.
+-- Offering a new benefit is a new row, not new code.
+CREATE TABLE IF NOT EXISTS benefit_products (
+    product             TEXT PRIMARY KEY,              -- e.g. 'dental'
+    gusto_benefit_name  TEXT NOT NULL,                 -- Catalog name, matched case-insensitively
+    description         TEXT NOT NULL,                 -- What the company benefit is called in Gusto
+    tiers               TEXT[] NOT NULL DEFAULT '{}',  -- Tiers that include it, e.g. '{enterprise}'
+    active              BOOLEAN NOT NULL DEFAULT TRUE, -- FALSE = no longer added on tier changes
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO benefit_products (product, gusto_benefit_name, description, tiers) VALUES
+    ('health',   'Medical Insurance',           'Company Health Plan',  '{professional,enterprise}'),
+    ('401k',     '401(k)',                      'Company 401(k) Plan',  '{enterprise}'),
+    ('dental',   'Dental Insurance',            'Company Dental Plan',  '{}'),
+    ('vision',   'Vision Insurance',            'Company Vision Plan',  '{}'),
+    ('hsa',      'Health Savings Account',      'Company HSA',          '{}'),
+    ('commuter', 'Commuter Benefits (Transit)', 'Commuter Benefits',    '{}')
+ON CONFLICT (product) DO NOTHING;
+
+-- The company benefit we created in each tenant's Gusto company, per product.
+-- Turned off on downgrade and back on (same Gusto object) on upgrade.
+CREATE TABLE IF NOT EXISTS tenant_benefits (
+    tenant_id              UUID NOT NULL REFERENCES tenants(id),
+    product                TEXT NOT NULL REFERENCES benefit_products(product),
+    company_benefit_uuid   TEXT NOT NULL,
+    benefit_type           TEXT NOT NULL,
+    active                 BOOLEAN NOT NULL DEFAULT TRUE,
+    monthly_employer_cost  DOUBLE PRECISION NOT NULL DEFAULT 0,  -- From the last wholesale sync
+    created_at             TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at             TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, product)
+);
+
+-- Carry over the benefits created with the old hard-coded ids ('1' and '105')
+INSERT INTO tenant_benefits (tenant_id, product, company_benefit_uuid, benefit_type, active, monthly_employer_cost)
+SELECT tenant_id, 'health', health_benefit_id, '1', COALESCE(health_active, FALSE), COALESCE(health_cost_wholesale, 0)
+FROM subscription_settings WHERE health_benefit_id IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+INSERT INTO tenant_benefits (tenant_id, product, company_benefit_uuid, benefit_type, active, monthly_employer_cost)
+SELECT tenant_id, '401k', retirement_benefit_id, '105', COALESCE(retirement_active, FALSE), COALESCE(retirement_cost_wholesale, 0)
+FROM subscription_settings WHERE retirement_benefit_id IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE subscription_settings
+    DROP COLUMN IF EXISTS health_benefit_id,
+    DROP COLUMN IF EXISTS retirement_benefit_id;
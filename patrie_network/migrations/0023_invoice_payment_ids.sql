@@ -0,0 +1,5 @@
+-- Invoices covered by account credit moved no money: they have no payment id.
+-- Their settlement is recorded by payment_method = 'account_credit'.
+UPDATE invoices
+SET unit_payment_id = NULL, payment_method = 'account_credit'
+WHERE unit_payment_id = 'no_charge_required';
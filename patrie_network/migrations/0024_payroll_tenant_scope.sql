@@ -0,0 +1,16 @@
+-- Payslips carry their tenant, and must match their run's tenant, so every
+-- payroll lookup can be scoped to the tenant asking
+ALTER TABLE payroll_payslips ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id);
+
+UPDATE payroll_payslips p
+SET tenant_id = r.tenant_id
+FROM payroll_runs r
+WHERE r.payroll_uuid = p.payroll_uuid AND p.tenant_id IS NULL;
+
+ALTER TABLE payroll_payslips ALTER COLUMN tenant_id SET NOT NULL;
+
+ALTER TABLE payroll_runs
+    ADD CONSTRAINT payroll_runs_uuid_tenant_key UNIQUE (payroll_uuid, tenant_id);
+ALTER TABLE payroll_payslips
+    ADD CONSTRAINT payroll_payslips_run_tenant_fkey
+    FOREIGN KEY (payroll_uuid, tenant_id) REFERENCES payroll_runs (payroll_uuid, tenant_id);
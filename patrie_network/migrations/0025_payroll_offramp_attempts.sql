@@ -0,0 +1,3 @@
+-- A payroll off-ramp Unit rejected is re-sent as a new attempt (new idempotency
+-- key); one with an unknown outcome is re-sent under the same key
+ALTER TABLE payroll_payslips ADD COLUMN IF NOT EXISTS offramp_attempt INT NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+-- Gusto's subscription verification tokens, encrypted like gusto_credentials
+-- (hex(nonce || ciphertext) under GUSTO_TOKEN_KEY). The token is the webhook
+-- signing secret: it is never logged. One row per subscription; repeat
+-- deliveries leave the first one in place.
+CREATE TABLE IF NOT EXISTS gusto_webhook_verifications (
+    subscription_uuid  TEXT PRIMARY KEY,
+    token_enc          TEXT NOT NULL,
+    received_at        TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
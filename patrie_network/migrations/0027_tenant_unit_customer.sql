@@ -0,0 +1,7 @@
+-- The tenant's own Unit (business) customer. A Unit account is only linked to
+-- a tenant when Unit says it belongs to this customer, or (user accounts) to
+-- a customer tagged with the tenant's id.
+ALTER TABLE tenants ADD COLUMN IF NOT EXISTS unit_customer_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tenants_unit_customer ON tenants (unit_customer_id)
+    WHERE unit_customer_id IS NOT NULL;
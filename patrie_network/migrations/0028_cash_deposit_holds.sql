@@ -0,0 +1,7 @@
+-- Cash paid in over the user's daily/monthly limit is not minted to them: it
+-- waits in CASH_HOLD_ACCOUNT_ID on-chain until an operator releases or returns it
+ALTER TABLE cash_deposit_barcodes
+    ADD COLUMN IF NOT EXISTS held_cents BIGINT NOT NULL DEFAULT 0 CHECK (held_cents >= 0);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_cash_barcodes_transaction ON cash_deposit_barcodes (unit_transaction_id)
+    WHERE unit_transaction_id IS NOT NULL;
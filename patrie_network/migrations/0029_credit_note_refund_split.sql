@@ -0,0 +1,5 @@
+-- A refund only returns as cash what the invoice actually collected; the part
+-- account credit paid for comes back as account credit. refund_cents is the cash part.
+ALTER TABLE credit_notes ADD COLUMN IF NOT EXISTS refund_cents BIGINT NOT NULL DEFAULT 0 CHECK (refund_cents >= 0);
+
+UPDATE credit_notes SET refund_cents = total_cents WHERE resolution = 'refund' AND refund_cents = 0;
@@ -0,0 +1,11 @@
+-- Onboarding (core/onboarding.rs): a tenant stays 'onboarding' until its Gusto
+-- company, Unit customer & deposit account and ledger domain all exist. Each id
+-- is saved as soon as the outside system returns it, so a retry resumes.
+ALTER TABLE tenants DROP CONSTRAINT IF EXISTS tenants_status_check;
+ALTER TABLE tenants ADD CONSTRAINT tenants_status_check
+    CHECK (status IN ('onboarding', 'active', 'suspended', 'offboarded'));
+
+ALTER TABLE tenants
+    ADD COLUMN IF NOT EXISTS company_name TEXT,
+    ADD COLUMN IF NOT EXISTS unit_application_id TEXT,  -- KYB, until Unit approves it
+    ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
@@ -0,0 +1,8 @@
+-- Delivery of the notifications outbox (core/notifications.rs, job 'notifications')
+ALTER TABLE notifications
+    ADD COLUMN IF NOT EXISTS attempts        INT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS last_attempt_at TIMESTAMPTZ,  -- Also the claim: a sender picked it up
+    ADD COLUMN IF NOT EXISTS last_error      TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_notifications_unsent
+    ON notifications (created_at) WHERE sent_at IS NULL;
@@ -0,0 +1,61 @@
+-- Unit cash barcodes can be used again and again until they expire, so a
+-- deposit no longer closes its barcode: each deposit is its own row.
+-- Cash over the user's limits is 'held' in CASH_HOLD_ACCOUNT_ID until an
+-- operator releases it to the user or returns it (ACH to their bank).
+CREATE TABLE IF NOT EXISTS cash_deposits (
+    id                   UUID PRIMARY KEY,
+    tenant_id            UUID NOT NULL REFERENCES tenants(id),
+    barcode_id           UUID REFERENCES cash_deposit_barcodes(id),  -- NULL: matched no barcode (all held)
+    unit_customer_id     TEXT,
+    unit_account_id      TEXT NOT NULL,
+    unit_transaction_id  TEXT NOT NULL UNIQUE,
+    amount_cents         BIGINT NOT NULL,
+    held_cents           BIGINT NOT NULL DEFAULT 0 CHECK (held_cents >= 0),
+    hold_status          TEXT NOT NULL DEFAULT 'none'
+        CHECK (hold_status IN ('none', 'held', 'releasing', 'released', 'returning', 'returned')),
+    hold_resolved_by     TEXT,           -- Operator who released/returned it
+    hold_tx_hash         TEXT,           -- Release: transfer from the hold account to the user
+    ach_payment_id       UUID,           -- Return: assigned before the ACH is sent, so a retry can't send twice
+    created_at           TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    hold_resolved_at     TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_cash_deposits_customer ON cash_deposits (unit_customer_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_cash_deposits_holds ON cash_deposits (tenant_id)
+    WHERE hold_status IN ('held', 'releasing', 'returning');
+
+-- Deposits recorded on their barcode until now
+INSERT INTO cash_deposits
+    (id, tenant_id, barcode_id, unit_customer_id, unit_account_id, unit_transaction_id,
+     amount_cents, held_cents, hold_status, created_at)
+SELECT gen_random_uuid(), tenant_id, id, unit_customer_id, unit_account_id, unit_transaction_id,
+       amount_cents, held_cents, CASE WHEN held_cents > 0 THEN 'held' ELSE 'none' END, used_at
+FROM cash_deposit_barcodes
+WHERE status = 'used' AND unit_transaction_id IS NOT NULL
+ON CONFLICT (unit_transaction_id) DO NOTHING;
+
+-- A barcode "used" before its date is still good at the till: the latest one
+-- per account is live again (unless the account already has a live one)
+UPDATE cash_deposit_barcodes b SET status = 'active'
+WHERE b.status = 'used' AND b.expires_at > NOW()
+  AND NOT EXISTS (
+      SELECT 1 FROM cash_deposit_barcodes a WHERE a.unit_account_id = b.unit_account_id AND a.status = 'active'
+  )
+  AND b.id = (
+      SELECT l.id FROM cash_deposit_barcodes l
+      WHERE l.unit_account_id = b.unit_account_id AND l.status = 'used'
+      ORDER BY l.created_at DESC LIMIT 1
+  );
+UPDATE cash_deposit_barcodes SET status = 'expired' WHERE status = 'used';
+
+ALTER TABLE cash_deposit_barcodes DROP CONSTRAINT IF EXISTS cash_deposit_barcodes_status_check;
+ALTER TABLE cash_deposit_barcodes ADD CONSTRAINT cash_deposit_barcodes_status_check
+    CHECK (status IN ('active', 'expired'));
+ALTER TABLE cash_deposit_barcodes
+    DROP COLUMN IF EXISTS amount_cents,
+    DROP COLUMN IF EXISTS held_cents,
+    DROP COLUMN IF EXISTS unit_transaction_id,
+    DROP COLUMN IF EXISTS used_at;
+
+-- Outgoing ACH burned from somewhere other than the user's wallet (returned cash)
+ALTER TABLE ach_payments ADD COLUMN IF NOT EXISTS burn_account_id TEXT;
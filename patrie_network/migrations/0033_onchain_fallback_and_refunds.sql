@@ -0,0 +1,10 @@
+-- An on-chain invoice whose settlement transfer was never sent can still move
+-- to Unit (treasury short at charge time). Set just before the transfer goes
+-- out: from then on the invoice stays on-chain, the transfer may still land.
+ALTER TABLE invoices ADD COLUMN IF NOT EXISTS onchain_submitted_at TIMESTAMPTZ;
+
+UPDATE invoices SET onchain_submitted_at = updated_at
+WHERE payment_method = 'onchain' AND (onchain_tx_hash IS NOT NULL OR status = 'failed');
+
+-- Refunds go back the way the invoice was paid: on-chain ones to the treasury
+ALTER TABLE credit_notes ADD COLUMN IF NOT EXISTS refund_tx_hash TEXT;
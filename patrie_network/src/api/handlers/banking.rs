@@ -0,0 +1,292 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use crate::core::ach::{AchService, NewAchPayment};
+use crate::core::cash_deposits::{CashDepositService, CashLimits, RetailerNetwork};
+use crate::core::dunning::ensure_tenant_active;
+use crate::core::fiat_banking::NewCounterparty;
+use crate::core::fiat_events::{FiatEventProcessor, FiatOwner};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct LinkAccountRequest {
+    pub unit_account_id: String,
+    pub owner: FiatOwner,           // "tenant" | "user"
+    pub onchain_account_id: String, // e.g. "treasury@tesla_supply_chain"
+}
+
+#[derive(Deserialize)]
+pub struct CashBarcodeRequest {
+    pub unit_account_id: String,
+    pub retailer: Option<RetailerNetwork>, // Defaults to any Green Dot location
+}
+
+#[derive(Deserialize)]
+pub struct CashHoldRequest {
+    pub operator: String, // Who decided (kept in the audit log)
+    pub reason: String,
+}
+
+#[derive(Deserialize)]
+pub struct CashReturnRequest {
+    pub operator: String,
+    pub reason: String,
+    pub counterparty_id: Uuid, // The depositor's own, verified bank account
+}
+
+#[derive(Deserialize)]
+pub struct VerifyCounterpartyRequest {
+    pub amounts_cents: [i64; 2], // The two micro-deposits, in any order
+}
+
+/// 1. Unit webhook: verify, store, then apply.
+/// Once stored we always answer 200; anything that fails to apply is retried
+/// by the fiat_events job instead of relying on Unit redelivering.
+#[post("/webhooks/unit")]
+pub async fn unit_webhook(
+    req: HttpRequest,
+    body: web::Bytes,
+    processor: web::Data<Arc<FiatEventProcessor>>,
+) -> impl Responder {
+    let signature = req.headers().get("X-Unit-Signature").and_then(|v| v.to_str().ok());
+    if let Err(e) = processor.verify(&body, signature) {
+        return HttpResponse::Unauthorized().body(format!("Webhook Rejected: {}", e));
+    }
+
+    // Not stored = not acknowledged, so Unit sends it again
+    let event_ids = match processor.receive(&body).await {
+        Ok(ids) => ids,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Webhook Failed: {}", e)),
+    };
+
+    for event_id in &event_ids {
+        if let Err(e) = processor.process(event_id).await {
+            eprintln!("❌ Unit event {} not processed: {}", event_id, e);
+        }
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({"received": event_ids.len()}))
+}
+
+/// 2. Mirror a Unit deposit account on-chain (tenant treasury or one of its users)
+#[post("/tenants/{id}/fiat-accounts")]
+pub async fn link_fiat_account(
+    path: web::Path<Uuid>,
+    req: web::Json<LinkAccountRequest>,
+    processor: web::Data<Arc<FiatEventProcessor>>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+    if let Err(e) = ensure_tenant_active(pool.get_ref(), tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
+    match processor
+        .link_account(tenant_id, &req.unit_account_id, req.owner, &req.onchain_account_id)
+        .await
+    {
+        Ok(link) => HttpResponse::Ok().json(link),
+        Err(e) => HttpResponse::BadRequest().body(format!("Link Failed: {}", e)),
+    }
+}
+
+/// 2b. Stop mirroring a Unit account (needed before linking it elsewhere)
+#[delete("/tenants/{id}/fiat-accounts/{unit_account_id}")]
+pub async fn unlink_fiat_account(
+    path: web::Path<(Uuid, String)>,
+    processor: web::Data<Arc<FiatEventProcessor>>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let (tenant_id, unit_account_id) = path.into_inner();
+    if let Err(e) = ensure_tenant_active(pool.get_ref(), tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
+    match processor.unlink_account(tenant_id, &unit_account_id).await {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({"unlinked": unit_account_id})),
+        Err(e) => HttpResponse::BadRequest().body(format!("Unlink Failed: {}", e)),
+    }
+}
+
+/// 3. Barcode for depositing cash at a retailer (the live one is reused until it expires)
+#[post("/tenants/{id}/users/{customer_id}/cash-barcodes")]
+pub async fn issue_cash_barcode(
+    path: web::Path<(Uuid, String)>,
+    req: web::Json<CashBarcodeRequest>,
+    cash: web::Data<Arc<CashDepositService>>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let (tenant_id, customer_id) = path.into_inner();
+    if let Err(e) = ensure_tenant_active(pool.get_ref(), tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
+    let retailer = req.retailer.unwrap_or(RetailerNetwork::GreenDot);
+    match cash.issue(tenant_id, &customer_id, &req.unit_account_id, retailer).await {
+        Ok(barcode) => HttpResponse::Ok().json(barcode),
+        Err(e) => HttpResponse::BadRequest().body(format!("Barcode Failed: {}", e)),
+    }
+}
+
+/// 4. A user's active barcodes and how much cash they may still deposit
+#[get("/tenants/{id}/users/{customer_id}/cash-barcodes")]
+pub async fn list_cash_barcodes(
+    path: web::Path<(Uuid, String)>,
+    cash: web::Data<Arc<CashDepositService>>,
+) -> impl Responder {
+    let (tenant_id, customer_id) = path.into_inner();
+
+    let barcodes = match cash.active_for_user(tenant_id, &customer_id).await {
+        Ok(barcodes) => barcodes,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    };
+    match cash.usage(&customer_id).await {
+        Ok(usage) => HttpResponse::Ok().json(serde_json::json!({
+            "barcodes": barcodes,
+            "limits": usage.limits,
+            "daily_remaining_cents": usage.daily_remaining_cents(),
+            "monthly_remaining_cents": usage.monthly_remaining_cents(),
+        })),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 5. Per-user cash deposit limits
+#[post("/tenants/{id}/users/{customer_id}/cash-limits")]
+pub async fn set_cash_limits(
+    path: web::Path<(Uuid, String)>,
+    req: web::Json<CashLimits>,
+    cash: web::Data<Arc<CashDepositService>>,
+) -> impl Responder {
+    let (tenant_id, customer_id) = path.into_inner();
+
+    match cash.set_limits(tenant_id, &customer_id, req.into_inner()).await {
+        Ok(usage) => HttpResponse::Ok().json(usage),
+        Err(e) => HttpResponse::BadRequest().body(format!("Limit Change Failed: {}", e)),
+    }
+}
+
+/// 5b. Cash over a user's limits, waiting for an operator
+#[get("/tenants/{id}/cash-holds")]
+pub async fn list_cash_holds(
+    path: web::Path<Uuid>,
+    cash: web::Data<Arc<CashDepositService>>,
+) -> impl Responder {
+    match cash.held_deposits(path.into_inner()).await {
+        Ok(deposits) => HttpResponse::Ok().json(deposits),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 5c. Let held cash through to the user
+#[post("/tenants/{id}/cash-deposits/{deposit_id}/release")]
+pub async fn release_cash_hold(
+    path: web::Path<(Uuid, Uuid)>,
+    req: web::Json<CashHoldRequest>,
+    cash: web::Data<Arc<CashDepositService>>,
+) -> impl Responder {
+    let (tenant_id, deposit_id) = path.into_inner();
+
+    match cash.release_hold(tenant_id, deposit_id, &req.operator, &req.reason).await {
+        Ok(deposit) => HttpResponse::Ok().json(deposit),
+        Err(e) => HttpResponse::BadRequest().body(format!("Release Failed: {}", e)),
+    }
+}
+
+/// 5d. Send held cash back to the depositor's bank account by ACH
+#[post("/tenants/{id}/cash-deposits/{deposit_id}/return")]
+pub async fn return_cash_hold(
+    path: web::Path<(Uuid, Uuid)>,
+    req: web::Json<CashReturnRequest>,
+    cash: web::Data<Arc<CashDepositService>>,
+) -> impl Responder {
+    let (tenant_id, deposit_id) = path.into_inner();
+
+    match cash
+        .return_hold(tenant_id, deposit_id, req.counterparty_id, &req.operator, &req.reason)
+        .await
+    {
+        Ok(deposit) => HttpResponse::Ok().json(deposit),
+        Err(e) => HttpResponse::BadRequest().body(format!("Return Failed: {}", e)),
+    }
+}
+
+/// 6. Add the user's own bank account (micro-deposits go out right away)
+#[post("/tenants/{id}/users/{customer_id}/counterparties")]
+pub async fn add_counterparty(
+    path: web::Path<(Uuid, String)>,
+    req: web::Json<NewCounterparty>,
+    ach: web::Data<Arc<AchService>>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let (tenant_id, customer_id) = path.into_inner();
+    if let Err(e) = ensure_tenant_active(pool.get_ref(), tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
+    match ach.add_counterparty(tenant_id, &customer_id, &req).await {
+        Ok(counterparty) => HttpResponse::Ok().json(counterparty),
+        Err(e) => HttpResponse::BadRequest().body(format!("Counterparty Failed: {}", e)),
+    }
+}
+
+#[get("/tenants/{id}/users/{customer_id}/counterparties")]
+pub async fn list_counterparties(
+    path: web::Path<(Uuid, String)>,
+    ach: web::Data<Arc<AchService>>,
+) -> impl Responder {
+    let (tenant_id, customer_id) = path.into_inner();
+
+    match ach.counterparties(tenant_id, &customer_id).await {
+        Ok(counterparties) => HttpResponse::Ok().json(counterparties),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 7. Confirm the micro-deposit amounts
+#[post("/tenants/{id}/users/{customer_id}/counterparties/{counterparty_id}/verify")]
+pub async fn verify_counterparty(
+    path: web::Path<(Uuid, String, Uuid)>,
+    req: web::Json<VerifyCounterpartyRequest>,
+    ach: web::Data<Arc<AchService>>,
+) -> impl Responder {
+    let (tenant_id, customer_id, counterparty_id) = path.into_inner();
+
+    match ach.verify_counterparty(tenant_id, &customer_id, counterparty_id, req.amounts_cents).await {
+        Ok(counterparty) => HttpResponse::Ok().json(counterparty),
+        Err(e) => HttpResponse::BadRequest().body(format!("Verification Failed: {}", e)),
+    }
+}
+
+/// 8. Send money to (Credit) or pull it from (Debit) a verified bank account
+#[post("/tenants/{id}/users/{customer_id}/ach-payments")]
+pub async fn originate_ach(
+    path: web::Path<(Uuid, String)>,
+    req: web::Json<NewAchPayment>,
+    ach: web::Data<Arc<AchService>>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let (tenant_id, customer_id) = path.into_inner();
+    if let Err(e) = ensure_tenant_active(pool.get_ref(), tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
+    match ach.originate(tenant_id, &customer_id, &req).await {
+        Ok(payment) => HttpResponse::Ok().json(payment),
+        Err(e) => HttpResponse::BadRequest().body(format!("ACH Failed: {}", e)),
+    }
+}
+
+#[get("/tenants/{id}/users/{customer_id}/ach-payments")]
+pub async fn list_ach_payments(
+    path: web::Path<(Uuid, String)>,
+    ach: web::Data<Arc<AchService>>,
+) -> impl Responder {
+    let (tenant_id, customer_id) = path.into_inner();
+
+    match ach.payments(tenant_id, &customer_id).await {
+        Ok(payments) => HttpResponse::Ok().json(payments),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
@@ -0,0 +1,175 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use crate::core::billing_engine::BillingEngine;
+use crate::core::dunning::DunningService;
+use crate::core::documents::{render_invoice_html, render_invoice_pdf};
+use crate::core::invoice::{BillingPeriod, Invoice, LineItem};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct DownloadQuery {
+    pub format: Option<String>, // "pdf" (default) or "html"
+}
+
+#[derive(Deserialize)]
+pub struct PreviewQuery {
+    pub period: Option<String>, // "2024-04". Defaults to NEXT month.
+}
+
+/// Resolves the period for a preview. Before the 1st, finance wants the upcoming month.
+fn preview_period(query: &PreviewQuery) -> Result<BillingPeriod, HttpResponse> {
+    match &query.period {
+        Some(p) => BillingPeriod::parse(p).map_err(|_| HttpResponse::BadRequest().body("Invalid period, expected YYYY-MM")),
+        None => Ok(BillingPeriod::current().next()),
+    }
+}
+
+/// 1. List a tenant's invoices (with itemized lines)
+#[get("/tenants/{id}/invoices")]
+pub async fn list_invoices(
+    path: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+
+    let invoices = match Invoice::list_for_tenant(pool.get_ref(), tenant_id).await {
+        Ok(list) => list,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    };
+
+    let mut body = Vec::with_capacity(invoices.len());
+    for invoice in invoices {
+        let lines = match LineItem::for_invoice(pool.get_ref(), invoice.id).await {
+            Ok(lines) => lines,
+            Err(e) => return HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+        };
+        body.push(serde_json::json!({
+            "id": invoice.id,
+            "period": invoice.period.to_string(),
+            "status": invoice.status,
+            "total_cents": invoice.total_cents,
+            "lines": lines,
+        }));
+    }
+
+    HttpResponse::Ok().json(body)
+}
+
+/// 2. Download the rendered invoice document (PDF or HTML)
+#[get("/tenants/{id}/invoices/{invoice_id}/download")]
+pub async fn download_invoice(
+    path: web::Path<(Uuid, Uuid)>,
+    query: web::Query<DownloadQuery>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let (tenant_id, invoice_id) = path.into_inner();
+
+    // Only serve the invoice if it belongs to the tenant in the URL
+    let invoice = match Invoice::get(pool.get_ref(), invoice_id).await {
+        Ok(Some(inv)) if inv.tenant_id == tenant_id => inv,
+        Ok(_) => return HttpResponse::NotFound().body("Invoice not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    };
+
+    let lines = match LineItem::for_invoice(pool.get_ref(), invoice.id).await {
+        Ok(lines) => lines,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    };
+
+    let filename = format!("invoice-{}-{}", invoice.period, invoice.id);
+
+    match query.format.as_deref().unwrap_or("pdf") {
+        "html" => match render_invoice_html(&invoice, &lines) {
+            Ok(html) => HttpResponse::Ok().content_type("text/html; charset=utf-8").body(html),
+            Err(e) => HttpResponse::InternalServerError().body(format!("Render Failed: {}", e)),
+        },
+        "pdf" => match render_invoice_pdf(&invoice, &lines) {
+            Ok(pdf) => HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.pdf\"", filename)))
+                .body(pdf),
+            Err(e) => HttpResponse::InternalServerError().body(format!("Render Failed: {}", e)),
+        },
+        other => HttpResponse::BadRequest().body(format!("Unsupported format: {}", other)),
+    }
+}
+
+/// 3. DRY RUN: What every tenant will be charged (no money moves)
+#[get("/billing/preview")]
+pub async fn preview_all_invoices(
+    query: web::Query<PreviewQuery>,
+    engine: web::Data<Arc<BillingEngine>>,
+) -> impl Responder {
+    let period = match preview_period(&query) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    match engine.preview_all_tenants(period).await {
+        Ok(previews) => HttpResponse::Ok().json(previews),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Preview Failed: {}", e)),
+    }
+}
+
+/// 4. DRY RUN for a single tenant
+#[get("/tenants/{id}/invoices/preview")]
+pub async fn preview_invoice(
+    path: web::Path<Uuid>,
+    query: web::Query<PreviewQuery>,
+    engine: web::Data<Arc<BillingEngine>>,
+) -> impl Responder {
+    let period = match preview_period(&query) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    match engine.preview_invoice(path.into_inner(), period).await {
+        Ok(preview) => HttpResponse::Ok().json(preview),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Preview Failed: {}", e)),
+    }
+}
+
+/// 5. Metered ledger usage for a period (defaults to the CURRENT month, to date)
+#[get("/tenants/{id}/usage")]
+pub async fn get_usage(
+    path: web::Path<Uuid>,
+    query: web::Query<PreviewQuery>,
+    engine: web::Data<Arc<BillingEngine>>,
+) -> impl Responder {
+    let period = match &query.period {
+        Some(p) => match BillingPeriod::parse(p) {
+            Ok(period) => period,
+            Err(_) => return HttpResponse::BadRequest().body("Invalid period, expected YYYY-MM"),
+        },
+        None => BillingPeriod::current(),
+    };
+
+    match engine.usage(path.into_inner(), period).await {
+        Ok(usage) => HttpResponse::Ok().json(serde_json::json!({"period": period.to_string(), "usage": usage})),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 6. Pay a failed invoice now (e.g. after funding the account).
+/// Reinstates a suspended tenant once nothing else is outstanding.
+#[post("/tenants/{id}/invoices/{invoice_id}/pay")]
+pub async fn pay_invoice(
+    path: web::Path<(Uuid, Uuid)>,
+    pool: web::Data<PgPool>,
+    dunning: web::Data<Arc<DunningService>>,
+) -> impl Responder {
+    let (tenant_id, invoice_id) = path.into_inner();
+
+    match Invoice::get(pool.get_ref(), invoice_id).await {
+        Ok(Some(inv)) if inv.tenant_id == tenant_id => {}
+        Ok(_) => return HttpResponse::NotFound().body("Invoice not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+
+    match dunning.pay_now(invoice_id).await {
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({"status": "Paid", "invoice_id": invoice_id})),
+        Err(e) => HttpResponse::PaymentRequired().body(format!("Payment Failed: {}", e)),
+    }
+}
@@ -0,0 +1,50 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use crate::core::contracts::{Contract, NewContract};
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct AutoRenewRequest {
+    pub auto_renew: bool,
+}
+
+/// 1. Sign a contract (term, billing frequency, committed usage spend)
+#[post("/tenants/{id}/contracts")]
+pub async fn create_contract(
+    path: web::Path<Uuid>,
+    req: web::Json<NewContract>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    match Contract::create(pool.get_ref(), path.into_inner(), &req).await {
+        Ok(contract) => HttpResponse::Created().json(contract),
+        Err(e) => HttpResponse::BadRequest().body(format!("Contract Failed: {}", e)),
+    }
+}
+
+/// 2. A tenant's contracts, including renewed and expired terms
+#[get("/tenants/{id}/contracts")]
+pub async fn list_contracts(
+    path: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    match Contract::list_for_tenant(pool.get_ref(), path.into_inner()).await {
+        Ok(contracts) => HttpResponse::Ok().json(contracts),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 3. Turn automatic renewal on or off (the notice says which one applies)
+#[post("/tenants/{id}/contracts/{contract_id}/auto-renew")]
+pub async fn set_auto_renew(
+    path: web::Path<(Uuid, Uuid)>,
+    req: web::Json<AutoRenewRequest>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let (tenant_id, contract_id) = path.into_inner();
+
+    match Contract::set_auto_renew(pool.get_ref(), tenant_id, contract_id, req.auto_renew).await {
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({"contract_id": contract_id, "auto_renew": req.auto_renew})),
+        Err(e) => HttpResponse::BadRequest().body(format!("Update Failed: {}", e)),
+    }
+}
@@ -0,0 +1,125 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use crate::core::audit::audit_log_for_tenant;
+use crate::core::credit_notes::{CreditLine, CreditNoteService, CreditResolution, TenantCredit};
+use crate::core::invoice::Invoice;
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct IssueCreditNoteRequest {
+    pub lines: Vec<CreditLine>,
+    pub resolution: CreditResolution, // "refund" or "account_credit"
+    pub reason: String,
+    pub operator: String, // Who is issuing it (kept in the audit log)
+}
+
+#[derive(Deserialize)]
+pub struct ManualAdjustmentRequest {
+    pub description: String,
+    pub amount_cents: i64, // + charge, - credit
+    pub reason: String,
+    pub operator: String,
+}
+
+/// 1. Issue a credit note against specific lines of an invoice
+#[post("/tenants/{id}/invoices/{invoice_id}/credit-notes")]
+pub async fn issue_credit_note(
+    path: web::Path<(Uuid, Uuid)>,
+    req: web::Json<IssueCreditNoteRequest>,
+    pool: web::Data<PgPool>,
+    credit_notes: web::Data<Arc<CreditNoteService>>,
+) -> impl Responder {
+    let (tenant_id, invoice_id) = path.into_inner();
+
+    match Invoice::get(pool.get_ref(), invoice_id).await {
+        Ok(Some(inv)) if inv.tenant_id == tenant_id => {}
+        Ok(_) => return HttpResponse::NotFound().body("Invoice not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+
+    match credit_notes.issue(invoice_id, &req.lines, req.resolution, &req.reason, &req.operator).await {
+        Ok(note) => HttpResponse::Created().json(note),
+        Err(e) => HttpResponse::BadRequest().body(format!("Credit Note Failed: {}", e)),
+    }
+}
+
+/// 2. Retry a refund that failed, or one stuck pending (e.g. after a crash)
+#[post("/tenants/{id}/credit-notes/{credit_note_id}/retry-refund")]
+pub async fn retry_refund(
+    path: web::Path<(Uuid, Uuid)>,
+    query: web::Query<OperatorQuery>,
+    credit_notes: web::Data<Arc<CreditNoteService>>,
+) -> impl Responder {
+    let (tenant_id, credit_note_id) = path.into_inner();
+
+    match credit_notes.get(credit_note_id).await {
+        Ok(Some(note)) if note.tenant_id == tenant_id => {}
+        Ok(_) => return HttpResponse::NotFound().body("Credit note not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+
+    match credit_notes.retry_refund(credit_note_id, &query.operator).await {
+        Ok(note) => HttpResponse::Ok().json(note),
+        Err(e) => HttpResponse::BadRequest().body(format!("Refund Failed: {}", e)),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct OperatorQuery {
+    pub operator: String,
+}
+
+/// 3. A tenant's credit notes
+#[get("/tenants/{id}/credit-notes")]
+pub async fn list_credit_notes(
+    path: web::Path<Uuid>,
+    credit_notes: web::Data<Arc<CreditNoteService>>,
+) -> impl Responder {
+    match credit_notes.list_for_tenant(path.into_inner()).await {
+        Ok(notes) => HttpResponse::Ok().json(notes),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 4. Remaining account credit (applied automatically to the next invoices)
+#[get("/tenants/{id}/credit-balance")]
+pub async fn get_credit_balance(
+    path: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+    match TenantCredit::balance(pool.get_ref(), tenant_id).await {
+        Ok(balance) => HttpResponse::Ok().json(serde_json::json!({"tenant_id": tenant_id, "balance_cents": balance})),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 5. Manual one-off adjustment for the next invoice (audited)
+#[post("/tenants/{id}/adjustments")]
+pub async fn create_adjustment(
+    path: web::Path<Uuid>,
+    req: web::Json<ManualAdjustmentRequest>,
+    credit_notes: web::Data<Arc<CreditNoteService>>,
+) -> impl Responder {
+    match credit_notes
+        .create_adjustment(path.into_inner(), &req.description, req.amount_cents, &req.reason, &req.operator)
+        .await
+    {
+        Ok(adjustment) => HttpResponse::Created().json(adjustment),
+        Err(e) => HttpResponse::BadRequest().body(format!("Adjustment Failed: {}", e)),
+    }
+}
+
+/// 6. Who changed this tenant's billing, and why
+#[get("/tenants/{id}/audit-log")]
+pub async fn get_audit_log(
+    path: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    match audit_log_for_tenant(pool.get_ref(), path.into_inner()).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
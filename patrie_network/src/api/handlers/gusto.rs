@@ -0,0 +1,168 @@
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use crate::core::benefit_catalog::{BenefitCatalog, BenefitProduct};
+use crate::core::dunning::ensure_tenant_active;
+use crate::core::employee_sync::EmployeeSync;
+use crate::core::gusto_events::{GustoDelivery, GustoEventProcessor};
+use crate::core::payroll::{PayrollMirror, PayrollSettings};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 1. Gusto webhook: verify, store, then apply.
+/// Once stored we always answer 200; anything that fails to apply is retried
+/// by the gusto_events job instead of relying on Gusto redelivering.
+#[post("/webhooks/gusto")]
+pub async fn gusto_webhook(
+    req: HttpRequest,
+    body: web::Bytes,
+    processor: web::Data<Arc<GustoEventProcessor>>,
+) -> impl Responder {
+    let event = match GustoDelivery::parse(&body) {
+        Ok(GustoDelivery::Event(event)) => event,
+        // Unsigned (we don't have the token yet) and nothing to act on:
+        // stored encrypted for an operator to confirm the subscription with
+        Ok(GustoDelivery::Verification { subscription_uuid, verification_token }) => {
+            match processor.store_verification(&subscription_uuid, &verification_token).await {
+                Ok(true) => println!("🔑 Gusto webhook verification token stored for subscription {}.", subscription_uuid),
+                Ok(false) => {}
+                Err(e) => return HttpResponse::InternalServerError().body(format!("Webhook Failed: {}", e)),
+            }
+            return HttpResponse::Ok().finish();
+        }
+        Err(e) => return HttpResponse::BadRequest().body(format!("Webhook Rejected: {}", e)),
+    };
+
+    let signature = req.headers().get("X-Gusto-Signature").and_then(|v| v.to_str().ok());
+    if let Err(e) = processor.verify(&body, signature) {
+        return HttpResponse::Unauthorized().body(format!("Webhook Rejected: {}", e));
+    }
+
+    // Not stored = not acknowledged, so Gusto sends it again
+    let event_id = match processor.receive(&event).await {
+        Ok(id) => id,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Webhook Failed: {}", e)),
+    };
+
+    if let Err(e) = processor.process(&event_id).await {
+        eprintln!("❌ Gusto event {} not processed: {}", event_id, e);
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({"received": event_id}))
+}
+
+/// 2. Sync the tenant's Gusto employees to accounts in its ledger domain (now)
+#[post("/tenants/{id}/employees/sync")]
+pub async fn sync_employees(
+    path: web::Path<Uuid>,
+    employees: web::Data<Arc<EmployeeSync>>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+    if let Err(e) = ensure_tenant_active(pool.get_ref(), tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
+    match employees.sync_tenant(tenant_id).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::BadRequest().body(format!("Employee Sync Failed: {}", e)),
+    }
+}
+
+/// 3. Employees and their on-chain accounts
+#[get("/tenants/{id}/employees")]
+pub async fn list_employees(path: web::Path<Uuid>, employees: web::Data<Arc<EmployeeSync>>) -> impl Responder {
+    match employees.accounts(path.into_inner()).await {
+        Ok(accounts) => HttpResponse::Ok().json(accounts),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 4. Payroll on chain: pay net pay from a treasury account, off-ramp it to Unit
+#[post("/tenants/{id}/payroll-settings")]
+pub async fn set_payroll_settings(
+    path: web::Path<Uuid>,
+    body: web::Json<PayrollSettings>,
+    payroll: web::Data<Arc<PayrollMirror>>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+    if let Err(e) = ensure_tenant_active(pool.get_ref(), tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
+    match payroll.set_settings(tenant_id, &body).await {
+        Ok(settings) => HttpResponse::Ok().json(settings),
+        Err(e) => HttpResponse::BadRequest().body(format!("Payroll Settings Failed: {}", e)),
+    }
+}
+
+/// 5. Payrolls mirrored on chain, newest first
+#[get("/tenants/{id}/payrolls")]
+pub async fn list_payrolls(path: web::Path<Uuid>, payroll: web::Data<Arc<PayrollMirror>>) -> impl Responder {
+    match payroll.runs(path.into_inner()).await {
+        Ok(runs) => HttpResponse::Ok().json(runs),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 6. Mirror (or finish mirroring) one payroll now. Safe to repeat.
+#[post("/tenants/{id}/payrolls/{payroll_uuid}/mirror")]
+pub async fn mirror_payroll(
+    path: web::Path<(Uuid, String)>,
+    payroll: web::Data<Arc<PayrollMirror>>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let (tenant_id, payroll_uuid) = path.into_inner();
+    if let Err(e) = ensure_tenant_active(pool.get_ref(), tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
+    match payroll.mirror(tenant_id, &payroll_uuid).await {
+        Ok(run) => HttpResponse::Ok().json(run),
+        Err(e) => HttpResponse::BadRequest().body(format!("Payroll Mirror Failed: {}", e)),
+    }
+}
+
+/// 7. Our benefit products, and every benefit Gusto supports (cached)
+#[get("/benefits/catalog")]
+pub async fn get_benefit_catalog(catalog: web::Data<Arc<BenefitCatalog>>) -> impl Responder {
+    let products = match catalog.products().await {
+        Ok(products) => products,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    };
+    match catalog.benefits().await {
+        Ok(gusto) => HttpResponse::Ok().json(serde_json::json!({"products": products, "gusto": gusto})),
+        Err(e) => HttpResponse::BadGateway().body(format!("Benefit Catalog Failed: {}", e)),
+    }
+}
+
+/// 8. Fetch Gusto's benefit list now instead of waiting for the cache to age
+#[post("/benefits/catalog/refresh")]
+pub async fn refresh_benefit_catalog(catalog: web::Data<Arc<BenefitCatalog>>) -> impl Responder {
+    match catalog.refresh().await {
+        Ok(gusto) => HttpResponse::Ok().json(gusto),
+        Err(e) => HttpResponse::BadGateway().body(format!("Benefit Catalog Failed: {}", e)),
+    }
+}
+
+/// 9. Add or change a benefit product (e.g. offer dental on Enterprise).
+/// Applies from the next tier change, no redeploy needed.
+#[post("/benefits/products")]
+pub async fn save_benefit_product(
+    body: web::Json<BenefitProduct>,
+    catalog: web::Data<Arc<BenefitCatalog>>,
+) -> impl Responder {
+    match catalog.save_product(&body).await {
+        Ok(product) => HttpResponse::Ok().json(product),
+        Err(e) => HttpResponse::BadRequest().body(format!("Benefit Product Failed: {}", e)),
+    }
+}
+
+/// 10. The tenant's company benefits and what they cost us
+#[get("/tenants/{id}/benefits")]
+pub async fn list_tenant_benefits(path: web::Path<Uuid>, catalog: web::Data<Arc<BenefitCatalog>>) -> impl Responder {
+    match catalog.tenant_benefits(path.into_inner()).await {
+        Ok(benefits) => HttpResponse::Ok().json(benefits),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
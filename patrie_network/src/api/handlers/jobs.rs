@@ -0,0 +1,66 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use crate::core::jobs::{JobName, JobRegistry, JobTrigger};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct RunHistoryQuery {
+    pub job: Option<String>, // e.g. "billing". Defaults to every job.
+    pub limit: Option<i64>,  // Defaults to 50
+}
+
+#[derive(Deserialize)]
+pub struct TriggerJobRequest {
+    pub operator: String, // Who pressed the button (kept in the run history)
+}
+
+/// 1. Registered jobs and their schedules
+#[get("/jobs")]
+pub async fn list_jobs(registry: web::Data<Arc<JobRegistry>>) -> impl Responder {
+    let jobs: Vec<_> = registry
+        .jobs()
+        .map(|(name, job)| serde_json::json!({"name": name, "schedule": job.schedule}))
+        .collect();
+
+    HttpResponse::Ok().json(jobs)
+}
+
+/// 2. Run history (most recent first)
+#[get("/jobs/runs")]
+pub async fn job_runs(
+    query: web::Query<RunHistoryQuery>,
+    registry: web::Data<Arc<JobRegistry>>,
+) -> impl Responder {
+    let name = match query.job.as_deref().map(JobName::parse).transpose() {
+        Ok(name) => name,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    match registry.history(name, query.limit.unwrap_or(50).clamp(1, 500)).await {
+        Ok(runs) => HttpResponse::Ok().json(runs),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 3. Run a job NOW. Takes the same lock as the scheduler, so it never
+/// overlaps a scheduled run on any replica.
+#[post("/jobs/{name}/run")]
+pub async fn trigger_job(
+    path: web::Path<String>,
+    req: web::Json<TriggerJobRequest>,
+    registry: web::Data<Arc<JobRegistry>>,
+) -> impl Responder {
+    let name = match JobName::parse(&path.into_inner()) {
+        Ok(name) => name,
+        Err(e) => return HttpResponse::NotFound().body(e.to_string()),
+    };
+    if req.operator.trim().is_empty() {
+        return HttpResponse::BadRequest().body("Manual runs require an operator");
+    }
+
+    match registry.run(name, JobTrigger::Manual, Some(&req.operator)).await {
+        Ok(run) if run.status == "skipped" => HttpResponse::Conflict().json(run),
+        Ok(run) => HttpResponse::Ok().json(run),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
@@ -0,0 +1,15 @@
+pub mod banking;
+pub mod billing;
+pub mod contracts;
+pub mod credit_notes;
+pub mod explorer;
+pub mod gusto;
+pub mod insurance;
+pub mod jobs;
+pub mod onboarding;
+pub mod pricing;
+pub mod reports;
+pub mod reserves;
+pub mod subscription;
+pub mod unit;
+pub mod wallet;
@@ -1,55 +1,50 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use crate::core::onboarding::{OnboardingStatus, SignupRequest, TenantOnboarding};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 201 once the tenant is live, 202 while a step (usually Unit's KYB review) is pending
+fn onboarding_response(status: OnboardingStatus) -> HttpResponse {
+    if status.is_complete() {
+        HttpResponse::Created().json(status)
+    } else {
+        HttpResponse::Accepted().json(status)
+    }
+}
+
+/// Sign up a tenant: Gusto company (triggers our commission), Unit business
+/// customer & deposit account, ledger domain. Every id is saved as it comes
+/// back, so sending the same signup again resumes a failed or pending one.
+#[post("/tenants")]
 pub async fn full_onboarding(
     req: web::Json<SignupRequest>,
-    data: web::Data<Arc<AppState>>,
+    onboarding: web::Data<Arc<TenantOnboarding>>,
 ) -> impl Responder {
-    
-    // Step 1: Create Gusto Account (Triggers your Commission)
-    let gusto_data = match data.gusto_client.create_partner_managed_company(
-        &req.company_name, 
-        &req.email
-    ).await {
-        Ok(d) => d,
-        Err(e) => return HttpResponse::BadRequest().body(format!("Gusto Setup Failed: {}", e)),
-    };
-
-    // Step 2: Store their tokens securely (Vault or DB)
-    // You need 'gusto_data.access_token' to manage their benefits later.
-    save_tokens_to_db(&req.company_name, &gusto_data.access_token);
-
-    // Step 3: Create Iroha 2 Domain (The Blockchain Space)
-    // We link the Iroha Domain Name to the Gusto Company UUID for tracking.
-    let domain_id = req.company_name.to_lowercase();
-    let register_domain = Register::domain(Domain::new(domain_id.parse().unwrap()));
-    
-    // ... Submit to Iroha ...
-
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "Onboarding Complete", 
-        "gusto_uuid": gusto_data.company_uuid,
-        "iroha_domain": domain_id
-    }))
+    match onboarding.sign_up(&req).await {
+        Ok(status) => onboarding_response(status),
+        Err(e) => HttpResponse::BadRequest().body(format!("Onboarding Failed: {}", e)),
+    }
 }
 
-use crate::core::tiers::ServiceTier;
-
-// ... inside your signup handler ...
-
-// 1. User selected "Enterprise" in the UI
-let selected_tier = ServiceTier::Enterprise;
-let config = selected_tier.get_config();
+/// Continue an unfinished onboarding (e.g. once Unit approved the KYB)
+#[post("/tenants/{id}/onboarding/resume")]
+pub async fn resume_onboarding(
+    path: web::Path<Uuid>,
+    onboarding: web::Data<Arc<TenantOnboarding>>,
+) -> impl Responder {
+    match onboarding.resume(path.into_inner()).await {
+        Ok(status) => onboarding_response(status),
+        Err(e) => HttpResponse::BadRequest().body(format!("Onboarding Failed: {}", e)),
+    }
+}
 
-// 2. Save these settings to your Database
-sqlx::query!(
-    r#"
-    INSERT INTO subscription_settings 
-    (tenant_id, base_fee_retail, health_active, retirement_active, crime_active)
-    VALUES ($1, $2, $3, $4, $5)
-    "#,
-    tenant_id,
-    config.base_price,        // 2500.00
-    config.includes_health,   // true
-    config.includes_401k,     // true
-    config.includes_crime_ins // true
-)
-.execute(&pool)
-.await?;
\ No newline at end of file
+#[get("/tenants/{id}/onboarding")]
+pub async fn onboarding_status(
+    path: web::Path<Uuid>,
+    onboarding: web::Data<Arc<TenantOnboarding>>,
+) -> impl Responder {
+    match onboarding.status(path.into_inner()).await {
+        Ok(status) => HttpResponse::Ok().json(status),
+        Err(e) => HttpResponse::NotFound().body(format!("{}", e)),
+    }
+}
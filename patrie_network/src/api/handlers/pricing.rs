@@ -0,0 +1,118 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use crate::core::metering::UsageRate;
+use crate::core::pricing::{add_discount, add_price_override, PriceBook, PriceEntry, PriceKey};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// --- Request/Response Structs ---
+
+#[derive(Deserialize)]
+pub struct PriceBookQuery {
+    pub date: Option<NaiveDate>, // Defaults to today
+}
+
+#[derive(Deserialize)]
+pub struct PublishPriceBookRequest {
+    pub effective_from: NaiveDate,   // e.g. "2025-01-01"
+    pub notes: Option<String>,
+    pub entries: Vec<PriceEntry>,
+    #[serde(default)]
+    pub usage_rates: Vec<UsageRate>,
+}
+
+#[derive(Deserialize)]
+pub struct PriceOverrideRequest {
+    pub price_key: PriceKey,         // e.g. "base_platform_fee"
+    pub amount_cents: i64,
+    pub effective_from: NaiveDate,
+    pub effective_until: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+pub struct DiscountRequest {
+    pub code: String,                // Coupon code or "NEGOTIATED"
+    pub description: String,
+    pub percent_off_bps: Option<i32>,  // 1500 = 15%
+    pub amount_off_cents: Option<i64>,
+    pub applies_to: Option<PriceKey>,  // None = all of our fees
+    pub valid_from: NaiveDate,
+    pub valid_until: Option<NaiveDate>,
+}
+
+// --- API Endpoints ---
+
+/// 1. The price book in force on a date
+#[get("/pricing/price-book")]
+pub async fn get_price_book(
+    query: web::Query<PriceBookQuery>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let result = match query.date {
+        Some(date) => PriceBook::effective_at(pool.get_ref(), date).await,
+        None => PriceBook::current(pool.get_ref()).await,
+    };
+
+    match result {
+        Ok(book) => HttpResponse::Ok().json(book),
+        Err(e) => HttpResponse::NotFound().body(format!("Error: {}", e)),
+    }
+}
+
+/// 2. Publish a new price book version (no redeploy needed)
+#[post("/pricing/price-books")]
+pub async fn publish_price_book(
+    req: web::Json<PublishPriceBookRequest>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let req = req.into_inner();
+
+    match PriceBook::publish(pool.get_ref(), req.effective_from, req.notes, &req.entries, &req.usage_rates).await {
+        Ok(book) => HttpResponse::Ok().json(book),
+        Err(e) => HttpResponse::BadRequest().body(format!("Publish Failed: {}", e)),
+    }
+}
+
+/// 3. Negotiated price for one tenant
+#[post("/tenants/{id}/price-overrides")]
+pub async fn create_price_override(
+    path: web::Path<Uuid>,
+    req: web::Json<PriceOverrideRequest>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    match add_price_override(
+        pool.get_ref(),
+        path.into_inner(),
+        req.price_key,
+        req.amount_cents,
+        req.effective_from,
+        req.effective_until,
+    ).await {
+        Ok(id) => HttpResponse::Ok().json(serde_json::json!({"status": "Override saved", "id": id})),
+        Err(e) => HttpResponse::BadRequest().body(format!("Error: {}", e)),
+    }
+}
+
+/// 4. Discount / coupon for one tenant
+#[post("/tenants/{id}/discounts")]
+pub async fn create_discount(
+    path: web::Path<Uuid>,
+    req: web::Json<DiscountRequest>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    match add_discount(
+        pool.get_ref(),
+        path.into_inner(),
+        &req.code,
+        &req.description,
+        req.percent_off_bps,
+        req.amount_off_cents,
+        req.applies_to,
+        req.valid_from,
+        req.valid_until,
+    ).await {
+        Ok(id) => HttpResponse::Ok().json(serde_json::json!({"status": "Discount saved", "id": id})),
+        Err(e) => HttpResponse::BadRequest().body(format!("Error: {}", e)),
+    }
+}
@@ -0,0 +1,75 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use crate::core::invoice::BillingPeriod;
+use crate::core::reporting::RevenueReporter;
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct ReportQuery {
+    pub period: Option<String>, // "2024-04". Defaults to the CURRENT month.
+}
+
+#[derive(Deserialize)]
+pub struct TrendQuery {
+    pub period: Option<String>, // Last month of the trend. Defaults to the CURRENT month.
+    pub months: Option<u32>,    // Defaults to 12
+}
+
+fn report_period(period: &Option<String>) -> Result<BillingPeriod, HttpResponse> {
+    match period {
+        Some(p) => BillingPeriod::parse(p).map_err(|_| HttpResponse::BadRequest().body("Invalid period, expected YYYY-MM")),
+        None => Ok(BillingPeriod::current()),
+    }
+}
+
+/// 1. MRR, ARR, churn, tier mix, retained vs. pass-through
+#[get("/reports/revenue")]
+pub async fn revenue_report(
+    query: web::Query<ReportQuery>,
+    reporter: web::Data<Arc<RevenueReporter>>,
+) -> impl Responder {
+    let period = match report_period(&query.period) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    match reporter.report(period).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Report Failed: {}", e)),
+    }
+}
+
+/// 2. Margin per tenant
+#[get("/reports/revenue/tenants")]
+pub async fn tenant_margins(
+    query: web::Query<ReportQuery>,
+    reporter: web::Data<Arc<RevenueReporter>>,
+) -> impl Responder {
+    let period = match report_period(&query.period) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    match reporter.tenant_margins(period).await {
+        Ok(margins) => HttpResponse::Ok().json(margins),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Report Failed: {}", e)),
+    }
+}
+
+/// 3. Month-over-month trend
+#[get("/reports/revenue/trend")]
+pub async fn revenue_trend(
+    query: web::Query<TrendQuery>,
+    reporter: web::Data<Arc<RevenueReporter>>,
+) -> impl Responder {
+    let end = match report_period(&query.period) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+    let months = query.months.unwrap_or(12).clamp(1, 36);
+
+    match reporter.trend(end, months).await {
+        Ok(points) => HttpResponse::Ok().json(points),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Report Failed: {}", e)),
+    }
+}
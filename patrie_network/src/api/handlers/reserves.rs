@@ -0,0 +1,39 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use crate::core::reserves::ReserveReconciler;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 1. The latest proof-of-reserves report (public: holders verify against it)
+#[get("/reserves/latest")]
+pub async fn latest_reserves(reserves: web::Data<Arc<ReserveReconciler>>) -> impl Responder {
+    match reserves.latest().await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::NotFound().body(format!("Report Failed: {}", e)),
+    }
+}
+
+/// 2. One report, with every discrepancy it found
+#[get("/reserves/{id}")]
+pub async fn get_reserve_report(
+    path: web::Path<Uuid>,
+    reserves: web::Data<Arc<ReserveReconciler>>,
+) -> impl Responder {
+    match reserves.get(path.into_inner()).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => HttpResponse::NotFound().body(format!("Report Failed: {}", e)),
+    }
+}
+
+/// 3. Merkle inclusion proof for one holder's balance
+#[get("/reserves/{id}/proof/{account_id}")]
+pub async fn reserve_proof(
+    path: web::Path<(Uuid, String)>,
+    reserves: web::Data<Arc<ReserveReconciler>>,
+) -> impl Responder {
+    let (report_id, account_id) = path.into_inner();
+
+    match reserves.proof(report_id, &account_id).await {
+        Ok(proof) => HttpResponse::Ok().json(proof),
+        Err(e) => HttpResponse::NotFound().body(format!("Proof Failed: {}", e)),
+    }
+}
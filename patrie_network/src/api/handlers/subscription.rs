@@ -0,0 +1,85 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use crate::core::dunning::ensure_tenant_active;
+use crate::core::subscription::SubscriptionManager;
+use crate::core::tiers::ServiceTier;
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct ChangeTierRequest {
+    pub tier: String, // "starter" | "professional" | "enterprise"
+}
+
+#[derive(Deserialize)]
+pub struct SettlementRequest {
+    pub onchain: bool,
+    pub treasury_account_id: Option<String>, // Required when turning on-chain settlement on
+}
+
+/// Upgrade or downgrade a tenant. Mid-cycle changes are prorated on the next invoice.
+#[post("/tenants/{id}/tier")]
+pub async fn change_tier(
+    path: web::Path<Uuid>,
+    req: web::Json<ChangeTierRequest>,
+    subscriptions: web::Data<Arc<SubscriptionManager>>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+    if let Err(e) = ensure_tenant_active(pool.get_ref(), tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
+    let new_tier = match ServiceTier::parse(&req.tier) {
+        Ok(tier) => tier,
+        Err(e) => return HttpResponse::BadRequest().body(format!("{}", e)),
+    };
+
+    match subscriptions.change_tier(tenant_id, new_tier).await {
+        Ok(change) => HttpResponse::Ok().json(change),
+        Err(e) => HttpResponse::BadRequest().body(format!("Tier Change Failed: {}", e)),
+    }
+}
+
+/// Opt in (or out) of paying invoices with on-chain USD.
+/// If the treasury balance is too low, the invoice falls back to the Unit deposit account.
+#[post("/tenants/{id}/settlement")]
+pub async fn set_settlement(
+    path: web::Path<Uuid>,
+    req: web::Json<SettlementRequest>,
+    subscriptions: web::Data<Arc<SubscriptionManager>>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+    if let Err(e) = ensure_tenant_active(pool.get_ref(), tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
+    match subscriptions
+        .set_onchain_settlement(tenant_id, req.onchain, req.treasury_account_id.as_deref())
+        .await
+    {
+        Ok(settings) => HttpResponse::Ok().json(serde_json::json!({
+            "tenant_id": tenant_id,
+            "onchain_settlement": settings.onchain_settlement,
+            "treasury_account_id": settings.treasury_account_id,
+        })),
+        Err(e) => HttpResponse::BadRequest().body(format!("Settlement Change Failed: {}", e)),
+    }
+}
+
+/// Offboard a tenant: turn off their Gusto benefits and revoke our Gusto access.
+/// Suspended tenants can be offboarded too; offboarding is final.
+#[post("/tenants/{id}/offboard")]
+pub async fn offboard_tenant(
+    path: web::Path<Uuid>,
+    subscriptions: web::Data<Arc<SubscriptionManager>>,
+) -> impl Responder {
+    let tenant_id = path.into_inner();
+
+    match subscriptions.offboard(tenant_id).await {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({ "tenant_id": tenant_id, "status": "offboarded" })),
+        Err(e) => HttpResponse::BadRequest().body(format!("Offboarding Failed: {}", e)),
+    }
+}
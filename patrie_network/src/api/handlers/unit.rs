@@ -2,8 +2,10 @@ use actix_web::{get, post, web, HttpResponse, Responder};
 use iroha_client::client::Client;
 use iroha_data_model::prelude::*;
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
 use std::sync::Arc;
 use crate::AppState; // Import from your main.rs
+use crate::core::dunning::ensure_domain_active;
 
 // --- Request/Response Structs ---
 
@@ -30,7 +32,13 @@ pub struct MintUnitRequest {
 pub async fn define_unit(
     req: web::Json<DefineUnitRequest>,
     data: web::Data<Arc<AppState>>,
+    pool: web::Data<PgPool>,
 ) -> impl Responder {
+    // Suspended tenants cannot write
+    if let Err(e) = ensure_domain_active(pool.get_ref(), &req.tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
     let client = &data.iroha_client;
 
     // Construct the AssetDefinitionId (e.g., "battery_pack#tesla_supply_chain")
@@ -62,7 +70,13 @@ pub async fn define_unit(
 pub async fn mint_unit(
     req: web::Json<MintUnitRequest>,
     data: web::Data<Arc<AppState>>,
+    pool: web::Data<PgPool>,
 ) -> impl Responder {
+    // Suspended tenants cannot write
+    if let Err(e) = ensure_domain_active(pool.get_ref(), &req.tenant_id).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
     let client = &data.iroha_client;
 
     // Target Asset: "battery_pack#tesla_supply_chain"
@@ -1,7 +1,15 @@
 use actix_web::{get, post, web, HttpResponse, Responder};
+use crate::core::cards::{CardService, CardStatus};
+use crate::core::documents::{render_statement_csv, render_statement_pdf};
+use crate::core::dunning::ensure_domain_active;
+use crate::core::fiat_banking::{CardLimits, PageRequest};
+use crate::core::invoice::BillingPeriod;
+use crate::core::statements::StatementService;
 use crate::ledger::client::IrohaClient;
 use serde::Deserialize;
+use sqlx::PgPool;
 use std::sync::Arc;
+use uuid::Uuid;
 
 #[derive(Deserialize)]
 pub struct TransferRequest {
@@ -12,6 +20,23 @@ pub struct TransferRequest {
     pub amount: f64,
 }
 
+#[derive(Deserialize)]
+pub struct StatementQuery {
+    pub format: Option<String>, // pdf (default) | csv | json
+}
+
+#[derive(Deserialize)]
+pub struct IssueCardRequest {
+    pub unit_account_id: Option<String>, // Only needed if the wallet has several Unit accounts
+    pub limits: Option<CardLimits>,
+}
+
+#[derive(Deserialize)]
+pub struct CardActivityQuery {
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
 /// 1. Get Wallet Balance
 #[get("/wallet/{account_id}/balance")]
 pub async fn get_balance(
@@ -32,8 +57,14 @@ pub async fn get_balance(
 pub async fn send_tokens(
     req: web::Json<TransferRequest>,
     iroha: web::Data<Arc<IrohaClient>>,
+    pool: web::Data<PgPool>,
 ) -> impl Responder {
-    
+    // Suspended tenants cannot move funds ("alice@tenant_domain")
+    let sender_domain = req.sender_id.split('@').nth(1).unwrap_or_default();
+    if let Err(e) = ensure_domain_active(pool.get_ref(), sender_domain).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
     match iroha.transfer_asset(
         &req.sender_id, 
         &req.private_key, 
@@ -44,4 +75,140 @@ pub async fn send_tokens(
         Ok(tx_hash) => HttpResponse::Ok().json(serde_json::json!({"status": "Sent", "tx_hash": tx_hash})),
         Err(e) => HttpResponse::BadRequest().body(format!("Transfer Failed: {}", e)),
     }
-}
\ No newline at end of file
+}
+/// 3. Issue a virtual debit card that spends this wallet's USD
+#[post("/wallet/{account_id}/cards")]
+pub async fn issue_card(
+    path: web::Path<String>,
+    req: web::Json<IssueCardRequest>,
+    cards: web::Data<Arc<CardService>>,
+    pool: web::Data<PgPool>,
+) -> impl Responder {
+    let account_id = path.into_inner();
+    let domain = account_id.split('@').nth(1).unwrap_or_default();
+    if let Err(e) = ensure_domain_active(pool.get_ref(), domain).await {
+        return HttpResponse::Forbidden().body(e);
+    }
+
+    let limits = req.limits.clone().unwrap_or_default();
+    match cards.issue(&account_id, req.unit_account_id.as_deref(), &limits).await {
+        Ok(card) => HttpResponse::Ok().json(card),
+        Err(e) => HttpResponse::BadRequest().body(format!("Card Failed: {}", e)),
+    }
+}
+
+#[get("/wallet/{account_id}/cards")]
+pub async fn list_cards(
+    path: web::Path<String>,
+    cards: web::Data<Arc<CardService>>,
+) -> impl Responder {
+    match cards.list(&path.into_inner()).await {
+        Ok(cards) => HttpResponse::Ok().json(cards),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 4. Card lifecycle: freeze, unfreeze, close (closing is permanent)
+#[post("/wallet/{account_id}/cards/{card_id}/freeze")]
+pub async fn freeze_card(path: web::Path<(String, Uuid)>, cards: web::Data<Arc<CardService>>) -> impl Responder {
+    change_card_status(path.into_inner(), CardStatus::Frozen, &cards).await
+}
+
+#[post("/wallet/{account_id}/cards/{card_id}/unfreeze")]
+pub async fn unfreeze_card(path: web::Path<(String, Uuid)>, cards: web::Data<Arc<CardService>>) -> impl Responder {
+    change_card_status(path.into_inner(), CardStatus::Active, &cards).await
+}
+
+#[post("/wallet/{account_id}/cards/{card_id}/close")]
+pub async fn close_card(path: web::Path<(String, Uuid)>, cards: web::Data<Arc<CardService>>) -> impl Responder {
+    change_card_status(path.into_inner(), CardStatus::Closed, &cards).await
+}
+
+async fn change_card_status((account_id, card_id): (String, Uuid), status: CardStatus, cards: &CardService) -> HttpResponse {
+    match cards.set_status(&account_id, card_id, status).await {
+        Ok(card) => HttpResponse::Ok().json(card),
+        Err(e) => HttpResponse::BadRequest().body(format!("Card Update Failed: {}", e)),
+    }
+}
+
+/// 5. Spending limits
+#[post("/wallet/{account_id}/cards/{card_id}/limits")]
+pub async fn set_card_limits(
+    path: web::Path<(String, Uuid)>,
+    req: web::Json<CardLimits>,
+    cards: web::Data<Arc<CardService>>,
+) -> impl Responder {
+    let (account_id, card_id) = path.into_inner();
+
+    match cards.set_limits(&account_id, card_id, &req).await {
+        Ok(card) => HttpResponse::Ok().json(card),
+        Err(e) => HttpResponse::BadRequest().body(format!("Limit Change Failed: {}", e)),
+    }
+}
+
+/// 6. Pending holds and settled card transactions
+#[get("/wallet/{account_id}/cards/{card_id}/transactions")]
+pub async fn card_transactions(
+    path: web::Path<(String, Uuid)>,
+    query: web::Query<CardActivityQuery>,
+    cards: web::Data<Arc<CardService>>,
+) -> impl Responder {
+    let (account_id, card_id) = path.into_inner();
+    let defaults = PageRequest::default();
+    let page = PageRequest {
+        limit: query.limit.unwrap_or(defaults.limit),
+        offset: query.offset.unwrap_or(defaults.offset),
+    };
+
+    match cards.activity(&account_id, card_id, page).await {
+        Ok(activity) => HttpResponse::Ok().json(activity),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 7. Monthly statements available for this wallet
+#[get("/wallet/{account_id}/statements")]
+pub async fn list_statements(path: web::Path<String>, statements: web::Data<Arc<StatementService>>) -> impl Responder {
+    match statements.list(&path.into_inner()).await {
+        Ok(list) => HttpResponse::Ok().json(list),
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+/// 8. Download one month's statement (generated on first request if the job hasn't run yet)
+#[get("/wallet/{account_id}/statements/{period}")]
+pub async fn get_statement(
+    path: web::Path<(String, String)>,
+    query: web::Query<StatementQuery>,
+    statements: web::Data<Arc<StatementService>>,
+) -> impl Responder {
+    let (account_id, period) = path.into_inner();
+
+    let period = match BillingPeriod::parse(&period) {
+        Ok(p) => p,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid period: {}", e)),
+    };
+
+    let statement = match statements.statement(&account_id, period).await {
+        Ok(s) => s,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Statement Failed: {}", e)),
+    };
+
+    let filename = format!("statement-{}-{}", account_id, statement.period);
+
+    match query.format.as_deref().unwrap_or("pdf") {
+        "json" => HttpResponse::Ok().json(statement),
+        "csv" => HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.csv\"", filename)))
+            .body(render_statement_csv(&statement)),
+        "pdf" => match render_statement_pdf(&statement) {
+            Ok(pdf) => HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.pdf\"", filename)))
+                .body(pdf),
+            Err(e) => HttpResponse::InternalServerError().body(format!("Render Failed: {}", e)),
+        },
+        other => HttpResponse::BadRequest().body(format!("Unsupported format: {}", other)),
+    }
+}
@@ -1,14 +1,102 @@
 use actix_web::web;
-use crate::api::handlers::{tenant, unit}; // Add 'unit' here
+use crate::api::handlers::{banking, billing, contracts, credit_notes, gusto, jobs, onboarding, pricing, reports, reserves, subscription, unit, wallet}; // Add 'unit' here
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/v1")
             // Tenant Endpoints
-            .service(tenant::create_tenant)
+            .service(onboarding::full_onboarding)
+            .service(onboarding::resume_onboarding)
+            .service(onboarding::onboarding_status)
+            .service(subscription::change_tier)
+            .service(subscription::set_settlement)
+            .service(subscription::offboard_tenant)
+
+            // Gusto Endpoints
+            .service(gusto::gusto_webhook)
+            .service(gusto::sync_employees)
+            .service(gusto::list_employees)
+            .service(gusto::set_payroll_settings)
+            .service(gusto::list_payrolls)
+            .service(gusto::mirror_payroll)
+            .service(gusto::get_benefit_catalog)
+            .service(gusto::refresh_benefit_catalog)
+            .service(gusto::save_benefit_product)
+            .service(gusto::list_tenant_benefits)
+
+            // Contract Endpoints
+            .service(contracts::create_contract)
+            .service(contracts::list_contracts)
+            .service(contracts::set_auto_renew)
             
             // Unit (Asset) Endpoints
             .service(unit::define_unit)
             .service(unit::mint_unit)
+
+            // Banking (Unit) Endpoints
+            .service(banking::unit_webhook)
+            .service(banking::link_fiat_account)
+            .service(banking::unlink_fiat_account)
+            .service(banking::issue_cash_barcode)
+            .service(banking::list_cash_barcodes)
+            .service(banking::set_cash_limits)
+            .service(banking::list_cash_holds)
+            .service(banking::release_cash_hold)
+            .service(banking::return_cash_hold)
+            .service(banking::add_counterparty)
+            .service(banking::list_counterparties)
+            .service(banking::verify_counterparty)
+            .service(banking::originate_ach)
+            .service(banking::list_ach_payments)
+
+            // Wallet Cards
+            .service(wallet::issue_card)
+            .service(wallet::list_cards)
+            .service(wallet::freeze_card)
+            .service(wallet::unfreeze_card)
+            .service(wallet::close_card)
+            .service(wallet::set_card_limits)
+            .service(wallet::card_transactions)
+
+            // Wallet Statements
+            .service(wallet::list_statements)
+            .service(wallet::get_statement)
+
+            // Billing Endpoints
+            .service(billing::preview_all_invoices)
+            .service(billing::preview_invoice)
+            .service(billing::list_invoices)
+            .service(billing::download_invoice)
+            .service(billing::get_usage)
+            .service(billing::pay_invoice)
+
+            // Credit Notes & Adjustments
+            .service(credit_notes::issue_credit_note)
+            .service(credit_notes::retry_refund)
+            .service(credit_notes::list_credit_notes)
+            .service(credit_notes::get_credit_balance)
+            .service(credit_notes::create_adjustment)
+            .service(credit_notes::get_audit_log)
+
+            // Pricing Endpoints
+            .service(pricing::get_price_book)
+            .service(pricing::publish_price_book)
+            .service(pricing::create_price_override)
+            .service(pricing::create_discount)
+
+            // Reporting Endpoints
+            .service(reports::revenue_report)
+            .service(reports::tenant_margins)
+            .service(reports::revenue_trend)
+
+            // Proof of Reserves
+            .service(reserves::latest_reserves)
+            .service(reserves::get_reserve_report)
+            .service(reserves::reserve_proof)
+
+            // Background Job Endpoints
+            .service(jobs::list_jobs)
+            .service(jobs::job_runs)
+            .service(jobs::trigger_job)
     );
 }
\ No newline at end of file
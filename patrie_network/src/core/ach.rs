@@ -0,0 +1,778 @@
+use crate::core::fiat_banking::{AchDirection, FiatAsset, NewCounterparty, Payment, UnitClient, UnitError};
+use crate::ledger::client::IrohaClient;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- ACH ORIGINATION ---
+// Users move money between their Unit account and their own bank accounts
+// (counterparties). A counterparty can be used once the user confirms the two
+// micro-deposits we sent it. On-chain `usd` follows every step:
+//   credit (push out): burned BEFORE we ask Unit to send, so it cannot be spent
+//                      twice; minted back if Unit rejects/cancels it, and by the
+//                      webhook if the bank returns it.
+//   debit (pull in):   minted by the webhook when Unit credits the account,
+//                      burned by the webhook if the pull is returned.
+// See core/fiat_events.rs for the webhook side.
+
+/// Wrong micro-deposit amounts allowed before the counterparty is failed
+const MAX_VERIFICATION_ATTEMPTS: i32 = 3;
+
+/// Sent payments are polled this long for returns the webhook may have missed
+const RETURN_WATCH_DAYS: i32 = 5;
+
+#[derive(Debug, Clone)]
+pub struct AchConfig {
+    pub usd_asset_id: String,
+    pub verification_account_id: String, // Platform Unit account that sends the micro-deposits
+}
+
+impl AchConfig {
+    /// Reads UNIT_VERIFICATION_ACCOUNT_ID
+    pub fn from_env(asset: &FiatAsset) -> Self {
+        Self {
+            usd_asset_id: asset.usd_asset_id.clone(),
+            verification_account_id: std::env::var("UNIT_VERIFICATION_ACCOUNT_ID").unwrap_or_default(),
+        }
+    }
+}
+
+/// Where an ACH payment is, following Unit's payment status
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AchStatus {
+    Submitting, // Stored, not yet accepted by Unit
+    Pending,
+    Clearing,
+    Sent,       // Settled as far as we are concerned (returns can still follow)
+    Returned,
+    Rejected,
+    Canceled,
+}
+
+impl AchStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AchStatus::Submitting => "submitting",
+            AchStatus::Pending => "pending",
+            AchStatus::Clearing => "clearing",
+            AchStatus::Sent => "sent",
+            AchStatus::Returned => "returned",
+            AchStatus::Rejected => "rejected",
+            AchStatus::Canceled => "canceled",
+        }
+    }
+
+    /// Unit's payment status ("Pending", "PendingReview", "Clearing", "Sent", ...)
+    pub fn from_unit(status: &str) -> Result<Self, Box<dyn Error>> {
+        match status {
+            "Pending" | "PendingReview" => Ok(AchStatus::Pending),
+            "Clearing" => Ok(AchStatus::Clearing),
+            "Sent" => Ok(AchStatus::Sent),
+            "Returned" => Ok(AchStatus::Returned),
+            "Rejected" => Ok(AchStatus::Rejected),
+            "Canceled" => Ok(AchStatus::Canceled),
+            other => Err(format!("Unknown Unit payment status '{}'", other).into()),
+        }
+    }
+}
+
+fn direction_str(direction: AchDirection) -> &'static str {
+    match direction {
+        AchDirection::Credit => "credit",
+        AchDirection::Debit => "debit",
+    }
+}
+
+/// An external bank account. The micro-deposit amounts never leave the database.
+#[derive(Debug, Serialize)]
+pub struct AchCounterparty {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub unit_customer_id: String,
+    pub unit_counterparty_id: String,
+    pub name: String,
+    pub routing_number: String,
+    pub account_last4: String,
+    pub account_type: String,
+    pub status: String, // pending_verification | verified | failed
+    pub verification_attempts: i32,
+    pub created_at: DateTime<Utc>,
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AchPayment {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub unit_customer_id: String,
+    pub unit_account_id: String,
+    pub counterparty_id: Uuid,
+    pub direction: String, // credit | debit
+    pub amount_cents: i64,
+    pub description: String,
+    pub unit_payment_id: Option<String>,
+    pub status: String,
+    pub reason: Option<String>,
+    pub burn_tx_hash: Option<String>,
+    pub refund_tx_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub settled_at: Option<DateTime<Utc>>,
+    pub returned_at: Option<DateTime<Utc>>,
+}
+
+/// What a user asks for when moving money to/from their bank
+#[derive(Debug, Deserialize)]
+pub struct NewAchPayment {
+    pub unit_account_id: String,
+    pub counterparty_id: Uuid,
+    pub direction: AchDirection, // "Credit" sends to the bank, "Debit" pulls from it
+    pub amount_cents: i64,
+    pub description: Option<String>, // Shows on the bank statement, max 10 characters
+}
+
+pub struct AchService {
+    db: PgPool,
+    unit: Arc<UnitClient>,
+    iroha: Arc<IrohaClient>,
+    config: AchConfig,
+}
+
+impl AchService {
+    pub fn new(db: PgPool, unit: Arc<UnitClient>, iroha: Arc<IrohaClient>, config: AchConfig) -> Self {
+        Self { db, unit, iroha, config }
+    }
+
+    /// 1. ADD A COUNTERPARTY
+    /// Registers the bank account with Unit and sends it two micro-deposits
+    /// (1-99 cents each) from the platform's verification account.
+    pub async fn add_counterparty(
+        &self,
+        tenant_id: Uuid,
+        unit_customer_id: &str,
+        counterparty: &NewCounterparty,
+    ) -> Result<AchCounterparty, Box<dyn Error>> {
+        if counterparty.routing_number.len() != 9 || !counterparty.routing_number.chars().all(|c| c.is_ascii_digit()) {
+            return Err("Routing number must be 9 digits".into());
+        }
+        let digits = counterparty.account_number.len();
+        if !(4..=17).contains(&digits) || !counterparty.account_number.chars().all(|c| c.is_ascii_digit()) {
+            return Err("Account number must be 4 to 17 digits".into());
+        }
+        if self.config.verification_account_id.is_empty() {
+            return Err("UNIT_VERIFICATION_ACCOUNT_ID is not configured".into());
+        }
+
+        // Unknown customers fail here rather than at Unit's counterparty endpoint
+        self.unit.get_customer(unit_customer_id).await?;
+
+        let id = Uuid::new_v4();
+        let created = self
+            .unit
+            .create_counterparty(unit_customer_id, counterparty, &format!("counterparty-{}", id))
+            .await?;
+
+        let (first, second) = micro_deposit_amounts();
+        for (n, amount) in [(1, first), (2, second)] {
+            self.unit
+                .create_ach_payment(
+                    &self.config.verification_account_id,
+                    &created.id,
+                    amount as u64,
+                    AchDirection::Credit,
+                    "ACCTVERIFY",
+                    &format!("microdeposit-{}-{}", id, n),
+                )
+                .await?;
+        }
+
+        let last4: String = counterparty.account_number[digits - 4..].to_string();
+        let row = sqlx::query_as!(
+            AchCounterparty,
+            r#"
+            INSERT INTO ach_counterparties
+            (id, tenant_id, unit_customer_id, unit_counterparty_id, name, routing_number, account_last4,
+             account_type, micro_deposit_1_cents, micro_deposit_2_cents)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id, tenant_id, unit_customer_id, unit_counterparty_id, name, routing_number, account_last4,
+                      account_type, status, verification_attempts, created_at, verified_at
+            "#,
+            id,
+            tenant_id,
+            unit_customer_id,
+            created.id,
+            counterparty.name,
+            counterparty.routing_number,
+            last4,
+            counterparty.account_type,
+            first,
+            second
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        println!("🏦 Counterparty ****{} added for {}, micro-deposits sent.", row.account_last4, unit_customer_id);
+        Ok(row)
+    }
+
+    /// 2. VERIFY: the user reports the two amounts they saw (in any order).
+    /// Each try counts, even concurrent ones; after 3 wrong tries the
+    /// counterparty is failed and has to be added again.
+    pub async fn verify_counterparty(
+        &self,
+        tenant_id: Uuid,
+        unit_customer_id: &str,
+        counterparty_id: Uuid,
+        amounts_cents: [i64; 2],
+    ) -> Result<AchCounterparty, Box<dyn Error>> {
+        let attempt = sqlx::query!(
+            r#"
+            UPDATE ach_counterparties
+            SET verification_attempts = verification_attempts + 1
+            WHERE id = $1 AND tenant_id = $2 AND unit_customer_id = $3
+              AND status = 'pending_verification' AND verification_attempts < $4
+            RETURNING micro_deposit_1_cents, micro_deposit_2_cents, verification_attempts
+            "#,
+            counterparty_id,
+            tenant_id,
+            unit_customer_id,
+            MAX_VERIFICATION_ATTEMPTS
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or("Counterparty not found or not awaiting verification")?;
+
+        let mut expected = [attempt.micro_deposit_1_cents, attempt.micro_deposit_2_cents];
+        let mut given = amounts_cents;
+        expected.sort_unstable();
+        given.sort_unstable();
+
+        if expected == given {
+            sqlx::query!(
+                "UPDATE ach_counterparties SET status = 'verified', verified_at = NOW() WHERE id = $1",
+                counterparty_id
+            )
+            .execute(&self.db)
+            .await?;
+            println!("✅ Counterparty {} verified.", counterparty_id);
+            return self.counterparty(tenant_id, unit_customer_id, counterparty_id).await;
+        }
+
+        let remaining = MAX_VERIFICATION_ATTEMPTS - attempt.verification_attempts;
+        if remaining <= 0 {
+            sqlx::query!("UPDATE ach_counterparties SET status = 'failed' WHERE id = $1", counterparty_id)
+                .execute(&self.db)
+                .await?;
+            println!("⚠️ Counterparty {} failed verification.", counterparty_id);
+            return Err("Amounts do not match. Verification failed, add the account again".into());
+        }
+        Err(format!("Amounts do not match ({} attempts left)", remaining).into())
+    }
+
+    pub async fn counterparties(&self, tenant_id: Uuid, unit_customer_id: &str) -> Result<Vec<AchCounterparty>, Box<dyn Error>> {
+        let rows = sqlx::query_as!(
+            AchCounterparty,
+            r#"
+            SELECT id, tenant_id, unit_customer_id, unit_counterparty_id, name, routing_number, account_last4,
+                   account_type, status, verification_attempts, created_at, verified_at
+            FROM ach_counterparties
+            WHERE tenant_id = $1 AND unit_customer_id = $2
+            ORDER BY created_at DESC
+            "#,
+            tenant_id,
+            unit_customer_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn counterparty(&self, tenant_id: Uuid, unit_customer_id: &str, id: Uuid) -> Result<AchCounterparty, Box<dyn Error>> {
+        let row = sqlx::query_as!(
+            AchCounterparty,
+            r#"
+            SELECT id, tenant_id, unit_customer_id, unit_counterparty_id, name, routing_number, account_last4,
+                   account_type, status, verification_attempts, created_at, verified_at
+            FROM ach_counterparties
+            WHERE id = $1 AND tenant_id = $2 AND unit_customer_id = $3
+            "#,
+            id,
+            tenant_id,
+            unit_customer_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or("Counterparty not found")?;
+        Ok(row)
+    }
+
+    /// 3. ORIGINATE an ACH payment
+    /// 1. The account must be a user account of this tenant, owned by the customer
+    /// 2. The counterparty must be the customer's and verified
+    /// 3. Outgoing: burn the USD on-chain first (with a receipt), then ask Unit
+    pub async fn originate(
+        &self,
+        tenant_id: Uuid,
+        unit_customer_id: &str,
+        req: &NewAchPayment,
+    ) -> Result<AchPayment, Box<dyn Error>> {
+        self.originate_from(Uuid::new_v4(), tenant_id, unit_customer_id, req, None).await
+    }
+
+    /// RETURN HELD CASH (core/cash_deposits.rs): an ACH credit paid out of the
+    /// cash hold account on-chain instead of the user's wallet; a refund goes
+    /// back there too. `payment_id` is assigned by the caller before the call,
+    /// so a retry finds the payment instead of sending a second one.
+    pub async fn return_held_cash(
+        &self,
+        payment_id: Uuid,
+        tenant_id: Uuid,
+        unit_customer_id: &str,
+        req: &NewAchPayment,
+        hold_account: &str,
+    ) -> Result<AchPayment, Box<dyn Error>> {
+        if req.direction != AchDirection::Credit {
+            return Err("Held cash can only be sent out".into());
+        }
+        self.originate_from(payment_id, tenant_id, unit_customer_id, req, Some(hold_account)).await
+    }
+
+    /// Burns from `burn_from` (default: the account's on-chain wallet)
+    async fn originate_from(
+        &self,
+        payment_id: Uuid,
+        tenant_id: Uuid,
+        unit_customer_id: &str,
+        req: &NewAchPayment,
+        burn_from: Option<&str>,
+    ) -> Result<AchPayment, Box<dyn Error>> {
+        if let Some(existing) = self.payment(payment_id).await? {
+            return Ok(existing); // Stuck ones are resubmitted by the ach_payments job
+        }
+        if req.amount_cents <= 0 {
+            return Err("Amount must be positive".into());
+        }
+
+        let onchain_account = sqlx::query!(
+            r#"
+            SELECT onchain_account_id FROM fiat_account_links
+            WHERE unit_account_id = $1 AND tenant_id = $2 AND owner_type = 'user'
+            "#,
+            req.unit_account_id,
+            tenant_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or("Account is not linked to an on-chain user account for this tenant")?
+        .onchain_account_id;
+
+        let counterparty = self.counterparty(tenant_id, unit_customer_id, req.counterparty_id).await?;
+        if counterparty.status != "verified" {
+            return Err("Counterparty has not been verified".into());
+        }
+
+        let account = self.unit.get_account(&req.unit_account_id).await?;
+        if account.related_id("customer").as_deref() != Some(unit_customer_id) {
+            return Err("Account does not belong to this customer".into());
+        }
+
+        let description = req.description.clone().unwrap_or_else(|| "TRANSFER".to_string());
+        let payment = sqlx::query_as!(
+            AchPayment,
+            r#"
+            INSERT INTO ach_payments
+            (id, tenant_id, unit_customer_id, unit_account_id, counterparty_id, direction, amount_cents, description,
+             burn_account_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id, tenant_id, unit_customer_id, unit_account_id, counterparty_id, direction, amount_cents,
+                      description, unit_payment_id, status, reason, burn_tx_hash, refund_tx_hash,
+                      created_at, updated_at, settled_at, returned_at
+            "#,
+            payment_id,
+            tenant_id,
+            unit_customer_id,
+            req.unit_account_id,
+            req.counterparty_id,
+            direction_str(req.direction),
+            req.amount_cents,
+            description.chars().take(10).collect::<String>(),
+            burn_from
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        if req.direction == AchDirection::Credit {
+            let receipt_key = format!("ach_out_{}", payment.id);
+            let burn_account = burn_from.unwrap_or(&onchain_account);
+            match self
+                .iroha
+                .burn_with_receipt(burn_account, &self.config.usd_asset_id, req.amount_cents, &receipt_key)
+                .await
+            {
+                Ok(tx_hash) => {
+                    sqlx::query!("UPDATE ach_payments SET burn_tx_hash = $2 WHERE id = $1", payment.id, tx_hash)
+                        .execute(&self.db)
+                        .await?;
+                }
+                Err(e) => {
+                    self.reject(payment.id, None, &format!("On-chain burn failed: {}", e)).await?;
+                    return Err(format!("Not enough on-chain USD to send: {}", e).into());
+                }
+            }
+        }
+
+        self.submit(payment.id).await
+    }
+
+    /// Sends a stored payment to Unit. The idempotency key is our payment id, so
+    /// resubmitting after a timeout can never send the money twice.
+    async fn submit(&self, payment_id: Uuid) -> Result<AchPayment, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT p.unit_account_id, p.direction, p.amount_cents, p.description, c.unit_counterparty_id
+            FROM ach_payments p
+            JOIN ach_counterparties c ON c.id = p.counterparty_id
+            WHERE p.id = $1
+            "#,
+            payment_id
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        let direction = if row.direction == "credit" { AchDirection::Credit } else { AchDirection::Debit };
+        let result = self
+            .unit
+            .create_ach_payment(
+                &row.unit_account_id,
+                &row.unit_counterparty_id,
+                row.amount_cents as u64,
+                direction,
+                &row.description,
+                &format!("ach-{}", payment_id),
+            )
+            .await;
+
+        match result {
+            Ok(unit_payment) => {
+                sqlx::query!(
+                    "UPDATE ach_payments SET unit_payment_id = $2, status = 'pending', updated_at = NOW() WHERE id = $1",
+                    payment_id,
+                    unit_payment.id
+                )
+                .execute(&self.db)
+                .await?;
+                println!(
+                    "🏦 ACH {} of ${:.2} submitted (Unit payment {}).",
+                    row.direction, row.amount_cents as f64 / 100.0, unit_payment.id
+                );
+                record_payment_status(&self.db, &self.iroha, &self.config.usd_asset_id, &unit_payment)
+                    .await?
+                    .ok_or_else(|| "ACH payment disappeared".into())
+            }
+            Err(UnitError::Rejected { payment_id: unit_payment_id, reason }) => {
+                self.reject(payment_id, Some(&unit_payment_id), &reason).await
+            }
+            // Failed validation: Unit never created the payment
+            Err(e) if e.is_definitive() => self.reject(payment_id, None, &e.to_string()).await,
+            // 5xx, 429, 409, timeout etc: the ACH may have gone out, so the burned USD
+            // stays burned. The payment stays 'submitting' and the ach_payments job
+            // resubmits it with the same key; the webhook or that sync settles it.
+            Err(e) => Err(format!("ACH payment {} outcome unknown: {}", payment_id, e).into()),
+        }
+    }
+
+    /// Unit never sent it: record why and give outgoing USD back
+    async fn reject(&self, payment_id: Uuid, unit_payment_id: Option<&str>, reason: &str) -> Result<AchPayment, Box<dyn Error>> {
+        let payment = sqlx::query_as!(
+            AchPayment,
+            r#"
+            UPDATE ach_payments
+            SET status = 'rejected', reason = $3, unit_payment_id = COALESCE($2, unit_payment_id), updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, tenant_id, unit_customer_id, unit_account_id, counterparty_id, direction, amount_cents,
+                      description, unit_payment_id, status, reason, burn_tx_hash, refund_tx_hash,
+                      created_at, updated_at, settled_at, returned_at
+            "#,
+            payment_id,
+            unit_payment_id,
+            reason
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        println!("⚠️ ACH payment {} rejected: {}", payment_id, reason);
+        refund_outgoing(&self.db, &self.iroha, &self.config.usd_asset_id, payment).await
+    }
+
+    async fn payment(&self, payment_id: Uuid) -> Result<Option<AchPayment>, Box<dyn Error>> {
+        let payment = sqlx::query_as!(
+            AchPayment,
+            r#"
+            SELECT id, tenant_id, unit_customer_id, unit_account_id, counterparty_id, direction, amount_cents,
+                   description, unit_payment_id, status, reason, burn_tx_hash, refund_tx_hash,
+                   created_at, updated_at, settled_at, returned_at
+            FROM ach_payments WHERE id = $1
+            "#,
+            payment_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(payment)
+    }
+
+    pub async fn payments(&self, tenant_id: Uuid, unit_customer_id: &str) -> Result<Vec<AchPayment>, Box<dyn Error>> {
+        let rows = sqlx::query_as!(
+            AchPayment,
+            r#"
+            SELECT id, tenant_id, unit_customer_id, unit_account_id, counterparty_id, direction, amount_cents,
+                   description, unit_payment_id, status, reason, burn_tx_hash, refund_tx_hash,
+                   created_at, updated_at, settled_at, returned_at
+            FROM ach_payments
+            WHERE tenant_id = $1 AND unit_customer_id = $2
+            ORDER BY created_at DESC
+            "#,
+            tenant_id,
+            unit_customer_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(rows)
+    }
+
+    /// 4. SYNC (ach_payments job): webhooks are the fast path, this is the safety net.
+    /// 1. Payments stuck in 'submitting' are resubmitted (same idempotency key)
+    /// 2. Open payments, and recently sent ones, are refreshed from Unit
+    pub async fn sync_open(&self) -> Result<(), Box<dyn Error>> {
+        let stuck = sqlx::query!(
+            r#"
+            SELECT p.id, p.direction, p.burn_tx_hash,
+                   COALESCE(p.burn_account_id, l.onchain_account_id) AS "onchain_account_id!"
+            FROM ach_payments p
+            JOIN fiat_account_links l ON l.unit_account_id = p.unit_account_id
+            WHERE p.status = 'submitting' AND p.created_at < NOW() - INTERVAL '2 minutes'
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut failed = 0;
+        for payment in &stuck {
+            // Crashed between storing and burning: only send if the burn landed
+            if payment.direction == "credit" && payment.burn_tx_hash.is_none() {
+                let receipt_key = format!("ach_out_{}", payment.id);
+                if self.iroha.has_receipt(&payment.onchain_account_id, &receipt_key).await.map_err(|e| e.to_string())? {
+                    sqlx::query!(
+                        "UPDATE ach_payments SET burn_tx_hash = $2 WHERE id = $1",
+                        payment.id,
+                        format!("receipt:{}", receipt_key)
+                    )
+                    .execute(&self.db)
+                    .await?;
+                } else {
+                    self.reject(payment.id, None, "Never submitted").await?;
+                    continue;
+                }
+            }
+
+            if let Err(e) = self.submit(payment.id).await {
+                eprintln!("❌ ACH payment {} resubmission failed: {}", payment.id, e);
+                failed += 1;
+            }
+        }
+
+        let open = sqlx::query!(
+            r#"
+            SELECT unit_payment_id AS "unit_payment_id!" FROM ach_payments
+            WHERE unit_payment_id IS NOT NULL
+              AND (status IN ('pending', 'clearing')
+                   OR (status = 'sent' AND settled_at > NOW() - make_interval(days => $1)))
+            "#,
+            RETURN_WATCH_DAYS
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        for row in &open {
+            let result = match self.unit.get_payment(&row.unit_payment_id).await {
+                Ok(payment) => record_payment_status(&self.db, &self.iroha, &self.config.usd_asset_id, &payment)
+                    .await
+                    .map(|_| ()),
+                Err(e) => Err(e.into()),
+            };
+            if let Err(e) = result {
+                eprintln!("❌ ACH payment {} status refresh failed: {}", row.unit_payment_id, e);
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("{} of {} ACH payments could not be synced", failed, stuck.len() + open.len()).into());
+        }
+        Ok(())
+    }
+}
+
+/// Two different amounts between 1 and 99 cents
+fn micro_deposit_amounts() -> (i64, i64) {
+    let bytes = *Uuid::new_v4().as_bytes(); // v4 = 122 random bits
+    let first = 1 + (bytes[0] as i64 % 99);
+    let mut second = 1 + (bytes[1] as i64 % 99);
+    if second == first {
+        second = second % 99 + 1;
+    }
+    (first, second)
+}
+
+/// STATUS UPDATE for a Unit payment (from a `payment.*` webhook or the job).
+/// Returns None if the payment is not an ACH payment a user originated.
+/// Statuses only move forward: nothing leaves returned/rejected/canceled.
+pub async fn record_payment_status(
+    db: &PgPool,
+    iroha: &IrohaClient,
+    usd_asset_id: &str,
+    payment: &Payment,
+) -> Result<Option<AchPayment>, Box<dyn Error>> {
+    let status = AchStatus::from_unit(&payment.attributes.status)?;
+
+    let row = sqlx::query_as!(
+        AchPayment,
+        r#"
+        UPDATE ach_payments
+        SET status = $2,
+            reason = COALESCE($3, reason),
+            updated_at = NOW(),
+            settled_at = CASE WHEN $2 = 'sent' THEN COALESCE(settled_at, NOW()) ELSE settled_at END,
+            returned_at = CASE WHEN $2 = 'returned' THEN COALESCE(returned_at, NOW()) ELSE returned_at END
+        WHERE unit_payment_id = $1 AND status NOT IN ('returned', 'rejected', 'canceled')
+        RETURNING id, tenant_id, unit_customer_id, unit_account_id, counterparty_id, direction, amount_cents,
+                  description, unit_payment_id, status, reason, burn_tx_hash, refund_tx_hash,
+                  created_at, updated_at, settled_at, returned_at
+        "#,
+        payment.id,
+        status.as_str(),
+        payment.attributes.reason
+    )
+    .fetch_optional(db)
+    .await?;
+
+    let row = match row {
+        Some(row) => row,
+        // Already final, or not ours (e.g. micro-deposits, tenant payouts)
+        None => {
+            return Ok(sqlx::query_as!(
+                AchPayment,
+                r#"
+                SELECT id, tenant_id, unit_customer_id, unit_account_id, counterparty_id, direction, amount_cents,
+                       description, unit_payment_id, status, reason, burn_tx_hash, refund_tx_hash,
+                       created_at, updated_at, settled_at, returned_at
+                FROM ach_payments WHERE unit_payment_id = $1
+                "#,
+                payment.id
+            )
+            .fetch_optional(db)
+            .await?)
+        }
+    };
+
+    match status {
+        AchStatus::Rejected | AchStatus::Canceled => refund_outgoing(db, iroha, usd_asset_id, row).await.map(Some),
+        _ => Ok(Some(row)),
+    }
+}
+
+/// Mints back the USD burned for an outgoing payment Unit never sent.
+/// If Unit did debit the account the money is gone; a return, if any, comes
+/// back through the webhook like any other returned ACH.
+async fn refund_outgoing(
+    db: &PgPool,
+    iroha: &IrohaClient,
+    usd_asset_id: &str,
+    payment: AchPayment,
+) -> Result<AchPayment, Box<dyn Error>> {
+    if payment.direction != "credit" || payment.burn_tx_hash.is_none() || payment.refund_tx_hash.is_some() {
+        return Ok(payment);
+    }
+
+    let debited = sqlx::query!(
+        "SELECT id FROM fiat_ledger_entries WHERE unit_payment_id = $1 AND action = 'burn'",
+        payment.unit_payment_id
+    )
+    .fetch_optional(db)
+    .await?;
+    if debited.is_some() {
+        return Ok(payment);
+    }
+
+    // Back where it was burned from: the user's wallet, or the cash hold account
+    let account = sqlx::query!(
+        r#"
+        SELECT COALESCE(p.burn_account_id, l.onchain_account_id) AS "account!"
+        FROM ach_payments p
+        JOIN fiat_account_links l ON l.unit_account_id = p.unit_account_id
+        WHERE p.id = $1
+        "#,
+        payment.id
+    )
+    .fetch_one(db)
+    .await?
+    .account;
+
+    let receipt_key = format!("ach_refund_{}", payment.id);
+    let tx_hash = if iroha.has_receipt(&account, &receipt_key).await.map_err(|e| e.to_string())? {
+        format!("receipt:{}", receipt_key)
+    } else {
+        iroha
+            .mint_with_receipt(&account, usd_asset_id, payment.amount_cents, &receipt_key)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+
+    sqlx::query!("UPDATE ach_payments SET refund_tx_hash = $2 WHERE id = $1", payment.id, tx_hash)
+        .execute(db)
+        .await?;
+
+    println!("↩️ Refunded ${:.2} to {} (ACH {} not sent).", payment.amount_cents as f64 / 100.0, account, payment.id);
+    Ok(AchPayment { refund_tx_hash: Some(tx_hash), ..payment })
+}
+
+/// For the webhook: the hash of the burn we already did for an outgoing ACH,
+/// so the Unit debit it belongs to is not burned a second time.
+/// Errors while a payment on the account is still being submitted (Unit can
+/// tell us about the debit before we know its payment id); the event is retried.
+pub async fn prefunded_burn(
+    db: &PgPool,
+    unit_account_id: &str,
+    unit_payment_id: &Option<String>,
+) -> Result<Option<String>, Box<dyn Error>> {
+    if let Some(unit_payment_id) = unit_payment_id {
+        let row = sqlx::query!(
+            "SELECT burn_tx_hash FROM ach_payments WHERE unit_payment_id = $1 AND direction = 'credit'",
+            unit_payment_id
+        )
+        .fetch_optional(db)
+        .await?;
+        if let Some(row) = row {
+            return Ok(row.burn_tx_hash);
+        }
+    }
+
+    let submitting = sqlx::query!(
+        r#"
+        SELECT id FROM ach_payments
+        WHERE unit_account_id = $1 AND direction = 'credit' AND status = 'submitting'
+        LIMIT 1
+        "#,
+        unit_account_id
+    )
+    .fetch_optional(db)
+    .await?;
+    if let Some(row) = submitting {
+        return Err(format!("ACH payment {} on {} is still being submitted", row.id, unit_account_id).into());
+    }
+
+    Ok(None)
+}
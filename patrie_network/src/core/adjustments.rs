@@ -0,0 +1,87 @@
+use sqlx::{PgPool, Postgres, Transaction};
+use std::error::Error;
+use uuid::Uuid;
+
+/// A one-off charge (+) or credit (-) that rides on the tenant's next invoice
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BillingAdjustment {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub description: String,
+    pub amount_cents: i64,
+}
+
+impl BillingAdjustment {
+    pub async fn create(
+        db: &PgPool,
+        tenant_id: Uuid,
+        description: &str,
+        amount_cents: i64,
+    ) -> Result<BillingAdjustment, Box<dyn Error>> {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO billing_adjustments (id, tenant_id, description, amount_cents)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            id,
+            tenant_id,
+            description,
+            amount_cents
+        )
+        .execute(db)
+        .await?;
+
+        Ok(BillingAdjustment { id, tenant_id, description: description.to_string(), amount_cents })
+    }
+
+    /// Adjustments not yet billed on any invoice
+    pub async fn pending_for(db: &PgPool, tenant_id: Uuid) -> Result<Vec<BillingAdjustment>, Box<dyn Error>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, tenant_id, description, amount_cents
+            FROM billing_adjustments
+            WHERE tenant_id = $1 AND applied_invoice_id IS NULL
+            ORDER BY created_at
+            "#,
+            tenant_id
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| BillingAdjustment {
+                id: r.id,
+                tenant_id: r.tenant_id,
+                description: r.description,
+                amount_cents: r.amount_cents,
+            })
+            .collect())
+    }
+
+    /// Marks adjustments as billed. Runs inside the invoice-finalizing transaction
+    /// so an adjustment can never land on two invoices.
+    pub async fn mark_applied(
+        tx: &mut Transaction<'_, Postgres>,
+        ids: &[Uuid],
+        invoice_id: Uuid,
+    ) -> Result<(), Box<dyn Error>> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE billing_adjustments
+            SET applied_invoice_id = $2
+            WHERE id = ANY($1) AND applied_invoice_id IS NULL
+            "#,
+            ids,
+            invoice_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        if result.rows_affected() != ids.len() as u64 {
+            return Err("Billing adjustment was already applied to another invoice".into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use serde::Serialize;
+use sqlx::{Executor, PgPool, Postgres};
+use std::error::Error;
+use uuid::Uuid;
+
+/// One entry of the billing audit trail
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub actor: String,
+    pub action: String,
+    pub tenant_id: Option<Uuid>,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub reason: Option<String>,
+    pub details: serde_json::Value,
+    pub created_at: String,
+}
+
+/// Writes an audit entry. Pass the open transaction when the audited change
+/// is part of one, so the entry is only kept if the change is.
+#[allow(clippy::too_many_arguments)]
+pub async fn record_audit<'c, E>(
+    executor: E,
+    actor: &str,
+    action: &str,
+    tenant_id: Option<Uuid>,
+    entity_type: &str,
+    entity_id: Uuid,
+    reason: Option<&str>,
+    details: serde_json::Value,
+) -> Result<(), Box<dyn Error>>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (id, actor, action, tenant_id, entity_type, entity_id, reason, details)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        Uuid::new_v4(),
+        actor,
+        action,
+        tenant_id,
+        entity_type,
+        entity_id,
+        reason,
+        details
+    )
+    .execute(executor)
+    .await?;
+
+    println!("📝 AUDIT: {} {} {} ({})", actor, action, entity_id, reason.unwrap_or("-"));
+    Ok(())
+}
+
+/// Most recent entries for a tenant
+pub async fn audit_log_for_tenant(db: &PgPool, tenant_id: Uuid) -> Result<Vec<AuditEntry>, Box<dyn Error>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, actor, action, tenant_id, entity_type, entity_id, reason, details, created_at
+        FROM audit_log
+        WHERE tenant_id = $1
+        ORDER BY created_at DESC
+        LIMIT 200
+        "#,
+        tenant_id
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| AuditEntry {
+            id: r.id,
+            actor: r.actor,
+            action: r.action,
+            tenant_id: r.tenant_id,
+            entity_type: r.entity_type,
+            entity_id: r.entity_id,
+            reason: r.reason,
+            details: r.details,
+            created_at: r.created_at.to_string(),
+        })
+        .collect())
+}
@@ -0,0 +1,520 @@
+use crate::core::gusto::{BenefitUpdate, CompanyBenefit, GustoBenefit, GustoClient};
+use crate::core::tiers::ServiceTier;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- BENEFIT CATALOG ---
+// Which Gusto benefit backs each of our products (and which tiers include it)
+// lives in `benefit_products`. Gusto's own list, with the `benefit_type` ids to
+// create them with, is cached in `gusto_benefit_catalog`. Tier changes,
+// offboarding and the wholesale cost sync all go through here, so offering
+// dental or commuter benefits is a new row, not a new method.
+
+/// Products the invoice bills a pass-through premium for (core/billing_engine.rs)
+pub const HEALTH: &str = "health";
+pub const RETIREMENT: &str = "401k";
+
+#[derive(Debug, Clone)]
+pub struct BenefitCatalogConfig {
+    pub max_age: Duration, // Older cache is fetched again before use
+}
+
+impl BenefitCatalogConfig {
+    /// Reads BENEFIT_CATALOG_MAX_AGE_HOURS (default 24)
+    pub fn from_env() -> Self {
+        let hours = std::env::var("BENEFIT_CATALOG_MAX_AGE_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(24);
+        Self { max_age: Duration::hours(hours) }
+    }
+}
+
+/// One of our benefit products (a row of `benefit_products`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenefitProduct {
+    pub product: String,            // e.g. "dental"
+    pub gusto_benefit_name: String, // e.g. "Dental Insurance"
+    pub description: String,        // What the company benefit is called in Gusto
+    #[serde(default)]
+    pub tiers: Vec<String>,         // e.g. ["enterprise"]
+    #[serde(default = "default_true")]
+    pub active: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl BenefitProduct {
+    pub fn included_in(&self, tier: ServiceTier) -> bool {
+        self.active && self.tiers.iter().any(|t| t == tier.as_str())
+    }
+}
+
+/// The Gusto benefit a product names (case and surrounding spaces don't matter)
+fn find_benefit(benefits: Vec<GustoBenefit>, name: &str) -> Option<GustoBenefit> {
+    benefits.into_iter().find(|b| b.name.eq_ignore_ascii_case(name.trim()))
+}
+
+/// The company benefit we created for a product before, if Gusto has one
+fn find_company_benefit<'a>(
+    benefits: &'a [CompanyBenefit],
+    benefit_type: &str,
+    description: &str,
+) -> Option<&'a CompanyBenefit> {
+    benefits
+        .iter()
+        .find(|b| b.benefit_type == benefit_type && b.description.trim() == description.trim())
+}
+
+/// What a tier change has to do: products the tier includes that are off,
+/// and active benefits the tier doesn't include (anymore)
+fn tier_changes<'a>(
+    products: &'a [BenefitProduct],
+    current: &'a [TenantBenefit],
+    tier: ServiceTier,
+) -> (Vec<&'a BenefitProduct>, Vec<&'a TenantBenefit>) {
+    let is_active = |product: &str| current.iter().any(|b| b.product == product && b.active);
+
+    let to_enable = products.iter().filter(|p| p.included_in(tier) && !is_active(&p.product)).collect();
+    let to_disable = current
+        .iter()
+        .filter(|b| b.active && !products.iter().any(|p| p.product == b.product && p.included_in(tier)))
+        .collect();
+    (to_enable, to_disable)
+}
+
+/// A product switched on for a tenant, and the Gusto company benefit behind it
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantBenefit {
+    pub product: String,
+    pub company_benefit_uuid: String,
+    pub benefit_type: String,
+    pub active: bool,
+    pub monthly_employer_cost: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub struct BenefitCatalog {
+    db: PgPool,
+    gusto: Arc<GustoClient>,
+    config: BenefitCatalogConfig,
+}
+
+impl BenefitCatalog {
+    pub fn new(db: PgPool, gusto: Arc<GustoClient>, config: BenefitCatalogConfig) -> Self {
+        Self { db, gusto, config }
+    }
+
+    // --- GUSTO'S LIST ---
+
+    /// REFRESH: replaces the cache with what Gusto supports today
+    pub async fn refresh(&self) -> Result<Vec<GustoBenefit>, Box<dyn Error>> {
+        let benefits = self.gusto.list_benefits().await?;
+        if benefits.is_empty() {
+            return Err("Gusto returned an empty benefit catalog".into());
+        }
+
+        let fetched_at = Utc::now();
+        let mut tx = self.db.begin().await?;
+        for b in &benefits {
+            sqlx::query!(
+                r#"
+                INSERT INTO gusto_benefit_catalog
+                    (benefit_type, name, description, pretax, posttax, healthcare, retirement, fetched_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (benefit_type) DO UPDATE
+                SET name = EXCLUDED.name, description = EXCLUDED.description,
+                    pretax = EXCLUDED.pretax, posttax = EXCLUDED.posttax,
+                    healthcare = EXCLUDED.healthcare, retirement = EXCLUDED.retirement,
+                    fetched_at = EXCLUDED.fetched_at
+                "#,
+                b.benefit_type,
+                b.name,
+                b.description,
+                b.pretax,
+                b.posttax,
+                b.healthcare,
+                b.retirement,
+                fetched_at
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        // Gone from Gusto = gone from the cache
+        sqlx::query!("DELETE FROM gusto_benefit_catalog WHERE fetched_at < $1", fetched_at)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        println!("📚 Gusto benefit catalog refreshed: {} benefit(s).", benefits.len());
+        Ok(benefits)
+    }
+
+    /// The cached list, fetched again once it is older than the configured max age.
+    /// If Gusto is unreachable a stale list beats none.
+    pub async fn benefits(&self) -> Result<Vec<GustoBenefit>, Box<dyn Error>> {
+        let cached = self.cached().await?;
+        let oldest = sqlx::query!("SELECT MIN(fetched_at) AS oldest FROM gusto_benefit_catalog")
+            .fetch_one(&self.db)
+            .await?
+            .oldest;
+
+        let stale = oldest.map(|at| Utc::now() - at > self.config.max_age).unwrap_or(true);
+        if !stale {
+            return Ok(cached);
+        }
+        match self.refresh().await {
+            Ok(fresh) => Ok(fresh),
+            Err(e) if !cached.is_empty() => {
+                println!("⚠️  Benefit catalog refresh failed, using the cached list: {}", e);
+                Ok(cached)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The Gusto benefit behind a product. An unknown name refreshes the cache
+    /// once, in case Gusto added it since.
+    pub async fn resolve(&self, product: &BenefitProduct) -> Result<GustoBenefit, Box<dyn Error>> {
+        if let Some(benefit) = find_benefit(self.benefits().await?, &product.gusto_benefit_name) {
+            return Ok(benefit);
+        }
+        find_benefit(self.refresh().await?, &product.gusto_benefit_name).ok_or_else(|| {
+            format!(
+                "Gusto has no benefit named '{}' (product {})",
+                product.gusto_benefit_name, product.product
+            )
+            .into()
+        })
+    }
+
+    async fn cached(&self) -> Result<Vec<GustoBenefit>, Box<dyn Error>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT benefit_type, name, description, pretax, posttax, healthcare, retirement
+            FROM gusto_benefit_catalog
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| GustoBenefit {
+                benefit_type: r.benefit_type,
+                name: r.name,
+                description: r.description,
+                pretax: r.pretax,
+                posttax: r.posttax,
+                healthcare: r.healthcare,
+                retirement: r.retirement,
+            })
+            .collect())
+    }
+
+    // --- OUR PRODUCTS ---
+
+    pub async fn products(&self) -> Result<Vec<BenefitProduct>, Box<dyn Error>> {
+        let products = sqlx::query_as!(
+            BenefitProduct,
+            "SELECT product, gusto_benefit_name, description, tiers, active FROM benefit_products ORDER BY product"
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(products)
+    }
+
+    /// ADD / CHANGE A PRODUCT (configuration, no redeploy needed)
+    /// Takes effect on the next tier change. The Gusto benefit must exist.
+    pub async fn save_product(&self, product: &BenefitProduct) -> Result<BenefitProduct, Box<dyn Error>> {
+        if product.product.trim().is_empty() {
+            return Err("A product needs a name".into());
+        }
+        for tier in &product.tiers {
+            ServiceTier::parse(tier)?;
+        }
+        let benefit = self.resolve(product).await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO benefit_products (product, gusto_benefit_name, description, tiers, active)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (product) DO UPDATE
+            SET gusto_benefit_name = EXCLUDED.gusto_benefit_name,
+                description = EXCLUDED.description,
+                tiers = EXCLUDED.tiers,
+                active = EXCLUDED.active,
+                updated_at = NOW()
+            "#,
+            product.product,
+            benefit.name,
+            product.description,
+            &product.tiers,
+            product.active
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(BenefitProduct { gusto_benefit_name: benefit.name, ..product.clone() })
+    }
+
+    pub async fn tenant_benefits(&self, tenant_id: Uuid) -> Result<Vec<TenantBenefit>, Box<dyn Error>> {
+        let benefits = sqlx::query_as!(
+            TenantBenefit,
+            r#"
+            SELECT product, company_benefit_uuid, benefit_type, active, monthly_employer_cost, updated_at
+            FROM tenant_benefits
+            WHERE tenant_id = $1
+            ORDER BY product
+            "#,
+            tenant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(benefits)
+    }
+
+    // --- PER TENANT ---
+
+    /// TIER CHANGE: every product the tier includes on, every other one off.
+    /// Only talks to Gusto when something changes. Returns the active products.
+    pub async fn apply_tier(&self, tenant_id: Uuid, tier: ServiceTier) -> Result<Vec<String>, Box<dyn Error>> {
+        let products = self.products().await?;
+        let current = self.tenant_benefits(tenant_id).await?;
+        let (to_enable, to_disable) = tier_changes(&products, &current, tier);
+
+        if !to_enable.is_empty() || !to_disable.is_empty() {
+            let company_uuid = self.gusto_company(tenant_id).await?;
+            for product in to_enable {
+                self.enable(tenant_id, &company_uuid, product, &current).await?;
+            }
+            for benefit in to_disable {
+                self.disable(tenant_id, &company_uuid, benefit).await?;
+            }
+        }
+
+        Ok(products
+            .into_iter()
+            .filter(|p| p.included_in(tier))
+            .map(|p| p.product)
+            .collect())
+    }
+
+    /// OFFBOARDING: every active company benefit off (before access is revoked)
+    pub async fn deactivate_all(&self, tenant_id: Uuid, company_uuid: &str) -> Result<(), Box<dyn Error>> {
+        for benefit in self.tenant_benefits(tenant_id).await? {
+            if benefit.active {
+                self.disable(tenant_id, company_uuid, &benefit).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// WHOLESALE: what each active benefit costs us this month, stored per product
+    pub async fn sync_costs(&self, tenant_id: Uuid) -> Result<Vec<TenantBenefit>, Box<dyn Error>> {
+        let benefits = self.tenant_benefits(tenant_id).await?;
+        if benefits.iter().any(|b| b.active) {
+            let company_uuid = self.gusto_company(tenant_id).await?;
+            for benefit in benefits.iter().filter(|b| b.active) {
+                let cost = self.gusto.monthly_employer_cost(&company_uuid, &benefit.company_benefit_uuid).await?;
+                sqlx::query!(
+                    r#"
+                    UPDATE tenant_benefits SET monthly_employer_cost = $3, updated_at = NOW()
+                    WHERE tenant_id = $1 AND product = $2
+                    "#,
+                    tenant_id,
+                    benefit.product,
+                    cost
+                )
+                .execute(&self.db)
+                .await?;
+            }
+        }
+        self.tenant_benefits(tenant_id).await
+    }
+
+    /// Creates the company benefit, or turns the one we made before back on.
+    /// A company benefit Gusto made for us last time, that we crashed before
+    /// recording, is found by its type and description and reused.
+    async fn enable(
+        &self,
+        tenant_id: Uuid,
+        company_uuid: &str,
+        product: &BenefitProduct,
+        current: &[TenantBenefit],
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(existing) = current.iter().find(|b| b.product == product.product) {
+            let update = BenefitUpdate { active: Some(true), description: Some(product.description.clone()) };
+            self.gusto.update_benefit(company_uuid, &existing.company_benefit_uuid, &update).await?;
+            sqlx::query!(
+                "UPDATE tenant_benefits SET active = TRUE, updated_at = NOW() WHERE tenant_id = $1 AND product = $2",
+                tenant_id,
+                product.product
+            )
+            .execute(&self.db)
+            .await?;
+        } else {
+            let benefit = self.resolve(product).await?;
+            let mut existing = self.gusto.list_company_benefits(company_uuid).await?;
+            existing.retain(|b| !current.iter().any(|c| c.company_benefit_uuid == b.uuid)); // Other products' own
+            let company_benefit_uuid = match find_company_benefit(&existing, &benefit.benefit_type, &product.description) {
+                Some(found) => {
+                    if !found.active {
+                        let update = BenefitUpdate { active: Some(true), ..Default::default() };
+                        self.gusto.update_benefit(company_uuid, &found.uuid, &update).await?;
+                    }
+                    found.uuid.clone()
+                }
+                None => self.gusto.add_benefit(company_uuid, &benefit.benefit_type, &product.description).await?,
+            };
+            sqlx::query!(
+                r#"
+                INSERT INTO tenant_benefits (tenant_id, product, company_benefit_uuid, benefit_type)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (tenant_id, product) DO UPDATE
+                SET company_benefit_uuid = EXCLUDED.company_benefit_uuid, active = TRUE, updated_at = NOW()
+                "#,
+                tenant_id,
+                product.product,
+                company_benefit_uuid,
+                benefit.benefit_type
+            )
+            .execute(&self.db)
+            .await?;
+        }
+
+        println!("➕ Benefit {} on for tenant {}.", product.product, tenant_id);
+        Ok(())
+    }
+
+    async fn disable(&self, tenant_id: Uuid, company_uuid: &str, benefit: &TenantBenefit) -> Result<(), Box<dyn Error>> {
+        self.gusto.deactivate_benefit(company_uuid, &benefit.company_benefit_uuid).await?;
+        sqlx::query!(
+            r#"
+            UPDATE tenant_benefits SET active = FALSE, monthly_employer_cost = 0, updated_at = NOW()
+            WHERE tenant_id = $1 AND product = $2
+            "#,
+            tenant_id,
+            benefit.product
+        )
+        .execute(&self.db)
+        .await?;
+
+        println!("➖ Benefit {} off for tenant {}.", benefit.product, tenant_id);
+        Ok(())
+    }
+
+    async fn gusto_company(&self, tenant_id: Uuid) -> Result<String, Box<dyn Error>> {
+        let rec = sqlx::query!("SELECT gusto_company_uuid FROM tenants WHERE id = $1", tenant_id)
+            .fetch_one(&self.db)
+            .await?;
+
+        rec.gusto_company_uuid.ok_or_else(|| "Tenant is not linked to a Gusto company".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(name: &str, tiers: &[&str], active: bool) -> BenefitProduct {
+        BenefitProduct {
+            product: name.to_string(),
+            gusto_benefit_name: format!("{} Insurance", name),
+            description: format!("Company {}", name),
+            tiers: tiers.iter().map(|t| t.to_string()).collect(),
+            active,
+        }
+    }
+
+    fn enrolled(name: &str, active: bool) -> TenantBenefit {
+        TenantBenefit {
+            product: name.to_string(),
+            company_benefit_uuid: format!("cb-{}", name),
+            benefit_type: "1".to_string(),
+            active,
+            monthly_employer_cost: 0.0,
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn gusto(benefit_type: &str, name: &str) -> GustoBenefit {
+        GustoBenefit {
+            benefit_type: benefit_type.to_string(),
+            name: name.to_string(),
+            description: None,
+            pretax: true,
+            posttax: false,
+            healthcare: false,
+            retirement: false,
+        }
+    }
+
+    #[test]
+    fn products_are_included_in_their_tiers_while_active() {
+        let dental = product("dental", &["professional", "enterprise"], true);
+        assert!(!dental.included_in(ServiceTier::Starter));
+        assert!(dental.included_in(ServiceTier::Professional));
+        assert!(dental.included_in(ServiceTier::Enterprise));
+
+        assert!(!product("dental", &["enterprise"], false).included_in(ServiceTier::Enterprise));
+        assert!(!product("hsa", &[], true).included_in(ServiceTier::Enterprise));
+    }
+
+    #[test]
+    fn tier_change_turns_on_what_is_included_and_off_what_is_not() {
+        let products = vec![
+            product("health", &["professional", "enterprise"], true),
+            product("401k", &["enterprise"], true),
+            product("dental", &["enterprise"], true),
+            product("vision", &["professional"], false), // Retired product
+        ];
+        let current = vec![enrolled("health", true), enrolled("401k", false), enrolled("vision", true)];
+
+        let (on, off) = tier_changes(&products, &current, ServiceTier::Enterprise);
+        let on: Vec<&str> = on.iter().map(|p| p.product.as_str()).collect();
+        let off: Vec<&str> = off.iter().map(|b| b.product.as_str()).collect();
+        assert_eq!(on, ["401k", "dental"]); // Health is on already
+        assert_eq!(off, ["vision"]);
+
+        let (on, off) = tier_changes(&products, &current, ServiceTier::Starter);
+        assert!(on.is_empty());
+        let off: Vec<&str> = off.iter().map(|b| b.product.as_str()).collect();
+        assert_eq!(off, ["health", "vision"]); // 401k is off already
+    }
+
+    #[test]
+    fn nothing_to_do_when_the_tier_already_matches() {
+        let products = vec![product("health", &["professional"], true)];
+        let current = vec![enrolled("health", true)];
+        let (on, off) = tier_changes(&products, &current, ServiceTier::Professional);
+        assert!(on.is_empty() && off.is_empty());
+    }
+
+    #[test]
+    fn product_names_match_gusto_benefits_loosely() {
+        let benefits = || vec![gusto("1", "Medical Insurance"), gusto("11", "Dental Insurance")];
+        assert_eq!(find_benefit(benefits(), "dental insurance").unwrap().benefit_type, "11");
+        assert_eq!(find_benefit(benefits(), "  MEDICAL INSURANCE ").unwrap().benefit_type, "1");
+        assert!(find_benefit(benefits(), "Vision Insurance").is_none());
+        assert!(find_benefit(benefits(), "Dental").is_none()); // No partial matches
+    }
+
+    #[test]
+    fn a_benefit_created_before_is_found_by_type_and_description() {
+        let company = vec![
+            CompanyBenefit { uuid: "a".into(), benefit_type: "1".into(), description: "Company Health".into(), active: true },
+            CompanyBenefit { uuid: "b".into(), benefit_type: "11".into(), description: "Company Dental".into(), active: false },
+        ];
+        assert_eq!(find_company_benefit(&company, "11", "Company Dental").unwrap().uuid, "b");
+        assert!(find_company_benefit(&company, "11", "Company Health").is_none());
+        assert!(find_company_benefit(&company, "6", "Company Dental").is_none());
+    }
+}
@@ -1,103 +1,646 @@
+use crate::core::adjustments::BillingAdjustment;
+use crate::core::contracts::Contract;
+use crate::core::billing_preview::InvoicePreview;
+use crate::core::credit_notes::TenantCredit;
 use crate::core::fiat_banking::UnitClient;
+use crate::core::invoice::{BillingPeriod, Invoice, InvoiceStatus, LineCategory, LineItem, PaymentMethod};
+use crate::core::metering::{MeteringConfig, UsageMeter, UsageQuantity};
+use crate::core::pricing::{PriceKey, TenantPricing};
+use crate::core::subscription::SubscriptionSettings;
+use crate::core::tiers::ServiceTier;
+use crate::ledger::client::IrohaClient;
+use serde::Serialize;
 use sqlx::{PgPool, Row};
 use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Where a refund went: back on the rail the invoice was paid on
+#[derive(Debug, Clone)]
+pub enum TenantRefund {
+    Unit(String),    // Book payment id
+    Onchain(String), // Transfer tx hash ("receipt:<key>" if an earlier try landed)
+}
+
+/// Where on-chain invoice payments go
+#[derive(Debug, Clone)]
+pub struct OnChainSettlement {
+    pub revenue_account_id: String,  // Our account on the private network
+    pub asset_definition_id: String, // The USD token tenants pay with
+}
+
+impl OnChainSettlement {
+    /// Reads ONCHAIN_REVENUE_ACCOUNT_ID and ONCHAIN_SETTLEMENT_ASSET_ID
+    pub fn from_env() -> Self {
+        Self {
+            revenue_account_id: std::env::var("ONCHAIN_REVENUE_ACCOUNT_ID")
+                .unwrap_or_else(|_| "revenue@my_ecosystem".to_string()),
+            asset_definition_id: std::env::var("ONCHAIN_SETTLEMENT_ASSET_ID")
+                .unwrap_or_else(|_| "usd#bank".to_string()),
+        }
+    }
+}
 
 pub struct BillingEngine {
     db: PgPool,
-    unit: UnitClient,
+    unit: Arc<UnitClient>,
+    iroha: Arc<IrohaClient>,
     my_revenue_account_id: String,
+    onchain: OnChainSettlement,
+    metering: MeteringConfig,
 }
 
 impl BillingEngine {
-    
+    pub fn new(
+        db: PgPool,
+        unit: Arc<UnitClient>,
+        iroha: Arc<IrohaClient>,
+        my_revenue_account_id: String,
+        onchain: OnChainSettlement,
+        metering: MeteringConfig,
+    ) -> Self {
+        Self { db, unit, iroha, my_revenue_account_id, onchain, metering }
+    }
+
+    /// Metered ledger usage of a tenant, as it would be billed
+    pub async fn usage(&self, tenant_id: Uuid, period: BillingPeriod) -> Result<Vec<UsageQuantity>, Box<dyn Error>> {
+        UsageMeter::measure(&self.db, &self.metering, tenant_id, period).await
+    }
+
+    /// Bills every tenant for `period` (the billing job's current month, in its timezone).
+    /// Safe to run more than once: tenants already billed for the period are skipped.
+    pub async fn process_all_tenants(&self, period: BillingPeriod) -> Result<(), Box<dyn Error>> {
+        let mut failures = 0;
+        for tenant_id in self.tenant_ids().await? {
+            if let Err(e) = self.process_monthly_invoice(&tenant_id.to_string(), period).await {
+                eprintln!("❌ Billing failed for tenant {} ({}): {}", tenant_id, period, e);
+                failures += 1;
+            }
+        }
+
+        if failures > 0 {
+            return Err(format!("{} tenant(s) failed billing for {}", failures, period).into());
+        }
+        Ok(())
+    }
+
+    /// DRY RUN: What every tenant will be charged for `period`, diffed against
+    /// the previous month. Uses the exact same calculator as the real run,
+    /// but skips `create_book_payment` and never writes an invoice.
+    pub async fn preview_all_tenants(&self, period: BillingPeriod) -> Result<Vec<InvoicePreview>, Box<dyn Error>> {
+        let mut previews = Vec::new();
+        for tenant_id in self.tenant_ids().await? {
+            previews.push(self.preview_invoice(tenant_id, period).await?);
+        }
+        Ok(previews)
+    }
+
+    /// DRY RUN for a single tenant
+    pub async fn preview_invoice(&self, tenant_id: Uuid, period: BillingPeriod) -> Result<InvoicePreview, Box<dyn Error>> {
+        // If the period is already finalized, the stored lines are what will be
+        // charged (the real run never recalculates a finalized invoice).
+        let lines = match Invoice::find(&self.db, tenant_id, period).await? {
+            Some(inv) if inv.status != InvoiceStatus::Draft => LineItem::for_invoice(&self.db, inv.id).await?,
+            _ => self.calculate_invoice(tenant_id, period).await?.lines,
+        };
+
+        let previous = match Invoice::find(&self.db, tenant_id, period.previous()).await? {
+            Some(inv) if inv.status != InvoiceStatus::Draft => {
+                Some((inv.total_cents, LineItem::for_invoice(&self.db, inv.id).await?))
+            }
+            _ => None,
+        };
+
+        Ok(InvoicePreview::build(tenant_id, period, lines, previous))
+    }
+
+    /// Tenants still onboarding have nothing to pay from yet
+    async fn tenant_ids(&self) -> Result<Vec<Uuid>, Box<dyn Error>> {
+        let rows = sqlx::query("SELECT id FROM tenants WHERE status <> 'onboarding'")
+            .fetch_all(&self.db)
+            .await?;
+        Ok(rows.iter().map(|row| row.get("id")).collect())
+    }
+
     /// Run this on the 1st of the month.
     /// ACCURATE, COMPLIANT BILLING ENGINE
-    pub async fn process_monthly_invoice(&self, tenant_id: &str) -> Result<(), Box<dyn Error>> {
-        
-        // 1. Fetch Tenant Settings & Wholesale Costs
-        // We look at the 'subscription_settings' table (The Store)
-        let rec = sqlx::query!(
-            r#"
-            SELECT 
-                t.unit_deposit_account_id,
-                s.base_fee_retail,
-                s.health_active, s.health_cost_wholesale,
-                s.retirement_active, s.retirement_cost_wholesale,
-                s.crime_active, s.crime_cost_wholesale
-            FROM tenants t
-            JOIN subscription_settings s ON t.id = s.tenant_id
-            WHERE t.id = $1
-            "#,
-            uuid::Uuid::parse_str(tenant_id)?
-        )
-        .fetch_one(&self.db)
-        .await?;
+    ///
+    /// EXACTLY-ONCE: The invoice row for (tenant, period) is written to Postgres
+    /// BEFORE we talk to Unit, and the Unit idempotency key is derived from it.
+    /// Re-running a period (double cron fire, restart mid-run, manual re-run)
+    /// resumes the invoice from whatever state it reached instead of charging again.
+    pub async fn process_monthly_invoice(
+        &self,
+        tenant_id: &str,
+        period: BillingPeriod,
+    ) -> Result<(), Box<dyn Error>> {
+        let tenant_uuid = Uuid::parse_str(tenant_id)?;
 
-        // --- THE CALCULATOR ---
-        
-        // A. Start with Base Fee ($2,500.00)
-        let mut total_charge = rec.base_fee_retail.unwrap_or(2500.00); 
-        
-        // We build a "Legal Receipt" string to store in your logs/email
-        let mut detailed_receipt = format!("Base Platform Access: ${:.2}", total_charge);
-        
-        // We build a "Bank Statement" string (shorter) for Unit
-        let mut bank_desc = String::from("Monthly SaaS Bundle");
-
-        // B. Health Insurance (Split: Premium + Tech Fee)
-        if rec.health_active.unwrap_or(false) {
-            let cost = rec.health_cost_wholesale.unwrap_or_default(); // e.g. $400.00
-            let admin_fee = 50.00;
-            
-            total_charge += cost + admin_fee;
-            
-            // COMPLIANCE FIX: List the fee separately
-            detailed_receipt.push_str(&format!("\n + Health Premium (Pass-through): ${:.2}", cost));
-            detailed_receipt.push_str(&format!("\n + Health Integration Fee: ${:.2}", admin_fee));
-        }
-
-        // C. 401k (Split: Contribution + Data Fee)
-        if rec.retirement_active.unwrap_or(false) {
-            let cost = rec.retirement_cost_wholesale.unwrap_or_default(); // e.g. $80.00
-            let admin_fee = 50.00;
-            
-            total_charge += cost + admin_fee;
-            
-            detailed_receipt.push_str(&format!("\n + 401k Contribution: ${:.2}", cost));
-            detailed_receipt.push_str(&format!("\n + 401k Data Connection Fee: ${:.2}", admin_fee));
-        }
-
-        // D. Crime Insurance (Split: Premium + Tech Fee)
-        if rec.crime_active.unwrap_or(false) {
-            let cost = rec.crime_cost_wholesale.unwrap_or_default(); // e.g. $30.00
-            let admin_fee = 50.00;
-            
-            total_charge += cost + admin_fee;
-            
-            detailed_receipt.push_str(&format!("\n + Crime Ins Premium: ${:.2}", cost));
-            detailed_receipt.push_str(&format!("\n + Crime Ins Admin Fee: ${:.2}", admin_fee));
+        // 0. Claim the invoice for this period (created as DRAFT if new)
+        let mut invoice = Invoice::get_or_create(&self.db, tenant_uuid, period).await?;
+
+        match invoice.status {
+            InvoiceStatus::Paid => {
+                println!("⏭️  Tenant {} already paid for {}. Skipping.", tenant_id, period);
+                return Ok(());
+            }
+            InvoiceStatus::Failed => {
+                // Failed charges are retried by the dunning process, not by a re-run.
+                println!("⏭️  Tenant {} has a failed invoice for {}. Skipping.", tenant_id, period);
+                return Ok(());
+            }
+            InvoiceStatus::Finalized | InvoiceStatus::PaymentPending => {
+                // The amount is locked and must never be recalculated. If we crashed
+                // mid-charge, `resend_pending` picks it up with the SAME idempotency key.
+                println!("⏭️  Tenant {} is already being charged for {}. Skipping.", tenant_id, period);
+                return Ok(());
+            }
+            InvoiceStatus::Draft => {}
         }
 
+        // 1. Run the calculator
+        let calculated = self.calculate_invoice(tenant_uuid, period).await?;
+
         // --- EXECUTION ---
 
-        println!("🧾 INVOICE GENERATED FOR TENANT {}:", tenant_id);
-        println!("{}", detailed_receipt);
+        // 2. DRAFT -> FINALIZED (Lock the amount AND the itemized lines before any money moves)
+        println!("🧾 INVOICE GENERATED FOR TENANT {} ({}):", tenant_id, period);
+        println!("{}", LineItem::receipt_text(&calculated.lines));
         println!("--------------------------------");
-        println!("   GRAND TOTAL: ${:.2}", total_charge);
+        println!("   GRAND TOTAL: ${:.2}", calculated.total_cents() as f64 / 100.0);
+
+        if !invoice.finalize(&self.db, &calculated).await? {
+            return Ok(()); // Another worker finalized it first
+        }
+
+        // 3. FINALIZED -> PAYMENT_PENDING (Only one worker wins this swap)
+        if !invoice
+            .transition(&self.db, InvoiceStatus::Finalized, InvoiceStatus::PaymentPending)
+            .await?
+        {
+            return Ok(()); // Another worker is charging this invoice
+        }
+
+        // 4. Pull the money instantly
+        self.charge(&mut invoice, calculated.deposit_account_id.as_deref()).await
+    }
+
+    /// DUNNING RETRY: Charges a FAILED invoice again.
+    /// Each retry is a new payment attempt with its own idempotency key
+    /// (Unit would replay the original failure for the old key).
+    /// An invoice still PAYMENT_PENDING never got a definite answer from Unit:
+    /// it is re-sent under the SAME key, so Unit can't charge it twice.
+    pub async fn retry_payment(&self, invoice_id: Uuid) -> Result<(), Box<dyn Error>> {
+        let mut invoice = Invoice::get(&self.db, invoice_id)
+            .await?
+            .ok_or("Invoice not found")?;
+
+        match invoice.status {
+            InvoiceStatus::Paid => return Ok(()),
+            InvoiceStatus::PaymentPending => {}
+            // Crashed between finalizing and charging: the locked amount is charged as is
+            InvoiceStatus::Finalized => {
+                if !invoice
+                    .transition(&self.db, InvoiceStatus::Finalized, InvoiceStatus::PaymentPending)
+                    .await?
+                {
+                    return Ok(()); // Another worker is charging this invoice
+                }
+            }
+            // FAILED -> PAYMENT_PENDING (Only one worker wins this swap)
+            _ => {
+                if !invoice.begin_retry(&self.db).await? {
+                    return Err(format!("Invoice {} is not in a retryable state ({})", invoice.id, invoice.status).into());
+                }
+            }
+        }
+
+        let settings = SubscriptionSettings::load(&self.db, invoice.tenant_id).await?;
+        self.charge(&mut invoice, settings.unit_deposit_account_id.as_deref()).await
+    }
 
-        // Convert to cents for Unit API (e.g. $3080.00 -> 308000)
-        let amount_cents = (total_charge * 100.0) as u64; 
-        
-        // Pull the money instantly
+    /// PAYMENT_PENDING -> PAID | FAILED
+    async fn charge(&self, invoice: &mut Invoice, deposit_account_id: Option<&str>) -> Result<(), Box<dyn Error>> {
         // Note: The 'description' here appears on their bank statement.
         // We keep it generic but accurate: "Monthly SaaS Bundle"
-        self.unit.create_book_payment(
-            &rec.unit_deposit_account_id.unwrap(),
+        let bank_desc = "Monthly SaaS Bundle";
+
+        // Fully covered by account credit: nothing to pull
+        if invoice.total_cents <= 0 {
+            invoice.mark_covered_by_credit(&self.db).await?;
+            println!("✅ Invoice {} covered by account credit.", invoice.id);
+            return Ok(());
+        }
+
+        // Unit or on-chain is decided ONCE, when the first attempt starts:
+        // a retry must never pay on the other rail while the first may have landed.
+        // The one exception is an on-chain invoice whose transfer never went out.
+        let method = match invoice.payment_method {
+            Some(method) => method,
+            None => {
+                let method = self.choose_payment_method(invoice).await?;
+                invoice.choose_payment_method(&self.db, method).await?
+            }
+        };
+        match method {
+            PaymentMethod::Onchain => {
+                // A transfer sent by an earlier attempt may still land: then it stays on-chain
+                if !self.treasury_short(invoice).await? || !invoice.fall_back_to_unit(&self.db).await? {
+                    return self.charge_onchain(invoice).await;
+                }
+                println!("💸 Treasury can't cover invoice {}. Falling back to Unit.", invoice.id);
+            }
+            PaymentMethod::AccountCredit => {
+                return Err(format!("Invoice {} owes money but is marked as covered by credit", invoice.id).into());
+            }
+            PaymentMethod::Unit => {}
+        }
+
+        let deposit_account_id = match deposit_account_id {
+            Some(id) => id,
+            None => {
+                invoice.mark_failed(&self.db, "Tenant has no Unit deposit account").await?;
+                return Err("Tenant has no Unit deposit account".into());
+            }
+        };
+
+        match self.unit.create_book_payment(
+            deposit_account_id,
             &self.my_revenue_account_id,
-            amount_cents,
-            &bank_desc 
-        ).await?;
+            invoice.total_cents as u64,
+            bank_desc,
+            &invoice.payment_key(),
+        ).await {
+            Ok(payment_id) => {
+                invoice.mark_paid(&self.db, &payment_id).await?;
+                println!("✅ Invoice {} paid (Unit payment {}).", invoice.id, payment_id);
+                Ok(())
+            }
+            // Unit definitively said no: this attempt is over, dunning takes it
+            // from here with a new key
+            Err(e) if e.is_definitive() => {
+                invoice.mark_failed(&self.db, &e.to_string()).await?;
+                Err(e.into())
+            }
+            // 5xx, 429, 409, timeout etc: Unit may have made the payment. Stay
+            // PAYMENT_PENDING; `resend_pending` re-sends with the same key.
+            Err(e) => Err(format!("Payment outcome for invoice {} unknown: {}", invoice.id, e).into()),
+        }
+    }
+
+    /// Invoices stuck in FINALIZED or PAYMENT_PENDING (crash or timeout while
+    /// charging): re-sent with the same idempotency key, so Unit returns the
+    /// original payment if it went through. Run by the dunning job.
+    pub async fn resend_pending(&self, older_than_minutes: i64) -> Result<(), Box<dyn Error>> {
+        let stuck = sqlx::query!(
+            r#"
+            SELECT id FROM invoices
+            WHERE status IN ('finalized', 'payment_pending') AND updated_at < NOW() - make_interval(mins => $1)
+            "#,
+            older_than_minutes as i32
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut failures = 0;
+        for row in stuck {
+            if let Err(e) = self.retry_payment(row.id).await {
+                eprintln!("❌ Re-sending payment for invoice {} failed: {}", row.id, e);
+                failures += 1;
+            }
+        }
 
+        if failures > 0 {
+            return Err(format!("{} pending payment(s) still unresolved", failures).into());
+        }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Tenants who opted in pay from their on-chain treasury, if it holds
+    /// enough USD when the first attempt starts. Otherwise Unit pays.
+    async fn choose_payment_method(&self, invoice: &Invoice) -> Result<PaymentMethod, Box<dyn Error>> {
+        let settings = SubscriptionSettings::load(&self.db, invoice.tenant_id).await?;
+        let treasury = match (settings.onchain_settlement, settings.treasury_account_id) {
+            (true, Some(treasury)) => treasury,
+            _ => return Ok(PaymentMethod::Unit),
+        };
+
+        // Invoices from before the method was stored may already be settled
+        let receipt_key = invoice.settlement_receipt_key();
+        if self.iroha
+            .has_settlement_receipt(&self.onchain.revenue_account_id, &receipt_key)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            return Ok(PaymentMethod::Onchain);
+        }
+
+        let balance = self.iroha
+            .balance_cents(&treasury, &self.onchain.asset_definition_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        if balance < invoice.total_cents {
+            println!(
+                "💸 Treasury {} holds ${:.2} < ${:.2}. Falling back to Unit for invoice {}.",
+                treasury, balance as f64 / 100.0, invoice.total_cents as f64 / 100.0, invoice.id
+            );
+            return Ok(PaymentMethod::Unit);
+        }
+        Ok(PaymentMethod::Onchain)
+    }
+
+    /// The treasury holds less than the invoice and no earlier attempt settled it
+    async fn treasury_short(&self, invoice: &Invoice) -> Result<bool, Box<dyn Error>> {
+        let receipt_key = invoice.settlement_receipt_key();
+        if self.iroha
+            .has_settlement_receipt(&self.onchain.revenue_account_id, &receipt_key)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            return Ok(false);
+        }
+        let settings = SubscriptionSettings::load(&self.db, invoice.tenant_id).await?;
+        let treasury = match settings.treasury_account_id {
+            Some(treasury) => treasury,
+            None => return Ok(true),
+        };
+        let balance = self.iroha
+            .balance_cents(&treasury, &self.onchain.asset_definition_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(balance < invoice.total_cents)
+    }
+
+    /// ON-CHAIN SETTLEMENT
+    /// The transfer and its receipt are ONE ledger transaction, so the receipt
+    /// proves payment: if we crashed after submitting last time, we find the
+    /// receipt and only mark the invoice paid instead of transferring again.
+    async fn charge_onchain(&self, invoice: &mut Invoice) -> Result<(), Box<dyn Error>> {
+        let receipt_key = invoice.settlement_receipt_key();
+        let revenue_account = &self.onchain.revenue_account_id;
+
+        // 1. Already settled by an earlier attempt?
+        if self.iroha.has_settlement_receipt(revenue_account, &receipt_key).await.map_err(|e| e.to_string())? {
+            invoice.mark_paid_onchain(&self.db, &format!("receipt:{}", receipt_key)).await?;
+            println!("✅ Invoice {} was already settled on-chain.", invoice.id);
+            return Ok(());
+        }
+
+        // 2. Still on-chain once a transfer went out: a retry never switches to Unit then
+        let settings = SubscriptionSettings::load(&self.db, invoice.tenant_id).await?;
+        let treasury = match settings.treasury_account_id {
+            Some(treasury) => treasury,
+            None => {
+                invoice.mark_failed(&self.db, "Tenant has no on-chain treasury").await?;
+                return Err("Tenant has no on-chain treasury".into());
+            }
+        };
+        let balance = self.iroha
+            .balance_cents(&treasury, &self.onchain.asset_definition_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        if balance < invoice.total_cents {
+            let reason = format!("Treasury {} holds ${:.2}, not enough", treasury, balance as f64 / 100.0);
+            invoice.mark_failed(&self.db, &reason).await?;
+            return Err(reason.into());
+        }
+
+        // 3. Transfer + receipt, atomically on the ledger. Recorded as sent first:
+        // from here on a retry must stay on-chain
+        invoice.mark_onchain_submitted(&self.db).await?;
+        match self.iroha.settle_invoice(
+            &treasury,
+            revenue_account,
+            &self.onchain.asset_definition_id,
+            invoice.total_cents,
+            &receipt_key,
+        ).await {
+            Ok(tx_hash) => {
+                invoice.mark_paid_onchain(&self.db, &tx_hash).await?;
+                println!("✅ Invoice {} paid on-chain (tx {}).", invoice.id, tx_hash);
+                Ok(())
+            }
+            Err(e) => {
+                // Rejected by the peers (nothing moved) or timed out waiting for
+                // the block (it may still commit). Dunning retries on-chain,
+                // checking the receipt first.
+                invoice.mark_failed(&self.db, &format!("On-chain settlement failed: {}", e)).await?;
+                Err(format!("On-chain settlement failed for invoice {}: {}", invoice.id, e).into())
+            }
+        }
+    }
+
+    /// REFUND: Sends money back the way the invoice was paid. Unit invoices get
+    /// a book payment from our revenue account to the tenant's deposit account;
+    /// on-chain ones a transfer from the revenue account back to their treasury.
+    /// The idempotency key (also the on-chain receipt key) makes retries safe.
+    pub async fn refund_to_tenant(
+        &self,
+        invoice: &Invoice,
+        amount_cents: i64,
+        description: &str,
+        idempotency_key: &str,
+    ) -> Result<TenantRefund, Box<dyn Error>> {
+        let settings = SubscriptionSettings::load(&self.db, invoice.tenant_id).await?;
+
+        match invoice.payment_method {
+            Some(PaymentMethod::Onchain) => {
+                let treasury = settings
+                    .treasury_account_id
+                    .ok_or("Tenant has no on-chain treasury")?;
+                // The receipt sits on the receiving treasury, in the same ledger transaction
+                if self.iroha.has_receipt(&treasury, idempotency_key).await.map_err(|e| e.to_string())? {
+                    return Ok(TenantRefund::Onchain(format!("receipt:{}", idempotency_key)));
+                }
+                let tx_hash = self.iroha
+                    .transfer_with_receipt(
+                        &self.onchain.revenue_account_id,
+                        &treasury,
+                        &self.onchain.asset_definition_id,
+                        amount_cents,
+                        idempotency_key,
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(TenantRefund::Onchain(tx_hash))
+            }
+            Some(PaymentMethod::AccountCredit) => {
+                Err(format!("Invoice {} was paid by account credit: nothing to refund", invoice.id).into())
+            }
+            Some(PaymentMethod::Unit) | None => {
+                let deposit_account_id = settings
+                    .unit_deposit_account_id
+                    .ok_or("Tenant has no Unit deposit account")?;
+
+                let payment_id = self.unit.create_book_payment(
+                    &self.my_revenue_account_id,
+                    &deposit_account_id,
+                    amount_cents as u64,
+                    description,
+                    idempotency_key,
+                ).await?;
+                Ok(TenantRefund::Unit(payment_id))
+            }
+        }
+    }
+
+    /// THE CALCULATOR
+    /// Builds the itemized invoice for a tenant from their current settings,
+    /// priced from the price book in force at the start of `period`, plus
+    /// overage on last month's metered usage, any pending one-off
+    /// adjustments (e.g. prorations from tier changes) and account credit.
+    /// Tenants on a contract pay recurring fees once per billing cycle, and
+    /// committed usage spend is trued up when a cycle's last month is metered.
+    /// Pure read: nothing is written and no money moves.
+    pub async fn calculate_invoice(
+        &self,
+        tenant_id: Uuid,
+        period: BillingPeriod,
+    ) -> Result<CalculatedInvoice, Box<dyn Error>> {
+
+        // 1. Fetch Tenant Settings & Wholesale Costs
+        // We look at the 'subscription_settings' table (The Store)
+        let settings = SubscriptionSettings::load(&self.db, tenant_id).await?;
+
+        // 2. Load the prices (price book + negotiated overrides + discounts)
+        let pricing = TenantPricing::load(&self.db, tenant_id, settings.tier, period.start).await?;
+        let recurring = recurring_lines(&pricing, &settings)?;
+
+        // 2b. Contracts bill the whole cycle upfront in its first month, nothing after
+        let mut lines = match Contract::covering(&self.db, tenant_id, period.start).await? {
+            Some(contract) if contract.is_cycle_start(period) => {
+                let (start, months) = contract.cycle_containing(period);
+                let range = Contract::cycle_label(start, months);
+                recurring.into_iter().map(|l| l.prepaid(months, &range)).collect()
+            }
+            Some(_) => Vec::new(), // Already paid at the start of the cycle
+            None => recurring,
+        };
+
+        // 3. Metered ledger usage, billed in arrears (last month's activity)
+        let usage_period = period.previous();
+        let usage = self.usage(tenant_id, usage_period).await?;
+        for u in &usage {
+            if let Some(line) = pricing
+                .book
+                .usage_rate(u.metric, settings.tier)
+                .and_then(|rate| rate.overage_line(u.quantity))
+            {
+                lines.push(line);
+            }
+        }
+
+        // 3b. Committed usage spend, once the cycle's last month has been metered
+        if let Some(contract) = Contract::covering(&self.db, tenant_id, usage_period.start).await? {
+            if let Some(line) = contract.true_up_line(&self.db, usage_period, &lines).await? {
+                lines.push(line);
+            }
+        }
+
+        // 4. One-off charges & credits waiting for this invoice
+        let adjustments = BillingAdjustment::pending_for(&self.db, tenant_id).await?;
+        for adj in &adjustments {
+            lines.push(LineItem::from_cents(LineCategory::Adjustment, &adj.description, adj.amount_cents));
+        }
+
+        // 5. Account credit (from credit notes) pays for as much as it can
+        let subtotal: i64 = lines.iter().map(|l| l.amount_cents).sum();
+        let credit_balance = TenantCredit::balance(&self.db, tenant_id).await?;
+        let credit_applied_cents = credit_balance.min(subtotal).max(0);
+        if credit_applied_cents > 0 {
+            lines.push(LineItem::from_cents(LineCategory::AccountCredit, "Account Credit Applied", -credit_applied_cents));
+        }
+
+        Ok(CalculatedInvoice {
+            deposit_account_id: settings.unit_deposit_account_id,
+            price_book_id: pricing.book.id,
+            tier: settings.tier,
+            adjustment_ids: adjustments.iter().map(|a| a.id).collect(),
+            usage_period,
+            usage,
+            credit_applied_cents,
+            lines,
+        })
+    }
+}
+
+/// The monthly recurring lines for a subscription (A-E).
+/// Shared by the invoice calculator and tier-change proration so both always agree.
+pub fn recurring_lines(pricing: &TenantPricing, settings: &SubscriptionSettings) -> Result<Vec<LineItem>, Box<dyn Error>> {
+    // Our own fees, remembered by price key so discounts can target them
+    let mut retained: Vec<(PriceKey, i64)> = Vec::new();
+
+    // A. Start with Base Fee (e.g. $2,500.00)
+    let base_fee = pricing.price_cents(PriceKey::BasePlatformFee)?;
+    retained.push((PriceKey::BasePlatformFee, base_fee));
+    let mut lines = vec![LineItem::from_cents(LineCategory::PlatformFee, "Base Platform Access", base_fee)];
+
+    // B. Health Insurance (Split: Premium + Tech Fee)
+    if settings.health_active {
+        let cost = settings.health_cost_wholesale; // e.g. $400.00
+        let admin_fee = pricing.price_cents(PriceKey::AdminFeeHealth)?;
+        retained.push((PriceKey::AdminFeeHealth, admin_fee));
+
+        // COMPLIANCE FIX: List the fee separately
+        lines.push(LineItem::new(LineCategory::PassThroughPremium, "Health Premium (Pass-through)", cost));
+        lines.push(LineItem::from_cents(LineCategory::AdminFee, "Health Integration Fee", admin_fee));
+    }
+
+    // C. 401k (Split: Contribution + Data Fee)
+    if settings.retirement_active {
+        let cost = settings.retirement_cost_wholesale; // e.g. $80.00
+        let admin_fee = pricing.price_cents(PriceKey::AdminFee401k)?;
+        retained.push((PriceKey::AdminFee401k, admin_fee));
+
+        lines.push(LineItem::new(LineCategory::PassThroughPremium, "401k Contribution", cost));
+        lines.push(LineItem::from_cents(LineCategory::AdminFee, "401k Data Connection Fee", admin_fee));
+    }
+
+    // D. Crime Insurance (Split: Premium + Tech Fee)
+    if settings.crime_active {
+        let cost = settings.crime_cost_wholesale; // e.g. $30.00
+        let admin_fee = pricing.price_cents(PriceKey::AdminFeeCrime)?;
+        retained.push((PriceKey::AdminFeeCrime, admin_fee));
+
+        lines.push(LineItem::new(LineCategory::PassThroughPremium, "Crime Ins Premium", cost));
+        lines.push(LineItem::from_cents(LineCategory::AdminFee, "Crime Ins Admin Fee", admin_fee));
+    }
+
+    // E. Discounts & Coupons (only ever reduce OUR fees, never pass-through premiums)
+    for discount in &pricing.discounts {
+        let base: i64 = retained
+            .iter()
+            .filter(|(key, _)| discount.applies_to.map_or(true, |target| target == *key))
+            .map(|(_, cents)| cents)
+            .sum();
+        let amount = discount.amount_for(base);
+        if amount > 0 {
+            lines.push(LineItem::from_cents(
+                LineCategory::Discount,
+                &format!("Discount: {} ({})", discount.description, discount.code),
+                -amount,
+            ));
+        }
+    }
+
+    Ok(lines)
+}
+
+/// The output of the calculator for one tenant
+#[derive(Debug, Clone, Serialize)]
+pub struct CalculatedInvoice {
+    pub deposit_account_id: Option<String>,
+    pub price_book_id: Uuid,
+    pub tier: ServiceTier,
+    pub adjustment_ids: Vec<Uuid>, // Marked as applied when the invoice is finalized
+    pub usage_period: BillingPeriod,
+    pub usage: Vec<UsageQuantity>,
+    pub credit_applied_cents: i64,
+    pub lines: Vec<LineItem>,
+}
+
+impl CalculatedInvoice {
+    pub fn total_cents(&self) -> i64 {
+        self.lines.iter().map(|l| l.amount_cents).sum()
+    }
+}
@@ -0,0 +1,132 @@
+use crate::core::invoice::{BillingPeriod, LineItem};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// What a tenant WILL be charged for a period, compared to the previous one.
+/// Produced by the dry-run mode of the billing engine: no money moves.
+#[derive(Debug, Serialize)]
+pub struct InvoicePreview {
+    pub tenant_id: Uuid,
+    pub period: String,
+    pub lines: Vec<LineItem>,
+    pub total_cents: i64,
+    pub previous_total_cents: Option<i64>, // None = tenant was not billed last month
+    pub delta_cents: i64,
+    pub changes: Vec<LineChange>,
+}
+
+/// A line that is new, removed, or priced differently than last month
+#[derive(Debug, Serialize)]
+pub struct LineChange {
+    pub description: String,
+    pub previous_cents: Option<i64>,
+    pub new_cents: Option<i64>,
+}
+
+impl InvoicePreview {
+    pub fn build(
+        tenant_id: Uuid,
+        period: BillingPeriod,
+        lines: Vec<LineItem>,
+        previous: Option<(i64, Vec<LineItem>)>,
+    ) -> Self {
+        let total_cents: i64 = lines.iter().map(|l| l.amount_cents).sum();
+        let (previous_total_cents, previous_lines) = match previous {
+            Some((total, lines)) => (Some(total), lines),
+            None => (None, vec![]),
+        };
+
+        let changes = diff_lines(&previous_lines, &lines);
+
+        Self {
+            tenant_id,
+            period: period.to_string(),
+            lines,
+            total_cents,
+            previous_total_cents,
+            delta_cents: total_cents - previous_total_cents.unwrap_or(0),
+            changes,
+        }
+    }
+}
+
+/// Lines are matched by description (e.g. "Health Integration Fee")
+fn diff_lines(previous: &[LineItem], current: &[LineItem]) -> Vec<LineChange> {
+    let mut changes = Vec::new();
+
+    for line in current {
+        let before = previous.iter().find(|p| p.description == line.description);
+        match before {
+            Some(p) if p.amount_cents == line.amount_cents => {}
+            _ => changes.push(LineChange {
+                description: line.description.clone(),
+                previous_cents: before.map(|p| p.amount_cents),
+                new_cents: Some(line.amount_cents),
+            }),
+        }
+    }
+
+    for line in previous {
+        if !current.iter().any(|c| c.description == line.description) {
+            changes.push(LineChange {
+                description: line.description.clone(),
+                previous_cents: Some(line.amount_cents),
+                new_cents: None,
+            });
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::invoice::LineCategory;
+
+    fn line(description: &str, cents: i64) -> LineItem {
+        LineItem::from_cents(LineCategory::PlatformFee, description, cents)
+    }
+
+    #[test]
+    fn preview_reports_new_changed_and_removed_lines() {
+        let previous = vec![line("Platform Fee", 9_900), line("Dental Admin Fee", 500), line("Setup", 2_000)];
+        let current = vec![line("Platform Fee", 9_900), line("Dental Admin Fee", 700), line("Vision Admin Fee", 300)];
+
+        let preview = InvoicePreview::build(
+            Uuid::new_v4(),
+            BillingPeriod::parse("2026-11").unwrap(),
+            current,
+            Some((12_400, previous)),
+        );
+
+        assert_eq!(preview.total_cents, 10_900);
+        assert_eq!(preview.delta_cents, -1_500);
+        let changes: Vec<_> = preview
+            .changes
+            .iter()
+            .map(|c| (c.description.as_str(), c.previous_cents, c.new_cents))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("Dental Admin Fee", Some(500), Some(700)),
+                ("Vision Admin Fee", None, Some(300)),
+                ("Setup", Some(2_000), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn first_invoice_is_all_new() {
+        let preview = InvoicePreview::build(
+            Uuid::new_v4(),
+            BillingPeriod::parse("2026-11").unwrap(),
+            vec![line("Platform Fee", 9_900)],
+            None,
+        );
+        assert_eq!(preview.previous_total_cents, None);
+        assert_eq!(preview.delta_cents, 9_900);
+        assert_eq!(preview.changes.len(), 1);
+    }
+}
@@ -0,0 +1,587 @@
+use crate::core::fiat_banking::{Authorization, CardLimits, FiatAsset, PageRequest, UnitClient};
+use crate::ledger::client::IrohaClient;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- VIRTUAL CARDS ---
+// A card spends the user's Unit deposit account, i.e. the money behind their
+// on-chain `usd`. Card activity is mirrored on-chain:
+//   authorization -> the amount moves from the user into the card hold account
+//   amount change -> the hold is topped up / partly handed back
+//   capture       -> the held USD is burned (Unit debited the account)
+//   cancel        -> the hold goes back to the user
+// Every move carries a receipt, so replayed webhooks never apply twice.
+// Authorization/capture events arrive through core/fiat_events.rs.
+
+/// Unit transaction types that capture a card authorization
+pub const CARD_TRANSACTION_TYPES: [&str; 3] = ["purchaseTransaction", "atmTransaction", "cardTransaction"];
+
+/// Holds untouched this long are re-checked against Unit by the card_holds job
+const STALE_HOLD_HOURS: i32 = 24;
+
+#[derive(Debug, Clone)]
+pub struct CardConfig {
+    pub usd_asset_id: String,
+    pub hold_account_id: String, // Where authorized USD waits for the capture
+}
+
+impl CardConfig {
+    /// Reads CARD_HOLD_ACCOUNT_ID
+    pub fn from_env(asset: &FiatAsset) -> Self {
+        Self {
+            usd_asset_id: asset.usd_asset_id.clone(),
+            hold_account_id: std::env::var("CARD_HOLD_ACCOUNT_ID")
+                .unwrap_or_else(|_| "card_holds@my_ecosystem".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CardStatus {
+    Active,
+    Frozen,
+    Closed,
+}
+
+impl CardStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CardStatus::Active => "active",
+            CardStatus::Frozen => "frozen",
+            CardStatus::Closed => "closed",
+        }
+    }
+
+    /// Unit's card status. Lost/stolen/fraud cards are closed for good.
+    pub fn from_unit(status: &str) -> Self {
+        match status {
+            "Active" | "Inactive" => CardStatus::Active,
+            "Frozen" => CardStatus::Frozen,
+            _ => CardStatus::Closed,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Card {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub unit_card_id: String,
+    pub unit_account_id: String,
+    pub onchain_account_id: String,
+    pub last4: String,
+    pub expiration_date: String,
+    pub status: String,
+    pub limits: Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CardHold {
+    pub unit_authorization_id: String,
+    pub card_id: Uuid,
+    pub merchant_name: Option<String>,
+    pub authorized_cents: i64,
+    pub held_cents: i64,
+    pub status: String, // held | captured | released | declined
+    pub captured_cents: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A settled card transaction, as shown in the wallet
+#[derive(Debug, Serialize)]
+pub struct CardTransaction {
+    pub unit_transaction_id: String,
+    pub kind: String,
+    pub direction: String,
+    pub amount_cents: i64,
+    pub summary: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CardActivity {
+    pub holds: Vec<CardHold>,                // Pending, newest first
+    pub transactions: Vec<CardTransaction>, // Settled, newest first
+}
+
+pub struct CardService {
+    db: PgPool,
+    unit: Arc<UnitClient>,
+    iroha: Arc<IrohaClient>,
+    config: CardConfig,
+}
+
+impl CardService {
+    pub fn new(db: PgPool, unit: Arc<UnitClient>, iroha: Arc<IrohaClient>, config: CardConfig) -> Self {
+        Self { db, unit, iroha, config }
+    }
+
+    /// 1. ISSUE a virtual card to a wallet
+    /// The wallet must mirror a user's Unit account (see fiat_account_links).
+    /// A wallet with several Unit accounts has to say which one the card spends.
+    pub async fn issue(
+        &self,
+        onchain_account_id: &str,
+        unit_account_id: Option<&str>,
+        limits: &CardLimits,
+    ) -> Result<Card, Box<dyn Error>> {
+        let links = sqlx::query!(
+            r#"
+            SELECT unit_account_id, tenant_id FROM fiat_account_links
+            WHERE onchain_account_id = $1 AND owner_type = 'user'
+              AND ($2::TEXT IS NULL OR unit_account_id = $2)
+            ORDER BY unit_account_id
+            "#,
+            onchain_account_id,
+            unit_account_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let link = match links.as_slice() {
+            [link] => link,
+            [] => return Err("Wallet is not linked to a Unit deposit account".into()),
+            _ => return Err("Wallet has several Unit accounts, pass unit_account_id".into()),
+        };
+
+        let id = Uuid::new_v4();
+        let account = self.unit.get_account(&link.unit_account_id).await?;
+        let customer_id = account.related_id("customer").ok_or("Unit account has no customer")?;
+        let card = self
+            .unit
+            .create_virtual_card(&link.unit_account_id, limits, &format!("card-{}", id))
+            .await?;
+
+        let row = sqlx::query_as!(
+            Card,
+            r#"
+            INSERT INTO cards
+            (id, tenant_id, unit_card_id, unit_customer_id, unit_account_id, onchain_account_id,
+             last4, expiration_date, status, limits)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id, tenant_id, unit_card_id, unit_account_id, onchain_account_id, last4, expiration_date,
+                      status, limits, created_at, updated_at, closed_at
+            "#,
+            id,
+            link.tenant_id,
+            card.id,
+            customer_id,
+            link.unit_account_id,
+            onchain_account_id,
+            card.attributes.last4_digits,
+            card.attributes.expiration_date,
+            CardStatus::from_unit(&card.attributes.status).as_str(),
+            serde_json::to_value(card.attributes.limits.unwrap_or_default())?
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        println!("💳 Virtual card ****{} issued to {}.", row.last4, onchain_account_id);
+        Ok(row)
+    }
+
+    pub async fn list(&self, onchain_account_id: &str) -> Result<Vec<Card>, Box<dyn Error>> {
+        let rows = sqlx::query_as!(
+            Card,
+            r#"
+            SELECT id, tenant_id, unit_card_id, unit_account_id, onchain_account_id, last4, expiration_date,
+                   status, limits, created_at, updated_at, closed_at
+            FROM cards
+            WHERE onchain_account_id = $1
+            ORDER BY created_at DESC
+            "#,
+            onchain_account_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(rows)
+    }
+
+    /// A card of this wallet (cards of other wallets are "not found")
+    pub async fn get(&self, onchain_account_id: &str, card_id: Uuid) -> Result<Card, Box<dyn Error>> {
+        let row = sqlx::query_as!(
+            Card,
+            r#"
+            SELECT id, tenant_id, unit_card_id, unit_account_id, onchain_account_id, last4, expiration_date,
+                   status, limits, created_at, updated_at, closed_at
+            FROM cards
+            WHERE id = $1 AND onchain_account_id = $2
+            "#,
+            card_id,
+            onchain_account_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or("Card not found")?;
+        Ok(row)
+    }
+
+    /// 2. FREEZE / UNFREEZE / CLOSE. Unit's answer is the status we store.
+    pub async fn set_status(
+        &self,
+        onchain_account_id: &str,
+        card_id: Uuid,
+        status: CardStatus,
+    ) -> Result<Card, Box<dyn Error>> {
+        let card = self.get(onchain_account_id, card_id).await?;
+        if card.status == CardStatus::Closed.as_str() {
+            return Err("Card is closed".into());
+        }
+
+        let updated = match status {
+            CardStatus::Frozen => self.unit.freeze_card(&card.unit_card_id).await?,
+            CardStatus::Active => self.unit.unfreeze_card(&card.unit_card_id).await?,
+            CardStatus::Closed => self.unit.close_card(&card.unit_card_id).await?,
+        };
+        let new_status = CardStatus::from_unit(&updated.attributes.status);
+
+        let row = sqlx::query_as!(
+            Card,
+            r#"
+            UPDATE cards
+            SET status = $2, updated_at = NOW(),
+                closed_at = CASE WHEN $2 = 'closed' THEN NOW() ELSE closed_at END
+            WHERE id = $1
+            RETURNING id, tenant_id, unit_card_id, unit_account_id, onchain_account_id, last4, expiration_date,
+                      status, limits, created_at, updated_at, closed_at
+            "#,
+            card_id,
+            new_status.as_str()
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        println!("💳 Card ****{} is now {}.", row.last4, row.status);
+        Ok(row)
+    }
+
+    /// 3. SPENDING LIMITS (unset limits keep Unit's defaults)
+    pub async fn set_limits(
+        &self,
+        onchain_account_id: &str,
+        card_id: Uuid,
+        limits: &CardLimits,
+    ) -> Result<Card, Box<dyn Error>> {
+        let all = [limits.daily_purchase, limits.monthly_purchase, limits.daily_withdrawal, limits.monthly_withdrawal];
+        if all.iter().flatten().any(|cents| *cents < 0) {
+            return Err("Limits cannot be negative".into());
+        }
+        if let (Some(daily), Some(monthly)) = (limits.daily_purchase, limits.monthly_purchase) {
+            if daily > monthly {
+                return Err("The daily purchase limit cannot exceed the monthly one".into());
+            }
+        }
+
+        let card = self.get(onchain_account_id, card_id).await?;
+        if card.status == CardStatus::Closed.as_str() {
+            return Err("Card is closed".into());
+        }
+        let updated = self.unit.update_card_limits(&card.unit_card_id, limits).await?;
+
+        let row = sqlx::query_as!(
+            Card,
+            r#"
+            UPDATE cards SET limits = $2, updated_at = NOW()
+            WHERE id = $1
+            RETURNING id, tenant_id, unit_card_id, unit_account_id, onchain_account_id, last4, expiration_date,
+                      status, limits, created_at, updated_at, closed_at
+            "#,
+            card_id,
+            serde_json::to_value(updated.attributes.limits.unwrap_or_default())?
+        )
+        .fetch_one(&self.db)
+        .await?;
+        Ok(row)
+    }
+
+    /// 4. ACTIVITY: open holds from our table, settled transactions from Unit
+    pub async fn activity(
+        &self,
+        onchain_account_id: &str,
+        card_id: Uuid,
+        page: PageRequest,
+    ) -> Result<CardActivity, Box<dyn Error>> {
+        let card = self.get(onchain_account_id, card_id).await?;
+
+        let holds = sqlx::query_as!(
+            CardHold,
+            r#"
+            SELECT unit_authorization_id, card_id, merchant_name, authorized_cents, held_cents, status,
+                   captured_cents, created_at, updated_at
+            FROM card_holds
+            WHERE card_id = $1 AND status = 'held'
+            ORDER BY created_at DESC
+            "#,
+            card_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let transactions = self
+            .unit
+            .list_card_transactions(&card.unit_card_id, page)
+            .await?
+            .items
+            .into_iter()
+            .map(|t| CardTransaction {
+                unit_transaction_id: t.id,
+                kind: t.kind,
+                direction: t.attributes.direction,
+                amount_cents: t.attributes.amount,
+                summary: t.attributes.summary,
+                created_at: t.attributes.created_at,
+            })
+            .collect();
+
+        Ok(CardActivity { holds, transactions })
+    }
+
+    /// 5. STALE HOLDS (card_holds job): authorizations we heard nothing about
+    /// for a day are re-read from Unit. A hold whose authorization completed
+    /// without our seeing the capture goes back to the user; the capture then
+    /// burns straight from their account when its event is applied.
+    pub async fn release_stale_holds(&self) -> Result<(), Box<dyn Error>> {
+        let stale = sqlx::query!(
+            r#"
+            SELECT unit_authorization_id FROM card_holds
+            WHERE status = 'held' AND updated_at < NOW() - make_interval(hours => $1)
+            "#,
+            STALE_HOLD_HOURS
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut failed = 0;
+        for hold in &stale {
+            let result = match self.unit.get_authorization(&hold.unit_authorization_id).await {
+                Ok(auth) if auth.attributes.status == "Completed" => {
+                    set_hold(&self.db, &self.iroha, &self.config, &auth, 0).await.map(|_| ())
+                }
+                Ok(auth) => sync_authorization(&self.db, &self.iroha, &self.config, &auth).await.map(|_| ()),
+                Err(e) => Err(e.into()),
+            };
+            if let Err(e) = result {
+                eprintln!("❌ Card hold {} not refreshed: {}", hold.unit_authorization_id, e);
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("{} of {} stale card holds could not be refreshed", failed, stale.len()).into());
+        }
+        Ok(())
+    }
+}
+
+/// AUTHORIZATION UPDATE (from an `authorization.*` webhook or the job).
+/// Returns None if the card is not one of ours.
+/// "Completed" leaves the hold alone: the capture transaction settles it.
+pub async fn sync_authorization(
+    db: &PgPool,
+    iroha: &IrohaClient,
+    config: &CardConfig,
+    auth: &Authorization,
+) -> Result<Option<CardHold>, Box<dyn Error>> {
+    let target_cents = match auth.attributes.status.as_str() {
+        "Authorized" => auth.attributes.amount,
+        "Canceled" | "Declined" => 0,
+        "Completed" => return hold_for(db, &auth.id).await,
+        other => return Err(format!("Unknown authorization status '{}'", other).into()),
+    };
+    set_hold(db, iroha, config, auth, target_cents).await
+}
+
+/// Moves USD between the user and the hold account until `target_cents` is held
+async fn set_hold(
+    db: &PgPool,
+    iroha: &IrohaClient,
+    config: &CardConfig,
+    auth: &Authorization,
+    target_cents: i64,
+) -> Result<Option<CardHold>, Box<dyn Error>> {
+    let unit_card_id = match auth.related_id("card") {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+    let card = match sqlx::query!("SELECT id, onchain_account_id FROM cards WHERE unit_card_id = $1", unit_card_id)
+        .fetch_optional(db)
+        .await?
+    {
+        Some(card) => card,
+        None => return Ok(None),
+    };
+
+    let mut tx = db.begin().await?;
+
+    // Declined authorizations never hold anything
+    let initial_status = if auth.attributes.status == "Declined" { "declined" } else { "held" };
+    sqlx::query!(
+        r#"
+        INSERT INTO card_holds (unit_authorization_id, card_id, onchain_account_id, merchant_name, authorized_cents, status)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (unit_authorization_id) DO NOTHING
+        "#,
+        auth.id,
+        card.id,
+        card.onchain_account_id,
+        auth.attributes.merchant.as_ref().map(|m| m.name.clone()),
+        auth.attributes.amount,
+        initial_status
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // One adjustment at a time per authorization
+    let hold = sqlx::query!(
+        "SELECT held_cents, status, version FROM card_holds WHERE unit_authorization_id = $1 FOR UPDATE",
+        auth.id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if hold.status != "held" {
+        tx.commit().await?;
+        return hold_for(db, &auth.id).await; // Captured, released or declined: final
+    }
+
+    let delta = target_cents - hold.held_cents;
+    let mut version = hold.version;
+    let mut tx_hash = None;
+    if delta != 0 {
+        version += 1;
+        let receipt_key = format!("card_hold_{}_{}", auth.id, version);
+        let (from, to) = if delta > 0 {
+            (card.onchain_account_id.as_str(), config.hold_account_id.as_str())
+        } else {
+            (config.hold_account_id.as_str(), card.onchain_account_id.as_str())
+        };
+
+        let hash = if iroha.has_receipt(to, &receipt_key).await.map_err(|e| e.to_string())? {
+            format!("receipt:{}", receipt_key) // We crashed after submitting last time
+        } else {
+            iroha
+                .transfer_with_receipt(from, to, &config.usd_asset_id, delta.abs(), &receipt_key)
+                .await
+                .map_err(|e| e.to_string())?
+        };
+        tx_hash = Some(hash);
+    }
+
+    let status = if target_cents == 0 { "released" } else { "held" };
+    sqlx::query!(
+        r#"
+        UPDATE card_holds
+        SET held_cents = $2, authorized_cents = $3, status = $4, version = $5,
+            last_tx_hash = COALESCE($6, last_tx_hash), updated_at = NOW()
+        WHERE unit_authorization_id = $1
+        "#,
+        auth.id,
+        target_cents,
+        auth.attributes.amount,
+        status,
+        version,
+        tx_hash
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    if delta != 0 {
+        println!(
+            "💳 Card hold {} for {}: ${:.2} held (authorization {}).",
+            if delta > 0 { "placed" } else { "reduced" },
+            card.onchain_account_id,
+            target_cents as f64 / 100.0,
+            auth.id
+        );
+    }
+    hold_for(db, &auth.id).await
+}
+
+/// CAPTURE, for the webhook: burns the held USD for a card purchase (plus
+/// anything captured beyond the hold) and hands back the rest.
+/// Returns None when nothing is held for the authorization; the caller then
+/// burns from the user's account as for any other debit.
+pub async fn capture_card_hold(
+    db: &PgPool,
+    iroha: &IrohaClient,
+    config: &CardConfig,
+    authorization_id: &str,
+    onchain_account_id: &str,
+    amount_cents: i64,
+    unit_transaction_id: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let mut tx = db.begin().await?;
+
+    let hold = sqlx::query!(
+        "SELECT held_cents, status FROM card_holds WHERE unit_authorization_id = $1 FOR UPDATE",
+        authorization_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let held_cents = match hold {
+        Some(hold) if hold.status == "held" => hold.held_cents,
+        _ => return Ok(None),
+    };
+
+    let receipt_key = format!("card_capture_{}", unit_transaction_id);
+    let tx_hash = if iroha.has_receipt(&config.hold_account_id, &receipt_key).await.map_err(|e| e.to_string())? {
+        format!("receipt:{}", receipt_key)
+    } else {
+        iroha
+            .capture_hold(
+                &config.hold_account_id,
+                onchain_account_id,
+                &config.usd_asset_id,
+                held_cents,
+                amount_cents,
+                &receipt_key,
+            )
+            .await
+            .map_err(|e| e.to_string())?
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE card_holds
+        SET held_cents = 0, captured_cents = $2, status = 'captured', unit_transaction_id = $3,
+            last_tx_hash = $4, updated_at = NOW()
+        WHERE unit_authorization_id = $1
+        "#,
+        authorization_id,
+        amount_cents,
+        unit_transaction_id,
+        tx_hash
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Some(tx_hash))
+}
+
+async fn hold_for(db: &PgPool, authorization_id: &str) -> Result<Option<CardHold>, Box<dyn Error>> {
+    let row = sqlx::query_as!(
+        CardHold,
+        r#"
+        SELECT unit_authorization_id, card_id, merchant_name, authorized_cents, held_cents, status,
+               captured_cents, created_at, updated_at
+        FROM card_holds
+        WHERE unit_authorization_id = $1
+        "#,
+        authorization_id
+    )
+    .fetch_optional(db)
+    .await?;
+    Ok(row)
+}
@@ -0,0 +1,727 @@
+use crate::core::ach::{AchService, NewAchPayment};
+use crate::core::audit::record_audit;
+use crate::core::fiat_banking::{AchDirection, FiatAsset, UnitClient};
+use crate::ledger::client::IrohaClient;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- CASH DEPOSITS ---
+// A user asks for a barcode, pays cash at a retailer, Unit credits their
+// deposit account and the webhook mints the same amount on-chain
+// (core/fiat_events.rs). A barcode takes any number of deposits until its
+// expiry date; each deposit is recorded on its own.
+// Unit's barcodes carry no amount, so the user's limits can't be handed to
+// Unit: they are enforced when the deposit settles (`record_cash_deposit`).
+// What goes over them waits in the cash hold account for an operator.
+
+/// Retail networks that accept Unit cash deposits
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetailerNetwork {
+    GreenDot, // Any Green Dot location
+    Walmart,
+    Cvs,
+    Walgreens,
+    SevenEleven,
+    DollarGeneral,
+}
+
+impl RetailerNetwork {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RetailerNetwork::GreenDot => "green_dot",
+            RetailerNetwork::Walmart => "walmart",
+            RetailerNetwork::Cvs => "cvs",
+            RetailerNetwork::Walgreens => "walgreens",
+            RetailerNetwork::SevenEleven => "seven_eleven",
+            RetailerNetwork::DollarGeneral => "dollar_general",
+        }
+    }
+
+    /// The `store` value Unit expects
+    pub fn unit_store(&self) -> &'static str {
+        match self {
+            RetailerNetwork::GreenDot => "GreenDotNetwork",
+            RetailerNetwork::Walmart => "Walmart",
+            RetailerNetwork::Cvs => "CVS",
+            RetailerNetwork::Walgreens => "Walgreens",
+            RetailerNetwork::SevenEleven => "7-Eleven",
+            RetailerNetwork::DollarGeneral => "DollarGeneral",
+        }
+    }
+}
+
+/// How much cash a user may deposit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CashLimits {
+    pub daily_limit_cents: i64,
+    pub monthly_limit_cents: i64,
+}
+
+impl CashLimits {
+    /// Defaults for users without their own limits:
+    /// CASH_DAILY_LIMIT_CENTS ($500) and CASH_MONTHLY_LIMIT_CENTS ($2,500)
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: i64| {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            daily_limit_cents: read("CASH_DAILY_LIMIT_CENTS", 50_000),
+            monthly_limit_cents: read("CASH_MONTHLY_LIMIT_CENTS", 250_000),
+        }
+    }
+}
+
+/// Default limits, and where cash over a user's limit waits
+#[derive(Debug, Clone)]
+pub struct CashConfig {
+    pub limits: CashLimits,
+    pub hold_account_id: String,
+    pub usd_asset_id: String,
+}
+
+impl CashConfig {
+    /// Reads CASH_HOLD_ACCOUNT_ID (and the default limits)
+    pub fn from_env(asset: &FiatAsset) -> Self {
+        Self {
+            limits: CashLimits::from_env(),
+            hold_account_id: std::env::var("CASH_HOLD_ACCOUNT_ID")
+                .unwrap_or_else(|_| "cash_holds@my_ecosystem".to_string()),
+            usd_asset_id: asset.usd_asset_id.clone(),
+        }
+    }
+}
+
+/// A user's limits and what they have already deposited against them
+#[derive(Debug, Serialize)]
+pub struct CashUsage {
+    pub limits: CashLimits,
+    pub deposited_today_cents: i64,
+    pub deposited_this_month_cents: i64,
+}
+
+impl CashUsage {
+    pub fn daily_remaining_cents(&self) -> i64 {
+        (self.limits.daily_limit_cents - self.deposited_today_cents).max(0)
+    }
+
+    pub fn monthly_remaining_cents(&self) -> i64 {
+        (self.limits.monthly_limit_cents - self.deposited_this_month_cents).max(0)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CashBarcode {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub unit_customer_id: String,
+    pub unit_account_id: String,
+    pub retailer: String,
+    pub barcode_number: String,
+    pub barcode_url: String,
+    pub status: String, // active | expired (reusable until then)
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One deposit that settled on a user's account
+#[derive(Debug, Serialize)]
+pub struct CashDeposit {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub barcode_id: Option<Uuid>, // None: matched no barcode, all of it held
+    pub unit_customer_id: Option<String>,
+    pub unit_account_id: String,
+    pub unit_transaction_id: String,
+    pub amount_cents: i64,
+    pub held_cents: i64,     // Part of the deposit over the user's limits: not minted to them
+    pub hold_status: String, // none | held | releasing | released | returning | returned
+    pub hold_resolved_by: Option<String>,
+    pub hold_tx_hash: Option<String>,
+    pub ach_payment_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub hold_resolved_at: Option<DateTime<Utc>>,
+}
+
+pub struct CashDepositService {
+    db: PgPool,
+    unit: Arc<UnitClient>,
+    iroha: Arc<IrohaClient>,
+    ach: Arc<AchService>,
+    config: CashConfig,
+}
+
+impl CashDepositService {
+    pub fn new(db: PgPool, unit: Arc<UnitClient>, iroha: Arc<IrohaClient>, ach: Arc<AchService>, config: CashConfig) -> Self {
+        Self { db, unit, iroha, ach, config }
+    }
+
+    /// ISSUE A BARCODE
+    /// 1. The account must be a user account mirrored on-chain for this tenant
+    /// 2. A still-valid barcode for the account is handed back instead of a new one
+    /// 3. Users at their daily/monthly cash limit get no barcode (and whatever
+    ///    they pay in over the limit is held when it settles)
+    pub async fn issue(
+        &self,
+        tenant_id: Uuid,
+        unit_customer_id: &str,
+        unit_account_id: &str,
+        retailer: RetailerNetwork,
+    ) -> Result<CashBarcode, Box<dyn Error>> {
+        let linked = sqlx::query!(
+            r#"
+            SELECT 1 AS "linked!" FROM fiat_account_links
+            WHERE unit_account_id = $1 AND tenant_id = $2 AND owner_type = 'user'
+            "#,
+            unit_account_id,
+            tenant_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        if linked.is_none() {
+            return Err("Account is not linked to an on-chain user account for this tenant".into());
+        }
+
+        // One live barcode per account (a deposit must map to exactly one barcode)
+        expire_stale_barcodes(&self.db).await?;
+        if let Some(existing) = self.active_for_account(unit_account_id).await? {
+            return Ok(existing);
+        }
+
+        let usage = self.usage(unit_customer_id).await?;
+        if usage.daily_remaining_cents() == 0 {
+            return Err(format!(
+                "Daily cash deposit limit of ${:.2} reached",
+                usage.limits.daily_limit_cents as f64 / 100.0
+            ).into());
+        }
+        if usage.monthly_remaining_cents() == 0 {
+            return Err(format!(
+                "Monthly cash deposit limit of ${:.2} reached",
+                usage.limits.monthly_limit_cents as f64 / 100.0
+            ).into());
+        }
+
+        let barcode = self
+            .unit
+            .generate_cash_deposit_barcode(unit_customer_id, unit_account_id, retailer.unit_store())
+            .await?;
+        // Unit normally tells us; if not, assume the shortest validity we have seen
+        let expires_at = barcode.expires_at.unwrap_or_else(|| Utc::now() + Duration::hours(24));
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO cash_deposit_barcodes
+            (id, tenant_id, unit_customer_id, unit_account_id, retailer, barcode_number, barcode_url, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT DO NOTHING
+            "#,
+            Uuid::new_v4(),
+            tenant_id,
+            unit_customer_id,
+            unit_account_id,
+            retailer.as_str(),
+            barcode.barcode_number,
+            barcode.barcode_url,
+            expires_at
+        )
+        .execute(&self.db)
+        .await?;
+
+        if inserted.rows_affected() == 1 {
+            println!("💵 Cash barcode issued for account {} ({}).", unit_account_id, retailer.as_str());
+        }
+
+        // Either ours, or the one a concurrent request just stored
+        self.active_for_account(unit_account_id)
+            .await?
+            .ok_or_else(|| "Barcode was issued but could not be stored".into())
+    }
+
+    /// A user's barcodes that can still be used
+    pub async fn active_for_user(&self, tenant_id: Uuid, unit_customer_id: &str) -> Result<Vec<CashBarcode>, Box<dyn Error>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, tenant_id, unit_customer_id, unit_account_id, retailer, barcode_number, barcode_url,
+                   status, expires_at, created_at
+            FROM cash_deposit_barcodes
+            WHERE tenant_id = $1 AND unit_customer_id = $2 AND status = 'active' AND expires_at > NOW()
+            ORDER BY created_at DESC
+            "#,
+            tenant_id,
+            unit_customer_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| CashBarcode {
+                id: r.id,
+                tenant_id: r.tenant_id,
+                unit_customer_id: r.unit_customer_id,
+                unit_account_id: r.unit_account_id,
+                retailer: r.retailer,
+                barcode_number: r.barcode_number,
+                barcode_url: r.barcode_url,
+                status: r.status,
+                expires_at: r.expires_at,
+                created_at: r.created_at,
+            })
+            .collect())
+    }
+
+    /// Limits for a user, and how much of them is used up
+    pub async fn usage(&self, unit_customer_id: &str) -> Result<CashUsage, Box<dyn Error>> {
+        let mut conn = self.db.acquire().await?;
+        usage_on(&mut conn, self.config.limits, unit_customer_id).await
+    }
+
+    pub async fn set_limits(
+        &self,
+        tenant_id: Uuid,
+        unit_customer_id: &str,
+        limits: CashLimits,
+    ) -> Result<CashUsage, Box<dyn Error>> {
+        if limits.daily_limit_cents < 0 || limits.monthly_limit_cents < 0 {
+            return Err("Limits cannot be negative".into());
+        }
+        if limits.daily_limit_cents > limits.monthly_limit_cents {
+            return Err("The daily limit cannot exceed the monthly limit".into());
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO cash_deposit_limits (unit_customer_id, tenant_id, daily_limit_cents, monthly_limit_cents)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (unit_customer_id) DO UPDATE
+            SET daily_limit_cents = EXCLUDED.daily_limit_cents,
+                monthly_limit_cents = EXCLUDED.monthly_limit_cents,
+                updated_at = NOW()
+            WHERE cash_deposit_limits.tenant_id = EXCLUDED.tenant_id
+            "#,
+            unit_customer_id,
+            tenant_id,
+            limits.daily_limit_cents,
+            limits.monthly_limit_cents
+        )
+        .execute(&self.db)
+        .await?;
+
+        self.usage(unit_customer_id).await
+    }
+
+    /// Deposits with cash waiting in the hold account (or on its way out of it)
+    pub async fn held_deposits(&self, tenant_id: Uuid) -> Result<Vec<CashDeposit>, Box<dyn Error>> {
+        let rows = sqlx::query_as!(
+            CashDeposit,
+            r#"
+            SELECT id, tenant_id, barcode_id, unit_customer_id, unit_account_id, unit_transaction_id, amount_cents,
+                   held_cents, hold_status, hold_resolved_by, hold_tx_hash, ach_payment_id, created_at, hold_resolved_at
+            FROM cash_deposits
+            WHERE tenant_id = $1 AND hold_status IN ('held', 'releasing', 'returning')
+            ORDER BY created_at
+            "#,
+            tenant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(rows)
+    }
+
+    /// RELEASE: an operator lets held cash through to the user after all.
+    /// 1. The deposit is claimed ('releasing') so a second click can't pay twice
+    /// 2. Hold account -> user, with a receipt on the user's account
+    /// 3. 'released' + audit entry; it now counts against the user's limits
+    /// A release that failed half way is finished by calling it again.
+    pub async fn release_hold(
+        &self,
+        tenant_id: Uuid,
+        deposit_id: Uuid,
+        operator: &str,
+        reason: &str,
+    ) -> Result<CashDeposit, Box<dyn Error>> {
+        let claimed = sqlx::query!(
+            r#"
+            UPDATE cash_deposits SET hold_status = 'releasing', hold_resolved_by = $3
+            WHERE id = $1 AND tenant_id = $2 AND hold_status IN ('held', 'releasing')
+            RETURNING held_cents, unit_account_id
+            "#,
+            deposit_id,
+            tenant_id,
+            operator
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or("Deposit has no cash on hold")?;
+
+        let account = sqlx::query!(
+            "SELECT onchain_account_id FROM fiat_account_links WHERE unit_account_id = $1",
+            claimed.unit_account_id
+        )
+        .fetch_one(&self.db)
+        .await?
+        .onchain_account_id;
+
+        let receipt_key = format!("cash_release_{}", deposit_id);
+        let tx_hash = if self.iroha.has_receipt(&account, &receipt_key).await.map_err(|e| e.to_string())? {
+            format!("receipt:{}", receipt_key) // We crashed after submitting last time
+        } else {
+            self.iroha
+                .transfer_with_receipt(
+                    &self.config.hold_account_id,
+                    &account,
+                    &self.config.usd_asset_id,
+                    claimed.held_cents,
+                    &receipt_key,
+                )
+                .await
+                .map_err(|e| e.to_string())?
+        };
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            r#"
+            UPDATE cash_deposits SET hold_status = 'released', hold_tx_hash = $2, hold_resolved_at = NOW()
+            WHERE id = $1 AND hold_status = 'releasing'
+            "#,
+            deposit_id,
+            tx_hash
+        )
+        .execute(&mut *tx)
+        .await?;
+        record_audit(
+            &mut *tx,
+            operator,
+            "cash_hold.released",
+            Some(tenant_id),
+            "cash_deposit",
+            deposit_id,
+            Some(reason),
+            serde_json::json!({ "held_cents": claimed.held_cents, "to": account, "tx_hash": tx_hash }),
+        )
+        .await?;
+        tx.commit().await?;
+
+        println!("🔓 Released ${:.2} of held cash to {}.", claimed.held_cents as f64 / 100.0, account);
+        self.deposit(tenant_id, deposit_id).await
+    }
+
+    /// RETURN: an operator sends held cash back to the depositor's own bank
+    /// account (core/ach.rs), burned from the hold account.
+    /// 1. The deposit is claimed ('returning') and gets its ACH payment id
+    ///    BEFORE the payment exists, so a retry finds it instead of sending twice
+    /// 2. ACH credit to the counterparty
+    /// 3. 'returned' + audit entry; a rejected payment puts the cash back on hold
+    pub async fn return_hold(
+        &self,
+        tenant_id: Uuid,
+        deposit_id: Uuid,
+        counterparty_id: Uuid,
+        operator: &str,
+        reason: &str,
+    ) -> Result<CashDeposit, Box<dyn Error>> {
+        let claimed = sqlx::query!(
+            r#"
+            UPDATE cash_deposits
+            SET hold_status = 'returning', hold_resolved_by = $3, ach_payment_id = COALESCE(ach_payment_id, $4)
+            WHERE id = $1 AND tenant_id = $2 AND hold_status IN ('held', 'returning')
+            RETURNING held_cents, unit_account_id, unit_customer_id, ach_payment_id AS "ach_payment_id!"
+            "#,
+            deposit_id,
+            tenant_id,
+            operator,
+            Uuid::new_v4()
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or("Deposit has no cash on hold")?;
+
+        // Deposits that matched no barcode don't know their customer: Unit does
+        let unit_customer_id = match claimed.unit_customer_id {
+            Some(customer) => customer,
+            None => self
+                .unit
+                .get_account(&claimed.unit_account_id)
+                .await
+                .map_err(|e| e.to_string())?
+                .related_id("customer")
+                .ok_or("Unit account has no customer")?,
+        };
+
+        let request = NewAchPayment {
+            unit_account_id: claimed.unit_account_id.clone(),
+            counterparty_id,
+            direction: AchDirection::Credit,
+            amount_cents: claimed.held_cents,
+            description: Some("CASH RTN".to_string()),
+        };
+        let payment = match self
+            .ach
+            .return_held_cash(claimed.ach_payment_id, tenant_id, &unit_customer_id, &request, &self.config.hold_account_id)
+            .await
+        {
+            Ok(payment) => payment,
+            Err(e) => {
+                // Never got as far as a payment (e.g. unverified bank account): back on hold
+                self.unclaim_return(deposit_id, claimed.ach_payment_id).await?;
+                return Err(e);
+            }
+        };
+        if payment.status == "rejected" {
+            self.unclaim_return(deposit_id, payment.id).await?;
+            return Err(format!(
+                "Return was rejected: {}",
+                payment.reason.as_deref().unwrap_or("no reason given")
+            ).into());
+        }
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            r#"
+            UPDATE cash_deposits SET hold_status = 'returned', hold_resolved_at = NOW()
+            WHERE id = $1 AND hold_status = 'returning'
+            "#,
+            deposit_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        record_audit(
+            &mut *tx,
+            operator,
+            "cash_hold.returned",
+            Some(tenant_id),
+            "cash_deposit",
+            deposit_id,
+            Some(reason),
+            serde_json::json!({
+                "held_cents": claimed.held_cents,
+                "counterparty_id": counterparty_id,
+                "ach_payment_id": payment.id,
+            }),
+        )
+        .await?;
+        tx.commit().await?;
+
+        println!("↩️  Returning ${:.2} of held cash by ACH ({}).", claimed.held_cents as f64 / 100.0, payment.id);
+        self.deposit(tenant_id, deposit_id).await
+    }
+
+    /// A return that sent nothing: the cash is on hold again, and the next
+    /// attempt gets a fresh payment id (this one is rejected or never existed)
+    async fn unclaim_return(&self, deposit_id: Uuid, payment_id: Uuid) -> Result<(), Box<dyn Error>> {
+        sqlx::query!(
+            r#"
+            UPDATE cash_deposits SET hold_status = 'held', hold_resolved_by = NULL, ach_payment_id = NULL
+            WHERE id = $1 AND hold_status = 'returning' AND ach_payment_id = $2
+              AND NOT EXISTS (SELECT 1 FROM ach_payments WHERE id = $2 AND status <> 'rejected')
+            "#,
+            deposit_id,
+            payment_id
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn deposit(&self, tenant_id: Uuid, deposit_id: Uuid) -> Result<CashDeposit, Box<dyn Error>> {
+        let deposit = sqlx::query_as!(
+            CashDeposit,
+            r#"
+            SELECT id, tenant_id, barcode_id, unit_customer_id, unit_account_id, unit_transaction_id, amount_cents,
+                   held_cents, hold_status, hold_resolved_by, hold_tx_hash, ach_payment_id, created_at, hold_resolved_at
+            FROM cash_deposits WHERE id = $1 AND tenant_id = $2
+            "#,
+            deposit_id,
+            tenant_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or("Cash deposit not found")?;
+        Ok(deposit)
+    }
+
+    async fn active_for_account(&self, unit_account_id: &str) -> Result<Option<CashBarcode>, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, tenant_id, unit_customer_id, unit_account_id, retailer, barcode_number, barcode_url,
+                   status, expires_at, created_at
+            FROM cash_deposit_barcodes
+            WHERE unit_account_id = $1 AND status = 'active'
+            "#,
+            unit_account_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(row.map(|r| CashBarcode {
+            id: r.id,
+            tenant_id: r.tenant_id,
+            unit_customer_id: r.unit_customer_id,
+            unit_account_id: r.unit_account_id,
+            retailer: r.retailer,
+            barcode_number: r.barcode_number,
+            barcode_url: r.barcode_url,
+            status: r.status,
+            expires_at: r.expires_at,
+            created_at: r.created_at,
+        }))
+    }
+}
+
+/// Limits for a user and what they have deposited against them. Cash that is
+/// held (or was returned) doesn't count: it never reached the user.
+async fn usage_on(
+    conn: &mut PgConnection,
+    default_limits: CashLimits,
+    unit_customer_id: &str,
+) -> Result<CashUsage, Box<dyn Error>> {
+    let limits = sqlx::query!(
+        "SELECT daily_limit_cents, monthly_limit_cents FROM cash_deposit_limits WHERE unit_customer_id = $1",
+        unit_customer_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .map(|r| CashLimits { daily_limit_cents: r.daily_limit_cents, monthly_limit_cents: r.monthly_limit_cents })
+    .unwrap_or(default_limits);
+
+    let now = Utc::now();
+    let day_start = Utc.with_ymd_and_hms(now.year(), now.month(), now.day(), 0, 0, 0).unwrap();
+    let month_start = Utc.with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0).unwrap();
+
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+            COALESCE(SUM(credited) FILTER (WHERE created_at >= $2), 0)::BIGINT AS "today!",
+            COALESCE(SUM(credited) FILTER (WHERE created_at >= $3), 0)::BIGINT AS "month!"
+        FROM (
+            SELECT created_at,
+                   amount_cents - CASE WHEN hold_status = 'released' THEN 0 ELSE held_cents END AS credited
+            FROM cash_deposits
+            WHERE unit_customer_id = $1
+        ) d
+        "#,
+        unit_customer_id,
+        day_start,
+        month_start
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(CashUsage {
+        limits,
+        deposited_today_cents: totals.today,
+        deposited_this_month_cents: totals.month,
+    })
+}
+
+/// DEPOSIT: cash settled on `unit_account_id`. Called by the Unit webhook
+/// processor before it mints; safe to call again. The barcode stays live.
+/// Returns how much of it to HOLD instead of minting to the user: what goes
+/// over their daily/monthly limit, or all of it when it matches none of our
+/// barcodes (we can't tell whose limits apply).
+pub async fn record_cash_deposit(
+    db: &PgPool,
+    default_limits: CashLimits,
+    unit_account_id: &str,
+    amount_cents: i64,
+    unit_transaction_id: &str,
+) -> Result<i64, Box<dyn Error>> {
+    let mut tx = db.begin().await?;
+
+    // Recorded already (we crashed before minting last time)
+    let recorded = sqlx::query!(
+        "SELECT held_cents FROM cash_deposits WHERE unit_transaction_id = $1",
+        unit_transaction_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(recorded) = recorded {
+        return Ok(recorded.held_cents);
+    }
+
+    let tenant_id = sqlx::query!("SELECT tenant_id FROM fiat_account_links WHERE unit_account_id = $1", unit_account_id)
+        .fetch_one(&mut *tx)
+        .await?
+        .tenant_id;
+
+    // A deposit can land just after our expiry job ran, so a barcode that
+    // expired in the last day still counts
+    let barcode = sqlx::query!(
+        r#"
+        SELECT id, unit_customer_id FROM cash_deposit_barcodes
+        WHERE unit_account_id = $1
+          AND (status = 'active' OR (status = 'expired' AND expires_at > NOW() - INTERVAL '1 day'))
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+        unit_account_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let held_cents = match &barcode {
+        Some(barcode) => {
+            // One deposit at a time per user, so two can't both fit the same headroom
+            sqlx::query!("SELECT pg_advisory_xact_lock(hashtext($1))", barcode.unit_customer_id)
+                .execute(&mut *tx)
+                .await?;
+            let usage = usage_on(&mut *tx, default_limits, &barcode.unit_customer_id).await?;
+            let remaining = usage.daily_remaining_cents().min(usage.monthly_remaining_cents());
+            (amount_cents - remaining).max(0)
+        }
+        None => amount_cents,
+    };
+
+    sqlx::query!(
+        r#"
+        INSERT INTO cash_deposits
+        (id, tenant_id, barcode_id, unit_customer_id, unit_account_id, unit_transaction_id, amount_cents, held_cents, hold_status)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        Uuid::new_v4(),
+        tenant_id,
+        barcode.as_ref().map(|b| b.id),
+        barcode.as_ref().map(|b| b.unit_customer_id.clone()),
+        unit_account_id,
+        unit_transaction_id,
+        amount_cents,
+        held_cents,
+        if held_cents > 0 { "held" } else { "none" }
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    match barcode {
+        None => println!(
+            "⚠️  Cash deposit {} on account {} matched no barcode: ${:.2} held.",
+            unit_transaction_id, unit_account_id, amount_cents as f64 / 100.0
+        ),
+        Some(barcode) if held_cents > 0 => println!(
+            "⚠️  Cash deposit {} is over {}'s limits: ${:.2} held.",
+            unit_transaction_id, barcode.unit_customer_id, held_cents as f64 / 100.0
+        ),
+        Some(_) => {}
+    }
+    Ok(held_cents)
+}
+
+/// EXPIRED: barcodes past their expiry (the cash_barcodes job)
+pub async fn expire_stale_barcodes(db: &PgPool) -> Result<u64, Box<dyn Error>> {
+    let expired = sqlx::query!(
+        "UPDATE cash_deposit_barcodes SET status = 'expired' WHERE status = 'active' AND expires_at <= NOW()"
+    )
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    if expired > 0 {
+        println!("⌛ {} cash barcode(s) expired.", expired);
+    }
+    Ok(expired)
+}
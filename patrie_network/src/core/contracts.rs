@@ -0,0 +1,504 @@
+use crate::core::invoice::{BillingPeriod, LineCategory, LineItem};
+use crate::core::notifications::notify_tenant_admin;
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::error::Error;
+use std::fmt;
+use uuid::Uuid;
+
+/// How often a contract's recurring fees are invoiced (always in advance)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BillingFrequency {
+    Monthly,
+    Quarterly,
+    Annual, // 12 months upfront
+}
+
+impl BillingFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BillingFrequency::Monthly => "monthly",
+            BillingFrequency::Quarterly => "quarterly",
+            BillingFrequency::Annual => "annual",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value {
+            "monthly" => Ok(BillingFrequency::Monthly),
+            "quarterly" => Ok(BillingFrequency::Quarterly),
+            "annual" => Ok(BillingFrequency::Annual),
+            other => Err(format!("Unknown billing frequency: {}", other).into()),
+        }
+    }
+
+    pub fn months(&self) -> i32 {
+        match self {
+            BillingFrequency::Monthly => 1,
+            BillingFrequency::Quarterly => 3,
+            BillingFrequency::Annual => 12,
+        }
+    }
+}
+
+impl fmt::Display for BillingFrequency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// What the API sends to sign a contract
+#[derive(Debug, Deserialize)]
+pub struct NewContract {
+    pub term_start: String, // "2025-01"
+    pub term_months: u32,   // e.g. 12
+    pub billing_frequency: BillingFrequency,
+    #[serde(default)]
+    pub committed_monthly_cents: i64,
+    #[serde(default = "default_true")]
+    pub auto_renew: bool,
+    pub renewal_notice_days: Option<i32>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// One row of `contracts`
+#[derive(Debug, Clone, Serialize)]
+pub struct Contract {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub status: String, // active | renewed | expired | cancelled
+    pub term_start: NaiveDate,
+    pub term_end: NaiveDate, // Exclusive
+    pub billing_frequency: BillingFrequency,
+    pub committed_monthly_cents: i64,
+    pub auto_renew: bool,
+    pub renewal_notice_days: i32,
+    pub notice_sent_at: Option<DateTime<Utc>>,
+    pub renewed_from: Option<Uuid>,
+}
+
+impl Contract {
+    /// SIGN A CONTRACT
+    /// The term must be whole billing cycles and must not overlap another contract.
+    pub async fn create(db: &PgPool, tenant_id: Uuid, new: &NewContract) -> Result<Contract, Box<dyn Error>> {
+        let start = BillingPeriod::parse(&new.term_start)?.start;
+        if new.term_months == 0 || new.term_months as i32 % new.billing_frequency.months() != 0 {
+            return Err(format!(
+                "A {}-month term cannot be billed {} (cycles of {} months)",
+                new.term_months, new.billing_frequency, new.billing_frequency.months()
+            ).into());
+        }
+        if new.committed_monthly_cents < 0 {
+            return Err("Committed spend cannot be negative".into());
+        }
+        let end = start + Months::new(new.term_months);
+
+        let overlapping = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM contracts
+            WHERE tenant_id = $1 AND status <> 'cancelled'
+              AND term_start < $3 AND term_end > $2
+            "#,
+            tenant_id,
+            start,
+            end
+        )
+        .fetch_one(db)
+        .await?
+        .count;
+        if overlapping > 0 {
+            return Err("The tenant already has a contract for part of this term".into());
+        }
+
+        Self::insert(
+            db,
+            tenant_id,
+            start,
+            end,
+            new.billing_frequency,
+            new.committed_monthly_cents,
+            new.auto_renew,
+            new.renewal_notice_days.unwrap_or(60),
+            None,
+        ).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert(
+        db: &PgPool,
+        tenant_id: Uuid,
+        term_start: NaiveDate,
+        term_end: NaiveDate,
+        billing_frequency: BillingFrequency,
+        committed_monthly_cents: i64,
+        auto_renew: bool,
+        renewal_notice_days: i32,
+        renewed_from: Option<Uuid>,
+    ) -> Result<Contract, Box<dyn Error>> {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO contracts
+            (id, tenant_id, term_start, term_end, billing_frequency, committed_monthly_cents,
+             auto_renew, renewal_notice_days, renewed_from)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            id,
+            tenant_id,
+            term_start,
+            term_end,
+            billing_frequency.as_str(),
+            committed_monthly_cents,
+            auto_renew,
+            renewal_notice_days,
+            renewed_from
+        )
+        .execute(db)
+        .await?;
+
+        Ok(Contract {
+            id,
+            tenant_id,
+            status: "active".to_string(),
+            term_start,
+            term_end,
+            billing_frequency,
+            committed_monthly_cents,
+            auto_renew,
+            renewal_notice_days,
+            notice_sent_at: None,
+            renewed_from,
+        })
+    }
+
+    /// The contract whose term covers `date` (renewed/expired ones still cover their own term)
+    pub async fn covering(db: &PgPool, tenant_id: Uuid, date: NaiveDate) -> Result<Option<Contract>, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id FROM contracts
+            WHERE tenant_id = $1 AND status <> 'cancelled'
+              AND term_start <= $2 AND term_end > $2
+            "#,
+            tenant_id,
+            date
+        )
+        .fetch_optional(db)
+        .await?;
+
+        match row {
+            Some(r) => Self::get(db, r.id).await,
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get(db: &PgPool, contract_id: Uuid) -> Result<Option<Contract>, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, tenant_id, status, term_start, term_end, billing_frequency, committed_monthly_cents,
+                   auto_renew, renewal_notice_days, notice_sent_at, renewed_from
+            FROM contracts
+            WHERE id = $1
+            "#,
+            contract_id
+        )
+        .fetch_optional(db)
+        .await?;
+
+        match row {
+            Some(r) => Ok(Some(Contract {
+                id: r.id,
+                tenant_id: r.tenant_id,
+                status: r.status,
+                term_start: r.term_start,
+                term_end: r.term_end,
+                billing_frequency: BillingFrequency::parse(&r.billing_frequency)?,
+                committed_monthly_cents: r.committed_monthly_cents,
+                auto_renew: r.auto_renew,
+                renewal_notice_days: r.renewal_notice_days,
+                notice_sent_at: r.notice_sent_at,
+                renewed_from: r.renewed_from,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn list_for_tenant(db: &PgPool, tenant_id: Uuid) -> Result<Vec<Contract>, Box<dyn Error>> {
+        let ids = sqlx::query!(
+            "SELECT id FROM contracts WHERE tenant_id = $1 ORDER BY term_start",
+            tenant_id
+        )
+        .fetch_all(db)
+        .await?;
+
+        let mut contracts = Vec::with_capacity(ids.len());
+        for row in ids {
+            if let Some(c) = Self::get(db, row.id).await? {
+                contracts.push(c);
+            }
+        }
+        Ok(contracts)
+    }
+
+    pub async fn set_auto_renew(db: &PgPool, tenant_id: Uuid, contract_id: Uuid, auto_renew: bool) -> Result<(), Box<dyn Error>> {
+        let result = sqlx::query!(
+            "UPDATE contracts SET auto_renew = $3 WHERE id = $1 AND tenant_id = $2 AND status = 'active'",
+            contract_id,
+            tenant_id,
+            auto_renew
+        )
+        .execute(db)
+        .await?;
+
+        if result.rows_affected() != 1 {
+            return Err("No active contract with this id".into());
+        }
+        Ok(())
+    }
+
+    fn term_months(&self) -> i32 {
+        months_between(self.term_start, self.term_end)
+    }
+
+    /// The billing cycle containing `period`: its first month and its length
+    /// (the last cycle is cut short if the term ends first)
+    pub fn cycle_containing(&self, period: BillingPeriod) -> (BillingPeriod, i32) {
+        let cycle = self.billing_frequency.months();
+        let offset = months_between(self.term_start, period.start) / cycle * cycle;
+        let months = cycle.min(self.term_months() - offset);
+        (BillingPeriod::containing(self.term_start + Months::new(offset as u32)), months)
+    }
+
+    /// Recurring fees are invoiced in the first month of each cycle
+    pub fn is_cycle_start(&self, period: BillingPeriod) -> bool {
+        self.cycle_containing(period).0 == period
+    }
+
+    /// The last month of a cycle: its usage completes the commitment
+    pub fn is_cycle_end(&self, period: BillingPeriod) -> bool {
+        let (start, months) = self.cycle_containing(period);
+        months_between(start.start, period.start) == months - 1
+    }
+
+    /// A label for a cycle, e.g. "2025-01 to 2025-03"
+    pub fn cycle_label(start: BillingPeriod, months: i32) -> String {
+        let last = BillingPeriod::containing(start.start + Months::new((months - 1) as u32));
+        format!("{} to {}", start, last)
+    }
+
+    /// COMMITTED SPEND TRUE-UP
+    /// When `usage_period` closes a cycle, charges the shortfall between the
+    /// committed usage spend for the cycle and the overage actually billed for it.
+    /// `current_lines` are the lines being calculated (they bill `usage_period`).
+    pub async fn true_up_line(
+        &self,
+        db: &PgPool,
+        usage_period: BillingPeriod,
+        current_lines: &[LineItem],
+    ) -> Result<Option<LineItem>, Box<dyn Error>> {
+        if self.committed_monthly_cents == 0 || !self.is_cycle_end(usage_period) {
+            return Ok(None);
+        }
+
+        let (cycle_start, months) = self.cycle_containing(usage_period);
+        let committed = self.committed_monthly_cents * months as i64;
+
+        // Usage is billed in arrears: the cycle's earlier months were billed
+        // on the invoices for the months after them
+        let billed_before = sqlx::query!(
+            r#"
+            SELECT COALESCE(SUM(li.amount_cents), 0)::BIGINT AS "used!"
+            FROM invoices i
+            JOIN invoice_line_items li ON li.invoice_id = i.id
+            WHERE i.tenant_id = $1 AND i.status <> 'draft'
+              AND li.category = 'usage_overage'
+              AND i.period_start > $2 AND i.period_start <= $3
+            "#,
+            self.tenant_id,
+            cycle_start.start,
+            usage_period.start
+        )
+        .fetch_one(db)
+        .await?
+        .used;
+
+        let billed_now: i64 = current_lines
+            .iter()
+            .filter(|l| l.category == LineCategory::UsageOverage)
+            .map(|l| l.amount_cents)
+            .sum();
+
+        let shortfall = committed - billed_before - billed_now;
+        if shortfall <= 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(LineItem::from_cents(
+            LineCategory::CommitmentTrueUp,
+            &format!(
+                "Committed Usage True-up ({}: ${:.2} committed, ${:.2} used)",
+                Self::cycle_label(cycle_start, months),
+                committed as f64 / 100.0,
+                (billed_before + billed_now) as f64 / 100.0
+            ),
+            shortfall,
+        )))
+    }
+}
+
+/// Whole months from `from` to `to` (both the 1st of a month)
+fn months_between(from: NaiveDate, to: NaiveDate) -> i32 {
+    (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32
+}
+
+// --- RENEWALS & EXPIRY ---
+
+/// Run daily. Sends renewal/expiry notices, then renews or expires ended terms.
+pub async fn process_contract_renewals(db: &PgPool) -> Result<(), Box<dyn Error>> {
+    let today = Utc::now().date_naive();
+
+    // 1. Notices, `renewal_notice_days` before the term ends
+    let due = sqlx::query!(
+        r#"
+        SELECT id, tenant_id, term_end, auto_renew
+        FROM contracts
+        WHERE status = 'active' AND notice_sent_at IS NULL
+          AND term_end - renewal_notice_days <= $1
+        "#,
+        today
+    )
+    .fetch_all(db)
+    .await?;
+
+    for c in due {
+        let (kind, subject, body) = if c.auto_renew {
+            (
+                "contract_renewal_notice",
+                "Your contract renews soon",
+                format!("Your contract renews automatically on {}. Contact us before then to change or cancel it.", c.term_end),
+            )
+        } else {
+            (
+                "contract_expiry_notice",
+                "Your contract is ending",
+                format!("Your contract ends on {}. After that you will be billed monthly at list prices.", c.term_end),
+            )
+        };
+        notify_tenant_admin(db, c.tenant_id, kind, subject, &body).await?;
+
+        sqlx::query!("UPDATE contracts SET notice_sent_at = NOW() WHERE id = $1", c.id)
+            .execute(db)
+            .await?;
+    }
+
+    // 2. Terms that have ended
+    let ended = sqlx::query!(
+        r#"
+        SELECT id FROM contracts WHERE status = 'active' AND term_end <= $1
+        "#,
+        today
+    )
+    .fetch_all(db)
+    .await?;
+
+    for row in ended {
+        let contract = Contract::get(db, row.id).await?.ok_or("Contract disappeared")?;
+
+        if contract.auto_renew {
+            // Only one worker wins the status swap, so a contract renews once
+            let claimed = sqlx::query!(
+                "UPDATE contracts SET status = 'renewed' WHERE id = $1 AND status = 'active'",
+                contract.id
+            )
+            .execute(db)
+            .await?
+            .rows_affected();
+            if claimed != 1 {
+                continue;
+            }
+
+            let next = Contract::insert(
+                db,
+                contract.tenant_id,
+                contract.term_end,
+                contract.term_end + Months::new(contract.term_months() as u32),
+                contract.billing_frequency,
+                contract.committed_monthly_cents,
+                true,
+                contract.renewal_notice_days,
+                Some(contract.id),
+            ).await?;
+            println!("🔁 Contract {} renewed as {} (until {}).", contract.id, next.id, next.term_end);
+        } else {
+            sqlx::query!("UPDATE contracts SET status = 'expired' WHERE id = $1 AND status = 'active'", contract.id)
+                .execute(db)
+                .await?;
+            notify_tenant_admin(
+                db,
+                contract.tenant_id,
+                "contract_expired",
+                "Your contract has ended",
+                "Your contract has ended. You are now billed monthly at list prices.",
+            ).await?;
+            println!("⌛ Contract {} expired.", contract.id);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract(term_start: &str, term_months: u32, billing_frequency: BillingFrequency) -> Contract {
+        let term_start = BillingPeriod::parse(term_start).unwrap().start;
+        Contract {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            status: "active".to_string(),
+            term_start,
+            term_end: term_start + Months::new(term_months),
+            billing_frequency,
+            committed_monthly_cents: 0,
+            auto_renew: true,
+            renewal_notice_days: 30,
+            notice_sent_at: None,
+            renewed_from: None,
+        }
+    }
+
+    fn period(value: &str) -> BillingPeriod {
+        BillingPeriod::parse(value).unwrap()
+    }
+
+    #[test]
+    fn quarterly_cycles_follow_the_term_start() {
+        let c = contract("2025-02", 12, BillingFrequency::Quarterly);
+        assert_eq!(c.cycle_containing(period("2025-06")), (period("2025-05"), 3));
+        assert!(c.is_cycle_start(period("2025-02")));
+        assert!(!c.is_cycle_start(period("2025-03")));
+        assert!(c.is_cycle_end(period("2025-04")));
+        assert!(c.is_cycle_end(period("2026-01"))); // Last cycle spans the new year
+        assert_eq!(Contract::cycle_label(period("2025-11"), 3), "2025-11 to 2026-01");
+    }
+
+    #[test]
+    fn last_cycle_is_cut_short_by_the_term() {
+        let c = contract("2025-01", 5, BillingFrequency::Quarterly);
+        assert_eq!(c.cycle_containing(period("2025-05")), (period("2025-04"), 2));
+        assert!(c.is_cycle_end(period("2025-05")));
+        assert!(!c.is_cycle_end(period("2025-04")));
+    }
+
+    #[test]
+    fn annual_contracts_bill_once() {
+        let c = contract("2025-01", 24, BillingFrequency::Annual);
+        assert!(c.is_cycle_start(period("2026-01")));
+        assert!(!c.is_cycle_start(period("2025-07")));
+        assert_eq!(months_between(period("2024-11").start, period("2025-02").start), 3);
+    }
+}
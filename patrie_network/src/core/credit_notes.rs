@@ -0,0 +1,460 @@
+use crate::core::adjustments::BillingAdjustment;
+use crate::core::audit::record_audit;
+use crate::core::billing_engine::{BillingEngine, TenantRefund};
+use crate::core::invoice::{Invoice, InvoiceStatus, PaymentMethod};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// A refund still pending after this long never heard back from its payment
+const STUCK_REFUND_MINUTES: i32 = 15;
+
+/// How the tenant gets the money back
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CreditResolution {
+    Refund,        // Back the way the invoice was paid: Unit deposit account or on-chain treasury
+    AccountCredit, // Credit balance, applied automatically to future invoices
+}
+
+impl CreditResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CreditResolution::Refund => "refund",
+            CreditResolution::AccountCredit => "account_credit",
+        }
+    }
+}
+
+/// Credit against ONE line of the original invoice
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreditLine {
+    pub line_item_id: Uuid,
+    pub amount_cents: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreditNote {
+    pub id: Uuid,
+    pub invoice_id: Uuid,
+    pub tenant_id: Uuid,
+    pub resolution: CreditResolution,
+    pub status: String,
+    pub total_cents: i64,
+    pub refund_cents: i64, // Cash part of a refund; the rest went to account credit
+    pub reason: String,
+    pub operator: String,
+    pub unit_payment_id: Option<String>,
+    pub refund_tx_hash: Option<String>, // Refunds of on-chain invoices
+    pub lines: Vec<CreditLine>,
+}
+
+/// The tenant's account credit ledger (+ granted, - consumed)
+pub struct TenantCredit;
+
+impl TenantCredit {
+    pub async fn balance(db: &PgPool, tenant_id: Uuid) -> Result<i64, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"SELECT COALESCE(SUM(amount_cents), 0)::BIGINT AS "balance!" FROM tenant_credits WHERE tenant_id = $1"#,
+            tenant_id
+        )
+        .fetch_one(db)
+        .await?;
+        Ok(row.balance)
+    }
+
+    /// Credit used to pay part of an invoice
+    pub async fn consume(
+        tx: &mut Transaction<'_, Postgres>,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        amount_cents: i64,
+    ) -> Result<(), Box<dyn Error>> {
+        Self::insert(tx, tenant_id, -amount_cents, None, Some(invoice_id)).await
+    }
+
+    /// An invoice that ended up negative (credits > charges) carries the rest forward
+    pub async fn carry_forward(
+        tx: &mut Transaction<'_, Postgres>,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        amount_cents: i64,
+    ) -> Result<(), Box<dyn Error>> {
+        Self::insert(tx, tenant_id, amount_cents, None, Some(invoice_id)).await
+    }
+
+    async fn insert(
+        tx: &mut Transaction<'_, Postgres>,
+        tenant_id: Uuid,
+        amount_cents: i64,
+        credit_note_id: Option<Uuid>,
+        invoice_id: Option<Uuid>,
+    ) -> Result<(), Box<dyn Error>> {
+        sqlx::query!(
+            r#"
+            INSERT INTO tenant_credits (id, tenant_id, amount_cents, credit_note_id, invoice_id)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            Uuid::new_v4(),
+            tenant_id,
+            amount_cents,
+            credit_note_id,
+            invoice_id
+        )
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+}
+
+pub struct CreditNoteService {
+    db: PgPool,
+    billing: Arc<BillingEngine>,
+}
+
+impl CreditNoteService {
+    pub fn new(db: PgPool, billing: Arc<BillingEngine>) -> Self {
+        Self { db, billing }
+    }
+
+    /// ISSUE A CREDIT NOTE
+    /// 1. Validates every credited line against the original invoice
+    /// 2. Stores the note + audit entry (and grants account credit) atomically
+    /// 3. For refunds, sends the money back the way the invoice was paid, up to
+    ///    what the invoice actually collected: what account credit paid for
+    ///    comes back as account credit
+    pub async fn issue(
+        &self,
+        invoice_id: Uuid,
+        lines: &[CreditLine],
+        resolution: CreditResolution,
+        reason: &str,
+        operator: &str,
+    ) -> Result<CreditNote, Box<dyn Error>> {
+        if reason.trim().is_empty() || operator.trim().is_empty() {
+            return Err("Credit notes require an operator and a reason".into());
+        }
+        if lines.is_empty() || lines.iter().any(|l| l.amount_cents <= 0) {
+            return Err("Credit lines must have positive amounts".into());
+        }
+
+        let invoice = Invoice::get(&self.db, invoice_id).await?.ok_or("Invoice not found")?;
+        match (invoice.status, resolution) {
+            (InvoiceStatus::Draft, _) => return Err("Cannot credit a draft invoice".into()),
+            (status, CreditResolution::Refund) if status != InvoiceStatus::Paid => {
+                return Err("Only paid invoices can be refunded; use account credit instead".into())
+            }
+            _ => {}
+        }
+
+        let id = Uuid::new_v4();
+        let total_cents: i64 = lines.iter().map(|l| l.amount_cents).sum();
+
+        let mut tx = self.db.begin().await?;
+
+        // One credit note per invoice at a time, so refunds can't both fit under what was collected
+        sqlx::query!("SELECT id FROM invoices WHERE id = $1 FOR UPDATE", invoice.id)
+            .execute(&mut *tx)
+            .await?;
+
+        let refund_cents = match resolution {
+            CreditResolution::Refund => {
+                let refunded = sqlx::query!(
+                    r#"SELECT COALESCE(SUM(refund_cents), 0)::BIGINT AS "refunded!" FROM credit_notes WHERE invoice_id = $1"#,
+                    invoice.id
+                )
+                .fetch_one(&mut *tx)
+                .await?
+                .refunded;
+                total_cents.min((collected_cents(&invoice) - refunded).max(0))
+            }
+            CreditResolution::AccountCredit => 0,
+        };
+        let credit_cents = total_cents - refund_cents;
+        let status = if refund_cents > 0 { "refund_pending" } else { "issued" };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO credit_notes (id, invoice_id, tenant_id, resolution, status, total_cents, refund_cents, reason, operator)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            id,
+            invoice.id,
+            invoice.tenant_id,
+            resolution.as_str(),
+            status,
+            total_cents,
+            refund_cents,
+            reason,
+            operator
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for line in lines {
+            // Never credit more than what is left on the original line
+            let original = sqlx::query!(
+                r#"
+                SELECT
+                    li.amount_cents,
+                    COALESCE((SELECT SUM(cl.amount_cents) FROM credit_note_lines cl
+                              WHERE cl.invoice_line_item_id = li.id), 0)::BIGINT AS "already_credited!"
+                FROM invoice_line_items li
+                WHERE li.id = $1 AND li.invoice_id = $2
+                FOR UPDATE
+                "#,
+                line.line_item_id,
+                invoice.id
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| format!("Line {} is not on invoice {}", line.line_item_id, invoice.id))?;
+
+            if original.already_credited + line.amount_cents > original.amount_cents {
+                return Err(format!(
+                    "Line {} can only be credited up to {} cents",
+                    line.line_item_id,
+                    original.amount_cents - original.already_credited
+                ).into());
+            }
+
+            sqlx::query!(
+                "INSERT INTO credit_note_lines (credit_note_id, invoice_line_item_id, amount_cents) VALUES ($1, $2, $3)",
+                id,
+                line.line_item_id,
+                line.amount_cents
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if credit_cents > 0 {
+            TenantCredit::insert(&mut tx, invoice.tenant_id, credit_cents, Some(id), None).await?;
+        }
+
+        record_audit(
+            &mut *tx,
+            operator,
+            "credit_note.issued",
+            Some(invoice.tenant_id),
+            "credit_note",
+            id,
+            Some(reason),
+            serde_json::json!({
+                "invoice_id": invoice.id,
+                "resolution": resolution.as_str(),
+                "total_cents": total_cents,
+                "refund_cents": refund_cents,
+                "lines": lines,
+            }),
+        ).await?;
+
+        tx.commit().await?;
+
+        let mut note = CreditNote {
+            id,
+            invoice_id: invoice.id,
+            tenant_id: invoice.tenant_id,
+            resolution,
+            status: status.to_string(),
+            total_cents,
+            refund_cents,
+            reason: reason.to_string(),
+            operator: operator.to_string(),
+            unit_payment_id: None,
+            refund_tx_hash: None,
+            lines: lines.to_vec(),
+        };
+
+        if note.refund_cents > 0 {
+            self.send_refund(&mut note).await?;
+        }
+
+        Ok(note)
+    }
+
+    /// Retries a refund that failed, or one stuck in refund_pending (we crashed
+    /// before hearing back). Same idempotency key, so never paid twice.
+    pub async fn retry_refund(&self, credit_note_id: Uuid, operator: &str) -> Result<CreditNote, Box<dyn Error>> {
+        let mut note = self.get(credit_note_id).await?.ok_or("Credit note not found")?;
+        let retryable = match note.status.as_str() {
+            "refund_failed" => true,
+            // A fresh one may still be on its way out from `issue`
+            "refund_pending" => sqlx::query!(
+                r#"
+                SELECT created_at < NOW() - make_interval(mins => $2) AS "stuck!"
+                FROM credit_notes WHERE id = $1
+                "#,
+                note.id,
+                STUCK_REFUND_MINUTES
+            )
+            .fetch_one(&self.db)
+            .await?
+            .stuck,
+            _ => false,
+        };
+        if !retryable {
+            return Err(format!("Credit note is {}, nothing to retry", note.status).into());
+        }
+
+        record_audit(
+            &self.db,
+            operator,
+            "credit_note.refund_retried",
+            Some(note.tenant_id),
+            "credit_note",
+            note.id,
+            None,
+            serde_json::json!({}),
+        ).await?;
+
+        self.send_refund(&mut note).await?;
+        Ok(note)
+    }
+
+    async fn send_refund(&self, note: &mut CreditNote) -> Result<(), Box<dyn Error>> {
+        let invoice = Invoice::get(&self.db, note.invoice_id).await?.ok_or("Invoice not found")?;
+        let result = self.billing.refund_to_tenant(
+            &invoice,
+            note.refund_cents,
+            "Refund - Patrie Network",
+            &format!("credit-note-{}", note.id),
+        ).await;
+
+        let (status, payment_id, tx_hash) = match &result {
+            Ok(TenantRefund::Unit(payment_id)) => ("refunded", Some(payment_id.clone()), None),
+            Ok(TenantRefund::Onchain(tx_hash)) => ("refunded", None, Some(tx_hash.clone())),
+            Err(_) => ("refund_failed", None, None),
+        };
+
+        sqlx::query!(
+            "UPDATE credit_notes SET status = $2, unit_payment_id = $3, refund_tx_hash = $4 WHERE id = $1",
+            note.id,
+            status,
+            payment_id,
+            tx_hash
+        )
+        .execute(&self.db)
+        .await?;
+
+        record_audit(
+            &self.db,
+            "system",
+            &format!("credit_note.{}", status),
+            Some(note.tenant_id),
+            "credit_note",
+            note.id,
+            None,
+            serde_json::json!({ "unit_payment_id": payment_id, "refund_tx_hash": tx_hash }),
+        ).await?;
+
+        note.status = status.to_string();
+        note.unit_payment_id = payment_id;
+        note.refund_tx_hash = tx_hash;
+        result.map(|_| ())
+    }
+
+    /// Manual one-off charge (+) or credit (-) for the next invoice, with its audit entry
+    pub async fn create_adjustment(
+        &self,
+        tenant_id: Uuid,
+        description: &str,
+        amount_cents: i64,
+        reason: &str,
+        operator: &str,
+    ) -> Result<BillingAdjustment, Box<dyn Error>> {
+        if reason.trim().is_empty() || operator.trim().is_empty() {
+            return Err("Manual adjustments require an operator and a reason".into());
+        }
+        if amount_cents == 0 {
+            return Err("Adjustment amount cannot be zero".into());
+        }
+
+        let adjustment = BillingAdjustment::create(&self.db, tenant_id, description, amount_cents).await?;
+
+        record_audit(
+            &self.db,
+            operator,
+            "adjustment.created",
+            Some(tenant_id),
+            "billing_adjustment",
+            adjustment.id,
+            Some(reason),
+            serde_json::json!({ "description": description, "amount_cents": amount_cents }),
+        ).await?;
+
+        Ok(adjustment)
+    }
+
+    pub async fn get(&self, credit_note_id: Uuid) -> Result<Option<CreditNote>, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, invoice_id, tenant_id, resolution, status, total_cents, refund_cents, reason, operator,
+                   unit_payment_id, refund_tx_hash
+            FROM credit_notes WHERE id = $1
+            "#,
+            credit_note_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let r = match row {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+
+        let lines = sqlx::query!(
+            "SELECT invoice_line_item_id, amount_cents FROM credit_note_lines WHERE credit_note_id = $1",
+            r.id
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|l| CreditLine { line_item_id: l.invoice_line_item_id, amount_cents: l.amount_cents })
+        .collect();
+
+        Ok(Some(CreditNote {
+            id: r.id,
+            invoice_id: r.invoice_id,
+            tenant_id: r.tenant_id,
+            resolution: if r.resolution == "refund" { CreditResolution::Refund } else { CreditResolution::AccountCredit },
+            status: r.status,
+            total_cents: r.total_cents,
+            refund_cents: r.refund_cents,
+            reason: r.reason,
+            operator: r.operator,
+            unit_payment_id: r.unit_payment_id,
+            refund_tx_hash: r.refund_tx_hash,
+            lines,
+        }))
+    }
+
+    pub async fn list_for_tenant(&self, tenant_id: Uuid) -> Result<Vec<CreditNote>, Box<dyn Error>> {
+        let ids = sqlx::query!(
+            "SELECT id FROM credit_notes WHERE tenant_id = $1 ORDER BY created_at DESC",
+            tenant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut notes = Vec::with_capacity(ids.len());
+        for row in ids {
+            if let Some(note) = self.get(row.id).await? {
+                notes.push(note);
+            }
+        }
+        Ok(notes)
+    }
+}
+
+/// What the tenant actually paid for an invoice. Account credit that covered
+/// part of it is already netted out of the total; an invoice credit covered
+/// entirely collected nothing.
+fn collected_cents(invoice: &Invoice) -> i64 {
+    match invoice.payment_method {
+        Some(PaymentMethod::AccountCredit) => 0,
+        _ => invoice.total_cents.max(0),
+    }
+}
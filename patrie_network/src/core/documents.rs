@@ -0,0 +1,288 @@
+use crate::core::invoice::{Invoice, LineCategory, LineItem};
+use crate::core::statements::Statement;
+use printpdf::{BuiltinFont, Mm, PdfDocument};
+use std::error::Error;
+use tera::{Context, Tera};
+
+// The HTML layout lives next to the crate so design changes don't touch Rust code
+const INVOICE_TEMPLATE: &str = include_str!("../../documents/invoice.html.tera");
+
+/// Formats cents as "$1,234.56"
+pub fn format_usd(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.abs();
+    let dollars = (cents / 100).to_string();
+
+    // Insert thousands separators
+    let mut grouped = String::new();
+    for (i, ch) in dollars.chars().enumerate() {
+        if i > 0 && (dollars.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(ch);
+    }
+
+    format!("{}${}.{:02}", sign, grouped, cents % 100)
+}
+
+fn subtotal(lines: &[LineItem], category: LineCategory) -> i64 {
+    lines.iter().filter(|l| l.category == category).map(|l| l.amount_cents).sum()
+}
+
+/// Renders the itemized invoice as a standalone HTML page
+pub fn render_invoice_html(invoice: &Invoice, lines: &[LineItem]) -> Result<String, Box<dyn Error>> {
+    let mut ctx = Context::new();
+    ctx.insert("invoice_id", &invoice.id.to_string());
+    ctx.insert("tenant_id", &invoice.tenant_id.to_string());
+    ctx.insert("period", &invoice.period.to_string());
+    ctx.insert("status", invoice.status.as_str());
+    ctx.insert("total", &format_usd(invoice.total_cents));
+
+    let rendered_lines: Vec<_> = lines
+        .iter()
+        .map(|l| serde_json::json!({
+            "description": l.description,
+            "category": l.category.as_str(),
+            "amount": format_usd(l.amount_cents),
+        }))
+        .collect();
+    ctx.insert("lines", &rendered_lines);
+
+    // Compliance: show the split between what we keep and what we forward
+    ctx.insert("platform_fees", &format_usd(subtotal(lines, LineCategory::PlatformFee)));
+    ctx.insert("pass_through", &format_usd(subtotal(lines, LineCategory::PassThroughPremium)));
+    ctx.insert("admin_fees", &format_usd(subtotal(lines, LineCategory::AdminFee)));
+    ctx.insert("discounts", &format_usd(subtotal(lines, LineCategory::Discount)));
+    ctx.insert("adjustments", &format_usd(subtotal(lines, LineCategory::Adjustment)));
+    ctx.insert("usage_overage", &format_usd(subtotal(lines, LineCategory::UsageOverage)));
+    ctx.insert("commitment_true_up", &format_usd(subtotal(lines, LineCategory::CommitmentTrueUp)));
+    ctx.insert("account_credit", &format_usd(subtotal(lines, LineCategory::AccountCredit)));
+
+    Ok(Tera::one_off(INVOICE_TEMPLATE, &ctx, true)?)
+}
+
+/// Renders the same invoice as an A4 PDF (as many pages as it takes)
+pub fn render_invoice_pdf(invoice: &Invoice, lines: &[LineItem]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (doc, first_page, first_layer) = PdfDocument::new(
+        format!("Invoice {}", invoice.id),
+        Mm(210.0),
+        Mm(297.0),
+        "Invoice",
+    );
+    let font = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let mut canvas = doc.get_page(first_page).get_layer(first_layer);
+
+    // 1. Header
+    let mut y = 270.0;
+    canvas.use_text("Patrie Network Invoice", 18.0, Mm(20.0), Mm(y), &bold);
+    y -= 10.0;
+    for meta in [
+        format!("Invoice #{}", invoice.id),
+        format!("Tenant: {}", invoice.tenant_id),
+        format!("Billing Period: {}", invoice.period),
+        format!("Status: {}", invoice.status),
+    ] {
+        canvas.use_text(meta, 10.0, Mm(20.0), Mm(y), &font);
+        y -= 6.0;
+    }
+
+    // 2. Line Items (the column headings are repeated on every new page)
+    y -= 6.0;
+    canvas.use_text("Description", 10.0, Mm(20.0), Mm(y), &bold);
+    canvas.use_text("Category", 10.0, Mm(110.0), Mm(y), &bold);
+    canvas.use_text("Amount", 10.0, Mm(165.0), Mm(y), &bold);
+    y -= 7.0;
+    for line in lines {
+        if y < 20.0 {
+            let (page, layer) = doc.add_page(Mm(210.0), Mm(297.0), "Invoice");
+            canvas = doc.get_page(page).get_layer(layer);
+            y = 280.0;
+            canvas.use_text("Description", 10.0, Mm(20.0), Mm(y), &bold);
+            canvas.use_text("Category", 10.0, Mm(110.0), Mm(y), &bold);
+            canvas.use_text("Amount", 10.0, Mm(165.0), Mm(y), &bold);
+            y -= 7.0;
+        }
+        canvas.use_text(line.description.as_str(), 10.0, Mm(20.0), Mm(y), &font);
+        canvas.use_text(line.category.as_str(), 10.0, Mm(110.0), Mm(y), &font);
+        canvas.use_text(format_usd(line.amount_cents), 10.0, Mm(165.0), Mm(y), &font);
+        y -= 6.0;
+    }
+
+    // 3. Totals, kept together on one page
+    if y < 60.0 {
+        let (page, layer) = doc.add_page(Mm(210.0), Mm(297.0), "Invoice");
+        canvas = doc.get_page(page).get_layer(layer);
+        y = 280.0;
+    }
+    y -= 4.0;
+    canvas.use_text("Grand Total", 11.0, Mm(20.0), Mm(y), &bold);
+    canvas.use_text(format_usd(invoice.total_cents), 11.0, Mm(165.0), Mm(y), &bold);
+    y -= 12.0;
+    for (label, category) in [
+        ("Platform Fees", LineCategory::PlatformFee),
+        ("Pass-through Premiums", LineCategory::PassThroughPremium),
+        ("Administrative Fees", LineCategory::AdminFee),
+        ("Discounts", LineCategory::Discount),
+        ("Adjustments", LineCategory::Adjustment),
+        ("Usage Overage", LineCategory::UsageOverage),
+        ("Commitment True-up", LineCategory::CommitmentTrueUp),
+        ("Account Credit", LineCategory::AccountCredit),
+    ] {
+        let text = format!("{}: {}", label, format_usd(subtotal(lines, category)));
+        canvas.use_text(text, 9.0, Mm(20.0), Mm(y), &font);
+        y -= 5.0;
+    }
+
+    Ok(doc.save_to_bytes()?)
+}
+
+/// Renders a monthly account statement as an A4 PDF (as many pages as it takes)
+pub fn render_statement_pdf(statement: &Statement) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (doc, first_page, first_layer) = PdfDocument::new(
+        format!("Statement {} {}", statement.onchain_account_id, statement.period),
+        Mm(210.0),
+        Mm(297.0),
+        "Statement",
+    );
+    let font = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let mut canvas = doc.get_page(first_page).get_layer(first_layer);
+
+    // 1. Header
+    let mut y = 270.0;
+    canvas.use_text("Patrie Network Account Statement", 18.0, Mm(20.0), Mm(y), &bold);
+    y -= 10.0;
+    for meta in [
+        format!("Account: {}", statement.onchain_account_id),
+        format!("Statement Period: {}", statement.period),
+        format!("Asset: {}", statement.asset_definition_id),
+        format!("Generated: {}", statement.generated_at.format("%Y-%m-%d %H:%M UTC")),
+    ] {
+        canvas.use_text(meta, 10.0, Mm(20.0), Mm(y), &font);
+        y -= 6.0;
+    }
+
+    // 2. One block per section: summary, then the itemized lines
+    for section in &statement.sections {
+        if y < 60.0 {
+            let (page, layer) = doc.add_page(Mm(210.0), Mm(297.0), "Statement");
+            canvas = doc.get_page(page).get_layer(layer);
+            y = 280.0;
+        }
+
+        y -= 8.0;
+        let title = match section.kind.as_str() {
+            "wallet" => format!("Wallet {}", section.account_id),
+            _ => format!("Deposit Account {}", section.account_id),
+        };
+        canvas.use_text(title, 12.0, Mm(20.0), Mm(y), &bold);
+        y -= 7.0;
+        for (label, cents) in [
+            ("Opening Balance", section.opening_balance_cents),
+            ("Credits", section.credits_cents),
+            ("Debits", section.debits_cents),
+            ("Fees", section.fees_cents),
+            ("Closing Balance", section.closing_balance_cents),
+        ] {
+            canvas.use_text(format!("{}: {}", label, format_usd(cents)), 9.0, Mm(20.0), Mm(y), &font);
+            y -= 5.0;
+        }
+
+        y -= 4.0;
+        canvas.use_text("Date", 9.0, Mm(20.0), Mm(y), &bold);
+        canvas.use_text("Description", 9.0, Mm(45.0), Mm(y), &bold);
+        canvas.use_text("Category", 9.0, Mm(120.0), Mm(y), &bold);
+        canvas.use_text("Amount", 9.0, Mm(148.0), Mm(y), &bold);
+        canvas.use_text("Balance", 9.0, Mm(175.0), Mm(y), &bold);
+        y -= 6.0;
+
+        if section.lines.is_empty() {
+            canvas.use_text("No activity this period.", 9.0, Mm(20.0), Mm(y), &font);
+            y -= 5.0;
+        }
+        for line in &section.lines {
+            if y < 20.0 {
+                let (page, layer) = doc.add_page(Mm(210.0), Mm(297.0), "Statement");
+                canvas = doc.get_page(page).get_layer(layer);
+                y = 280.0;
+            }
+            let description: String = line.description.chars().take(45).collect();
+            canvas.use_text(line.posted_at.format("%Y-%m-%d").to_string(), 9.0, Mm(20.0), Mm(y), &font);
+            canvas.use_text(description, 9.0, Mm(45.0), Mm(y), &font);
+            canvas.use_text(line.category.as_str(), 9.0, Mm(120.0), Mm(y), &font);
+            canvas.use_text(format_usd(line.amount_cents), 9.0, Mm(148.0), Mm(y), &font);
+            canvas.use_text(format_usd(line.balance_cents), 9.0, Mm(175.0), Mm(y), &font);
+            y -= 5.0;
+        }
+    }
+
+    Ok(doc.save_to_bytes()?)
+}
+
+/// Renders a statement as CSV: one row per line, plus opening/closing rows
+/// per section. Amounts are plain decimals ("-12.50") for spreadsheets.
+pub fn render_statement_csv(statement: &Statement) -> String {
+    let mut csv = String::from("section,account_id,date,category,description,amount,balance,reference\n");
+    let decimal = |cents: i64| {
+        let sign = if cents < 0 { "-" } else { "" };
+        format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+    };
+
+    for section in &statement.sections {
+        let start = statement.period.start.format("%Y-%m-%d").to_string();
+        let last_day = statement.period.end().pred_opt().unwrap().format("%Y-%m-%d").to_string();
+
+        csv.push_str(&csv_row(&[
+            &section.kind, &section.account_id, &start, "opening_balance", "Opening balance", "",
+            &decimal(section.opening_balance_cents), "",
+        ]));
+        for line in &section.lines {
+            csv.push_str(&csv_row(&[
+                &section.kind,
+                &section.account_id,
+                &line.posted_at.to_rfc3339(),
+                line.category.as_str(),
+                &line.description,
+                &decimal(line.amount_cents),
+                &decimal(line.balance_cents),
+                &line.reference,
+            ]));
+        }
+        csv.push_str(&csv_row(&[
+            &section.kind, &section.account_id, &last_day, "closing_balance", "Closing balance", "",
+            &decimal(section.closing_balance_cents), "",
+        ]));
+    }
+    csv
+}
+
+/// RFC 4180: quote fields containing commas, quotes or newlines
+fn csv_row(fields: &[&str]) -> String {
+    let escaped: Vec<String> = fields
+        .iter()
+        .map(|f| {
+            if f.contains(',') || f.contains('"') || f.contains('\n') {
+                format!("\"{}\"", f.replace('"', "\"\""))
+            } else {
+                f.to_string()
+            }
+        })
+        .collect();
+    format!("{}\n", escaped.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_usd_with_thousands_separators() {
+        assert_eq!(format_usd(0), "$0.00");
+        assert_eq!(format_usd(5), "$0.05");
+        assert_eq!(format_usd(99_900), "$999.00");
+        assert_eq!(format_usd(123_456), "$1,234.56");
+        assert_eq!(format_usd(123_456_789), "$1,234,567.89");
+        assert_eq!(format_usd(-4_900), "-$49.00"); // Credits & discounts
+    }
+}
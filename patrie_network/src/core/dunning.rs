@@ -0,0 +1,343 @@
+use crate::core::billing_engine::BillingEngine;
+use crate::core::notifications::notify_tenant_admin;
+use crate::ledger::client::IrohaClient;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// How hard we chase a failed charge before suspending the tenant
+#[derive(Debug, Clone)]
+pub struct DunningPolicy {
+    /// Days after the failure (or previous retry) to try again, e.g. [1, 3, 7]
+    pub retry_schedule_days: Vec<i64>,
+    /// Days between the last failed retry and suspension
+    pub grace_period_days: i64,
+    /// Minutes a charge may sit in payment_pending before it is re-sent
+    pub pending_resend_minutes: i64,
+}
+
+impl DunningPolicy {
+    /// Reads DUNNING_RETRY_DAYS ("1,3,7"), DUNNING_GRACE_DAYS ("7") and
+    /// DUNNING_PENDING_RESEND_MINUTES ("15")
+    pub fn from_env() -> Self {
+        let retry_schedule_days = std::env::var("DUNNING_RETRY_DAYS")
+            .ok()
+            .map(|v| v.split(',').filter_map(|d| d.trim().parse().ok()).collect())
+            .unwrap_or_else(|| vec![1, 3, 7]);
+
+        let grace_period_days = std::env::var("DUNNING_GRACE_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(7);
+
+        let pending_resend_minutes = std::env::var("DUNNING_PENDING_RESEND_MINUTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(15);
+
+        Self { retry_schedule_days, grace_period_days, pending_resend_minutes }
+    }
+}
+
+/// Chases failed invoices: retries, notifications, grace period, suspension.
+pub struct DunningService {
+    db: PgPool,
+    billing: Arc<BillingEngine>,
+    iroha: Arc<IrohaClient>,
+    policy: DunningPolicy,
+}
+
+impl DunningService {
+    pub fn new(db: PgPool, billing: Arc<BillingEngine>, iroha: Arc<IrohaClient>, policy: DunningPolicy) -> Self {
+        Self { db, billing, iroha, policy }
+    }
+
+    /// Run this every hour. Each step is safe to repeat.
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        // Charges with no answer from Unit yet are re-sent, not retried
+        if let Err(e) = self.billing.resend_pending(self.policy.pending_resend_minutes).await {
+            eprintln!("❌ {}", e);
+        }
+        self.open_new_cases().await?;
+        self.retry_due_cases().await?;
+        self.suspend_expired_grace().await?;
+        Ok(())
+    }
+
+    /// 1. Every FAILED invoice without a case gets one
+    async fn open_new_cases(&self) -> Result<(), Box<dyn Error>> {
+        let first_retry = Utc::now() + Duration::days(self.delay_for_attempt(0));
+
+        let opened = sqlx::query!(
+            r#"
+            INSERT INTO dunning_cases (id, invoice_id, tenant_id, next_attempt_at, last_error)
+            SELECT gen_random_uuid(), i.id, i.tenant_id, $1, i.failure_reason
+            FROM invoices i
+            LEFT JOIN dunning_cases d ON d.invoice_id = i.id
+            WHERE i.status = 'failed' AND d.id IS NULL
+            RETURNING tenant_id, invoice_id
+            "#,
+            first_retry
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        for case in opened {
+            notify_tenant_admin(
+                &self.db,
+                case.tenant_id,
+                "dunning_payment_failed",
+                "Action needed: your monthly payment failed",
+                &format!(
+                    "We could not collect invoice {}. We will retry on {}. Please make sure your deposit account is funded.",
+                    case.invoice_id,
+                    first_retry.format("%Y-%m-%d")
+                ),
+            ).await?;
+        }
+        Ok(())
+    }
+
+    /// 2. Retry every case whose next attempt is due
+    async fn retry_due_cases(&self) -> Result<(), Box<dyn Error>> {
+        let due = sqlx::query!(
+            r#"
+            SELECT id, invoice_id, tenant_id, attempts
+            FROM dunning_cases
+            WHERE status = 'open' AND next_attempt_at <= NOW()
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        for case in due {
+            let attempts = case.attempts + 1;
+
+            match self.billing.retry_payment(case.invoice_id).await {
+                Ok(_) => self.resolve(case.id, case.tenant_id, case.invoice_id).await?,
+                Err(e) if (attempts as usize) < self.policy.retry_schedule_days.len() => {
+                    // Schedule the next retry
+                    let next = Utc::now() + Duration::days(self.delay_for_attempt(attempts as usize));
+                    self.update_case(case.id, "open", attempts, Some(next), None, &e.to_string()).await?;
+
+                    notify_tenant_admin(
+                        &self.db,
+                        case.tenant_id,
+                        "dunning_retry_failed",
+                        "Payment retry failed",
+                        &format!(
+                            "Retry {} for invoice {} failed. Next attempt on {}.",
+                            attempts,
+                            case.invoice_id,
+                            next.format("%Y-%m-%d")
+                        ),
+                    ).await?;
+                }
+                Err(e) => {
+                    // Out of retries: start the grace period
+                    let grace_ends = Utc::now() + Duration::days(self.policy.grace_period_days);
+                    self.update_case(case.id, "grace", attempts, None, Some(grace_ends), &e.to_string()).await?;
+
+                    notify_tenant_admin(
+                        &self.db,
+                        case.tenant_id,
+                        "dunning_final_notice",
+                        "Final notice: your account will be suspended",
+                        &format!(
+                            "All retries for invoice {} failed. Unless it is paid by {}, API access and your ledger domain will be suspended.",
+                            case.invoice_id,
+                            grace_ends.format("%Y-%m-%d")
+                        ),
+                    ).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 3. Grace period over: suspend the tenant
+    async fn suspend_expired_grace(&self) -> Result<(), Box<dyn Error>> {
+        let expired = sqlx::query!(
+            r#"
+            SELECT id, invoice_id, tenant_id
+            FROM dunning_cases
+            WHERE status = 'grace' AND grace_ends_at <= NOW()
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        for case in expired {
+            sqlx::query!(
+                "UPDATE dunning_cases SET status = 'suspended', updated_at = NOW() WHERE id = $1",
+                case.id
+            )
+            .execute(&self.db)
+            .await?;
+
+            self.suspend_tenant(case.tenant_id).await?;
+
+            notify_tenant_admin(
+                &self.db,
+                case.tenant_id,
+                "dunning_suspended",
+                "Your account has been suspended",
+                &format!(
+                    "Invoice {} remains unpaid. API writes are blocked and your ledger domain is frozen until the balance is paid.",
+                    case.invoice_id
+                ),
+            ).await?;
+        }
+        Ok(())
+    }
+
+    /// Manual "pay now" (e.g. after the tenant funded their account).
+    /// Works in any dunning state, including suspended.
+    pub async fn pay_now(&self, invoice_id: Uuid) -> Result<(), Box<dyn Error>> {
+        let case = sqlx::query!(
+            "SELECT id, tenant_id FROM dunning_cases WHERE invoice_id = $1 AND status <> 'recovered'",
+            invoice_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        self.billing.retry_payment(invoice_id).await?;
+
+        if let Some(case) = case {
+            self.resolve(case.id, case.tenant_id, invoice_id).await?;
+        }
+        Ok(())
+    }
+
+    /// The invoice is paid: close the case and reinstate the tenant if nothing else is outstanding
+    async fn resolve(&self, case_id: Uuid, tenant_id: Uuid, invoice_id: Uuid) -> Result<(), Box<dyn Error>> {
+        sqlx::query!(
+            "UPDATE dunning_cases SET status = 'recovered', updated_at = NOW() WHERE id = $1",
+            case_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        notify_tenant_admin(
+            &self.db,
+            tenant_id,
+            "dunning_recovered",
+            "Payment received",
+            &format!("Invoice {} has been paid. Thank you!", invoice_id),
+        ).await?;
+
+        let still_suspended = sqlx::query!(
+            r#"SELECT COUNT(*) AS "count!" FROM dunning_cases WHERE tenant_id = $1 AND status = 'suspended'"#,
+            tenant_id
+        )
+        .fetch_one(&self.db)
+        .await?
+        .count;
+
+        if still_suspended == 0 && tenant_status(&self.db, tenant_id).await? == "suspended" {
+            self.reinstate_tenant(tenant_id).await?;
+        }
+        Ok(())
+    }
+
+    async fn suspend_tenant(&self, tenant_id: Uuid) -> Result<(), Box<dyn Error>> {
+        let domain = sqlx::query!(
+            "UPDATE tenants SET status = 'suspended' WHERE id = $1 RETURNING iroha_domain",
+            tenant_id
+        )
+        .fetch_one(&self.db)
+        .await?
+        .iroha_domain;
+
+        if let Some(domain) = domain {
+            self.iroha.freeze_domain(&domain).await.map_err(|e| e.to_string())?;
+        }
+        println!("⛔ Tenant {} suspended for non-payment.", tenant_id);
+        Ok(())
+    }
+
+    async fn reinstate_tenant(&self, tenant_id: Uuid) -> Result<(), Box<dyn Error>> {
+        let domain = sqlx::query!(
+            "UPDATE tenants SET status = 'active' WHERE id = $1 RETURNING iroha_domain",
+            tenant_id
+        )
+        .fetch_one(&self.db)
+        .await?
+        .iroha_domain;
+
+        if let Some(domain) = domain {
+            self.iroha.unfreeze_domain(&domain).await.map_err(|e| e.to_string())?;
+        }
+        println!("✅ Tenant {} reinstated.", tenant_id);
+        Ok(())
+    }
+
+    fn delay_for_attempt(&self, attempt: usize) -> i64 {
+        self.policy.retry_schedule_days.get(attempt).copied().unwrap_or(1)
+    }
+
+    async fn update_case(
+        &self,
+        case_id: Uuid,
+        status: &str,
+        attempts: i32,
+        next_attempt_at: Option<DateTime<Utc>>,
+        grace_ends_at: Option<DateTime<Utc>>,
+        last_error: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        sqlx::query!(
+            r#"
+            UPDATE dunning_cases
+            SET status = $2, attempts = $3, next_attempt_at = $4, grace_ends_at = $5,
+                last_error = $6, updated_at = NOW()
+            WHERE id = $1
+            "#,
+            case_id,
+            status,
+            attempts,
+            next_attempt_at,
+            grace_ends_at,
+            last_error
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+}
+
+async fn tenant_status(db: &PgPool, tenant_id: Uuid) -> Result<String, Box<dyn Error>> {
+    Ok(sqlx::query!("SELECT status FROM tenants WHERE id = $1", tenant_id)
+        .fetch_one(db)
+        .await?
+        .status)
+}
+
+// --- WRITE GUARDS (used by the API handlers) ---
+
+/// Blocks API writes for a suspended tenant
+pub async fn ensure_tenant_active(db: &PgPool, tenant_id: Uuid) -> Result<(), String> {
+    match tenant_status(db, tenant_id).await {
+        Ok(status) if status == "active" => Ok(()),
+        Ok(status) if status == "offboarded" => Err("Tenant has been offboarded".to_string()),
+        Ok(status) if status == "onboarding" => Err("Tenant onboarding is not complete".to_string()),
+        Ok(_) => Err("Tenant is suspended for non-payment".to_string()),
+        Err(e) => Err(format!("Tenant lookup failed: {}", e)),
+    }
+}
+
+/// Same guard, for handlers that address the tenant by ledger domain ("tesla_supply_chain")
+pub async fn ensure_domain_active(db: &PgPool, domain: &str) -> Result<(), String> {
+    let row = sqlx::query!("SELECT status FROM tenants WHERE iroha_domain = $1", domain)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| format!("Tenant lookup failed: {}", e))?;
+
+    match row {
+        Some(r) if r.status == "offboarded" => Err("Tenant has been offboarded".to_string()),
+        Some(r) if r.status == "onboarding" => Err("Tenant onboarding is not complete".to_string()),
+        Some(r) if r.status != "active" => Err("Tenant is suspended for non-payment".to_string()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,363 @@
+use crate::core::gusto::{GustoClient, GustoEmployee};
+use crate::ledger::client::IrohaClient;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- GUSTO EMPLOYEES -> LEDGER ---
+// Every Gusto employee of a tenant gets an account in the tenant's Iroha
+// domain, carrying the employee UUID in its metadata. Terminated employees
+// keep their account (and history), but it is frozen: its own keys and
+// asset-moving permissions are taken away until a rehire gives them back.
+
+/// Metadata keys on the employee's account
+const EMPLOYEE_UUID_KEY: &str = "gusto_employee_uuid";
+const STATUS_KEY: &str = "status";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmployeeAccountStatus {
+    Pending, // Row written, registration not confirmed yet
+    Active,
+    Terminated,
+}
+
+impl EmployeeAccountStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmployeeAccountStatus::Pending => "pending",
+            EmployeeAccountStatus::Active => "active",
+            EmployeeAccountStatus::Terminated => "terminated",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value {
+            "pending" => Ok(EmployeeAccountStatus::Pending),
+            "active" => Ok(EmployeeAccountStatus::Active),
+            "terminated" => Ok(EmployeeAccountStatus::Terminated),
+            other => Err(format!("Unknown employee account status '{}'", other).into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmployeeAccount {
+    pub employee_uuid: String,
+    pub onchain_account_id: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<String>,
+    pub status: EmployeeAccountStatus,
+    pub synced_at: DateTime<Utc>,
+    pub terminated_at: Option<DateTime<Utc>>,
+}
+
+/// What one sync changed
+#[derive(Debug, Default, Serialize)]
+pub struct SyncReport {
+    pub tenant_id: Uuid,
+    pub registered: usize,
+    pub terminated: usize,
+    pub reactivated: usize,
+    pub unchanged: usize,
+}
+
+/// What happened to one employee
+enum EmployeeChange {
+    Registered,
+    Terminated,
+    Reactivated,
+    Unchanged,
+}
+
+/// The tenant's side of the link
+struct TenantLink {
+    company_uuid: String,
+    domain: String,
+}
+
+pub struct EmployeeSync {
+    db: PgPool,
+    gusto: Arc<GustoClient>,
+    iroha: Arc<IrohaClient>,
+}
+
+impl EmployeeSync {
+    pub fn new(db: PgPool, gusto: Arc<GustoClient>, iroha: Arc<IrohaClient>) -> Self {
+        Self { db, gusto, iroha }
+    }
+
+    /// FULL SYNC for one tenant (on demand, and nightly)
+    /// 1. Registers an account for every employee we haven't seen
+    /// 2. Marks terminated employees' accounts (and reactivates rehires)
+    /// 3. Employees Gusto no longer lists at all are treated as terminated
+    pub async fn sync_tenant(&self, tenant_id: Uuid) -> Result<SyncReport, Box<dyn Error>> {
+        let link = self.tenant_link(tenant_id).await?;
+        let employees = self.gusto.list_employees(&link.company_uuid).await?;
+
+        let mut report = SyncReport { tenant_id, ..Default::default() };
+        for employee in &employees {
+            match self.apply(tenant_id, &link, employee).await? {
+                EmployeeChange::Registered => report.registered += 1,
+                EmployeeChange::Terminated => report.terminated += 1,
+                EmployeeChange::Reactivated => report.reactivated += 1,
+                EmployeeChange::Unchanged => report.unchanged += 1,
+            }
+        }
+
+        // 3. Deleted in Gusto
+        let listed: Vec<String> = employees.iter().map(|e| e.uuid.clone()).collect();
+        let vanished = sqlx::query!(
+            r#"
+            SELECT employee_uuid, onchain_account_id FROM gusto_employee_accounts
+            WHERE tenant_id = $1 AND status = 'active' AND NOT (employee_uuid = ANY($2))
+            "#,
+            tenant_id,
+            &listed
+        )
+        .fetch_all(&self.db)
+        .await?;
+        for row in vanished {
+            self.terminate(&row.employee_uuid, &row.onchain_account_id).await?;
+            report.terminated += 1;
+        }
+
+        println!(
+            "👥 Tenant {} employees synced: {} registered, {} terminated, {} reactivated.",
+            tenant_id, report.registered, report.terminated, report.reactivated
+        );
+        Ok(report)
+    }
+
+    /// ONE EMPLOYEE (employee.* webhook events)
+    pub async fn sync_employee(&self, tenant_id: Uuid, employee_uuid: &str) -> Result<(), Box<dyn Error>> {
+        let link = self.tenant_link(tenant_id).await?;
+        let employee = self.gusto.get_employee(&link.company_uuid, employee_uuid).await?;
+        self.apply(tenant_id, &link, &employee).await?;
+        Ok(())
+    }
+
+    /// Every tenant linked to Gusto (nightly job)
+    pub async fn sync_all(&self) -> Result<(), Box<dyn Error>> {
+        let tenants = sqlx::query!(
+            r#"
+            SELECT id FROM tenants
+            WHERE gusto_company_uuid IS NOT NULL AND iroha_domain IS NOT NULL
+              AND status = 'active' AND gusto_company_status = 'active'
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut failures = 0;
+        for tenant in &tenants {
+            if let Err(e) = self.sync_tenant(tenant.id).await {
+                eprintln!("❌ Employee sync failed for tenant {}: {}", tenant.id, e);
+                failures += 1;
+            }
+        }
+
+        if failures > 0 {
+            return Err(format!("{} tenant(s) failed employee sync", failures).into());
+        }
+        Ok(())
+    }
+
+    pub async fn accounts(&self, tenant_id: Uuid) -> Result<Vec<EmployeeAccount>, Box<dyn Error>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT employee_uuid, onchain_account_id, first_name, last_name, email,
+                   status, synced_at, terminated_at
+            FROM gusto_employee_accounts
+            WHERE tenant_id = $1
+            ORDER BY last_name, first_name
+            "#,
+            tenant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        rows.into_iter()
+            .map(|r| {
+                Ok(EmployeeAccount {
+                    employee_uuid: r.employee_uuid,
+                    onchain_account_id: r.onchain_account_id,
+                    first_name: r.first_name,
+                    last_name: r.last_name,
+                    email: r.email,
+                    status: EmployeeAccountStatus::parse(&r.status)?,
+                    synced_at: r.synced_at,
+                    terminated_at: r.terminated_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Brings one employee's account in line with Gusto
+    async fn apply(&self, tenant_id: Uuid, link: &TenantLink, employee: &GustoEmployee) -> Result<EmployeeChange, Box<dyn Error>> {
+        let existing = sqlx::query!(
+            "SELECT onchain_account_id, status FROM gusto_employee_accounts WHERE employee_uuid = $1",
+            employee.uuid
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let (account, status) = match existing {
+            Some(row) => (row.onchain_account_id, EmployeeAccountStatus::parse(&row.status)?),
+            None if employee.terminated => return Ok(EmployeeChange::Unchanged), // Left before we linked the company
+            None => {
+                // Write the row first: a crash between here and the ledger leaves a
+                // 'pending' row that the next sync finishes
+                let account = employee_account_id(employee, &link.domain);
+                sqlx::query!(
+                    r#"
+                    INSERT INTO gusto_employee_accounts
+                        (employee_uuid, tenant_id, company_uuid, onchain_account_id, first_name, last_name, email)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    ON CONFLICT (employee_uuid) DO NOTHING
+                    "#,
+                    employee.uuid,
+                    tenant_id,
+                    link.company_uuid,
+                    account,
+                    employee.first_name,
+                    employee.last_name,
+                    employee.email
+                )
+                .execute(&self.db)
+                .await?;
+                (account, EmployeeAccountStatus::Pending)
+            }
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE gusto_employee_accounts
+            SET first_name = $2, last_name = $3, email = $4, synced_at = NOW()
+            WHERE employee_uuid = $1
+            "#,
+            employee.uuid,
+            employee.first_name,
+            employee.last_name,
+            employee.email
+        )
+        .execute(&self.db)
+        .await?;
+
+        match (status, employee.terminated) {
+            (EmployeeAccountStatus::Pending, _) => {
+                self.register(&employee.uuid, &account).await?;
+                if employee.terminated {
+                    self.terminate(&employee.uuid, &account).await?;
+                }
+                Ok(EmployeeChange::Registered)
+            }
+            (EmployeeAccountStatus::Active, true) => {
+                self.terminate(&employee.uuid, &account).await?;
+                Ok(EmployeeChange::Terminated)
+            }
+            (EmployeeAccountStatus::Terminated, false) => {
+                self.iroha.unfreeze_account(&account, STATUS_KEY, "active").await.map_err(|e| e.to_string())?;
+                sqlx::query!(
+                    "UPDATE gusto_employee_accounts SET status = 'active', terminated_at = NULL WHERE employee_uuid = $1",
+                    employee.uuid
+                )
+                .execute(&self.db)
+                .await?;
+                println!("🔁 Employee {} rehired: {} reactivated.", employee.uuid, account);
+                Ok(EmployeeChange::Reactivated)
+            }
+            _ => Ok(EmployeeChange::Unchanged),
+        }
+    }
+
+    /// Account + employee metadata in ONE ledger transaction. If the account is
+    /// already there (we crashed after registering), just confirm the row.
+    async fn register(&self, employee_uuid: &str, account: &str) -> Result<(), Box<dyn Error>> {
+        // A ledger we can't read must not look like a missing account
+        let already_registered = self.iroha.has_account(account).await.map_err(|e| e.to_string())?
+            && self.iroha.has_receipt(account, EMPLOYEE_UUID_KEY).await.map_err(|e| e.to_string())?;
+
+        let tx_hash = if already_registered {
+            None
+        } else {
+            let metadata = [(EMPLOYEE_UUID_KEY, employee_uuid), (STATUS_KEY, "active")];
+            Some(self.iroha.register_account(account, &metadata).await.map_err(|e| e.to_string())?)
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE gusto_employee_accounts
+            SET status = 'active', register_tx_hash = COALESCE($2, register_tx_hash)
+            WHERE employee_uuid = $1
+            "#,
+            employee_uuid,
+            tx_hash
+        )
+        .execute(&self.db)
+        .await?;
+
+        println!("🪪 Employee {} registered as {}.", employee_uuid, account);
+        Ok(())
+    }
+
+    /// Freezes the account (marked "terminated" in the same transaction)
+    async fn terminate(&self, employee_uuid: &str, account: &str) -> Result<(), Box<dyn Error>> {
+        self.iroha.freeze_account(account, STATUS_KEY, "terminated").await.map_err(|e| e.to_string())?;
+        sqlx::query!(
+            r#"
+            UPDATE gusto_employee_accounts
+            SET status = 'terminated', terminated_at = NOW()
+            WHERE employee_uuid = $1
+            "#,
+            employee_uuid
+        )
+        .execute(&self.db)
+        .await?;
+
+        println!("🚪 Employee {} terminated: {} frozen.", employee_uuid, account);
+        Ok(())
+    }
+
+    async fn tenant_link(&self, tenant_id: Uuid) -> Result<TenantLink, Box<dyn Error>> {
+        let row = sqlx::query!(
+            "SELECT gusto_company_uuid, iroha_domain, status FROM tenants WHERE id = $1",
+            tenant_id
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        if row.status == "offboarded" {
+            return Err("Tenant has been offboarded".into());
+        }
+        match (row.gusto_company_uuid, row.iroha_domain) {
+            (Some(company_uuid), Some(domain)) => Ok(TenantLink { company_uuid, domain }),
+            (None, _) => Err("Tenant is not linked to a Gusto company".into()),
+            (_, None) => Err("Tenant has no ledger domain".into()),
+        }
+    }
+}
+
+/// "Ada Lovelace" (uuid ...-4f1e9c2a5e6f7a8b) -> "ada_lovelace_5e6f7a8b@domain".
+/// The uuid suffix keeps namesakes apart; the name is only for humans.
+fn employee_account_id(employee: &GustoEmployee, domain: &str) -> String {
+    let name: String = format!("{} {}", employee.first_name, employee.last_name)
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let name = name.split('_').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("_");
+
+    let uuid: Vec<char> = employee.uuid.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    let suffix: String = uuid[uuid.len().saturating_sub(8)..].iter().collect();
+
+    if name.is_empty() {
+        format!("employee_{}@{}", suffix, domain)
+    } else {
+        format!("{}_{}@{}", name, suffix, domain)
+    }
+}
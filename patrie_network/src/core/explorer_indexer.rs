@@ -1,68 +1,217 @@
-use iroha_client::client::Client;
+use crate::ledger::client::IrohaClient;
+use chrono::DateTime;
 use iroha_data_model::prelude::*;
+use serde::Serialize;
 use sqlx::PgPool;
+use std::error::Error;
+use std::num::NonZeroU64;
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 
 pub struct ExplorerIndexer {
     db: PgPool,
-    iroha: Arc<Client>,
+    iroha: Arc<IrohaClient>,
 }
 
 impl ExplorerIndexer {
-    /// Starts the indexing loop
+    pub fn new(db: PgPool, iroha: Arc<IrohaClient>) -> Self {
+        Self { db, iroha }
+    }
+
+    /// Starts the indexing loop. Picks up after the last indexed block, so a
+    /// restart neither skips nor re-indexes anything.
     pub async fn start_syncing(&self) {
         println!("🔍 Explorer Indexer Started...");
-        
-        let mut last_height = self.get_last_indexed_height().await;
 
         loop {
-            // Poll for the next block
-            let next_height = last_height + 1;
-            
-            // Note: In real Iroha 2, you use the Event Stream. 
-            // For simplicity, we use a mock "get_block" query here.
-            if let Ok(Some(block)) = self.fetch_block_from_iroha(next_height).await {
-                
-                self.save_block_to_db(&block).await;
-                last_height = next_height;
-                println!("📦 Indexed Block #{}", last_height);
-                
-            } else {
+            let next_height = match self.get_last_indexed_height().await {
+                Ok(height) => height + 1,
+                Err(e) => {
+                    eprintln!("❌ Indexer could not read its position: {}", e);
+                    sleep(Duration::from_secs(10)).await;
+                    continue;
+                }
+            };
+
+            match self.fetch_block_from_iroha(next_height).await {
+                Ok(Some(block)) => match self.save_block_to_db(&block).await {
+                    Ok(()) => println!("📦 Indexed Block #{}", next_height),
+                    Err(e) => {
+                        eprintln!("❌ Indexing block #{} failed: {}", next_height, e);
+                        sleep(Duration::from_secs(10)).await;
+                    }
+                },
                 // No new block, wait 2 seconds
-                sleep(Duration::from_secs(2)).await;
+                Ok(None) => sleep(Duration::from_secs(2)).await,
+                Err(e) => {
+                    eprintln!("❌ Fetching block #{} failed: {}", next_height, e);
+                    sleep(Duration::from_secs(10)).await;
+                }
             }
         }
     }
 
-    async fn save_block_to_db(&self, block: &Block) {
+    /// One block, its transactions and their instructions, in ONE database
+    /// transaction: a crash never leaves a block half indexed
+    async fn save_block_to_db(&self, block: &Block) -> Result<(), Box<dyn Error>> {
+        let height = block.header.height as i64;
+        let timestamp = DateTime::from_timestamp_millis(block.header.timestamp_ms as i64)
+            .ok_or("Block timestamp out of range")?
+            .naive_utc();
+        let mut db_tx = self.db.begin().await?;
+
         // 1. Insert Block
         sqlx::query!(
-            "INSERT INTO chain_blocks (block_height, block_hash) VALUES ($1, $2)",
-            block.header.height as i64,
-            block.hash().to_string()
+            "INSERT INTO chain_blocks (block_height, block_hash, timestamp) VALUES ($1, $2, $3)",
+            height,
+            block.hash().to_string(),
+            timestamp
         )
-        .execute(&self.db).await.unwrap();
+        .execute(&mut *db_tx)
+        .await?;
 
         // 2. Insert Transactions
         for tx in &block.transactions {
             sqlx::query!(
-                "INSERT INTO chain_transactions (tx_hash, block_height, sender_account_id, command_type, payload) VALUES ($1, $2, $3, $4, $5)",
+                r#"
+                INSERT INTO chain_transactions (tx_hash, block_height, sender_account_id, command_type, payload, timestamp)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
                 tx.hash().to_string(),
-                block.header.height as i64,
+                height,
                 tx.payload.account_id.to_string(),
                 "Instruction", // Simplify for demo
-                serde_json::json!(format!("{:?}", tx.payload.instructions))
+                serde_json::json!(format!("{:?}", tx.payload.instructions)),
+                timestamp
             )
-            .execute(&self.db).await.unwrap();
+            .execute(&mut *db_tx)
+            .await?;
+
+            // 3. Insert Instructions (feeds usage metering: mints, bridge exits)
+            if let Executable::Instructions(instructions) = &tx.payload.instructions {
+                for (position, isi) in instructions.iter().enumerate() {
+                    let summary = summarize_instruction(isi);
+                    sqlx::query!(
+                        r#"
+                        INSERT INTO chain_instructions
+                        (tx_hash, position, kind, source_account_id, destination_account_id, quantity, asset_definition_id)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7)
+                        "#,
+                        tx.hash().to_string(),
+                        position as i32,
+                        summary.kind,
+                        summary.source,
+                        summary.destination,
+                        summary.quantity,
+                        summary.asset
+                    )
+                    .execute(&mut *db_tx)
+                    .await?;
+                }
+            }
+        }
+
+        db_tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_last_indexed_height(&self) -> Result<u64, Box<dyn Error>> {
+        let height = sqlx::query!(r#"SELECT COALESCE(MAX(block_height), 0)::BIGINT AS "height!" FROM chain_blocks"#)
+            .fetch_one(&self.db)
+            .await?
+            .height;
+        Ok(height as u64)
+    }
+
+    /// The committed block at `height`, or `None` if the chain isn't there yet
+    async fn fetch_block_from_iroha(&self, height: u64) -> Result<Option<Block>, Box<dyn Error>> {
+        if self.iroha.block_height().await.map_err(|e| e.to_string())? < height {
+            return Ok(None);
         }
+        let client = self.iroha.raw().map_err(|e| e.to_string())?.clone();
+        let from = NonZeroU64::new(height).ok_or("Block heights start at 1")?;
+
+        // The block stream is a blocking websocket: read its first block off the runtime
+        let block = tokio::task::spawn_blocking(move || -> Result<Option<Block>, String> {
+            let mut blocks = client.listen_for_blocks(from).map_err(|e| e.to_string())?;
+            blocks.next().transpose().map_err(|e| e.to_string())
+        })
+        .await??;
+        Ok(block)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexerHealth {
+    pub chain_height: u64,
+    pub indexed_height: u64,
+    pub lag_blocks: u64,
+}
+
+/// Compares the last indexed block with the chain. Usage metering and revenue
+/// reporting read the index, so falling behind is an error, not a warning.
+pub async fn check_indexer_health(
+    db: &PgPool,
+    iroha: &IrohaClient,
+    max_lag_blocks: u64,
+) -> Result<IndexerHealth, Box<dyn Error>> {
+    let indexed_height = sqlx::query!(r#"SELECT COALESCE(MAX(block_height), 0)::BIGINT AS "height!" FROM chain_blocks"#)
+        .fetch_one(db)
+        .await?
+        .height as u64;
+    let chain_height = iroha.block_height().await.map_err(|e| e.to_string())?;
+
+    let health = IndexerHealth {
+        chain_height,
+        indexed_height,
+        lag_blocks: chain_height.saturating_sub(indexed_height),
+    };
+
+    if health.lag_blocks > max_lag_blocks {
+        return Err(format!(
+            "Explorer indexer is {} blocks behind (indexed {}, chain {})",
+            health.lag_blocks, health.indexed_height, health.chain_height
+        ).into());
     }
+    Ok(health)
+}
 
-    async fn get_last_indexed_height(&self) -> u64 {
-        // Query DB for max(block_height)
-        0 // Placeholder
+/// The parts of an instruction we need for metering
+struct InstructionSummary {
+    kind: &'static str,
+    source: Option<String>,
+    destination: Option<String>,
+    quantity: Option<f64>,
+    asset: Option<String>,
+}
+
+fn summarize_instruction(isi: &InstructionBox) -> InstructionSummary {
+    match isi {
+        // Mint: units created INTO an account
+        InstructionBox::Mint(MintBox::Asset(mint)) => InstructionSummary {
+            kind: "Mint",
+            source: None,
+            destination: Some(mint.destination_id.account_id().to_string()),
+            quantity: mint.object.to_string().parse().ok(),
+            asset: Some(mint.destination_id.definition_id().to_string()),
+        },
+        // Burn: units destroyed FROM an account
+        InstructionBox::Burn(BurnBox::Asset(burn)) => InstructionSummary {
+            kind: "Burn",
+            source: Some(burn.destination_id.account_id().to_string()),
+            destination: None,
+            quantity: burn.object.to_string().parse().ok(),
+            asset: Some(burn.destination_id.definition_id().to_string()),
+        },
+        // Transfer: from one account to another (incl. the bridge escrow)
+        InstructionBox::Transfer(TransferBox::Asset(transfer)) => InstructionSummary {
+            kind: "Transfer",
+            source: Some(transfer.source_id().account_id().to_string()),
+            destination: Some(transfer.destination_id().to_string()),
+            quantity: transfer.object().to_string().parse().ok(),
+            asset: Some(transfer.source_id().definition_id().to_string()),
+        },
+        InstructionBox::Register(_) => InstructionSummary { kind: "Register", source: None, destination: None, quantity: None, asset: None },
+        _ => InstructionSummary { kind: "Other", source: None, destination: None, quantity: None, asset: None },
     }
-    
-    // Mock wrapper for Iroha Client
-    async fn fetch_block_from_iroha(&self, _height: u64) -> Result<Option<Block>, ()> { Ok(None) }
-}
\ No newline at end of file
+}
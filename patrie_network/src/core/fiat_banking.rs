@@ -1,39 +1,798 @@
-// In src/core/fiat_banking.rs
+use chrono::{DateTime, Utc};
+use reqwest::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+// --- UNIT BANKING API (JSON:API) ---
+// Every resource comes back as { "data": { "type", "id", "attributes", "relationships" } }.
+// Amounts are always integer CENTS. Create calls take an idempotency key so a
+// retried request returns the original resource instead of creating another.
+
+/// Everything that can go wrong talking to Unit
+#[derive(Debug)]
+pub enum UnitError {
+    /// Network / TLS / timeout
+    Http(reqwest::Error),
+    /// Unit answered with an error document
+    Api { status: u16, errors: Vec<UnitApiError> },
+    /// Unit accepted the request but refused the payment (e.g. insufficient funds)
+    Rejected { payment_id: String, reason: String },
+    /// The response did not have the shape we expect
+    Decode(String),
+}
+
+/// One entry of a JSON:API `errors` array
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnitApiError {
+    pub title: String,
+    pub detail: Option<String>,
+    pub code: Option<String>,
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnitError::Http(e) => write!(f, "Unit request failed: {}", e),
+            UnitError::Api { status, errors } => {
+                let messages: Vec<String> = errors
+                    .iter()
+                    .map(|e| match &e.detail {
+                        Some(detail) => format!("{}: {}", e.title, detail),
+                        None => e.title.clone(),
+                    })
+                    .collect();
+                write!(f, "Unit API error {}: {}", status, messages.join("; "))
+            }
+            UnitError::Rejected { payment_id, reason } => write!(f, "Unit rejected payment {}: {}", payment_id, reason),
+            UnitError::Decode(msg) => write!(f, "Unexpected Unit response: {}", msg),
+        }
+    }
+}
+
+impl Error for UnitError {}
+
+impl UnitError {
+    /// Unit definitely did NOT carry out the request: the payment was rejected,
+    /// or the request failed validation (4xx). Anything else (5xx, 408, 409, 429,
+    /// timeouts, an unreadable 2xx) may have gone through, so the caller must
+    /// re-send with the SAME idempotency key instead of starting a new attempt.
+    pub fn is_definitive(&self) -> bool {
+        match self {
+            UnitError::Rejected { .. } => true,
+            UnitError::Api { status, .. } => (400..500).contains(status) && !matches!(status, 408 | 409 | 429),
+            UnitError::Http(_) | UnitError::Decode(_) => false,
+        }
+    }
+}
+
+impl From<reqwest::Error> for UnitError {
+    fn from(e: reqwest::Error) -> Self {
+        UnitError::Http(e)
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorDocument {
+    errors: Vec<UnitApiError>,
+}
+
+/// The on-chain asset that mirrors the USD held at Unit. Read once at startup
+/// and handed to every service that mints, burns or reports it.
+#[derive(Debug, Clone)]
+pub struct FiatAsset {
+    pub usd_asset_id: String,
+}
+
+impl FiatAsset {
+    /// Reads FIAT_USD_ASSET_ID
+    pub fn from_env() -> Self {
+        Self { usd_asset_id: std::env::var("FIAT_USD_ASSET_ID").unwrap_or_else(|_| "usd#bank".to_string()) }
+    }
+}
+
+// --- Resources ---
+
+/// A JSON:API resource
+#[derive(Debug, Clone, Deserialize)]
+pub struct Resource<A> {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub attributes: A,
+    #[serde(default)]
+    pub relationships: Value,
+}
+
+impl<A> Resource<A> {
+    /// Id of a related resource, e.g. `related_id("customer")`
+    pub fn related_id(&self, name: &str) -> Option<String> {
+        self.relationships[name]["data"]["id"].as_str().map(str::to_string)
+    }
+}
+
+#[derive(Deserialize)]
+struct Document<T> {
+    data: T,
+    #[serde(default)]
+    meta: Value,
+}
+
+/// One page of a list endpoint
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub offset: u32,
+    pub limit: u32,
+    pub total: Option<u64>,
+}
+
+impl<T> Page<T> {
+    pub fn has_more(&self) -> bool {
+        match self.total {
+            Some(total) => (self.offset + self.items.len() as u32) < total as u32,
+            None => self.items.len() as u32 == self.limit,
+        }
+    }
+}
+
+/// `page[limit]` / `page[offset]` for list endpoints
+#[derive(Debug, Clone, Copy)]
+pub struct PageRequest {
+    pub limit: u32,
+    pub offset: u32,
+}
+
+impl Default for PageRequest {
+    fn default() -> Self {
+        Self { limit: 100, offset: 0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Address {
+    pub street: String,
+    pub city: String,
+    pub state: String,
+    pub postal_code: String,
+    pub country: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FullName {
+    pub first: String,
+    pub last: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Phone {
+    #[serde(rename = "countryCode")]
+    pub country_code: String,
+    pub number: String,
+}
+
+/// Individual (sole person) customer application
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndividualApplication {
+    pub full_name: FullName,
+    pub email: String,
+    pub phone: Phone,
+    pub ssn: String,
+    pub date_of_birth: String, // "1990-04-05"
+    pub address: Address,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub tags: Value, // Copied to the customer: {"tenant_id": ...} lets the tenant link its accounts
+    pub idempotency_key: String,
+}
+
+/// A business officer / beneficial owner on a business application
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessPerson {
+    pub full_name: FullName,
+    pub email: String,
+    pub phone: Phone,
+    pub ssn: String,
+    pub date_of_birth: String,
+    pub address: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>, // Officers only, e.g. "CEO"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<u8>, // Beneficial owners only
+}
+
+/// Business customer application (our tenants)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BusinessApplication {
+    pub name: String,
+    pub ein: String,
+    pub entity_type: String, // "LLC" | "Corporation" | "Partnership" ...
+    pub state_of_incorporation: String,
+    pub phone: Phone,
+    pub address: Address,
+    pub contact: BusinessPerson,
+    pub officer: BusinessPerson,
+    pub beneficial_owners: Vec<BusinessPerson>,
+    #[serde(default)]
+    pub idempotency_key: String, // Set by us, never by the API caller
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationAttributes {
+    pub status: String, // "Approved" | "Denied" | "Pending" | "AwaitingDocuments" ...
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomerAttributes {
+    pub created_at: Option<DateTime<Utc>>,
+    pub email: Option<String>,
+    pub name: Option<String>,      // Business customers
+    pub full_name: Option<FullName>, // Individual customers
+    pub status: Option<String>,
+    #[serde(default)]
+    pub tags: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositAccountAttributes {
+    pub name: String,
+    pub status: String,
+    pub deposit_product: String,
+    pub routing_number: Option<String>,
+    pub account_number: Option<String>,
+    pub currency: String,
+    pub balance: i64,
+    pub hold: i64,
+    pub available: i64,
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Value,
+}
+
+/// Balance of a deposit account, in cents
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Balance {
+    pub balance_cents: i64,
+    pub hold_cents: i64,
+    pub available_cents: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentAttributes {
+    pub status: String, // Book: "Sent" | "Rejected". ACH: "Pending" | "Sent" | "Returned" | "Rejected" ...
+    pub amount: i64,
+    pub description: String,
+    pub direction: Option<String>,
+    pub reason: Option<String>, // Why it was rejected / returned
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// "Credit" pushes money to the counterparty, "Debit" pulls it from them
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum AchDirection {
+    Credit,
+    Debit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewCounterparty {
+    pub name: String,
+    pub routing_number: String,
+    pub account_number: String,
+    pub account_type: String, // "Checking" | "Savings"
+    #[serde(rename = "type")]
+    pub kind: String, // "Business" | "Person" | "Unknown"
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CounterpartyAttributes {
+    pub name: String,
+    pub routing_number: String,
+    pub account_number: String, // Masked by Unit
+    pub account_type: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionAttributes {
+    pub created_at: DateTime<Utc>,
+    pub amount: i64,
+    pub direction: String, // "Credit" | "Debit"
+    pub balance: i64,      // Account balance after the transaction
+    pub summary: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BarcodeAttributes {
+    pub barcode_number: Option<String>,
+    pub barcode_url: Option<String>,
+    pub expiration: Option<DateTime<Utc>>,
+}
+
+/// A cash deposit barcode, as issued by Unit
+#[derive(Debug, Clone, Serialize)]
+pub struct CashDepositBarcode {
+    pub barcode_number: String,
+    pub barcode_url: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Card spending limits in cents. Unset limits keep Unit's defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardLimits {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_purchase: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_purchase: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_withdrawal: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_withdrawal: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardAttributes {
+    pub status: String, // "Active" | "Inactive" | "Frozen" | "ClosedByCustomer" ...
+    pub last4_digits: String,
+    pub expiration_date: String, // "2028-05"
+    pub limits: Option<CardLimits>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Merchant {
+    pub name: String,
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizationAttributes {
+    pub amount: i64,
+    pub status: String, // "Authorized" | "Completed" | "Canceled" | "Declined"
+    pub merchant: Option<Merchant>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+pub type Application = Resource<ApplicationAttributes>;
+pub type Customer = Resource<CustomerAttributes>;
+pub type DepositAccount = Resource<DepositAccountAttributes>;
+pub type Payment = Resource<PaymentAttributes>;
+pub type Counterparty = Resource<CounterpartyAttributes>;
+pub type Transaction = Resource<TransactionAttributes>;
+pub type Card = Resource<CardAttributes>;
+pub type Authorization = Resource<AuthorizationAttributes>;
+
+/// Filters for the transaction list
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+// --- The Client ---
+
+pub struct UnitClient {
+    http: Client,
+    token: String,
+    base_url: String,
+}
 
 impl UnitClient {
-    /// Generates a Barcode so the user can deposit cash at Walmart/CVS
-    pub async fn generate_cash_deposit_barcode(
-        &self, 
-        user_id: &str, 
-        account_id: &str
-    ) -> Result<String, Box<dyn Error>> {
-        let url = format!("{}/cash-deposits/barcode", self.base_url);
-        
-        let payload = serde_json::json!({
-            "data": {
-                "type": "cashDepositBarcode",
-                "attributes": {
-                    "store": "GreenDotNetwork" // or specific retailer
-                },
-                "relationships": {
-                    "customer": {
-                        "data": { "type": "customer", "id": user_id }
-                    },
-                    "account": {
-                        "data": { "type": "depositAccount", "id": account_id }
-                    }
-                }
+    pub fn new(token: String) -> Self {
+        Self {
+            http: Client::new(),
+            token,
+            // Switch to "https://api.unit.co" for PRODUCTION
+            base_url: "https://api.s.unit.sh".to_string(),
+        }
+    }
+
+    /// Same client against another host (e.g. the sandbox simulator)
+    pub fn with_base_url(token: String, base_url: String) -> Self {
+        Self { base_url, ..Self::new(token) }
+    }
+
+    // --- Customers ---
+
+    /// 1. KYC: apply for an individual customer
+    pub async fn create_individual_application(&self, app: &IndividualApplication) -> Result<Application, UnitError> {
+        self.create("applications", "individualApplication", serde_json::to_value(app).map_err(decode)?, Value::Null)
+            .await
+    }
+
+    /// 1b. KYB: apply for a business customer (our tenants)
+    pub async fn create_business_application(&self, app: &BusinessApplication) -> Result<Application, UnitError> {
+        self.create("applications", "businessApplication", serde_json::to_value(app).map_err(decode)?, Value::Null)
+            .await
+    }
+
+    pub async fn get_application(&self, application_id: &str) -> Result<Application, UnitError> {
+        self.get(&format!("applications/{}", application_id)).await
+    }
+
+    pub async fn get_customer(&self, customer_id: &str) -> Result<Customer, UnitError> {
+        self.get(&format!("customers/{}", customer_id)).await
+    }
+
+    pub async fn list_customers(&self, page: PageRequest) -> Result<Page<Customer>, UnitError> {
+        self.list("customers", &[], page).await
+    }
+
+    // --- Deposit Accounts ---
+
+    /// 2. Open a deposit account for an approved customer
+    pub async fn create_deposit_account(
+        &self,
+        customer_id: &str,
+        deposit_product: &str,
+        tags: Value,
+        idempotency_key: &str,
+    ) -> Result<DepositAccount, UnitError> {
+        self.create(
+            "accounts",
+            "depositAccount",
+            serde_json::json!({ "depositProduct": deposit_product, "tags": tags, "idempotencyKey": idempotency_key }),
+            serde_json::json!({ "customer": { "data": { "type": "customer", "id": customer_id } } }),
+        ).await
+    }
+
+    pub async fn get_account(&self, account_id: &str) -> Result<DepositAccount, UnitError> {
+        self.get(&format!("accounts/{}", account_id)).await
+    }
+
+    pub async fn get_balance(&self, account_id: &str) -> Result<Balance, UnitError> {
+        let account = self.get_account(account_id).await?;
+        Ok(Balance {
+            balance_cents: account.attributes.balance,
+            hold_cents: account.attributes.hold,
+            available_cents: account.attributes.available,
+        })
+    }
+
+    // --- Payments ---
+
+    /// 3. BOOK PAYMENT: instant transfer between two Unit accounts.
+    /// Returns the payment id. A "Rejected" payment is an error.
+    pub async fn create_book_payment(
+        &self,
+        from_account_id: &str,
+        to_account_id: &str,
+        amount_cents: u64,
+        description: &str,
+        idempotency_key: &str,
+    ) -> Result<String, UnitError> {
+        let payment: Payment = self.create(
+            "payments",
+            "bookPayment",
+            serde_json::json!({
+                "amount": amount_cents,
+                "description": description,
+                "idempotencyKey": idempotency_key
+            }),
+            serde_json::json!({
+                "account": { "data": { "type": "depositAccount", "id": from_account_id } },
+                "counterpartyAccount": { "data": { "type": "depositAccount", "id": to_account_id } }
+            }),
+        ).await?;
+
+        Ok(ensure_not_rejected(payment)?.id)
+    }
+
+    /// 4. ACH PAYMENT to/from an external bank account (counterparty).
+    /// ACH settles later: "Pending" is a success here, returns arrive as events.
+    pub async fn create_ach_payment(
+        &self,
+        account_id: &str,
+        counterparty_id: &str,
+        amount_cents: u64,
+        direction: AchDirection,
+        description: &str, // Unit allows at most 10 characters
+        idempotency_key: &str,
+    ) -> Result<Payment, UnitError> {
+        let payment: Payment = self.create(
+            "payments",
+            "achPayment",
+            serde_json::json!({
+                "amount": amount_cents,
+                "direction": direction,
+                "description": description.chars().take(10).collect::<String>(),
+                "idempotencyKey": idempotency_key
+            }),
+            serde_json::json!({
+                "account": { "data": { "type": "depositAccount", "id": account_id } },
+                "counterparty": { "data": { "type": "counterparty", "id": counterparty_id } }
+            }),
+        ).await?;
+
+        ensure_not_rejected(payment)
+    }
+
+    pub async fn get_payment(&self, payment_id: &str) -> Result<Payment, UnitError> {
+        self.get(&format!("payments/{}", payment_id)).await
+    }
+
+    // --- Counterparties ---
+
+    /// 5. Link an external bank account to a customer
+    pub async fn create_counterparty(
+        &self,
+        customer_id: &str,
+        counterparty: &NewCounterparty,
+        idempotency_key: &str,
+    ) -> Result<Counterparty, UnitError> {
+        let mut attributes = serde_json::to_value(counterparty).map_err(decode)?;
+        attributes["idempotencyKey"] = Value::from(idempotency_key);
+
+        self.create(
+            "counterparties",
+            "achCounterparty",
+            attributes,
+            serde_json::json!({ "customer": { "data": { "type": "customer", "id": customer_id } } }),
+        ).await
+    }
+
+    pub async fn list_counterparties(&self, customer_id: &str, page: PageRequest) -> Result<Page<Counterparty>, UnitError> {
+        self.list("counterparties", &[("filter[customerId]", customer_id.to_string())], page).await
+    }
+
+    // --- Transactions ---
+
+    /// 6. One page of an account's transactions (newest first)
+    pub async fn list_transactions(
+        &self,
+        account_id: &str,
+        filter: &TransactionFilter,
+        page: PageRequest,
+    ) -> Result<Page<Transaction>, UnitError> {
+        let mut query = vec![("filter[accountId]", account_id.to_string())];
+        if let Some(since) = filter.since {
+            query.push(("filter[since]", since.to_rfc3339()));
+        }
+        if let Some(until) = filter.until {
+            query.push(("filter[until]", until.to_rfc3339()));
+        }
+        self.list("transactions", &query, page).await
+    }
+
+    pub async fn get_transaction(&self, account_id: &str, transaction_id: &str) -> Result<Transaction, UnitError> {
+        self.get(&format!("accounts/{}/transactions/{}", account_id, transaction_id)).await
+    }
+
+    /// Every transaction matching `filter`, following pagination
+    pub async fn all_transactions(&self, account_id: &str, filter: &TransactionFilter) -> Result<Vec<Transaction>, UnitError> {
+        let mut all = Vec::new();
+        let mut page = PageRequest::default();
+        loop {
+            let result = self.list_transactions(account_id, filter, page).await?;
+            let more = result.has_more();
+            page.offset += result.items.len() as u32;
+            all.extend(result.items);
+            if !more {
+                return Ok(all);
             }
+        }
+    }
+
+    // --- Cards ---
+
+    /// 7. Virtual debit card on a deposit account (usable online / in wallets right away)
+    pub async fn create_virtual_card(
+        &self,
+        account_id: &str,
+        limits: &CardLimits,
+        idempotency_key: &str,
+    ) -> Result<Card, UnitError> {
+        self.create(
+            "cards",
+            "individualVirtualDebitCard",
+            serde_json::json!({
+                "limits": limits,
+                "idempotencyKey": idempotency_key
+            }),
+            serde_json::json!({ "account": { "data": { "type": "depositAccount", "id": account_id } } }),
+        ).await
+    }
+
+    pub async fn get_card(&self, card_id: &str) -> Result<Card, UnitError> {
+        self.get(&format!("cards/{}", card_id)).await
+    }
+
+    /// Frozen cards decline every authorization until unfrozen
+    pub async fn freeze_card(&self, card_id: &str) -> Result<Card, UnitError> {
+        self.action(&format!("cards/{}/freeze", card_id)).await
+    }
+
+    pub async fn unfreeze_card(&self, card_id: &str) -> Result<Card, UnitError> {
+        self.action(&format!("cards/{}/unfreeze", card_id)).await
+    }
+
+    /// Permanent
+    pub async fn close_card(&self, card_id: &str) -> Result<Card, UnitError> {
+        self.action(&format!("cards/{}/close", card_id)).await
+    }
+
+    pub async fn update_card_limits(&self, card_id: &str, limits: &CardLimits) -> Result<Card, UnitError> {
+        self.update(
+            &format!("cards/{}", card_id),
+            "individualVirtualDebitCard",
+            serde_json::json!({ "limits": limits }),
+        ).await
+    }
+
+    pub async fn get_authorization(&self, authorization_id: &str) -> Result<Authorization, UnitError> {
+        self.get(&format!("authorizations/{}", authorization_id)).await
+    }
+
+    /// 8. One page of a card's settled transactions (newest first)
+    pub async fn list_card_transactions(&self, card_id: &str, page: PageRequest) -> Result<Page<Transaction>, UnitError> {
+        self.list("transactions", &[("filter[cardId]", card_id.to_string())], page).await
+    }
+
+    // --- Cash ---
+
+    /// Generates a Barcode so the user can deposit cash at Walmart/CVS.
+    /// `store` is the retailer network, e.g. "GreenDotNetwork" or "Walmart".
+    pub async fn generate_cash_deposit_barcode(
+        &self,
+        user_id: &str,
+        account_id: &str,
+        store: &str,
+    ) -> Result<CashDepositBarcode, UnitError> {
+        let barcode: Resource<BarcodeAttributes> = self.create(
+            "cash-deposits/barcode",
+            "cashDepositBarcode",
+            serde_json::json!({ "store": store }),
+            serde_json::json!({
+                "customer": { "data": { "type": "customer", "id": user_id } },
+                "account": { "data": { "type": "depositAccount", "id": account_id } }
+            }),
+        ).await?;
+
+        // The barcode image is what the user shows at the till
+        let barcode_url = barcode
+            .attributes
+            .barcode_url
+            .ok_or_else(|| UnitError::Decode("barcodeUrl missing from cash deposit barcode".to_string()))?;
+
+        Ok(CashDepositBarcode {
+            barcode_number: barcode.attributes.barcode_number.unwrap_or(barcode.id),
+            barcode_url,
+            expires_at: barcode.attributes.expiration,
+        })
+    }
+
+    // --- Plumbing ---
+
+    fn authorized(&self, req: RequestBuilder) -> RequestBuilder {
+        req.header("Authorization", format!("Bearer {}", self.token))
+            .header("Content-Type", "application/vnd.api+json")
+    }
+
+    async fn create<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        kind: &str,
+        attributes: Value,
+        relationships: Value,
+    ) -> Result<T, UnitError> {
+        let mut data = serde_json::json!({ "type": kind, "attributes": attributes });
+        if !relationships.is_null() {
+            data["relationships"] = relationships;
+        }
+
+        let req = self.http.post(format!("{}/{}", self.base_url, path)).json(&serde_json::json!({ "data": data }));
+        Ok(self.send::<Document<T>>(req).await?.data)
+    }
+
+    /// PATCH: only the given attributes change
+    async fn update<T: DeserializeOwned>(&self, path: &str, kind: &str, attributes: Value) -> Result<T, UnitError> {
+        let body = serde_json::json!({ "data": { "type": kind, "attributes": attributes } });
+        let req = self.http.patch(format!("{}/{}", self.base_url, path)).json(&body);
+        Ok(self.send::<Document<T>>(req).await?.data)
+    }
+
+    /// POST without a body (freeze, unfreeze, close ...)
+    async fn action<T: DeserializeOwned>(&self, path: &str) -> Result<T, UnitError> {
+        let req = self.http.post(format!("{}/{}", self.base_url, path));
+        Ok(self.send::<Document<T>>(req).await?.data)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, UnitError> {
+        let req = self.http.get(format!("{}/{}", self.base_url, path));
+        Ok(self.send::<Document<T>>(req).await?.data)
+    }
+
+    async fn list<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        filters: &[(&str, String)],
+        page: PageRequest,
+    ) -> Result<Page<T>, UnitError> {
+        let mut query: Vec<(&str, String)> = filters.to_vec();
+        query.push(("page[limit]", page.limit.to_string()));
+        query.push(("page[offset]", page.offset.to_string()));
+
+        let req = self.http.get(format!("{}/{}", self.base_url, path)).query(&query);
+        let doc: Document<Vec<T>> = self.send(req).await?;
+
+        Ok(Page {
+            items: doc.data,
+            offset: page.offset,
+            limit: page.limit,
+            total: doc.meta["pagination"]["total"].as_u64(),
+        })
+    }
+
+    async fn send<T: DeserializeOwned>(&self, req: RequestBuilder) -> Result<T, UnitError> {
+        let resp = self.authorized(req).send().await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+
+        if !status.is_success() {
+            let errors = serde_json::from_str::<ErrorDocument>(&body)
+                .map(|doc| doc.errors)
+                .unwrap_or_else(|_| vec![UnitApiError { title: body.clone(), detail: None, code: None }]);
+            return Err(UnitError::Api { status: status.as_u16(), errors });
+        }
+
+        serde_json::from_str(&body).map_err(decode)
+    }
+}
+
+fn ensure_not_rejected(payment: Payment) -> Result<Payment, UnitError> {
+    if payment.attributes.status == "Rejected" {
+        return Err(UnitError::Rejected {
+            reason: payment.attributes.reason.unwrap_or_else(|| "unknown".to_string()),
+            payment_id: payment.id,
         });
+    }
+    Ok(payment)
+}
+
+fn decode(e: serde_json::Error) -> UnitError {
+    UnitError::Decode(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api(status: u16) -> UnitError {
+        UnitError::Api { status, errors: Vec::new() }
+    }
 
-        let resp = self.http.post(&url)
-            .header("Authorization", format!("Bearer {}", self.token))
-            .json(&payload)
-            .send()
-            .await?;
+    #[test]
+    fn only_rejections_and_validation_errors_are_definitive() {
+        let rejected = UnitError::Rejected { payment_id: "1".to_string(), reason: "InsufficientFunds".to_string() };
+        assert!(rejected.is_definitive());
+        assert!(api(400).is_definitive());
+        assert!(api(422).is_definitive());
 
-        // Extract the barcode image URL or numeric code
-        let json: serde_json::Value = resp.json().await?;
-        Ok(json["data"]["attributes"]["barcodeUrl"].as_str().unwrap().to_string())
+        // Unit may have moved the money: re-send with the same key
+        for status in [408, 409, 429, 500, 502, 503] {
+            assert!(!api(status).is_definitive(), "{} must not be definitive", status);
+        }
+        assert!(!UnitError::Decode("truncated body".to_string()).is_definitive());
     }
-}
\ No newline at end of file
+}
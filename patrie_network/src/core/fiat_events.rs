@@ -0,0 +1,627 @@
+use crate::core::ach::{prefunded_burn, record_payment_status};
+use crate::core::cards::{capture_card_hold, sync_authorization, CardConfig, CARD_TRANSACTION_TYPES};
+use crate::core::cash_deposits::{record_cash_deposit, CashConfig};
+use crate::core::fiat_banking::{FiatAsset, UnitClient};
+use crate::core::payroll::payroll_prefunded;
+use crate::ledger::client::IrohaClient;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha1::Sha1;
+use sqlx::PgPool;
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- FIAT -> LEDGER ---
+// Unit tells us about money moving through webhooks. Each event is stored
+// first, then applied: money into a linked deposit account mints `usd` to the
+// mirrored on-chain account, money out burns it. Mint/burn carry an on-chain
+// receipt keyed by the Unit transaction, so replays never double-apply.
+
+/// Transaction types that undo an earlier movement
+const REVERSAL_TYPES: [&str; 5] = [
+    "returnedAchTransaction",         // Our outgoing ACH came back
+    "returnedReceivedAchTransaction", // We sent back an incoming ACH
+    "dishonoredAchTransaction",
+    "reversalTransaction",
+    "chargebackTransaction",
+];
+
+/// After this many failed attempts an event waits for an operator
+const MAX_ATTEMPTS: i32 = 10;
+
+#[derive(Debug, Clone)]
+pub struct FiatConfig {
+    pub webhook_secret: String,
+    pub usd_asset_id: String,
+    pub cards: CardConfig, // Card holds (core/cards.rs)
+    pub cash: CashConfig,  // Cash deposit limits & holds (core/cash_deposits.rs)
+}
+
+impl FiatConfig {
+    /// Reads UNIT_WEBHOOK_SECRET (and the card & cash settings)
+    pub fn from_env(asset: &FiatAsset) -> Self {
+        Self {
+            webhook_secret: std::env::var("UNIT_WEBHOOK_SECRET").unwrap_or_default(),
+            usd_asset_id: asset.usd_asset_id.clone(),
+            cards: CardConfig::from_env(asset),
+            cash: CashConfig::from_env(asset),
+        }
+    }
+}
+
+/// Whose money sits in a linked Unit account
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FiatOwner {
+    Tenant,
+    User,
+}
+
+impl FiatOwner {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FiatOwner::Tenant => "tenant",
+            FiatOwner::User => "user",
+        }
+    }
+}
+
+/// Where an event ended up
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventStatus {
+    Processed, // Minted / burned (or already had been)
+    Ignored,   // Not a money movement, or not a linked account
+    Failed,    // Will be retried by the fiat_events (or gusto_events) job
+}
+
+impl EventStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventStatus::Processed => "processed",
+            EventStatus::Ignored => "ignored",
+            EventStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LedgerAction {
+    Mint,
+    Burn,
+}
+
+impl LedgerAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LedgerAction::Mint => "mint",
+            LedgerAction::Burn => "burn",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FiatAccountLink {
+    pub unit_account_id: String,
+    pub tenant_id: Uuid,
+    pub owner_type: String,
+    pub onchain_account_id: String,
+}
+
+/// Unit signs the raw body: base64(HMAC-SHA1(secret, body)) in `X-Unit-Signature`
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let expected = match STANDARD.decode(signature.trim()) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+    let mut mac = match Hmac::<Sha1>::new_from_slice(secret.as_bytes()) {
+        Ok(mac) => mac,
+        Err(_) => return false,
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok() // Constant-time comparison
+}
+
+#[derive(Deserialize)]
+struct WebhookBody {
+    data: Vec<Value>,
+}
+
+pub struct FiatEventProcessor {
+    db: PgPool,
+    unit: Arc<UnitClient>,
+    iroha: Arc<IrohaClient>,
+    config: FiatConfig,
+}
+
+impl FiatEventProcessor {
+    pub fn new(db: PgPool, unit: Arc<UnitClient>, iroha: Arc<IrohaClient>, config: FiatConfig) -> Self {
+        Self { db, unit, iroha, config }
+    }
+
+    pub fn verify(&self, body: &[u8], signature: Option<&str>) -> Result<(), Box<dyn Error>> {
+        if self.config.webhook_secret.is_empty() {
+            return Err("UNIT_WEBHOOK_SECRET is not configured".into());
+        }
+        let signature = signature.ok_or("Missing X-Unit-Signature header")?;
+        if !verify_signature(&self.config.webhook_secret, body, signature) {
+            return Err("Invalid webhook signature".into());
+        }
+        Ok(())
+    }
+
+    /// 1. STORE: persists every event in the delivery (redeliveries are no-ops).
+    /// Returns the event ids, so the caller can process them right away.
+    pub async fn receive(&self, body: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        let delivery: WebhookBody = serde_json::from_slice(body)?;
+        let mut ids = Vec::new();
+
+        for event in delivery.data {
+            let id = event["id"].as_str().ok_or("Event without id")?.to_string();
+            let event_type = event["type"].as_str().ok_or("Event without type")?.to_string();
+
+            sqlx::query!(
+                r#"
+                INSERT INTO unit_webhook_events (id, event_type, payload)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (id) DO NOTHING
+                "#,
+                id,
+                event_type,
+                event
+            )
+            .execute(&self.db)
+            .await?;
+
+            ids.push(id);
+        }
+
+        Ok(ids)
+    }
+
+    /// 2. PROCESS one stored event. Safe to call any number of times.
+    pub async fn process(&self, event_id: &str) -> Result<EventStatus, Box<dyn Error>> {
+        // Claim it, so two replicas don't work the same event at once.
+        // A claim older than 10 minutes belongs to a crashed worker.
+        let claimed = sqlx::query!(
+            r#"
+            UPDATE unit_webhook_events
+            SET status = 'processing', attempts = attempts + 1, updated_at = NOW()
+            WHERE id = $1
+              AND (status IN ('received', 'failed')
+                   OR (status = 'processing' AND updated_at < NOW() - INTERVAL '10 minutes'))
+            RETURNING payload
+            "#,
+            event_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let payload = match claimed {
+            Some(row) => row.payload,
+            None => return Ok(EventStatus::Processed), // Done already, or someone else has it
+        };
+
+        let (status, error) = match self.apply(event_id, &payload).await {
+            Ok(status) => (status, None),
+            Err(e) => {
+                eprintln!("❌ Unit event {} failed: {}", event_id, e);
+                (EventStatus::Failed, Some(e.to_string()))
+            }
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE unit_webhook_events
+            SET status = $2, error = $3, updated_at = NOW(),
+                processed_at = CASE WHEN $2 = 'failed' THEN NULL ELSE NOW() END
+            WHERE id = $1
+            "#,
+            event_id,
+            status.as_str(),
+            error
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(status)
+    }
+
+    /// 3. RETRY: everything not yet applied (called by the fiat_events job)
+    pub async fn process_pending(&self) -> Result<(), Box<dyn Error>> {
+        let pending = sqlx::query!(
+            r#"
+            SELECT id FROM unit_webhook_events
+            WHERE (status IN ('received', 'failed') AND attempts < $1)
+               OR (status = 'processing' AND updated_at < NOW() - INTERVAL '10 minutes')
+            ORDER BY received_at
+            "#,
+            MAX_ATTEMPTS
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut failed = 0;
+        for event in &pending {
+            if self.process(&event.id).await? == EventStatus::Failed {
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("{} of {} Unit events still failing", failed, pending.len()).into());
+        }
+        Ok(())
+    }
+
+    /// Mint or burn for one `transaction.created` event; `payment.*` events
+    /// move the status of ACH payments users originated (core/ach.rs),
+    /// `authorization.*` events place and release card holds (core/cards.rs)
+    async fn apply(&self, event_id: &str, event: &Value) -> Result<EventStatus, Box<dyn Error>> {
+        let event_type = event["type"].as_str().unwrap_or_default();
+        if event_type.starts_with("payment.") {
+            return self.apply_payment_event(event).await;
+        }
+        if event_type.starts_with("authorization.") {
+            return self.apply_authorization_event(event).await;
+        }
+        if event_type != "transaction.created" {
+            return Ok(EventStatus::Ignored);
+        }
+
+        let account_id = event["relationships"]["account"]["data"]["id"]
+            .as_str()
+            .ok_or("Event has no account")?;
+        let transaction_id = event["relationships"]["transaction"]["data"]["id"]
+            .as_str()
+            .ok_or("Event has no transaction")?;
+
+        let link = match self.link_for(account_id).await? {
+            Some(link) => link,
+            None => return Ok(EventStatus::Ignored), // Not an account we mirror (e.g. our revenue account)
+        };
+
+        // Applied already (the same transaction can arrive under several events)
+        let existing = sqlx::query!(
+            "SELECT id FROM fiat_ledger_entries WHERE unit_transaction_id = $1",
+            transaction_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        if existing.is_some() {
+            return Ok(EventStatus::Processed);
+        }
+
+        // Amount & direction come from Unit itself, not from the event body
+        let transaction = self.unit.get_transaction(account_id, transaction_id).await?;
+        let amount_cents = transaction.attributes.amount;
+        if amount_cents <= 0 {
+            return Ok(EventStatus::Ignored);
+        }
+
+        let action = match transaction.attributes.direction.as_str() {
+            "Credit" => LedgerAction::Mint,
+            "Debit" => LedgerAction::Burn,
+            other => return Err(format!("Unknown direction '{}'", other).into()),
+        };
+
+        // Mint/burn + receipt in one ledger transaction
+        let receipt_key = format!("unit_tx_{}", transaction_id);
+        let account = &link.onchain_account_id;
+        let asset = &self.config.usd_asset_id;
+
+        // Already set aside on-chain: outgoing ACH a user originated was burned
+        // before it was sent, card purchases are paid out of their hold
+        let payment_id = transaction.related_id("payment");
+        let kind = transaction.kind.as_str();
+        let prefunded = match (action, transaction.related_id("authorization")) {
+            (LedgerAction::Burn, _) if kind == "originatedAchTransaction" => {
+                prefunded_burn(&self.db, account_id, &payment_id).await?
+            }
+            (LedgerAction::Burn, Some(authorization_id)) if CARD_TRANSACTION_TYPES.contains(&kind) => {
+                capture_card_hold(
+                    &self.db,
+                    &self.iroha,
+                    &self.config.cards,
+                    &authorization_id,
+                    account,
+                    amount_cents,
+                    transaction_id,
+                )
+                .await?
+            }
+            (_, _) if kind == "bookTransaction" => payroll_prefunded(&self.db, account_id, &payment_id).await?,
+            _ => None,
+        };
+
+        // Cash paid in at a retailer is recorded against the user's limits;
+        // what goes over them is held rather than minted to them
+        let held_cents = if transaction.kind == "cashDepositTransaction" && action == LedgerAction::Mint {
+            record_cash_deposit(&self.db, self.config.cash.limits, account_id, amount_cents, transaction_id).await?
+        } else {
+            0
+        };
+
+        let tx_hash = if let Some(hash) = prefunded {
+            hash
+        } else if self.iroha.has_receipt(account, &receipt_key).await.map_err(|e| e.to_string())? {
+            format!("receipt:{}", receipt_key) // We crashed after submitting last time
+        } else if held_cents > 0 {
+            self.mint_with_hold(account, amount_cents, held_cents, &receipt_key).await?
+        } else {
+            match action {
+                LedgerAction::Mint => self.iroha.mint_with_receipt(account, asset, amount_cents, &receipt_key).await,
+                LedgerAction::Burn => self.iroha.burn_with_receipt(account, asset, amount_cents, &receipt_key).await,
+            }
+            .map_err(|e| e.to_string())?
+        };
+
+        // Returns & reversals point at the entry they compensate
+        let reverses_entry_id = if REVERSAL_TYPES.contains(&transaction.kind.as_str()) {
+            self.original_entry(&transaction.related_id("relatedTransaction"), &payment_id, action).await?
+        } else {
+            None
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO fiat_ledger_entries
+            (id, unit_transaction_id, unit_event_id, unit_account_id, transaction_type, unit_payment_id, action,
+             onchain_account_id, amount_cents, reverses_entry_id, tx_hash)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (unit_transaction_id) DO NOTHING
+            "#,
+            Uuid::new_v4(),
+            transaction_id,
+            event_id,
+            account_id,
+            transaction.kind,
+            payment_id,
+            action.as_str(),
+            account,
+            amount_cents,
+            reverses_entry_id,
+            tx_hash
+        )
+        .execute(&self.db)
+        .await?;
+
+        let verb = if action == LedgerAction::Mint { "Minted" } else { "Burned" };
+        println!(
+            "🏦 {} ${:.2} for {} (Unit {} {}).",
+            verb, amount_cents as f64 / 100.0, account, transaction.kind, transaction_id
+        );
+        Ok(EventStatus::Processed)
+    }
+
+    /// Mint a cash deposit that went over the user's limits: the held part to
+    /// the cash hold account (so reserves still match Unit), the rest to the user.
+    /// The user's mint goes last: its receipt means both are done.
+    async fn mint_with_hold(
+        &self,
+        account: &str,
+        amount_cents: i64,
+        held_cents: i64,
+        receipt_key: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let asset = &self.config.usd_asset_id;
+        let hold_account = &self.config.cash.hold_account_id;
+        let held_key = format!("{}_held", receipt_key);
+
+        let mut tx_hash = if self.iroha.has_receipt(hold_account, &held_key).await.map_err(|e| e.to_string())? {
+            format!("receipt:{}", held_key)
+        } else {
+            self.iroha
+                .mint_with_receipt(hold_account, asset, held_cents, &held_key)
+                .await
+                .map_err(|e| e.to_string())?
+        };
+        if amount_cents > held_cents {
+            tx_hash = self
+                .iroha
+                .mint_with_receipt(account, asset, amount_cents - held_cents, receipt_key)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(tx_hash)
+    }
+
+    /// Status change of a payment. Like transactions, the payment itself is
+    /// read back from Unit rather than trusted from the event body.
+    async fn apply_payment_event(&self, event: &Value) -> Result<EventStatus, Box<dyn Error>> {
+        let payment_id = event["relationships"]["payment"]["data"]["id"]
+            .as_str()
+            .ok_or("Event has no payment")?;
+
+        let payment = self.unit.get_payment(payment_id).await?;
+        match record_payment_status(&self.db, &self.iroha, &self.config.usd_asset_id, &payment).await? {
+            Some(ach) => {
+                println!("🏦 ACH payment {} is {}.", ach.id, ach.status);
+                Ok(EventStatus::Processed)
+            }
+            None => Ok(EventStatus::Ignored),
+        }
+    }
+
+    /// A card authorization was created, changed amount, or went away
+    async fn apply_authorization_event(&self, event: &Value) -> Result<EventStatus, Box<dyn Error>> {
+        let authorization_id = event["relationships"]["authorization"]["data"]["id"]
+            .as_str()
+            .ok_or("Event has no authorization")?;
+
+        let auth = self.unit.get_authorization(authorization_id).await?;
+        match sync_authorization(&self.db, &self.iroha, &self.config.cards, &auth).await? {
+            Some(_) => Ok(EventStatus::Processed),
+            None => Ok(EventStatus::Ignored), // Not one of our cards
+        }
+    }
+
+    /// The entry a return/reversal undoes: the related transaction, or the
+    /// opposite movement for the same payment (ACH returns)
+    async fn original_entry(
+        &self,
+        related_transaction_id: &Option<String>,
+        payment_id: &Option<String>,
+        action: LedgerAction,
+    ) -> Result<Option<Uuid>, Box<dyn Error>> {
+        let opposite = match action {
+            LedgerAction::Mint => LedgerAction::Burn,
+            LedgerAction::Burn => LedgerAction::Mint,
+        };
+
+        let row = sqlx::query!(
+            r#"
+            SELECT id FROM fiat_ledger_entries
+            WHERE action = $3
+              AND (unit_transaction_id = $1 OR ($2::TEXT IS NOT NULL AND unit_payment_id = $2))
+            ORDER BY created_at
+            LIMIT 1
+            "#,
+            related_transaction_id.as_deref(),
+            payment_id.as_deref(),
+            opposite.as_str()
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(row.map(|r| r.id))
+    }
+
+    async fn link_for(&self, unit_account_id: &str) -> Result<Option<FiatAccountLink>, Box<dyn Error>> {
+        let row = sqlx::query!(
+            "SELECT unit_account_id, tenant_id, owner_type, onchain_account_id FROM fiat_account_links WHERE unit_account_id = $1",
+            unit_account_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(row.map(|r| FiatAccountLink {
+            unit_account_id: r.unit_account_id,
+            tenant_id: r.tenant_id,
+            owner_type: r.owner_type,
+            onchain_account_id: r.onchain_account_id,
+        }))
+    }
+
+    /// LINK a Unit deposit account to the on-chain account that mirrors it.
+    /// Unit has to say the account is the tenant's: held by its own customer
+    /// (owner "tenant"), or by a customer tagged with its id (owner "user").
+    /// The on-chain account must live in the tenant's domain. An existing link
+    /// is never re-pointed: unlink it first.
+    pub async fn link_account(
+        &self,
+        tenant_id: Uuid,
+        unit_account_id: &str,
+        owner: FiatOwner,
+        onchain_account_id: &str,
+    ) -> Result<FiatAccountLink, Box<dyn Error>> {
+        let tenant = sqlx::query!("SELECT iroha_domain, unit_customer_id FROM tenants WHERE id = $1", tenant_id)
+            .fetch_one(&self.db)
+            .await?;
+        let domain = tenant.iroha_domain.ok_or("Tenant has no ledger domain")?;
+
+        if onchain_account_id.split('@').nth(1) != Some(domain.as_str()) {
+            return Err(format!("On-chain account must belong to domain {}", domain).into());
+        }
+
+        if let Some(link) = self.link_for(unit_account_id).await? {
+            if link.tenant_id != tenant_id {
+                return Err("Unit account is linked to another tenant".into());
+            }
+            if link.owner_type != owner.as_str() || link.onchain_account_id != onchain_account_id {
+                return Err(format!(
+                    "Unit account is already linked to {}: unlink it first",
+                    link.onchain_account_id
+                )
+                .into());
+            }
+            return Ok(link); // Same link again
+        }
+
+        let account = self.unit.get_account(unit_account_id).await?;
+        let customer_id = account.related_id("customer").ok_or("Unit account has no customer")?;
+        let owned = match owner {
+            FiatOwner::Tenant => tenant.unit_customer_id.as_deref() == Some(customer_id.as_str()),
+            FiatOwner::User => {
+                let customer = self.unit.get_customer(&customer_id).await?;
+                customer.attributes.tags["tenant_id"].as_str() == Some(tenant_id.to_string().as_str())
+            }
+        };
+        if !owned {
+            return Err(format!("Unit account {} does not belong to this tenant", unit_account_id).into());
+        }
+
+        // A concurrent link of the same account loses here instead of overwriting
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO fiat_account_links (unit_account_id, tenant_id, owner_type, onchain_account_id)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (unit_account_id) DO NOTHING
+            "#,
+            unit_account_id,
+            tenant_id,
+            owner.as_str(),
+            onchain_account_id
+        )
+        .execute(&self.db)
+        .await?
+        .rows_affected();
+        if inserted == 0 {
+            return Err("Unit account was linked concurrently".into());
+        }
+
+        Ok(FiatAccountLink {
+            unit_account_id: unit_account_id.to_string(),
+            tenant_id,
+            owner_type: owner.as_str().to_string(),
+            onchain_account_id: onchain_account_id.to_string(),
+        })
+    }
+
+    /// UNLINK a Unit account, so it can be linked again elsewhere. Refused while
+    /// barcodes, ACH payments or cards still refer to it.
+    pub async fn unlink_account(&self, tenant_id: Uuid, unit_account_id: &str) -> Result<(), Box<dyn Error>> {
+        let result = sqlx::query!(
+            "DELETE FROM fiat_account_links WHERE unit_account_id = $1 AND tenant_id = $2",
+            unit_account_id,
+            tenant_id
+        )
+        .execute(&self.db)
+        .await;
+
+        match result {
+            Ok(done) if done.rows_affected() == 0 => Err(format!("Unit account {} is not linked", unit_account_id).into()),
+            Ok(_) => Ok(()),
+            Err(sqlx::Error::Database(e)) if e.is_foreign_key_violation() => {
+                Err(format!("Unit account {} is still in use (barcodes, ACH payments or cards)", unit_account_id).into())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify_signature;
+
+    // RFC 2104-style reference vector: HMAC-SHA1("key", BODY), base64
+    const BODY: &[u8] = b"The quick brown fox jumps over the lazy dog";
+    const SIGNATURE: &str = "3nybhbi3iqa8ino29wqQcBydtNk=";
+
+    #[test]
+    fn accepts_unit_signature() {
+        assert!(verify_signature("key", BODY, SIGNATURE));
+        assert!(verify_signature("key", BODY, &format!(" {}\n", SIGNATURE))); // Header whitespace
+    }
+
+    #[test]
+    fn rejects_wrong_secret_body_or_encoding() {
+        assert!(!verify_signature("other-key", BODY, SIGNATURE));
+        assert!(!verify_signature("key", b"The quick brown fox jumps over the lazy cat", SIGNATURE));
+        assert!(!verify_signature("key", BODY, "de7c9b85b8b78aa6bc8a7a36f70a90701c9db4d9")); // Hex, not base64
+        assert!(!verify_signature("key", BODY, ""));
+    }
+}
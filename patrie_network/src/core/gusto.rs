@@ -1,6 +1,9 @@
-use reqwest::Client;
+use crate::core::gusto_tokens::{GustoTokenStore, DEFAULT_TOKEN_LIFETIME_SECS};
+use chrono::NaiveDate;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::Value; // Added this missing import
+use sqlx::PgPool;
 use std::error::Error;
 
 // --- Data Models ---
@@ -26,8 +29,153 @@ struct CompanyInfo {
 #[derive(Deserialize, Debug)]
 pub struct CreateCompanyResponse {
     pub company_uuid: String,
-    pub access_token: String, // SAVE THIS! This is the key to their specific account
+    pub access_token: String, // Stored encrypted by `create_partner_managed_company`
     pub refresh_token: String,
+    pub expires_in: Option<i64>, // Seconds
+}
+
+/// One benefit Gusto supports (GET /v1/benefits). `benefit_type` is the id a
+/// company benefit is created with; core/benefit_catalog.rs caches these.
+#[derive(Serialize, Debug, Clone)]
+pub struct GustoBenefit {
+    pub benefit_type: String, // Gusto sends a number: kept as text, like we send it back
+    pub name: String,         // e.g. "Medical Insurance"
+    pub description: Option<String>,
+    pub pretax: bool,
+    pub posttax: bool,
+    pub healthcare: bool,
+    pub retirement: bool,
+}
+
+/// A benefit a company already offers (GET /v1/companies/{uuid}/company_benefits)
+#[derive(Debug, Clone)]
+pub struct CompanyBenefit {
+    pub uuid: String,
+    pub benefit_type: String,
+    pub description: String,
+    pub active: bool,
+}
+
+/// What to change on a company benefit; `None` leaves the field as is
+#[derive(Debug, Default, Clone)]
+pub struct BenefitUpdate {
+    pub active: Option<bool>,
+    pub description: Option<String>,
+}
+
+/// The fields of a Gusto employee we mirror on-chain
+#[derive(Deserialize, Debug, Clone)]
+pub struct GustoEmployee {
+    pub uuid: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub terminated: bool,
+}
+
+/// A processed payroll, with what each employee was paid.
+/// Gusto sends amounts as strings ("2500.00"): see `to_cents`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct GustoPayroll {
+    #[serde(alias = "payroll_uuid")]
+    pub uuid: String,
+    #[serde(default)]
+    pub processed: bool,
+    pub check_date: NaiveDate,
+    pub pay_period: GustoPayPeriod,
+    #[serde(default)]
+    pub employee_compensations: Vec<EmployeeCompensation>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GustoPayPeriod {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EmployeeCompensation {
+    pub employee_uuid: String,
+    pub gross_pay: Option<String>,
+    pub net_pay: Option<String>,
+    #[serde(default)]
+    pub taxes: Vec<PayrollTax>,
+    #[serde(default)]
+    pub benefits: Vec<PayrollBenefit>,
+    #[serde(default)]
+    pub deductions: Vec<PayrollDeduction>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PayrollTax {
+    pub name: String,
+    pub employer: bool, // false = withheld from the employee
+    pub amount: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PayrollBenefit {
+    pub name: String,
+    pub employee_deduction: Option<String>,
+    pub company_contribution: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PayrollDeduction {
+    pub name: String,
+    pub amount: String,
+}
+
+/// "2500.00" -> 250000. Parsed exactly (no floats); more than 2 decimals is an error.
+pub fn to_cents(amount: &str) -> Result<i64, Box<dyn Error>> {
+    let invalid = || format!("Invalid amount '{}'", amount);
+    let trimmed = amount.trim();
+    let (negative, digits) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !all_digits(whole) || !all_digits(fraction) {
+        return Err(invalid().into());
+    }
+    if fraction.len() > 2 {
+        return Err(format!("Amount '{}' has more than 2 decimal places", amount).into());
+    }
+
+    let dollars: i64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+    let cents: i64 = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+    let total = dollars.checked_mul(100).and_then(|d| d.checked_add(cents)).ok_or_else(invalid)?;
+    Ok(if negative { -total } else { total })
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: Option<i64>,
+}
+
+/// OAuth application credentials (token refresh & revocation)
+#[derive(Debug, Clone)]
+pub struct GustoOAuth {
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_uri: String,
+}
+
+impl GustoOAuth {
+    /// Reads GUSTO_CLIENT_ID, GUSTO_CLIENT_SECRET and GUSTO_REDIRECT_URI
+    pub fn from_env() -> Self {
+        let var = |key: &str| std::env::var(key).unwrap_or_default();
+        Self {
+            client_id: var("GUSTO_CLIENT_ID"),
+            client_secret: var("GUSTO_CLIENT_SECRET"),
+            redirect_uri: var("GUSTO_REDIRECT_URI"),
+        }
+    }
 }
 
 // --- The Client ---
@@ -36,18 +184,27 @@ pub struct GustoClient {
     http: Client,
     api_token: String, // Your "System" Token (Organization Level)
     base_url: String,
+    oauth: GustoOAuth,
+    tokens: GustoTokenStore, // Per-company tokens, refreshed on demand
 }
 
 impl GustoClient {
-    pub fn new(api_token: String) -> Self {
+    pub fn new(api_token: String, oauth: GustoOAuth, tokens: GustoTokenStore) -> Self {
         Self {
             http: Client::new(),
             api_token,
             // Switch to "https://api.gusto.com" for PRODUCTION
             base_url: "https://api.gusto-demo.com".to_string(), 
+            oauth,
+            tokens,
         }
     }
 
+    /// Same client against another host (e.g. the sandbox simulator)
+    pub fn with_base_url(api_token: String, base_url: String, oauth: GustoOAuth, tokens: GustoTokenStore) -> Self {
+        Self { base_url, ..Self::new(api_token, oauth, tokens) }
+    }
+
     /// 1. CREATE THE CLIENT (The "Sign Up")
     /// This is the Money Maker. Once this succeeds, this company is permanently 
     /// linked to your ecosystem, and you begin earning revenue share.
@@ -83,63 +240,228 @@ impl GustoClient {
         }
 
         let data: CreateCompanyResponse = resp.json().await?;
+
+        // The company token is the only way back into their account: store it
+        // before anything else can fail
+        self.tokens
+            .save(
+                &data.company_uuid,
+                &data.access_token,
+                Some(&data.refresh_token),
+                Some(data.expires_in.unwrap_or(DEFAULT_TOKEN_LIFETIME_SECS)),
+            )
+            .await?;
+
         Ok(data)
     }
 
-    /// 2. ADD HEALTH INSURANCE (The Upsell)
-    /// This adds a benefit to the company. Gusto handles the carrier selection 
+    /// 2. GUSTO'S BENEFIT CATALOG
+    /// Every benefit Gusto supports and the `benefit_type` to create it with.
+    /// Not company specific, so it uses the system token.
+    pub async fn list_benefits(&self) -> Result<Vec<GustoBenefit>, Box<dyn Error>> {
+        let url = format!("{}/v1/benefits", self.base_url);
+
+        let resp = self.http.get(&url)
+            .header("Authorization", format!("Bearer {}", self.api_token))
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let error_text = resp.text().await?;
+            return Err(format!("Failed to list benefits: {}", error_text).into());
+        }
+
+        let entries: Vec<Value> = resp.json().await?;
+        let flag = |entry: &Value, key: &str| entry[key].as_bool().unwrap_or(false);
+        entries
+            .iter()
+            .map(|entry| {
+                let benefit_type = match &entry["benefit_type"] {
+                    Value::Number(n) => n.to_string(),
+                    Value::String(s) => s.clone(),
+                    _ => return Err("Benefit without a benefit_type in Gusto response".into()),
+                };
+                Ok(GustoBenefit {
+                    benefit_type,
+                    name: entry["name"].as_str().unwrap_or_default().to_string(),
+                    description: entry["description"].as_str().map(str::to_string),
+                    pretax: flag(entry, "pretax"),
+                    posttax: flag(entry, "posttax"),
+                    healthcare: flag(entry, "healthcare"),
+                    retirement: flag(entry, "retirement"),
+                })
+            })
+            .collect()
+    }
+
+    /// 2b. ADD A BENEFIT TO THE COMPANY (The Upsell)
+    /// `benefit_type` comes from the catalog. Gusto handles the carrier selection
     /// (Blue Cross, United, etc.) via their UI Flows.
-    pub async fn add_health_benefit(
-        &self, 
-        company_uuid: &str, 
-        company_access_token: &str // Note: Use the COMPANY token, not your System token
-    ) -> Result<(), Box<dyn Error>> {
-        
+    /// Returns the company benefit UUID (needed to update it later).
+    pub async fn add_benefit(
+        &self,
+        company_uuid: &str,
+        benefit_type: &str,
+        description: &str
+    ) -> Result<String, Box<dyn Error>> {
         let url = format!("{}/v1/companies/{}/company_benefits", self.base_url, company_uuid);
-        
-        // ID '1' is usually Medical in Gusto's system.
-        // Best practice: Query /v1/benefits first to get the exact ID for "Medical"
+
         let payload = serde_json::json!({
-            "benefit_type": "1", 
+            "benefit_type": benefit_type,
             "active": true,
-            "description": "Company Health Plan"
+            "description": description
         });
 
-        let resp = self.http.post(&url)
-            .header("Authorization", format!("Bearer {}", company_access_token))
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
+        let resp = self.send_as_company(company_uuid, |token| {
+            self.http.post(&url)
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .json(&payload)
+        }).await?;
 
         if !resp.status().is_success() {
             let error_text = resp.text().await?;
             return Err(format!("Failed to add benefit: {}", error_text).into());
         }
 
+        let json: Value = resp.json().await?;
+        match json["uuid"].as_str() {
+            Some(uuid) => Ok(uuid.to_string()),
+            None => Err("No benefit UUID found in Gusto response".into()),
+        }
+    }
+
+    /// 2b'. THE COMPANY'S BENEFITS (to find one we created but never recorded)
+    pub async fn list_company_benefits(&self, company_uuid: &str) -> Result<Vec<CompanyBenefit>, Box<dyn Error>> {
+        let url = format!("{}/v1/companies/{}/company_benefits", self.base_url, company_uuid);
+
+        let resp = self.send_as_company(company_uuid, |token| {
+            self.http.get(&url).header("Authorization", format!("Bearer {}", token))
+        }).await?;
+
+        if !resp.status().is_success() {
+            let error_text = resp.text().await?;
+            return Err(format!("Failed to list company benefits: {}", error_text).into());
+        }
+
+        let entries: Vec<Value> = resp.json().await?;
+        entries
+            .iter()
+            .map(|entry| {
+                let benefit_type = match &entry["benefit_type"] {
+                    Value::Number(n) => n.to_string(),
+                    Value::String(s) => s.clone(),
+                    _ => return Err("Company benefit without a benefit_type in Gusto response".into()),
+                };
+                Ok(CompanyBenefit {
+                    uuid: entry["uuid"].as_str().ok_or("Company benefit without a uuid")?.to_string(),
+                    benefit_type,
+                    description: entry["description"].as_str().unwrap_or_default().to_string(),
+                    active: entry["active"].as_bool().unwrap_or(false),
+                })
+            })
+            .collect()
+    }
+
+    /// 2c. UPDATE A BENEFIT (rename, turn off, turn back on)
+    /// Gusto requires the current 'version' of the object for any update,
+    /// so we read it first and send it back with the changes.
+    pub async fn update_benefit(
+        &self,
+        company_uuid: &str,
+        company_benefit_uuid: &str,
+        update: &BenefitUpdate
+    ) -> Result<(), Box<dyn Error>> {
+        let url = format!("{}/v1/company_benefits/{}", self.base_url, company_benefit_uuid);
+
+        let resp = self.send_as_company(company_uuid, |token| {
+            self.http.get(&url).header("Authorization", format!("Bearer {}", token))
+        }).await?;
+
+        if !resp.status().is_success() {
+            let error_text = resp.text().await?;
+            return Err(format!("Failed to read benefit: {}", error_text).into());
+        }
+
+        let current: Value = resp.json().await?;
+        let version = current["version"].as_str().ok_or("No version found in Gusto response")?;
+
+        let mut payload = serde_json::json!({ "version": version });
+        if let Some(active) = update.active {
+            payload["active"] = Value::Bool(active);
+        }
+        if let Some(description) = &update.description {
+            payload["description"] = Value::String(description.clone());
+        }
+
+        let resp = self.send_as_company(company_uuid, |token| {
+            self.http.put(&url)
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .json(&payload)
+        }).await?;
+
+        if !resp.status().is_success() {
+            let error_text = resp.text().await?;
+            return Err(format!("Failed to update benefit: {}", error_text).into());
+        }
+
         Ok(())
     }
 
+    /// 2d. TURN OFF A BENEFIT (Downgrades)
+    pub async fn deactivate_benefit(
+        &self,
+        company_uuid: &str,
+        company_benefit_uuid: &str
+    ) -> Result<(), Box<dyn Error>> {
+        let update = BenefitUpdate { active: Some(false), ..Default::default() };
+        self.update_benefit(company_uuid, company_benefit_uuid, &update).await
+    }
+
+    /// 2e. WHAT THE BENEFIT COSTS US (Wholesale)
+    /// Sums the employer contribution of every active enrollment in the benefit.
+    pub async fn monthly_employer_cost(
+        &self,
+        company_uuid: &str,
+        company_benefit_uuid: &str
+    ) -> Result<f64, Box<dyn Error>> {
+        let url = format!("{}/v1/company_benefits/{}/employee_benefits", self.base_url, company_benefit_uuid);
+
+        let resp = self.send_as_company(company_uuid, |token| {
+            self.http.get(&url).header("Authorization", format!("Bearer {}", token))
+        }).await?;
+
+        if !resp.status().is_success() {
+            let error_text = resp.text().await?;
+            return Err(format!("Failed to read enrollments: {}", error_text).into());
+        }
+
+        // Gusto sends amounts as strings ("412.50")
+        let enrollments: Vec<Value> = resp.json().await?;
+        Ok(enrollments
+            .iter()
+            .filter(|e| e["active"].as_bool().unwrap_or(true))
+            .filter_map(|e| e["company_contribution"].as_str().and_then(|c| c.parse::<f64>().ok()))
+            .sum())
+    }
+
     /// 3. GENERATE MAGIC LINK (Insurance Flow)
     /// This generates the URL you redirect the user to so they can select
     /// their specific insurance plan or crime insurance.
-    pub async fn get_insurance_flow_url(
-        &self, 
-        company_uuid: &str,
-        company_access_token: &str
-    ) -> Result<String, Box<dyn Error>> {
+    pub async fn get_insurance_flow_url(&self, company_uuid: &str) -> Result<String, Box<dyn Error>> {
         let url = format!("{}/v1/companies/{}/flows", self.base_url, company_uuid);
         
         let payload = serde_json::json!({
             "flow_type": "benefits_setup" // checks if they need to pick a plan
         });
 
-        let resp = self.http.post(&url)
-            .header("Authorization", format!("Bearer {}", company_access_token))
-            .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await?;
+        let resp = self.send_as_company(company_uuid, |token| {
+            self.http.post(&url)
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .json(&payload)
+        }).await?;
 
         if !resp.status().is_success() {
             let error_text = resp.text().await?;
@@ -154,27 +476,216 @@ impl GustoClient {
             None => Err("No URL found in Gusto response".into()),
         }
     }
+
+    /// 4. EMPLOYEES (every page, terminated ones included)
+    pub async fn list_employees(&self, company_uuid: &str) -> Result<Vec<GustoEmployee>, Box<dyn Error>> {
+        const PER_PAGE: usize = 100;
+        let mut employees = Vec::new();
+
+        for page in 1.. {
+            let url = format!(
+                "{}/v1/companies/{}/employees?terminated=true&page={}&per={}",
+                self.base_url, company_uuid, page, PER_PAGE
+            );
+            let resp = self.send_as_company(company_uuid, |token| {
+                self.http.get(&url).header("Authorization", format!("Bearer {}", token))
+            }).await?;
+
+            if !resp.status().is_success() {
+                let error_text = resp.text().await?;
+                return Err(format!("Failed to list employees: {}", error_text).into());
+            }
+
+            let batch: Vec<GustoEmployee> = resp.json().await?;
+            let last_page = batch.len() < PER_PAGE;
+            employees.extend(batch);
+            if last_page {
+                break;
+            }
+        }
+
+        Ok(employees)
+    }
+
+    /// 4b. ONE EMPLOYEE (webhooks only carry the uuid)
+    pub async fn get_employee(&self, company_uuid: &str, employee_uuid: &str) -> Result<GustoEmployee, Box<dyn Error>> {
+        let url = format!("{}/v1/employees/{}", self.base_url, employee_uuid);
+
+        let resp = self.send_as_company(company_uuid, |token| {
+            self.http.get(&url).header("Authorization", format!("Bearer {}", token))
+        }).await?;
+
+        if !resp.status().is_success() {
+            let error_text = resp.text().await?;
+            return Err(format!("Failed to read employee: {}", error_text).into());
+        }
+
+        Ok(resp.json().await?)
+    }
+
+    /// 4c. ONE PAYROLL, with taxes, benefits and deductions per employee
+    pub async fn get_payroll(&self, company_uuid: &str, payroll_uuid: &str) -> Result<GustoPayroll, Box<dyn Error>> {
+        let url = format!(
+            "{}/v1/companies/{}/payrolls/{}?include=taxes,benefits,deductions",
+            self.base_url, company_uuid, payroll_uuid
+        );
+
+        let resp = self.send_as_company(company_uuid, |token| {
+            self.http.get(&url).header("Authorization", format!("Bearer {}", token))
+        }).await?;
+
+        if !resp.status().is_success() {
+            let error_text = resp.text().await?;
+            return Err(format!("Failed to read payroll: {}", error_text).into());
+        }
+
+        Ok(resp.json().await?)
+    }
+
+    /// 5. REVOKE (Offboarding)
+    /// Tells Gusto to invalidate the company's refresh token (which kills the
+    /// access tokens issued from it), then forgets our copy.
+    pub async fn revoke_company_access(&self, company_uuid: &str) -> Result<(), Box<dyn Error>> {
+        let tokens = self.tokens.get(company_uuid).await?;
+        let url = format!("{}/oauth/revoke", self.base_url);
+
+        let payload = serde_json::json!({
+            "client_id": self.oauth.client_id,
+            "client_secret": self.oauth.client_secret,
+            "token": tokens.refresh_token.unwrap_or(tokens.access_token)
+        });
+
+        let resp = self.http.post(&url)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let error_text = resp.text().await?;
+            return Err(format!("Failed to revoke Gusto access: {}", error_text).into());
+        }
+
+        self.tokens.mark_revoked(company_uuid).await?;
+        println!("🔒 Gusto access revoked for company {}.", company_uuid);
+        Ok(())
+    }
+
+    // --- COMPANY TOKENS ---
+
+    /// Sends a company-scoped request with a valid access token: refreshes
+    /// first if the stored one has expired, and once more if Gusto answers 401.
+    async fn send_as_company<F>(&self, company_uuid: &str, build: F) -> Result<Response, Box<dyn Error>>
+    where
+        F: Fn(&str) -> RequestBuilder,
+    {
+        let tokens = self.tokens.get(company_uuid).await?;
+        let token = if tokens.needs_refresh() {
+            self.refresh_company_token(company_uuid, &tokens.access_token).await?
+        } else {
+            tokens.access_token
+        };
+
+        let resp = build(&token).send().await?;
+        if resp.status() != StatusCode::UNAUTHORIZED {
+            return Ok(resp);
+        }
+
+        let token = self.refresh_company_token(company_uuid, &token).await?;
+        Ok(build(&token).send().await?)
+    }
+
+    /// Exchanges the refresh token for a new pair. Refresh tokens are single use,
+    /// so the row stays locked for the round trip; if another request already
+    /// refreshed while we waited, its token is used instead.
+    async fn refresh_company_token(&self, company_uuid: &str, stale_token: &str) -> Result<String, Box<dyn Error>> {
+        let (tx, current) = self.tokens.begin_refresh(company_uuid).await?;
+        if current.access_token != stale_token && !current.needs_refresh() {
+            return Ok(current.access_token);
+        }
+
+        let refresh_token = current
+            .refresh_token
+            .ok_or_else(|| format!("Gusto token for company {} expired and cannot be refreshed: re-authorize", company_uuid))?;
+
+        let url = format!("{}/oauth/token", self.base_url);
+        let payload = serde_json::json!({
+            "client_id": self.oauth.client_id,
+            "client_secret": self.oauth.client_secret,
+            "redirect_uri": self.oauth.redirect_uri,
+            "refresh_token": refresh_token,
+            "grant_type": "refresh_token"
+        });
+
+        let resp = self.http.post(&url)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            let error_text = resp.text().await?;
+            return Err(format!("Gusto token refresh failed for company {}: {}", company_uuid, error_text).into());
+        }
+
+        let fresh: TokenResponse = resp.json().await?;
+        self.tokens
+            .finish_refresh(
+                tx,
+                company_uuid,
+                &fresh.access_token,
+                &fresh.refresh_token,
+                fresh.expires_in.unwrap_or(DEFAULT_TOKEN_LIFETIME_SECS),
+            )
+            .await?;
+
+        println!("🔑 Gusto token refreshed for company {}.", company_uuid);
+        Ok(fresh.access_token)
+    }
 }
 
 // Helper: Update the wholesale cost in DB so the billing engine is accurate
 pub async fn update_wholesale_cost(
     pool: &PgPool, 
-    tenant_id: &str, 
+    tenant_id: uuid::Uuid, 
     new_health_cost: f64, 
     new_401k_cost: f64
 ) -> Result<(), sqlx::Error> {
     sqlx::query!(
         r#"
-        UPDATE subscription_tiers 
+        UPDATE subscription_settings 
         SET health_cost_wholesale = $1, retirement_cost_wholesale = $2
         WHERE tenant_id = $3
         "#,
         new_health_cost,
         new_401k_cost,
-        uuid::Uuid::parse_str(tenant_id).unwrap()
+        tenant_id
     )
     .execute(pool)
     .await?;
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_cents;
+
+    #[test]
+    fn parses_amounts_exactly() {
+        assert_eq!(to_cents("2500.00").unwrap(), 250_000);
+        assert_eq!(to_cents("0.29").unwrap(), 29); // 0.29 * 100.0 is 28.999... as a float
+        assert_eq!(to_cents("1234567.89").unwrap(), 123_456_789);
+        assert_eq!(to_cents("12.5").unwrap(), 1_250);
+        assert_eq!(to_cents("7").unwrap(), 700);
+        assert_eq!(to_cents(" .05 ").unwrap(), 5);
+        assert_eq!(to_cents("-12.30").unwrap(), -1_230);
+    }
+
+    #[test]
+    fn rejects_sub_cent_and_malformed_amounts() {
+        for amount in ["1.005", "0.001", "", ".", "-", "1.2.3", "12a", "1e3", "+5", "NaN", "99999999999999999999"] {
+            assert!(to_cents(amount).is_err(), "{:?} should be rejected", amount);
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use crate::core::employee_sync::EmployeeSync;
+use crate::core::fiat_events::EventStatus;
+use crate::core::gusto_tokens::TokenCipher;
+use crate::core::payroll::PayrollMirror;
+use crate::core::subscription::SubscriptionManager;
+use chrono::{TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- GUSTO -> PLATFORM ---
+// Gusto tells us about payroll, employee, benefit and company changes through
+// webhooks (employees are mirrored on the ledger by core/employee_sync.rs,
+// payrolls by core/payroll.rs). Same shape as the Unit pipeline
+// (core/fiat_events.rs): store first, answer 200, apply, and let the
+// gusto_events job retry whatever failed.
+
+/// After this many failed attempts an event waits for an operator
+const MAX_ATTEMPTS: i32 = 10;
+
+/// Benefit changes that move what a tenant's benefits cost us
+const BENEFIT_EVENT_PREFIXES: [&str; 2] = ["company_benefit.", "employee_benefit."];
+
+/// Verification deliveries are unsigned, so anyone can send one: store at most
+/// this many per hour
+const MAX_VERIFICATIONS_PER_HOUR: i64 = 5;
+
+#[derive(Debug, Clone)]
+pub struct GustoWebhookConfig {
+    pub verification_token: String,
+}
+
+impl GustoWebhookConfig {
+    /// Reads GUSTO_WEBHOOK_TOKEN (the subscription's verification token)
+    pub fn from_env() -> Self {
+        Self { verification_token: std::env::var("GUSTO_WEBHOOK_TOKEN").unwrap_or_default() }
+    }
+}
+
+/// Gusto signs the raw body: hex(HMAC-SHA256(verification_token, body)) in `X-Gusto-Signature`
+pub fn verify_gusto_signature(token: &str, body: &[u8], signature: &str) -> bool {
+    let expected = match hex::decode(signature.trim()) {
+        Ok(bytes) => bytes,
+        Err(_) => return false,
+    };
+    let mut mac = match Hmac::<Sha256>::new_from_slice(token.as_bytes()) {
+        Ok(mac) => mac,
+        Err(_) => return false,
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok() // Constant-time comparison
+}
+
+/// What a delivery turned out to be
+pub enum GustoDelivery {
+    /// Sent once, unsigned, when the subscription is created: the token has to be
+    /// sent back to Gusto (PUT /v1/webhook_subscriptions/{uuid}/verify) to activate it
+    Verification { subscription_uuid: String, verification_token: String },
+    Event(Value),
+}
+
+impl GustoDelivery {
+    pub fn parse(body: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let payload: Value = serde_json::from_slice(body)?;
+        Ok(match payload["verification_token"].as_str() {
+            Some(token) => GustoDelivery::Verification {
+                subscription_uuid: payload["webhook_subscription_uuid"]
+                    .as_str()
+                    .ok_or("Verification without webhook_subscription_uuid")?
+                    .to_string(),
+                verification_token: token.to_string(),
+            },
+            None => GustoDelivery::Event(payload),
+        })
+    }
+}
+
+pub struct GustoEventProcessor {
+    db: PgPool,
+    subscriptions: Arc<SubscriptionManager>,
+    employees: Arc<EmployeeSync>,
+    payroll: Arc<PayrollMirror>,
+    config: GustoWebhookConfig,
+    cipher: TokenCipher,
+}
+
+impl GustoEventProcessor {
+    pub fn new(
+        db: PgPool,
+        subscriptions: Arc<SubscriptionManager>,
+        employees: Arc<EmployeeSync>,
+        payroll: Arc<PayrollMirror>,
+        config: GustoWebhookConfig,
+        cipher: TokenCipher,
+    ) -> Self {
+        Self { db, subscriptions, employees, payroll, config, cipher }
+    }
+
+    pub fn verify(&self, body: &[u8], signature: Option<&str>) -> Result<(), Box<dyn Error>> {
+        if self.config.verification_token.is_empty() {
+            return Err("GUSTO_WEBHOOK_TOKEN is not configured".into());
+        }
+        let signature = signature.ok_or("Missing X-Gusto-Signature header")?;
+        if !verify_gusto_signature(&self.config.verification_token, body, signature) {
+            return Err("Invalid webhook signature".into());
+        }
+        Ok(())
+    }
+
+    /// Keep a subscription's verification token (encrypted) for an operator to
+    /// confirm with Gusto and set as GUSTO_WEBHOOK_TOKEN. The token is the
+    /// signing secret, so it is never logged. Returns false when the delivery
+    /// was ignored: a token is already configured, the subscription already has
+    /// one stored, or too many arrived this hour.
+    pub async fn store_verification(&self, subscription_uuid: &str, token: &str) -> Result<bool, Box<dyn Error>> {
+        if !self.config.verification_token.is_empty() {
+            return Ok(false);
+        }
+
+        let recent = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM gusto_webhook_verifications WHERE received_at > NOW() - INTERVAL '1 hour'"#
+        )
+        .fetch_one(&self.db)
+        .await?;
+        if recent >= MAX_VERIFICATIONS_PER_HOUR {
+            return Ok(false);
+        }
+
+        let stored = sqlx::query!(
+            r#"
+            INSERT INTO gusto_webhook_verifications (subscription_uuid, token_enc)
+            VALUES ($1, $2)
+            ON CONFLICT (subscription_uuid) DO NOTHING
+            "#,
+            subscription_uuid,
+            self.cipher.encrypt(token)?
+        )
+        .execute(&self.db)
+        .await?
+        .rows_affected();
+
+        Ok(stored == 1)
+    }
+
+    /// 1. STORE a verified event (redeliveries are no-ops). Returns its id.
+    pub async fn receive(&self, event: &Value) -> Result<String, Box<dyn Error>> {
+        let id = event["uuid"].as_str().ok_or("Event without uuid")?.to_string();
+        let event_type = event["event_type"].as_str().ok_or("Event without event_type")?;
+        let company_uuid = match event["resource_type"].as_str() {
+            Some("Company") => event["resource_uuid"].as_str(),
+            _ => None,
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO gusto_webhook_events (id, event_type, company_uuid, entity_type, entity_uuid, payload)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+            id,
+            event_type,
+            company_uuid,
+            event["entity_type"].as_str(),
+            event["entity_uuid"].as_str(),
+            event
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// 2. PROCESS one stored event. Safe to call any number of times.
+    pub async fn process(&self, event_id: &str) -> Result<EventStatus, Box<dyn Error>> {
+        // Claim it, so two replicas don't work the same event at once.
+        // A claim older than 10 minutes belongs to a crashed worker.
+        let claimed = sqlx::query!(
+            r#"
+            UPDATE gusto_webhook_events
+            SET status = 'processing', attempts = attempts + 1, updated_at = NOW()
+            WHERE id = $1
+              AND (status IN ('received', 'failed')
+                   OR (status = 'processing' AND updated_at < NOW() - INTERVAL '10 minutes'))
+            RETURNING payload
+            "#,
+            event_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let payload = match claimed {
+            Some(row) => row.payload,
+            None => return Ok(EventStatus::Processed), // Done already, or someone else has it
+        };
+
+        let (status, error) = match self.apply(&payload).await {
+            Ok(status) => (status, None),
+            Err(e) => {
+                eprintln!("❌ Gusto event {} failed: {}", event_id, e);
+                (EventStatus::Failed, Some(e.to_string()))
+            }
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE gusto_webhook_events
+            SET status = $2, error = $3, updated_at = NOW(),
+                processed_at = CASE WHEN $2 = 'failed' THEN NULL ELSE NOW() END
+            WHERE id = $1
+            "#,
+            event_id,
+            status.as_str(),
+            error
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(status)
+    }
+
+    /// 3. RETRY: everything not yet applied (called by the gusto_events job)
+    pub async fn process_pending(&self) -> Result<(), Box<dyn Error>> {
+        let pending = sqlx::query!(
+            r#"
+            SELECT id FROM gusto_webhook_events
+            WHERE (status IN ('received', 'failed') AND attempts < $1)
+               OR (status = 'processing' AND updated_at < NOW() - INTERVAL '10 minutes')
+            ORDER BY received_at
+            "#,
+            MAX_ATTEMPTS
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut failed = 0;
+        for event in &pending {
+            if self.process(&event.id).await? == EventStatus::Failed {
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("{} of {} Gusto events still failing", failed, pending.len()).into());
+        }
+        Ok(())
+    }
+
+    /// Routes one event to the tenant that owns the Gusto company
+    async fn apply(&self, event: &Value) -> Result<EventStatus, Box<dyn Error>> {
+        let event_type = event["event_type"].as_str().unwrap_or_default();
+        let company_uuid = match (event["resource_type"].as_str(), event["resource_uuid"].as_str()) {
+            (Some("Company"), Some(uuid)) => uuid,
+            _ => return Ok(EventStatus::Ignored),
+        };
+
+        let tenant_id = match self.tenant_for(company_uuid).await? {
+            Some(id) => id,
+            None => return Ok(EventStatus::Ignored), // Not one of our companies (or offboarded)
+        };
+
+        if BENEFIT_EVENT_PREFIXES.iter().any(|prefix| event_type.starts_with(prefix)) {
+            // Enrollment or plan change: what we pay Gusto moved
+            self.subscriptions.sync_tenant_costs(tenant_id).await?;
+            return Ok(EventStatus::Processed);
+        }
+
+        if event_type.starts_with("employee.") {
+            // Hired, updated, terminated, rehired: mirror on the ledger. A deleted
+            // employee can't be read back, so the full sync retires the account.
+            match (event_type, event["entity_uuid"].as_str()) {
+                ("employee.deleted", _) | (_, None) => {
+                    self.employees.sync_tenant(tenant_id).await?;
+                }
+                (_, Some(employee_uuid)) => self.employees.sync_employee(tenant_id, employee_uuid).await?,
+            }
+            return Ok(EventStatus::Processed);
+        }
+
+        match event_type {
+            "payroll.processed" => self.payroll_processed(tenant_id, event).await,
+            "company.deactivated" => self.company_deactivated(tenant_id).await,
+            _ => Ok(EventStatus::Ignored),
+        }
+    }
+
+    async fn payroll_processed(&self, tenant_id: Uuid, event: &Value) -> Result<EventStatus, Box<dyn Error>> {
+        let payroll_uuid = event["entity_uuid"].as_str().ok_or("Payroll event has no entity_uuid")?;
+        let processed_at = event["timestamp"]
+            .as_i64()
+            .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+            .unwrap_or_else(Utc::now);
+
+        // Events can arrive out of order: only move forward
+        sqlx::query!(
+            r#"
+            UPDATE tenants
+            SET gusto_last_payroll_uuid = $2, gusto_last_payroll_at = $3
+            WHERE id = $1 AND (gusto_last_payroll_at IS NULL OR gusto_last_payroll_at <= $3)
+            "#,
+            tenant_id,
+            payroll_uuid,
+            processed_at
+        )
+        .execute(&self.db)
+        .await?;
+
+        println!("🧾 Payroll {} processed for tenant {}.", payroll_uuid, tenant_id);
+
+        // Payslips (and net pay) on chain; an error retries the event, which is safe
+        self.payroll.mirror(tenant_id, payroll_uuid).await?;
+        Ok(EventStatus::Processed)
+    }
+
+    /// The company left Gusto: its benefits are gone, so stop passing their cost through
+    async fn company_deactivated(&self, tenant_id: Uuid) -> Result<EventStatus, Box<dyn Error>> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query!("UPDATE tenants SET gusto_company_status = 'deactivated' WHERE id = $1", tenant_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            r#"
+            UPDATE subscription_settings
+            SET health_active = FALSE, retirement_active = FALSE,
+                health_cost_wholesale = 0, retirement_cost_wholesale = 0
+            WHERE tenant_id = $1
+            "#,
+            tenant_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            UPDATE tenant_benefits SET active = FALSE, monthly_employer_cost = 0, updated_at = NOW()
+            WHERE tenant_id = $1
+            "#,
+            tenant_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        println!("⚠️  Gusto company for tenant {} was deactivated; benefits switched off.", tenant_id);
+        Ok(EventStatus::Processed)
+    }
+
+    async fn tenant_for(&self, company_uuid: &str) -> Result<Option<Uuid>, Box<dyn Error>> {
+        let row = sqlx::query!(
+            "SELECT id FROM tenants WHERE gusto_company_uuid = $1 AND status <> 'offboarded'",
+            company_uuid
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(row.map(|r| r.id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference vector: hex(HMAC-SHA256("key", BODY))
+    const BODY: &[u8] = b"The quick brown fox jumps over the lazy dog";
+    const SIGNATURE: &str = "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8";
+
+    #[test]
+    fn accepts_gusto_signature() {
+        assert!(verify_gusto_signature("key", BODY, SIGNATURE));
+        assert!(verify_gusto_signature("key", BODY, &SIGNATURE.to_uppercase())); // Hex case doesn't matter
+    }
+
+    #[test]
+    fn rejects_wrong_token_body_or_encoding() {
+        assert!(!verify_gusto_signature("other-token", BODY, SIGNATURE));
+        assert!(!verify_gusto_signature("key", b"The quick brown fox jumps over the lazy cat", SIGNATURE));
+        assert!(!verify_gusto_signature("key", BODY, &SIGNATURE[..62])); // Truncated
+        assert!(!verify_gusto_signature("key", BODY, "not hex"));
+    }
+
+    #[test]
+    fn tells_verifications_from_events() {
+        let verification = br#"{"verification_token": "secret", "webhook_subscription_uuid": "sub-1"}"#;
+        match GustoDelivery::parse(verification).unwrap() {
+            GustoDelivery::Verification { subscription_uuid, verification_token } => {
+                assert_eq!(subscription_uuid, "sub-1");
+                assert_eq!(verification_token, "secret");
+            }
+            GustoDelivery::Event(_) => panic!("expected a verification"),
+        }
+
+        let event = br#"{"uuid": "evt-1", "event_type": "payroll.processed"}"#;
+        assert!(matches!(GustoDelivery::parse(event).unwrap(), GustoDelivery::Event(_)));
+
+        // Without the subscription there's nothing to confirm it against
+        assert!(GustoDelivery::parse(br#"{"verification_token": "secret"}"#).is_err());
+    }
+}
@@ -0,0 +1,295 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::error::Error;
+
+// Refresh a little early so a token never expires mid-request
+const EXPIRY_MARGIN_SECS: i64 = 120;
+const NONCE_LEN: usize = 12;
+
+/// Gusto access tokens live 2 hours when the response doesn't say otherwise
+pub const DEFAULT_TOKEN_LIFETIME_SECS: i64 = 7200;
+
+/// AES-256-GCM for tokens at rest. Stored as hex(nonce || ciphertext).
+#[derive(Clone)]
+pub struct TokenCipher {
+    cipher: Aes256Gcm,
+}
+
+impl TokenCipher {
+    /// GUSTO_TOKEN_KEY (hex, 32 bytes)
+    pub fn from_env() -> Result<Self, Box<dyn Error>> {
+        let key = std::env::var("GUSTO_TOKEN_KEY").map_err(|_| "GUSTO_TOKEN_KEY is not configured")?;
+        let bytes: [u8; 32] = hex::decode(key.trim())?
+            .try_into()
+            .map_err(|_| "GUSTO_TOKEN_KEY must be 32 bytes of hex")?;
+        Ok(Self { cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes)) })
+    }
+
+    /// Throwaway key for sandbox mode: the simulated companies don't outlive the process either
+    pub fn ephemeral() -> Self {
+        Self { cipher: Aes256Gcm::new(&Aes256Gcm::generate_key(OsRng)) }
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, Box<dyn Error>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| "Token encryption failed")?;
+
+        let mut stored = nonce.to_vec();
+        stored.extend(ciphertext);
+        Ok(hex::encode(stored))
+    }
+
+    pub fn decrypt(&self, stored: &str) -> Result<String, Box<dyn Error>> {
+        let bytes = hex::decode(stored)?;
+        if bytes.len() <= NONCE_LEN {
+            return Err("Stored token is truncated".into());
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Token decryption failed (wrong GUSTO_TOKEN_KEY?)")?;
+        Ok(String::from_utf8(plaintext)?)
+    }
+}
+
+/// Decrypted tokens for one Gusto company
+#[derive(Debug, Clone)]
+pub struct CompanyTokens {
+    pub company_uuid: String,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl CompanyTokens {
+    /// Expired, or about to be
+    pub fn needs_refresh(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => expires_at - Duration::seconds(EXPIRY_MARGIN_SECS) <= Utc::now(),
+            None => false,
+        }
+    }
+}
+
+/// Encrypted storage of per-company Gusto tokens (`gusto_credentials`)
+#[derive(Clone)]
+pub struct GustoTokenStore {
+    db: PgPool,
+    cipher: TokenCipher,
+}
+
+impl GustoTokenStore {
+    pub fn new(db: PgPool, cipher: TokenCipher) -> Self {
+        Self { db, cipher }
+    }
+
+    /// Insert or replace a company's tokens (onboarding, re-authorization)
+    pub async fn save(
+        &self,
+        company_uuid: &str,
+        access_token: &str,
+        refresh_token: Option<&str>,
+        expires_in_secs: Option<i64>,
+    ) -> Result<(), Box<dyn Error>> {
+        let access_enc = self.cipher.encrypt(access_token)?;
+        let refresh_enc = refresh_token.map(|t| self.cipher.encrypt(t)).transpose()?;
+        let expires_at = expires_in_secs.map(|secs| Utc::now() + Duration::seconds(secs));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO gusto_credentials (company_uuid, access_token_enc, refresh_token_enc, expires_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (company_uuid) DO UPDATE
+            SET access_token_enc = EXCLUDED.access_token_enc,
+                refresh_token_enc = EXCLUDED.refresh_token_enc,
+                expires_at = EXCLUDED.expires_at,
+                refreshed_at = NULL,
+                revoked_at = NULL
+            "#,
+            company_uuid,
+            access_enc,
+            refresh_enc,
+            expires_at
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, company_uuid: &str) -> Result<CompanyTokens, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT access_token_enc, refresh_token_enc, expires_at, revoked_at
+            FROM gusto_credentials WHERE company_uuid = $1
+            "#,
+            company_uuid
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or_else(|| format!("No Gusto credentials for company {}", company_uuid))?;
+
+        self.decode(company_uuid, row.access_token_enc, row.refresh_token_enc, row.expires_at, row.revoked_at)
+    }
+
+    /// REFRESH, STEP 1: lock the row so concurrent refreshes don't burn each
+    /// other's (single-use) refresh token. The lock is held until `finish_refresh`.
+    pub async fn begin_refresh(
+        &self,
+        company_uuid: &str,
+    ) -> Result<(Transaction<'static, Postgres>, CompanyTokens), Box<dyn Error>> {
+        let mut tx = self.db.begin().await?;
+        let row = sqlx::query!(
+            r#"
+            SELECT access_token_enc, refresh_token_enc, expires_at, revoked_at
+            FROM gusto_credentials WHERE company_uuid = $1
+            FOR UPDATE
+            "#,
+            company_uuid
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| format!("No Gusto credentials for company {}", company_uuid))?;
+
+        let tokens = self.decode(company_uuid, row.access_token_enc, row.refresh_token_enc, row.expires_at, row.revoked_at)?;
+        Ok((tx, tokens))
+    }
+
+    /// REFRESH, STEP 2: store the rotated pair and release the lock
+    pub async fn finish_refresh(
+        &self,
+        mut tx: Transaction<'static, Postgres>,
+        company_uuid: &str,
+        access_token: &str,
+        refresh_token: &str,
+        expires_in_secs: i64,
+    ) -> Result<(), Box<dyn Error>> {
+        sqlx::query!(
+            r#"
+            UPDATE gusto_credentials
+            SET access_token_enc = $2, refresh_token_enc = $3, expires_at = $4, refreshed_at = NOW()
+            WHERE company_uuid = $1
+            "#,
+            company_uuid,
+            self.cipher.encrypt(access_token)?,
+            self.cipher.encrypt(refresh_token)?,
+            Utc::now() + Duration::seconds(expires_in_secs)
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Forget the tokens (after Gusto revoked them). The row stays for the audit trail.
+    pub async fn mark_revoked(&self, company_uuid: &str) -> Result<(), Box<dyn Error>> {
+        sqlx::query!(
+            r#"
+            UPDATE gusto_credentials
+            SET access_token_enc = NULL, refresh_token_enc = NULL, revoked_at = NOW()
+            WHERE company_uuid = $1
+            "#,
+            company_uuid
+        )
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// ONE-TIME MIGRATION
+    /// Moves plaintext tokens from `tenants.gusto_access_token` into encrypted
+    /// storage, then clears the column. Imported tokens have no refresh token:
+    /// once Gusto rejects them the company has to re-authorize.
+    pub async fn import_plaintext_tokens(&self) -> Result<usize, Box<dyn Error>> {
+        let legacy = sqlx::query!(
+            r#"
+            SELECT id, gusto_company_uuid AS "company_uuid!", gusto_access_token AS "access_token!"
+            FROM tenants
+            WHERE gusto_company_uuid IS NOT NULL AND gusto_access_token IS NOT NULL
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        for tenant in &legacy {
+            let mut tx = self.db.begin().await?;
+            sqlx::query!(
+                r#"
+                INSERT INTO gusto_credentials (company_uuid, access_token_enc)
+                VALUES ($1, $2)
+                ON CONFLICT (company_uuid) DO NOTHING
+                "#,
+                tenant.company_uuid,
+                self.cipher.encrypt(&tenant.access_token)?
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!("UPDATE tenants SET gusto_access_token = NULL WHERE id = $1", tenant.id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+        }
+
+        Ok(legacy.len())
+    }
+
+    fn decode(
+        &self,
+        company_uuid: &str,
+        access_enc: Option<String>,
+        refresh_enc: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+        revoked_at: Option<DateTime<Utc>>,
+    ) -> Result<CompanyTokens, Box<dyn Error>> {
+        if revoked_at.is_some() {
+            return Err(format!("Gusto access for company {} was revoked", company_uuid).into());
+        }
+        let access_enc = access_enc.ok_or_else(|| format!("No Gusto access token for company {}", company_uuid))?;
+
+        Ok(CompanyTokens {
+            company_uuid: company_uuid.to_string(),
+            access_token: self.cipher.decrypt(&access_enc)?,
+            refresh_token: refresh_enc.map(|t| self.cipher.decrypt(&t)).transpose()?,
+            expires_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cipher_round_trips_and_rejects_other_keys() {
+        let cipher = TokenCipher::ephemeral();
+        let stored = cipher.encrypt("gusto-access-token").unwrap();
+        assert!(!stored.contains("gusto-access-token"));
+        assert_eq!(cipher.decrypt(&stored).unwrap(), "gusto-access-token");
+
+        // Fresh nonce every time
+        assert_ne!(cipher.encrypt("gusto-access-token").unwrap(), stored);
+
+        assert!(TokenCipher::ephemeral().decrypt(&stored).is_err());
+        assert!(cipher.decrypt(&stored[..NONCE_LEN * 2]).is_err());
+    }
+
+    #[test]
+    fn tokens_refresh_inside_the_expiry_margin() {
+        let tokens = |expires_at| CompanyTokens {
+            company_uuid: "c1".to_string(),
+            access_token: "a".to_string(),
+            refresh_token: Some("r".to_string()),
+            expires_at,
+        };
+        assert!(!tokens(None).needs_refresh());
+        assert!(!tokens(Some(Utc::now() + Duration::hours(1))).needs_refresh());
+        assert!(tokens(Some(Utc::now() + Duration::seconds(EXPIRY_MARGIN_SECS / 2))).needs_refresh());
+        assert!(tokens(Some(Utc::now() - Duration::minutes(1))).needs_refresh());
+    }
+}
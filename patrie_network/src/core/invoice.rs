@@ -0,0 +1,798 @@
+use crate::core::adjustments::BillingAdjustment;
+use crate::core::billing_engine::CalculatedInvoice;
+use crate::core::credit_notes::TenantCredit;
+use crate::core::metering::UsageMeter;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::error::Error;
+use std::fmt;
+use uuid::Uuid;
+
+/// The lifecycle of a monthly invoice.
+/// draft -> finalized -> payment_pending -> paid | failed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceStatus {
+    Draft,          // Row exists, amounts not calculated yet
+    Finalized,      // Amounts locked, no money moved
+    PaymentPending, // Book payment sent to Unit (outcome unknown until confirmed)
+    Paid,           // Unit confirmed the payment
+    Failed,         // Unit rejected the payment
+}
+
+impl InvoiceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InvoiceStatus::Draft => "draft",
+            InvoiceStatus::Finalized => "finalized",
+            InvoiceStatus::PaymentPending => "payment_pending",
+            InvoiceStatus::Paid => "paid",
+            InvoiceStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value {
+            "draft" => Ok(InvoiceStatus::Draft),
+            "finalized" => Ok(InvoiceStatus::Finalized),
+            "payment_pending" => Ok(InvoiceStatus::PaymentPending),
+            "paid" => Ok(InvoiceStatus::Paid),
+            "failed" => Ok(InvoiceStatus::Failed),
+            other => Err(format!("Unknown invoice status: {}", other).into()),
+        }
+    }
+
+    /// The lifecycle above, plus failed -> payment_pending for dunning retries
+    pub fn can_move_to(&self, to: InvoiceStatus) -> bool {
+        use InvoiceStatus::*;
+        matches!(
+            (self, to),
+            (Draft, Finalized)
+                | (Finalized, PaymentPending)
+                | (PaymentPending, Paid)
+                | (PaymentPending, Failed)
+                | (Failed, PaymentPending)
+        )
+    }
+}
+
+impl fmt::Display for InvoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// How an invoice is settled. Chosen when its first payment attempt starts and
+/// kept for every retry, so an invoice can never be paid by both rails.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentMethod {
+    Unit,          // Book payment from the tenant's deposit account
+    Onchain,       // Transfer from the tenant's on-chain treasury
+    AccountCredit, // Nothing to collect: covered by credit notes
+}
+
+impl PaymentMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentMethod::Unit => "unit",
+            PaymentMethod::Onchain => "onchain",
+            PaymentMethod::AccountCredit => "account_credit",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value {
+            "unit" => Ok(PaymentMethod::Unit),
+            "onchain" => Ok(PaymentMethod::Onchain),
+            "account_credit" => Ok(PaymentMethod::AccountCredit),
+            other => Err(format!("Unknown payment method: {}", other).into()),
+        }
+    }
+}
+
+/// A calendar month we bill for. Always anchored on the 1st.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BillingPeriod {
+    pub start: NaiveDate,
+}
+
+impl BillingPeriod {
+    /// The period containing `date` (e.g. 2024-03-17 -> 2024-03-01)
+    pub fn containing(date: NaiveDate) -> Self {
+        Self { start: date.with_day(1).unwrap() }
+    }
+
+    /// The month we are currently in (UTC)
+    pub fn current() -> Self {
+        Self::containing(Utc::now().date_naive())
+    }
+
+    /// The month we are currently in, on the clock of `tz`. Jobs use their
+    /// configured timezone: at 9pm on Oct 31 in New York it is already November in UTC.
+    pub fn current_in(tz: Tz) -> Self {
+        Self::at(Utc::now(), tz)
+    }
+
+    /// The period `instant` falls in, on the clock of `tz`
+    pub fn at(instant: DateTime<Utc>, tz: Tz) -> Self {
+        Self::containing(instant.with_timezone(&tz).date_naive())
+    }
+
+    /// Parses "2024-03" or "2024-03-01"
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d"))?;
+        Ok(Self::containing(date))
+    }
+
+    /// First day of the following month (exclusive end of this period)
+    pub fn end(&self) -> NaiveDate {
+        if self.start.month() == 12 {
+            NaiveDate::from_ymd_opt(self.start.year() + 1, 1, 1).unwrap()
+        } else {
+            NaiveDate::from_ymd_opt(self.start.year(), self.start.month() + 1, 1).unwrap()
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        Self::containing(self.start.pred_opt().unwrap())
+    }
+
+    pub fn next(&self) -> Self {
+        Self::containing(self.end())
+    }
+}
+
+impl fmt::Display for BillingPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.start.format("%Y-%m"))
+    }
+}
+
+/// What a line on the invoice is FOR. Keeps pass-through money separate
+/// from what we actually earn (compliance requirement).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineCategory {
+    PlatformFee,        // Our SaaS fee
+    PassThroughPremium, // Insurance premium / 401k contribution we forward
+    AdminFee,           // Our integration fee on top of a pass-through
+    Discount,           // Negotiated discount / coupon (negative amount)
+    Adjustment,         // One-off charge or credit (e.g. tier change proration)
+    UsageOverage,       // Metered ledger usage above the tier's included quota
+    AccountCredit,      // Credit balance (from credit notes) applied to this invoice
+    CommitmentTrueUp,   // Shortfall against a contract's committed usage spend
+}
+
+impl LineCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineCategory::PlatformFee => "platform_fee",
+            LineCategory::PassThroughPremium => "pass_through_premium",
+            LineCategory::AdminFee => "admin_fee",
+            LineCategory::Discount => "discount",
+            LineCategory::Adjustment => "adjustment",
+            LineCategory::UsageOverage => "usage_overage",
+            LineCategory::AccountCredit => "account_credit",
+            LineCategory::CommitmentTrueUp => "commitment_true_up",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value {
+            "platform_fee" => Ok(LineCategory::PlatformFee),
+            "pass_through_premium" => Ok(LineCategory::PassThroughPremium),
+            "admin_fee" => Ok(LineCategory::AdminFee),
+            "discount" => Ok(LineCategory::Discount),
+            "adjustment" => Ok(LineCategory::Adjustment),
+            "usage_overage" => Ok(LineCategory::UsageOverage),
+            "account_credit" => Ok(LineCategory::AccountCredit),
+            "commitment_true_up" => Ok(LineCategory::CommitmentTrueUp),
+            other => Err(format!("Unknown line category: {}", other).into()),
+        }
+    }
+}
+
+/// A single itemized line on an invoice
+#[derive(Debug, Clone, Serialize)]
+pub struct LineItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>, // Set once stored (credit notes point at it)
+    pub category: LineCategory,
+    pub description: String,
+    pub amount_cents: i64,
+    pub service_months: i32, // Months this line pays for (contract cycles billed upfront)
+}
+
+impl LineItem {
+    pub fn new(category: LineCategory, description: &str, amount_usd: f64) -> Self {
+        Self::from_cents(category, description, (amount_usd * 100.0).round() as i64)
+    }
+
+    pub fn from_cents(category: LineCategory, description: &str, amount_cents: i64) -> Self {
+        Self {
+            id: None,
+            category,
+            description: description.to_string(),
+            amount_cents,
+            service_months: 1,
+        }
+    }
+
+    /// The same monthly line, billed upfront for `months` months
+    pub fn prepaid(mut self, months: i32, range: &str) -> Self {
+        if months > 1 {
+            self.amount_cents *= months as i64;
+            self.description = format!("{} ({} months, {})", self.description, months, range);
+            self.service_months = months;
+        }
+        self
+    }
+
+    /// The "Legal Receipt" text used in logs and emails
+    pub fn receipt_text(lines: &[LineItem]) -> String {
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, l)| {
+                let prefix = if i == 0 { "" } else { " + " };
+                format!("{}{}: ${:.2}", prefix, l.description, l.amount_cents as f64 / 100.0)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub async fn for_invoice(db: &PgPool, invoice_id: Uuid) -> Result<Vec<LineItem>, Box<dyn Error>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, category, description, amount_cents, service_months
+            FROM invoice_line_items
+            WHERE invoice_id = $1
+            ORDER BY position
+            "#,
+            invoice_id
+        )
+        .fetch_all(db)
+        .await?;
+
+        rows.into_iter()
+            .map(|r| {
+                Ok(LineItem {
+                    id: Some(r.id),
+                    category: LineCategory::parse(&r.category)?,
+                    description: r.description,
+                    amount_cents: r.amount_cents,
+                    service_months: r.service_months,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Raw row as stored in Postgres (status is TEXT)
+struct InvoiceRow {
+    id: Uuid,
+    tenant_id: Uuid,
+    period_start: NaiveDate,
+    status: String,
+    total_cents: i64,
+    idempotency_key: String,
+    unit_payment_id: Option<String>,
+    failure_reason: Option<String>,
+    price_book_id: Option<Uuid>,
+    payment_attempt: i32,
+    payment_method: Option<String>,
+}
+
+impl TryFrom<InvoiceRow> for Invoice {
+    type Error = Box<dyn Error>;
+
+    fn try_from(r: InvoiceRow) -> Result<Self, Self::Error> {
+        Ok(Invoice {
+            id: r.id,
+            tenant_id: r.tenant_id,
+            period: BillingPeriod::containing(r.period_start),
+            status: InvoiceStatus::parse(&r.status)?,
+            total_cents: r.total_cents,
+            idempotency_key: r.idempotency_key,
+            unit_payment_id: r.unit_payment_id,
+            failure_reason: r.failure_reason,
+            price_book_id: r.price_book_id,
+            payment_attempt: r.payment_attempt,
+            payment_method: r.payment_method.as_deref().map(PaymentMethod::parse).transpose()?,
+        })
+    }
+}
+
+/// One row of the `invoices` table
+#[derive(Debug, Clone, Serialize)]
+pub struct Invoice {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub period: BillingPeriod,
+    pub status: InvoiceStatus,
+    pub total_cents: i64,
+    pub idempotency_key: String,
+    pub unit_payment_id: Option<String>,
+    pub failure_reason: Option<String>,
+    pub price_book_id: Option<Uuid>, // The price book that priced this invoice
+    pub payment_attempt: i32,         // 0 = first charge, 1+ = dunning retries
+    pub payment_method: Option<PaymentMethod>, // None until the first attempt starts
+}
+
+impl Invoice {
+    /// Returns the invoice for (tenant, period), creating a DRAFT if none exists.
+    /// The unique constraint on (tenant_id, period_start) makes this safe to
+    /// call from two workers at the same time: both end up with the same row.
+    pub async fn get_or_create(
+        db: &PgPool,
+        tenant_id: Uuid,
+        period: BillingPeriod,
+    ) -> Result<Invoice, Box<dyn Error>> {
+        let new_id = Uuid::new_v4();
+
+        // The idempotency key is derived from the invoice id, so every retry
+        // of this invoice hits Unit with the SAME key.
+        sqlx::query!(
+            r#"
+            INSERT INTO invoices (id, tenant_id, period_start, status, idempotency_key)
+            VALUES ($1, $2, $3, 'draft', $4)
+            ON CONFLICT (tenant_id, period_start) DO NOTHING
+            "#,
+            new_id,
+            tenant_id,
+            period.start,
+            idempotency_key_for(new_id)
+        )
+        .execute(db)
+        .await?;
+
+        Self::find(db, tenant_id, period)
+            .await?
+            .ok_or_else(|| "Invoice vanished after insert".into())
+    }
+
+    pub async fn find(
+        db: &PgPool,
+        tenant_id: Uuid,
+        period: BillingPeriod,
+    ) -> Result<Option<Invoice>, Box<dyn Error>> {
+        let row = sqlx::query_as!(
+            InvoiceRow,
+            r#"
+            SELECT id, tenant_id, period_start, status, total_cents,
+                   idempotency_key, unit_payment_id, failure_reason, price_book_id,
+                   payment_attempt, payment_method
+            FROM invoices
+            WHERE tenant_id = $1 AND period_start = $2
+            "#,
+            tenant_id,
+            period.start
+        )
+        .fetch_optional(db)
+        .await?;
+
+        row.map(Invoice::try_from).transpose()
+    }
+
+    pub async fn get(db: &PgPool, invoice_id: Uuid) -> Result<Option<Invoice>, Box<dyn Error>> {
+        let row = sqlx::query_as!(
+            InvoiceRow,
+            r#"
+            SELECT id, tenant_id, period_start, status, total_cents,
+                   idempotency_key, unit_payment_id, failure_reason, price_book_id,
+                   payment_attempt, payment_method
+            FROM invoices
+            WHERE id = $1
+            "#,
+            invoice_id
+        )
+        .fetch_optional(db)
+        .await?;
+
+        row.map(Invoice::try_from).transpose()
+    }
+
+    /// All invoices for a tenant, newest period first
+    pub async fn list_for_tenant(db: &PgPool, tenant_id: Uuid) -> Result<Vec<Invoice>, Box<dyn Error>> {
+        let rows = sqlx::query_as!(
+            InvoiceRow,
+            r#"
+            SELECT id, tenant_id, period_start, status, total_cents,
+                   idempotency_key, unit_payment_id, failure_reason, price_book_id,
+                   payment_attempt, payment_method
+            FROM invoices
+            WHERE tenant_id = $1
+            ORDER BY period_start DESC
+            "#,
+            tenant_id
+        )
+        .fetch_all(db)
+        .await?;
+
+        rows.into_iter().map(Invoice::try_from).collect()
+    }
+
+    /// Moves the invoice from `from` to `to` ONLY if it is still in `from`.
+    /// Returns false if another worker got there first (compare-and-swap).
+    pub async fn transition(
+        &mut self,
+        db: &PgPool,
+        from: InvoiceStatus,
+        to: InvoiceStatus,
+    ) -> Result<bool, Box<dyn Error>> {
+        if !from.can_move_to(to) {
+            return Err(format!("Invoice {} cannot move from {} to {}", self.id, from, to).into());
+        }
+        let result = sqlx::query!(
+            r#"
+            UPDATE invoices
+            SET status = $3, updated_at = NOW()
+            WHERE id = $1 AND status = $2
+            "#,
+            self.id,
+            from.as_str(),
+            to.as_str()
+        )
+        .execute(db)
+        .await?;
+
+        let moved = result.rows_affected() == 1;
+        if moved {
+            self.status = to;
+        }
+        Ok(moved)
+    }
+
+    /// draft -> finalized, locking in the amount to charge.
+    /// The line items, the consumed adjustments and the status change are written
+    /// in ONE database transaction, so a finalized invoice always has its itemized receipt.
+    pub async fn finalize(
+        &mut self,
+        db: &PgPool,
+        calculated: &CalculatedInvoice,
+    ) -> Result<bool, Box<dyn Error>> {
+        let lines = &calculated.lines;
+        let price_book_id = calculated.price_book_id;
+        let total_cents = calculated.total_cents();
+        let mut tx = db.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE invoices
+            SET status = 'finalized', total_cents = $2, price_book_id = $3, tier = $4, updated_at = NOW()
+            WHERE id = $1 AND status = 'draft'
+            "#,
+            self.id,
+            total_cents,
+            price_book_id,
+            calculated.tier.as_str()
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() != 1 {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+
+        for (position, line) in lines.iter().enumerate() {
+            sqlx::query!(
+                r#"
+                INSERT INTO invoice_line_items (id, invoice_id, position, category, description, amount_cents, service_months)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+                Uuid::new_v4(),
+                self.id,
+                position as i32,
+                line.category.as_str(),
+                line.description,
+                line.amount_cents,
+                line.service_months
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Keep the usage that was billed, for audit and reporting
+        UsageMeter::record(&mut tx, self.tenant_id, calculated.usage_period, &calculated.usage).await?;
+
+        // Account credit consumed by this invoice
+        if calculated.credit_applied_cents > 0 {
+            TenantCredit::consume(&mut tx, self.tenant_id, self.id, calculated.credit_applied_cents).await?;
+        }
+
+        // Credits larger than the charges: nothing to collect, the rest rolls forward
+        if total_cents < 0 {
+            TenantCredit::carry_forward(&mut tx, self.tenant_id, self.id, -total_cents).await?;
+        }
+
+        // Pending adjustments are consumed by THIS invoice only
+        if !calculated.adjustment_ids.is_empty() {
+            BillingAdjustment::mark_applied(&mut tx, &calculated.adjustment_ids, self.id).await?;
+        }
+
+        tx.commit().await?;
+
+        self.status = InvoiceStatus::Finalized;
+        self.total_cents = total_cents;
+        self.price_book_id = Some(price_book_id);
+        Ok(true)
+    }
+
+    /// failed -> payment_pending, as a NEW payment attempt (dunning retry)
+    pub async fn begin_retry(&mut self, db: &PgPool) -> Result<bool, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE invoices
+            SET status = 'payment_pending', payment_attempt = payment_attempt + 1, updated_at = NOW()
+            WHERE id = $1 AND status = 'failed'
+            RETURNING payment_attempt
+            "#,
+            self.id
+        )
+        .fetch_optional(db)
+        .await?;
+
+        match row {
+            Some(r) => {
+                self.status = InvoiceStatus::PaymentPending;
+                self.payment_attempt = r.payment_attempt;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Locks in how this invoice is settled. The first caller's choice wins;
+    /// returns the method in force (which may be an earlier choice).
+    pub async fn choose_payment_method(
+        &mut self,
+        db: &PgPool,
+        method: PaymentMethod,
+    ) -> Result<PaymentMethod, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE invoices
+            SET payment_method = COALESCE(payment_method, $2), updated_at = NOW()
+            WHERE id = $1
+            RETURNING payment_method
+            "#,
+            self.id,
+            method.as_str()
+        )
+        .fetch_one(db)
+        .await?;
+
+        let chosen = PaymentMethod::parse(row.payment_method.as_deref().unwrap_or(method.as_str()))?;
+        self.payment_method = Some(chosen);
+        Ok(chosen)
+    }
+
+    /// Set just before the settlement transfer goes out: from then on the
+    /// transfer may land at any time, so the invoice can't move to Unit anymore
+    pub async fn mark_onchain_submitted(&self, db: &PgPool) -> Result<(), Box<dyn Error>> {
+        sqlx::query!(
+            "UPDATE invoices SET onchain_submitted_at = COALESCE(onchain_submitted_at, NOW()) WHERE id = $1",
+            self.id
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// onchain -> unit, only while no settlement transfer was ever sent.
+    /// Returns whether the invoice now pays through Unit.
+    pub async fn fall_back_to_unit(&mut self, db: &PgPool) -> Result<bool, Box<dyn Error>> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE invoices SET payment_method = 'unit', updated_at = NOW()
+            WHERE id = $1 AND status = 'payment_pending' AND payment_method = 'onchain'
+              AND onchain_submitted_at IS NULL
+            "#,
+            self.id
+        )
+        .execute(db)
+        .await?;
+
+        if result.rows_affected() == 1 {
+            self.payment_method = Some(PaymentMethod::Unit);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// The idempotency key for the CURRENT payment attempt.
+    /// Re-sending the same attempt never double-charges. A new attempt (and key)
+    /// only starts once Unit definitively rejected the last one (`begin_retry`).
+    pub fn payment_key(&self) -> String {
+        if self.payment_attempt == 0 {
+            self.idempotency_key.clone()
+        } else {
+            format!("{}-retry-{}", self.idempotency_key, self.payment_attempt)
+        }
+    }
+
+    /// Key of the on-chain receipt for this invoice. Unlike `payment_key` it does
+    /// NOT change between attempts: one receipt means the invoice is paid, whatever attempt wrote it.
+    pub fn settlement_receipt_key(&self) -> String {
+        self.idempotency_key.clone()
+    }
+
+    /// payment_pending -> paid, by a Unit book payment
+    pub async fn mark_paid(&mut self, db: &PgPool, unit_payment_id: &str) -> Result<(), Box<dyn Error>> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE invoices
+            SET status = 'paid', unit_payment_id = $2, payment_method = 'unit', failure_reason = NULL, updated_at = NOW()
+            WHERE id = $1 AND status = 'payment_pending'
+            "#,
+            self.id,
+            unit_payment_id
+        )
+        .execute(db)
+        .await?;
+
+        ensure_moved(result.rows_affected(), self.id)?;
+
+        self.status = InvoiceStatus::Paid;
+        self.unit_payment_id = Some(unit_payment_id.to_string());
+        self.payment_method = Some(PaymentMethod::Unit);
+        Ok(())
+    }
+
+    /// payment_pending -> paid, with nothing to collect (account credit covered it).
+    /// No money moved, so there is no payment id.
+    pub async fn mark_covered_by_credit(&mut self, db: &PgPool) -> Result<(), Box<dyn Error>> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE invoices
+            SET status = 'paid', unit_payment_id = NULL, payment_method = 'account_credit',
+                failure_reason = NULL, updated_at = NOW()
+            WHERE id = $1 AND status = 'payment_pending'
+            "#,
+            self.id
+        )
+        .execute(db)
+        .await?;
+
+        ensure_moved(result.rows_affected(), self.id)?;
+
+        self.status = InvoiceStatus::Paid;
+        self.unit_payment_id = None;
+        self.payment_method = Some(PaymentMethod::AccountCredit);
+        Ok(())
+    }
+
+    /// payment_pending -> paid, settled by an on-chain transfer
+    pub async fn mark_paid_onchain(&mut self, db: &PgPool, tx_hash: &str) -> Result<(), Box<dyn Error>> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE invoices
+            SET status = 'paid', payment_method = 'onchain', onchain_tx_hash = $2,
+                failure_reason = NULL, updated_at = NOW()
+            WHERE id = $1 AND status = 'payment_pending'
+            "#,
+            self.id,
+            tx_hash
+        )
+        .execute(db)
+        .await?;
+
+        ensure_moved(result.rows_affected(), self.id)?;
+
+        self.status = InvoiceStatus::Paid;
+        self.payment_method = Some(PaymentMethod::Onchain);
+        Ok(())
+    }
+
+    /// payment_pending -> failed
+    pub async fn mark_failed(&mut self, db: &PgPool, reason: &str) -> Result<(), Box<dyn Error>> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE invoices
+            SET status = 'failed', failure_reason = $2, updated_at = NOW()
+            WHERE id = $1 AND status = 'payment_pending'
+            "#,
+            self.id,
+            reason
+        )
+        .execute(db)
+        .await?;
+
+        ensure_moved(result.rows_affected(), self.id)?;
+
+        self.status = InvoiceStatus::Failed;
+        self.failure_reason = Some(reason.to_string());
+        Ok(())
+    }
+}
+
+/// The guarded `WHERE status = 'payment_pending'` matched nothing: another
+/// worker already settled or failed the invoice, so this outcome must not count
+fn ensure_moved(rows_affected: u64, invoice_id: Uuid) -> Result<(), Box<dyn Error>> {
+    if rows_affected != 1 {
+        return Err(format!("Invoice {} is no longer payment_pending", invoice_id).into());
+    }
+    Ok(())
+}
+
+/// Unit deduplicates book payments by this key, so a retried invoice can never
+/// produce a second charge.
+pub fn idempotency_key_for(invoice_id: Uuid) -> String {
+    format!("invoice-{}", invoice_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn invoice(payment_attempt: i32) -> Invoice {
+        Invoice {
+            id: Uuid::new_v4(),
+            tenant_id: Uuid::new_v4(),
+            period: BillingPeriod::parse("2026-10").unwrap(),
+            status: InvoiceStatus::PaymentPending,
+            total_cents: 4_900,
+            idempotency_key: "inv-tenant-2026-10".to_string(),
+            unit_payment_id: None,
+            failure_reason: None,
+            price_book_id: None,
+            payment_attempt,
+            payment_method: Some(PaymentMethod::Unit),
+        }
+    }
+
+    #[test]
+    fn payment_key_is_stable_per_attempt() {
+        // The first charge keeps the invoice's own key, so a resend can't pay twice
+        assert_eq!(invoice(0).payment_key(), "inv-tenant-2026-10");
+        assert_eq!(invoice(0).payment_key(), invoice(0).payment_key());
+
+        // Only a new attempt (after a definitive rejection) gets a new key
+        assert_eq!(invoice(1).payment_key(), "inv-tenant-2026-10-retry-1");
+        assert_ne!(invoice(1).payment_key(), invoice(2).payment_key());
+    }
+
+    #[test]
+    fn invoice_lifecycle_moves() {
+        use InvoiceStatus::*;
+        assert!(Draft.can_move_to(Finalized));
+        assert!(Finalized.can_move_to(PaymentPending));
+        assert!(PaymentPending.can_move_to(Paid));
+        assert!(PaymentPending.can_move_to(Failed));
+        assert!(Failed.can_move_to(PaymentPending));
+
+        // No skipping ahead, and nothing leaves paid
+        assert!(!Draft.can_move_to(PaymentPending));
+        assert!(!Finalized.can_move_to(Paid));
+        assert!(!Failed.can_move_to(Paid));
+        for to in [Draft, Finalized, PaymentPending, Paid, Failed] {
+            assert!(!Paid.can_move_to(to));
+        }
+    }
+
+    #[test]
+    fn statuses_and_methods_round_trip() {
+        use InvoiceStatus::*;
+        for status in [Draft, Finalized, PaymentPending, Paid, Failed] {
+            assert_eq!(InvoiceStatus::parse(status.as_str()).unwrap(), status);
+        }
+        for method in [PaymentMethod::Unit, PaymentMethod::Onchain, PaymentMethod::AccountCredit] {
+            assert_eq!(PaymentMethod::parse(method.as_str()).unwrap(), method);
+        }
+        assert!(InvoiceStatus::parse("refunded").is_err());
+    }
+
+    #[test]
+    fn period_follows_the_job_timezone() {
+        // 9pm on Oct 31 in New York is already Nov 1 in UTC
+        let instant = Utc.with_ymd_and_hms(2026, 11, 1, 1, 0, 0).unwrap();
+        assert_eq!(BillingPeriod::at(instant, chrono_tz::America::New_York).to_string(), "2026-10");
+        assert_eq!(BillingPeriod::at(instant, chrono_tz::UTC).to_string(), "2026-11");
+    }
+}
@@ -0,0 +1,423 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- BACKGROUND JOBS ---
+// Every replica runs the scheduler. A Postgres advisory lock per job makes sure
+// only ONE replica actually executes a given run; the others record a skip.
+
+/// The background jobs we know about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobName {
+    Billing,           // Monthly invoices
+    WholesaleCostSync, // Gusto benefit costs -> subscription_settings
+    IndexerHealth,     // Explorer index vs. chain height
+    Dunning,           // Failed charge retries & suspensions
+    RevenueReport,     // Month-end revenue snapshot
+    ContractRenewals,  // Renewal/expiry notices, renewals
+    FiatEvents,        // Unit webhook events that failed to apply
+    CashBarcodes,      // Expire unused cash deposit barcodes
+    Reserves,          // Fiat vs. on-chain reconciliation, proof of reserves
+    AchPayments,       // Resubmit stuck ACH payments, refresh open ones
+    CardHolds,         // Re-check card holds Unit went quiet about
+    Statements,        // Monthly account statements for every banked wallet
+    GustoEvents,       // Gusto webhook events that failed to apply
+    EmployeeSync,      // Gusto employees -> accounts in tenant domains
+    Notifications,     // Send the notifications outbox
+}
+
+impl JobName {
+    pub const ALL: [JobName; 15] = [
+        JobName::Billing,
+        JobName::WholesaleCostSync,
+        JobName::IndexerHealth,
+        JobName::Dunning,
+        JobName::RevenueReport,
+        JobName::ContractRenewals,
+        JobName::FiatEvents,
+        JobName::CashBarcodes,
+        JobName::Reserves,
+        JobName::AchPayments,
+        JobName::CardHolds,
+        JobName::Statements,
+        JobName::GustoEvents,
+        JobName::EmployeeSync,
+        JobName::Notifications,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobName::Billing => "billing",
+            JobName::WholesaleCostSync => "wholesale_cost_sync",
+            JobName::IndexerHealth => "indexer_health",
+            JobName::Dunning => "dunning",
+            JobName::RevenueReport => "revenue_report",
+            JobName::ContractRenewals => "contract_renewals",
+            JobName::FiatEvents => "fiat_events",
+            JobName::CashBarcodes => "cash_barcodes",
+            JobName::Reserves => "reserves",
+            JobName::AchPayments => "ach_payments",
+            JobName::CardHolds => "card_holds",
+            JobName::Statements => "statements",
+            JobName::GustoEvents => "gusto_events",
+            JobName::EmployeeSync => "employee_sync",
+            JobName::Notifications => "notifications",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value {
+            "billing" => Ok(JobName::Billing),
+            "wholesale_cost_sync" => Ok(JobName::WholesaleCostSync),
+            "indexer_health" => Ok(JobName::IndexerHealth),
+            "dunning" => Ok(JobName::Dunning),
+            "revenue_report" => Ok(JobName::RevenueReport),
+            "contract_renewals" => Ok(JobName::ContractRenewals),
+            "fiat_events" => Ok(JobName::FiatEvents),
+            "cash_barcodes" => Ok(JobName::CashBarcodes),
+            "reserves" => Ok(JobName::Reserves),
+            "ach_payments" => Ok(JobName::AchPayments),
+            "card_holds" => Ok(JobName::CardHolds),
+            "statements" => Ok(JobName::Statements),
+            "gusto_events" => Ok(JobName::GustoEvents),
+            "employee_sync" => Ok(JobName::EmployeeSync),
+            "notifications" => Ok(JobName::Notifications),
+            other => Err(format!("Unknown job: {}", other).into()),
+        }
+    }
+
+    /// Key for `pg_try_advisory_lock`. Never change these: two versions of the
+    /// app running side by side during a deploy must agree on them.
+    fn lock_key(&self) -> i64 {
+        match self {
+            JobName::Billing => 7_301_001,
+            JobName::WholesaleCostSync => 7_301_002,
+            JobName::IndexerHealth => 7_301_003,
+            JobName::Dunning => 7_301_004,
+            JobName::RevenueReport => 7_301_005,
+            JobName::ContractRenewals => 7_301_006,
+            JobName::FiatEvents => 7_301_007,
+            JobName::CashBarcodes => 7_301_008,
+            JobName::Reserves => 7_301_009,
+            JobName::AchPayments => 7_301_010,
+            JobName::CardHolds => 7_301_011,
+            JobName::Statements => 7_301_012,
+            JobName::GustoEvents => 7_301_013,
+            JobName::EmployeeSync => 7_301_014,
+            JobName::Notifications => 7_301_015,
+        }
+    }
+
+    /// CRON EXPRESSION: "sec min hour day_of_month month day_of_week"
+    fn default_cron(&self) -> &'static str {
+        match self {
+            JobName::Billing => "0 0 9 1 * *",           // 09:00 on the 1st
+            JobName::WholesaleCostSync => "0 0 3 * * *", // 03:00 daily
+            JobName::IndexerHealth => "0 */5 * * * *",   // Every 5 minutes
+            JobName::Dunning => "0 0 * * * *",           // Every hour
+            JobName::RevenueReport => "0 0 6 2 * *",     // 06:00 on the 2nd
+            JobName::ContractRenewals => "0 0 7 * * *",  // 07:00 daily
+            JobName::FiatEvents => "0 */10 * * * *",     // Every 10 minutes
+            JobName::CashBarcodes => "0 */15 * * * *",   // Every 15 minutes
+            JobName::Reserves => "0 30 4 * * *",         // 04:30 daily
+            JobName::AchPayments => "0 */15 * * * *",    // Every 15 minutes
+            JobName::CardHolds => "0 20 * * * *",        // Hourly at :20
+            JobName::Statements => "0 0 8 2 * *",        // 08:00 on the 2nd
+            JobName::GustoEvents => "0 5-59/10 * * * *", // Every 10 minutes, offset from fiat_events
+            JobName::EmployeeSync => "0 30 3 * * *",      // 03:30 daily
+            JobName::Notifications => "0 * * * * *",      // Every minute
+        }
+    }
+
+    fn env_prefix(&self) -> String {
+        format!("JOB_{}", self.as_str().to_uppercase())
+    }
+}
+
+/// When (and whether) a job runs
+#[derive(Debug, Clone, Serialize)]
+pub struct JobSchedule {
+    pub cron: String,
+    pub timezone: String,
+    pub enabled: bool,
+}
+
+impl JobSchedule {
+    /// Reads JOB_<NAME>_CRON, JOB_<NAME>_TZ and JOB_<NAME>_ENABLED.
+    /// The timezone falls back to JOB_DEFAULT_TZ, then UTC.
+    pub fn from_env(job: JobName) -> Result<Self, Box<dyn Error>> {
+        let prefix = job.env_prefix();
+        let default_tz = std::env::var("JOB_DEFAULT_TZ").unwrap_or_else(|_| "UTC".to_string());
+
+        let schedule = Self {
+            cron: std::env::var(format!("{}_CRON", prefix)).unwrap_or_else(|_| job.default_cron().to_string()),
+            timezone: std::env::var(format!("{}_TZ", prefix)).unwrap_or(default_tz),
+            enabled: std::env::var(format!("{}_ENABLED", prefix)).map(|v| v != "false").unwrap_or(true),
+        };
+
+        // Fail at startup, not at 9am on the 1st
+        schedule.tz()?;
+        Ok(schedule)
+    }
+
+    pub fn tz(&self) -> Result<Tz, Box<dyn Error>> {
+        self.timezone
+            .parse::<Tz>()
+            .map_err(|e| format!("Invalid timezone '{}': {}", self.timezone, e).into())
+    }
+}
+
+/// How a run was started
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobTrigger {
+    Schedule,
+    Manual,
+}
+
+impl JobTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobTrigger::Schedule => "schedule",
+            JobTrigger::Manual => "manual",
+        }
+    }
+}
+
+/// One row of `job_runs`
+#[derive(Debug, Serialize)]
+pub struct JobRun {
+    pub id: Uuid,
+    pub job_name: String,
+    pub trigger: String,
+    pub triggered_by: Option<String>,
+    pub status: String, // running | succeeded | failed | skipped
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// What a job does. Errors are strings so the future is `Send` for the scheduler.
+pub type JobTask = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = Result<(), String>> + Send>> + Send + Sync>;
+
+pub struct RegisteredJob {
+    pub schedule: JobSchedule,
+    task: JobTask,
+}
+
+pub struct JobRegistry {
+    db: PgPool,
+    jobs: BTreeMap<JobName, RegisteredJob>,
+}
+
+impl JobRegistry {
+    pub fn new(db: PgPool) -> Self {
+        Self { db, jobs: BTreeMap::new() }
+    }
+
+    /// Adds a job with its schedule from configuration
+    pub fn register<F, Fut>(&mut self, name: JobName, task: F) -> Result<(), Box<dyn Error>>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        let schedule = JobSchedule::from_env(name)?;
+        let task: JobTask = Arc::new(move || Box::pin(task()));
+        self.jobs.insert(name, RegisteredJob { schedule, task });
+        Ok(())
+    }
+
+    pub fn jobs(&self) -> impl Iterator<Item = (&JobName, &RegisteredJob)> {
+        self.jobs.iter()
+    }
+
+    /// RUN A JOB (scheduled or manual)
+    /// 1. Takes the job's advisory lock on a dedicated connection (skip if another replica has it)
+    /// 2. Records the run, executes it, records the outcome
+    /// 3. Releases the lock on the SAME connection
+    pub async fn run(
+        &self,
+        name: JobName,
+        trigger: JobTrigger,
+        triggered_by: Option<&str>,
+    ) -> Result<JobRun, Box<dyn Error>> {
+        let job = self.jobs.get(&name).ok_or_else(|| format!("Job {} is not registered", name.as_str()))?;
+
+        // Advisory locks belong to the session, so hold one connection for the whole run
+        let mut conn = self.db.acquire().await?;
+        let locked = sqlx::query!(r#"SELECT pg_try_advisory_lock($1) AS "locked!""#, name.lock_key())
+            .fetch_one(&mut *conn)
+            .await?
+            .locked;
+
+        if !locked {
+            println!("⏭️  Job {} is already running on another replica, skipping.", name.as_str());
+            return self.record_run(name, trigger, triggered_by, "skipped", None, true).await;
+        }
+
+        let run = self.record_run(name, trigger, triggered_by, "running", None, false).await;
+        let outcome = match run {
+            Ok(run) => {
+                println!("▶️  Job {} started ({}).", name.as_str(), trigger.as_str());
+                let result = (job.task)().await;
+                self.finish_run(run.id, result).await
+            }
+            Err(e) => Err(e),
+        };
+
+        // Always release, even if recording the run failed
+        sqlx::query!("SELECT pg_advisory_unlock($1)", name.lock_key())
+            .execute(&mut *conn)
+            .await?;
+
+        outcome
+    }
+
+    /// Most recent runs, optionally for one job
+    pub async fn history(&self, name: Option<JobName>, limit: i64) -> Result<Vec<JobRun>, Box<dyn Error>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, job_name, trigger, triggered_by, status, error, started_at, finished_at
+            FROM job_runs
+            WHERE $1::TEXT IS NULL OR job_name = $1
+            ORDER BY started_at DESC
+            LIMIT $2
+            "#,
+            name.map(|n| n.as_str()),
+            limit
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| JobRun {
+                id: r.id,
+                job_name: r.job_name,
+                trigger: r.trigger,
+                triggered_by: r.triggered_by,
+                status: r.status,
+                error: r.error,
+                started_at: r.started_at,
+                finished_at: r.finished_at,
+            })
+            .collect())
+    }
+
+    async fn record_run(
+        &self,
+        name: JobName,
+        trigger: JobTrigger,
+        triggered_by: Option<&str>,
+        status: &str,
+        error: Option<&str>,
+        finished: bool,
+    ) -> Result<JobRun, Box<dyn Error>> {
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        let finished_at = if finished { Some(now) } else { None };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO job_runs (id, job_name, trigger, triggered_by, status, error, started_at, finished_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+            id,
+            name.as_str(),
+            trigger.as_str(),
+            triggered_by,
+            status,
+            error,
+            now,
+            finished_at
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(JobRun {
+            id,
+            job_name: name.as_str().to_string(),
+            trigger: trigger.as_str().to_string(),
+            triggered_by: triggered_by.map(str::to_string),
+            status: status.to_string(),
+            error: error.map(str::to_string),
+            started_at: now,
+            finished_at,
+        })
+    }
+
+    async fn finish_run(&self, run_id: Uuid, result: Result<(), String>) -> Result<JobRun, Box<dyn Error>> {
+        let (status, error) = match &result {
+            Ok(_) => ("succeeded", None),
+            Err(e) => ("failed", Some(e.as_str())),
+        };
+
+        let r = sqlx::query!(
+            r#"
+            UPDATE job_runs
+            SET status = $2, error = $3, finished_at = NOW()
+            WHERE id = $1
+            RETURNING id, job_name, trigger, triggered_by, status, error, started_at, finished_at
+            "#,
+            run_id,
+            status,
+            error
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        match &result {
+            Ok(_) => println!("✅ Job {} succeeded.", r.job_name),
+            Err(e) => eprintln!("CRITICAL: Job {} failed: {}", r.job_name, e),
+        }
+
+        Ok(JobRun {
+            id: r.id,
+            job_name: r.job_name,
+            trigger: r.trigger,
+            triggered_by: r.triggered_by,
+            status: r.status,
+            error: r.error,
+            started_at: r.started_at,
+            finished_at: r.finished_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn job_names_round_trip() {
+        for job in JobName::ALL {
+            assert_eq!(JobName::parse(job.as_str()).unwrap(), job);
+        }
+        assert!(JobName::parse("payroll").is_err());
+    }
+
+    #[test]
+    fn every_job_has_its_own_lock_key() {
+        let keys: HashSet<i64> = JobName::ALL.iter().map(JobName::lock_key).collect();
+        assert_eq!(keys.len(), JobName::ALL.len());
+    }
+
+    #[test]
+    fn schedules_reject_unknown_timezones() {
+        let schedule = |timezone: &str| JobSchedule {
+            cron: JobName::Billing.default_cron().to_string(),
+            timezone: timezone.to_string(),
+            enabled: true,
+        };
+        assert_eq!(schedule("America/New_York").tz().unwrap(), chrono_tz::America::New_York);
+        assert!(schedule("Mars/Olympus_Mons").tz().is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::core::fiat_banking::FiatAsset;
+use crate::core::invoice::{BillingPeriod, LineCategory, LineItem};
+use crate::core::tiers::ServiceTier;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::error::Error;
+use uuid::Uuid;
+
+/// What we meter on the ledger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageMetric {
+    TransactionsSubmitted, // Transactions signed by an account in the tenant's domain
+    UnitsMinted,           // Quantity the tenant minted into its domain (not USD, not our mints)
+    ActiveAccounts,        // Distinct accounts that submitted at least one transaction
+    BridgeWithdrawals,     // Transfers to the bridge escrow (exits to the public network)
+}
+
+impl UsageMetric {
+    pub const ALL: [UsageMetric; 4] = [
+        UsageMetric::TransactionsSubmitted,
+        UsageMetric::UnitsMinted,
+        UsageMetric::ActiveAccounts,
+        UsageMetric::BridgeWithdrawals,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageMetric::TransactionsSubmitted => "transactions_submitted",
+            UsageMetric::UnitsMinted => "units_minted",
+            UsageMetric::ActiveAccounts => "active_accounts",
+            UsageMetric::BridgeWithdrawals => "bridge_withdrawals",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value {
+            "transactions_submitted" => Ok(UsageMetric::TransactionsSubmitted),
+            "units_minted" => Ok(UsageMetric::UnitsMinted),
+            "active_accounts" => Ok(UsageMetric::ActiveAccounts),
+            "bridge_withdrawals" => Ok(UsageMetric::BridgeWithdrawals),
+            other => Err(format!("Unknown usage metric: {}", other).into()),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            UsageMetric::TransactionsSubmitted => "Transactions Submitted",
+            UsageMetric::UnitsMinted => "Units Minted",
+            UsageMetric::ActiveAccounts => "Active Accounts",
+            UsageMetric::BridgeWithdrawals => "Bridge Withdrawals",
+        }
+    }
+}
+
+/// A measured quantity for one metric
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageQuantity {
+    pub metric: UsageMetric,
+    pub quantity: i64,
+}
+
+/// Included quota + overage rate for a metric (lives in the price book)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRate {
+    pub metric: UsageMetric,
+    pub tier: Option<ServiceTier>,
+    pub included_quantity: i64,
+    pub overage_block_size: i64,
+    pub overage_cents_per_block: i64,
+}
+
+impl UsageRate {
+    /// Overage is charged per STARTED block above the included quantity
+    pub fn overage_line(&self, used: i64) -> Option<LineItem> {
+        let over = used - self.included_quantity;
+        if over <= 0 {
+            return None;
+        }
+
+        let blocks = (over + self.overage_block_size - 1) / self.overage_block_size;
+        Some(LineItem::from_cents(
+            LineCategory::UsageOverage,
+            &format!(
+                "Overage: {} ({} used, {} included)",
+                self.metric.label(),
+                used,
+                self.included_quantity
+            ),
+            blocks * self.overage_cents_per_block,
+        ))
+    }
+}
+
+/// What isn't the tenant's own usage
+#[derive(Debug, Clone)]
+pub struct MeteringConfig {
+    pub settlement_asset_id: String, // On-chain USD: minted by us against fiat deposits
+    pub platform_account_id: String, // Our signing account (on-ramps, payroll, refunds)
+}
+
+impl MeteringConfig {
+    /// Reads IROHA_ACCOUNT_ID, the account the platform signs with
+    pub fn from_env(asset: &FiatAsset) -> Self {
+        Self {
+            settlement_asset_id: asset.usd_asset_id.clone(),
+            platform_account_id: std::env::var("IROHA_ACCOUNT_ID").unwrap_or_else(|_| "admin@my_ecosystem".to_string()),
+        }
+    }
+}
+
+/// Aggregates ledger activity per tenant from the explorer index
+pub struct UsageMeter;
+
+impl UsageMeter {
+    /// Measures every metric for the tenant's domain over `period`.
+    /// Mints of the settlement asset and mints we signed (fiat on-ramps,
+    /// payroll) are the platform's doing, not the tenant's: they aren't metered.
+    pub async fn measure(
+        db: &PgPool,
+        config: &MeteringConfig,
+        tenant_id: Uuid,
+        period: BillingPeriod,
+    ) -> Result<Vec<UsageQuantity>, Box<dyn Error>> {
+        let domain = sqlx::query!("SELECT iroha_domain FROM tenants WHERE id = $1", tenant_id)
+            .fetch_one(db)
+            .await?
+            .iroha_domain;
+
+        // A tenant without a ledger domain has no ledger usage
+        let domain = match domain {
+            Some(d) => d,
+            None => return Ok(vec![]),
+        };
+
+        let from = period.start.and_hms_opt(0, 0, 0).unwrap();
+        let to = period.end().and_hms_opt(0, 0, 0).unwrap();
+
+        let tx = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) AS "transactions!",
+                COUNT(DISTINCT sender_account_id) AS "active_accounts!"
+            FROM chain_transactions
+            WHERE split_part(sender_account_id, '@', 2) = $1
+              AND timestamp >= $2 AND timestamp < $3
+            "#,
+            domain,
+            from,
+            to
+        )
+        .fetch_one(db)
+        .await?;
+
+        let isi = sqlx::query!(
+            r#"
+            SELECT
+                COALESCE(SUM(i.quantity) FILTER (
+                    WHERE i.kind = 'Mint' AND split_part(i.destination_account_id, '@', 2) = $1
+                      AND i.asset_definition_id IS DISTINCT FROM $4
+                      AND t.sender_account_id <> $5
+                ), 0) AS "units_minted!",
+                COUNT(*) FILTER (
+                    WHERE i.kind = 'Transfer'
+                      AND i.destination_account_id LIKE 'bridge_escrow@%'
+                      AND split_part(i.source_account_id, '@', 2) = $1
+                ) AS "bridge_withdrawals!"
+            FROM chain_instructions i
+            JOIN chain_transactions t ON t.tx_hash = i.tx_hash
+            WHERE t.timestamp >= $2 AND t.timestamp < $3
+            "#,
+            domain,
+            from,
+            to,
+            config.settlement_asset_id,
+            config.platform_account_id
+        )
+        .fetch_one(db)
+        .await?;
+
+        Ok(vec![
+            UsageQuantity { metric: UsageMetric::TransactionsSubmitted, quantity: tx.transactions },
+            UsageQuantity { metric: UsageMetric::UnitsMinted, quantity: isi.units_minted.round() as i64 },
+            UsageQuantity { metric: UsageMetric::ActiveAccounts, quantity: tx.active_accounts },
+            UsageQuantity { metric: UsageMetric::BridgeWithdrawals, quantity: isi.bridge_withdrawals },
+        ])
+    }
+
+    /// Stores the measured usage next to the invoice that billed it
+    pub async fn record(
+        tx: &mut Transaction<'_, Postgres>,
+        tenant_id: Uuid,
+        period: BillingPeriod,
+        usage: &[UsageQuantity],
+    ) -> Result<(), Box<dyn Error>> {
+        for u in usage {
+            sqlx::query!(
+                r#"
+                INSERT INTO tenant_usage (tenant_id, period_start, metric, quantity)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (tenant_id, period_start, metric)
+                DO UPDATE SET quantity = EXCLUDED.quantity, measured_at = NOW()
+                "#,
+                tenant_id,
+                period.start,
+                u.metric.as_str(),
+                u.quantity
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate() -> UsageRate {
+        UsageRate {
+            metric: UsageMetric::TransactionsSubmitted,
+            tier: None,
+            included_quantity: 10_000,
+            overage_block_size: 1_000,
+            overage_cents_per_block: 250,
+        }
+    }
+
+    #[test]
+    fn nothing_owed_within_the_included_quantity() {
+        assert!(rate().overage_line(0).is_none());
+        assert!(rate().overage_line(10_000).is_none());
+    }
+
+    #[test]
+    fn every_started_block_is_charged() {
+        let cents = |used| rate().overage_line(used).unwrap().amount_cents;
+        assert_eq!(cents(10_001), 250); // One unit over starts a block
+        assert_eq!(cents(11_000), 250);
+        assert_eq!(cents(11_001), 500);
+        assert_eq!(cents(25_000), 3_750);
+    }
+
+    #[test]
+    fn overage_line_describes_the_usage() {
+        let line = rate().overage_line(12_500).unwrap();
+        assert_eq!(line.category, LineCategory::UsageOverage);
+        assert_eq!(line.description, "Overage: Transactions Submitted (12500 used, 10000 included)");
+    }
+}
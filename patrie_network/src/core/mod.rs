@@ -0,0 +1,32 @@
+pub mod ach;
+pub mod adjustments;
+pub mod audit;
+pub mod benefit_catalog;
+pub mod billing_engine;
+pub mod billing_preview;
+pub mod bridge;
+pub mod cards;
+pub mod cash_deposits;
+pub mod contracts;
+pub mod credit_notes;
+pub mod documents;
+pub mod dunning;
+pub mod employee_sync;
+pub mod explorer_indexer;
+pub mod fiat_banking;
+pub mod fiat_events;
+pub mod gusto;
+pub mod gusto_events;
+pub mod gusto_tokens;
+pub mod invoice;
+pub mod jobs;
+pub mod metering;
+pub mod notifications;
+pub mod onboarding;
+pub mod payroll;
+pub mod pricing;
+pub mod reporting;
+pub mod reserves;
+pub mod statements;
+pub mod subscription;
+pub mod tiers;
@@ -0,0 +1,147 @@
+use reqwest::Client;
+use sqlx::PgPool;
+use std::error::Error;
+use uuid::Uuid;
+
+/// Give up on a message after this many failed sends (it stays in the outbox)
+const MAX_ATTEMPTS: i32 = 8;
+/// Messages per job run
+const BATCH_SIZE: i64 = 100;
+
+/// Queues a message for the tenant's admin.
+/// Messages go to the `notifications` outbox first, so nothing is lost if the
+/// mail provider is down; `NotificationSender` delivers them and sets `sent_at`.
+pub async fn notify_tenant_admin(
+    db: &PgPool,
+    tenant_id: Uuid,
+    kind: &str,
+    subject: &str,
+    body: &str,
+) -> Result<(), Box<dyn Error>> {
+    let recipient = sqlx::query!("SELECT admin_email FROM tenants WHERE id = $1", tenant_id)
+        .fetch_one(db)
+        .await?
+        .admin_email;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO notifications (id, tenant_id, recipient, kind, subject, body)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        Uuid::new_v4(),
+        tenant_id,
+        recipient,
+        kind,
+        subject,
+        body
+    )
+    .execute(db)
+    .await?;
+
+    println!("📧 [{}] {} -> {}", kind, subject, recipient.as_deref().unwrap_or("(no admin email)"));
+    Ok(())
+}
+
+/// Outbound mail (Postmark-style JSON API)
+#[derive(Debug, Clone)]
+pub struct MailConfig {
+    pub api_url: String,
+    pub api_token: String,
+    pub from: String,
+}
+
+impl MailConfig {
+    /// Reads MAIL_API_URL, MAIL_API_TOKEN and MAIL_FROM.
+    /// No token = no provider: messages are only logged (sandbox, local dev).
+    pub fn from_env() -> Option<Self> {
+        let api_token = std::env::var("MAIL_API_TOKEN").ok().filter(|t| !t.is_empty())?;
+        Some(Self {
+            api_url: std::env::var("MAIL_API_URL").unwrap_or_else(|_| "https://api.postmarkapp.com/email".to_string()),
+            api_token,
+            from: std::env::var("MAIL_FROM").unwrap_or_else(|_| "billing@patrie.network".to_string()),
+        })
+    }
+}
+
+/// Empties the `notifications` outbox (the 'notifications' job)
+pub struct NotificationSender {
+    db: PgPool,
+    http: Client,
+    mail: Option<MailConfig>,
+}
+
+impl NotificationSender {
+    pub fn new(db: PgPool, mail: Option<MailConfig>) -> Self {
+        Self { db, http: Client::new(), mail }
+    }
+
+    /// Sends every unsent message. Each one is claimed first (attempts + 1),
+    /// so two replicas never send the same message; a claim older than 10
+    /// minutes belongs to a crashed sender and is picked up again. Failures
+    /// are kept on the row and retried next run, up to MAX_ATTEMPTS.
+    pub async fn deliver_pending(&self) -> Result<(), Box<dyn Error>> {
+        let claimed = sqlx::query!(
+            r#"
+            UPDATE notifications SET attempts = attempts + 1, last_attempt_at = NOW()
+            WHERE id IN (
+                SELECT id FROM notifications
+                WHERE sent_at IS NULL AND recipient IS NOT NULL AND attempts < $1
+                  AND (last_attempt_at IS NULL OR last_attempt_at < NOW() - INTERVAL '10 minutes')
+                ORDER BY created_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, recipient, kind, subject, body
+            "#,
+            MAX_ATTEMPTS,
+            BATCH_SIZE
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut failed = 0;
+        for message in claimed {
+            let recipient = message.recipient.unwrap_or_default();
+            match self.send(&recipient, &message.subject, &message.body).await {
+                Ok(()) => {
+                    sqlx::query!("UPDATE notifications SET sent_at = NOW(), last_error = NULL WHERE id = $1", message.id)
+                        .execute(&self.db)
+                        .await?;
+                }
+                Err(e) => {
+                    failed += 1;
+                    eprintln!("❌ Notification {} ({}) to {} failed: {}", message.id, message.kind, recipient, e);
+                    sqlx::query!("UPDATE notifications SET last_error = $2 WHERE id = $1", message.id, e.to_string())
+                        .execute(&self.db)
+                        .await?;
+                }
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("{} notification(s) could not be sent", failed).into());
+        }
+        Ok(())
+    }
+
+    async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), Box<dyn Error>> {
+        let mail = match &self.mail {
+            Some(mail) => mail,
+            None => {
+                println!("📧 (not sent, no MAIL_API_TOKEN) {} -> {}", subject, to);
+                return Ok(());
+            }
+        };
+        let resp = self
+            .http
+            .post(&mail.api_url)
+            .header("X-Postmark-Server-Token", &mail.api_token)
+            .json(&serde_json::json!({ "From": mail.from, "To": to, "Subject": subject, "TextBody": body }))
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(format!("Mail provider answered {}: {}", resp.status(), resp.text().await.unwrap_or_default()).into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+use crate::core::fiat_banking::{BusinessApplication, UnitClient};
+use crate::core::gusto::GustoClient;
+use crate::core::pricing::PriceBook;
+use crate::core::tiers::ServiceTier;
+use crate::ledger::client::IrohaClient;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- TENANT ONBOARDING ---
+// A tenant is three outside accounts: a Gusto company, a Unit business customer
+// (with the deposit account invoices are pulled from) and a ledger domain.
+// The tenant row, its subscription and its domain name are written first, in
+// ONE transaction, with status 'onboarding'. Every outside id is saved the
+// moment it comes back, so a crash, a timeout or a KYB review still pending
+// resumes where it stopped instead of creating a second company or customer.
+// The tenant turns 'active' (billable, writable) once all three exist.
+
+/// Deposit product of the tenant's operating account
+const DEPOSIT_PRODUCT: &str = "checking";
+
+/// What the signup form sends
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignupRequest {
+    pub company_name: String, // Also becomes the ledger domain, e.g. "Tesla Supply Chain" -> "tesla_supply_chain"
+    pub email: String,        // The tenant's admin
+    pub tier: String,         // "starter" | "professional" | "enterprise"
+    pub business: BusinessApplication, // KYB details for Unit
+}
+
+/// Where a tenant's onboarding stands
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingStatus {
+    pub tenant_id: Uuid,
+    pub status: String, // onboarding | active
+    pub iroha_domain: String,
+    pub gusto_company_uuid: Option<String>,
+    pub unit_application_id: Option<String>,
+    pub unit_application_status: Option<String>, // Anything but "Approved" waits for Unit's review
+    pub unit_customer_id: Option<String>,
+    pub unit_deposit_account_id: Option<String>,
+}
+
+impl OnboardingStatus {
+    pub fn is_complete(&self) -> bool {
+        self.status != "onboarding"
+    }
+}
+
+pub struct TenantOnboarding {
+    db: PgPool,
+    gusto: Arc<GustoClient>,
+    unit: Arc<UnitClient>,
+    iroha: Arc<IrohaClient>,
+}
+
+impl TenantOnboarding {
+    pub fn new(db: PgPool, gusto: Arc<GustoClient>, unit: Arc<UnitClient>, iroha: Arc<IrohaClient>) -> Self {
+        Self { db, gusto, unit, iroha }
+    }
+
+    /// SIGN UP
+    /// Claims the domain name and writes the tenant, then runs every step.
+    /// Sending the same signup again (same company, same admin) resumes the
+    /// unfinished tenant instead of failing on the taken domain.
+    pub async fn sign_up(&self, req: &SignupRequest) -> Result<OnboardingStatus, Box<dyn Error>> {
+        let tier = ServiceTier::parse(&req.tier)?;
+        let domain = domain_name(&req.company_name)?;
+
+        let existing = sqlx::query!(
+            "SELECT id, status, admin_email FROM tenants WHERE iroha_domain = $1",
+            domain
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        let tenant_id = match existing {
+            Some(t) if t.status == "onboarding" && t.admin_email.as_deref() == Some(req.email.as_str()) => t.id,
+            Some(_) => return Err(format!("Domain '{}' is already taken", domain).into()),
+            None => self.create_tenant(req, tier, &domain).await?,
+        };
+
+        self.advance(tenant_id, Some(&req.business)).await
+    }
+
+    /// RESUME (e.g. once Unit approved a KYB that was under review)
+    pub async fn resume(&self, tenant_id: Uuid) -> Result<OnboardingStatus, Box<dyn Error>> {
+        self.advance(tenant_id, None).await
+    }
+
+    /// Tenant row + subscription, in ONE transaction. The unique domain is the
+    /// lock: of two concurrent signups for the same name, one gets an error.
+    async fn create_tenant(&self, req: &SignupRequest, tier: ServiceTier, domain: &str) -> Result<Uuid, Box<dyn Error>> {
+        let prices = PriceBook::current(&self.db).await?;
+        let config = tier.get_config(&prices)?;
+        let tenant_id = Uuid::new_v4();
+
+        let mut tx = self.db.begin().await?;
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO tenants (id, company_name, admin_email, iroha_domain, status)
+            VALUES ($1, $2, $3, $4, 'onboarding')
+            ON CONFLICT (iroha_domain) DO NOTHING
+            "#,
+            tenant_id,
+            req.company_name,
+            req.email,
+            domain
+        )
+        .execute(&mut *tx)
+        .await?;
+        if inserted.rows_affected() != 1 {
+            tx.rollback().await?;
+            return Err(format!("Domain '{}' is already taken", domain).into());
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO subscription_settings
+            (tenant_id, tier, base_fee_retail, health_active, retirement_active, crime_active)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            tenant_id,
+            tier.as_str(),
+            config.base_price,
+            config.includes_health,
+            config.includes_401k,
+            config.includes_crime_ins
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        println!("📝 Tenant {} ({}) signed up on {}.", tenant_id, req.company_name, tier);
+        Ok(tenant_id)
+    }
+
+    /// Runs every step that hasn't happened yet, in order
+    async fn advance(&self, tenant_id: Uuid, kyb: Option<&BusinessApplication>) -> Result<OnboardingStatus, Box<dyn Error>> {
+        let mut status = self.status(tenant_id).await?;
+        if status.is_complete() {
+            return Ok(status);
+        }
+
+        // 1. Gusto company (this is what earns our commission)
+        if status.gusto_company_uuid.is_none() {
+            let row = sqlx::query!("SELECT company_name, admin_email FROM tenants WHERE id = $1", tenant_id)
+                .fetch_one(&self.db)
+                .await?;
+            let company = self
+                .gusto
+                .create_partner_managed_company(
+                    row.company_name.as_deref().unwrap_or(&status.iroha_domain),
+                    row.admin_email.as_deref().ok_or("Tenant has no admin email")?,
+                )
+                .await?;
+            sqlx::query!(
+                "UPDATE tenants SET gusto_company_uuid = $2 WHERE id = $1 AND gusto_company_uuid IS NULL",
+                tenant_id,
+                company.company_uuid
+            )
+            .execute(&self.db)
+            .await?;
+            status.gusto_company_uuid = Some(company.company_uuid);
+        }
+
+        // 2. Unit business customer. The application's idempotency key is the
+        // tenant id, so re-sending it can't open a second customer.
+        if status.unit_customer_id.is_none() {
+            let application = match &status.unit_application_id {
+                Some(application_id) => self.unit.get_application(application_id).await?,
+                None => {
+                    let mut kyb = kyb.ok_or("KYB details are missing: send the signup again")?.clone();
+                    kyb.idempotency_key = format!("onboarding-{}", tenant_id);
+                    let application = self.unit.create_business_application(&kyb).await?;
+                    sqlx::query!(
+                        "UPDATE tenants SET unit_application_id = $2 WHERE id = $1",
+                        tenant_id,
+                        application.id
+                    )
+                    .execute(&self.db)
+                    .await?;
+                    status.unit_application_id = Some(application.id.clone());
+                    application
+                }
+            };
+            status.unit_application_status = Some(application.attributes.status.clone());
+            if application.attributes.status != "Approved" {
+                println!(
+                    "⏳ Tenant {} waits for Unit KYB ({}: {}).",
+                    tenant_id, application.id, application.attributes.status
+                );
+                return Ok(status);
+            }
+            let customer_id = application.related_id("customer").ok_or("Approved application has no customer")?;
+
+            // 3. The account invoices are pulled from. Customer and account are
+            // saved together: a tenant never has one without the other.
+            let account = self
+                .unit
+                .create_deposit_account(
+                    &customer_id,
+                    DEPOSIT_PRODUCT,
+                    serde_json::json!({ "tenant_id": tenant_id.to_string() }),
+                    &format!("onboarding-account-{}", tenant_id),
+                )
+                .await?;
+            sqlx::query!(
+                "UPDATE tenants SET unit_customer_id = $2, unit_deposit_account_id = $3 WHERE id = $1",
+                tenant_id,
+                customer_id,
+                account.id
+            )
+            .execute(&self.db)
+            .await?;
+            status.unit_customer_id = Some(customer_id);
+            status.unit_deposit_account_id = Some(account.id);
+        }
+
+        // 4. Ledger domain, tagged with the tenant and its Gusto company
+        let domain = status.iroha_domain.clone();
+        if !self.iroha.has_domain(&domain).await.map_err(|e| e.to_string())? {
+            let tenant = tenant_id.to_string();
+            let company = status.gusto_company_uuid.clone().unwrap_or_default();
+            self.iroha
+                .register_domain(&domain, &[("tenant_id", tenant.as_str()), ("gusto_company_uuid", company.as_str())])
+                .await
+                .map_err(|e| format!("Registering domain {} failed: {}", domain, e))?;
+        }
+
+        // 5. Open for business
+        sqlx::query!("UPDATE tenants SET status = 'active' WHERE id = $1 AND status = 'onboarding'", tenant_id)
+            .execute(&self.db)
+            .await?;
+        status.status = "active".to_string();
+
+        println!("✅ Tenant {} onboarded (domain {}).", tenant_id, domain);
+        Ok(status)
+    }
+
+    pub async fn status(&self, tenant_id: Uuid) -> Result<OnboardingStatus, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT status, iroha_domain, gusto_company_uuid, unit_application_id,
+                   unit_customer_id, unit_deposit_account_id
+            FROM tenants WHERE id = $1
+            "#,
+            tenant_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or("Tenant not found")?;
+
+        Ok(OnboardingStatus {
+            tenant_id,
+            status: row.status,
+            iroha_domain: row.iroha_domain.ok_or("Tenant has no ledger domain")?,
+            gusto_company_uuid: row.gusto_company_uuid,
+            unit_application_id: row.unit_application_id,
+            unit_application_status: None,
+            unit_customer_id: row.unit_customer_id,
+            unit_deposit_account_id: row.unit_deposit_account_id,
+        })
+    }
+}
+
+/// The ledger domain for a company name: lowercase ASCII letters, digits and
+/// underscores ("Acme Supply, LLC" -> "acme_supply_llc")
+pub fn domain_name(company_name: &str) -> Result<String, Box<dyn Error>> {
+    let mut domain = String::new();
+    for c in company_name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            domain.push(c.to_ascii_lowercase());
+        } else if !domain.is_empty() && !domain.ends_with('_') {
+            domain.push('_');
+        }
+    }
+    let domain = domain.trim_end_matches('_').to_string();
+    if domain.is_empty() {
+        return Err(format!("'{}' can't be turned into a domain name", company_name).into());
+    }
+    Ok(domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::domain_name;
+
+    #[test]
+    fn company_names_become_domain_names() {
+        assert_eq!(domain_name("Tesla Supply Chain").unwrap(), "tesla_supply_chain");
+        assert_eq!(domain_name("  Acme Supply, LLC. ").unwrap(), "acme_supply_llc");
+        assert_eq!(domain_name("ÉCOLE 42").unwrap(), "cole_42");
+        assert!(domain_name("!!!").is_err());
+    }
+}
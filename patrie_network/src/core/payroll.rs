@@ -0,0 +1,639 @@
+use crate::core::employee_sync::EmployeeSync;
+use crate::core::fiat_banking::{FiatAsset, UnitClient};
+use crate::core::gusto::{to_cents, EmployeeCompensation, GustoClient, GustoPayroll};
+use crate::ledger::client::{IrohaClient, StoreRecord, StoreValue};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- PAYROLL -> LEDGER ---
+// When Gusto processes a payroll, every employee's payslip is written into a
+// `payslip#<domain>` Store asset on their account, and (if the tenant opted
+// in) their net pay moves from the tenant treasury to their wallet as `usd`.
+// All of it is ONE ledger transaction per payroll: no payslip without its
+// payment, no payment without its payslip.
+//
+// Instant off-ramp: employees whose wallet mirrors their own Unit account also
+// get the fiat right away, by a book payment from the tenant's Unit account.
+// Its webhooks mint/burn nothing (see `payroll_prefunded`): the ledger side
+// already happened in the payroll transaction.
+
+#[derive(Debug, Clone)]
+pub struct PayrollConfig {
+    pub usd_asset_id: String,
+}
+
+impl PayrollConfig {
+    pub fn new(asset: &FiatAsset) -> Self {
+        Self { usd_asset_id: asset.usd_asset_id.clone() }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PayrollSettings {
+    pub disburse_net_pay: bool,
+    pub treasury_account_id: Option<String>, // Pays the net pay; must be in the tenant's domain
+    pub instant_offramp: bool,
+}
+
+impl PayrollSettings {
+    pub async fn load(db: &PgPool, tenant_id: Uuid) -> Result<Self, Box<dyn Error>> {
+        let row = sqlx::query!(
+            "SELECT disburse_net_pay, treasury_account_id, instant_offramp FROM payroll_settings WHERE tenant_id = $1",
+            tenant_id
+        )
+        .fetch_optional(db)
+        .await?;
+
+        Ok(row
+            .map(|r| PayrollSettings {
+                disburse_net_pay: r.disburse_net_pay,
+                treasury_account_id: r.treasury_account_id,
+                instant_offramp: r.instant_offramp,
+            })
+            .unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PayrollRun {
+    pub payroll_uuid: String,
+    pub pay_period_start: NaiveDate,
+    pub pay_period_end: NaiveDate,
+    pub check_date: NaiveDate,
+    pub employee_count: i32,
+    pub gross_cents: i64,
+    pub net_cents: i64,
+    pub disbursed_cents: i64,
+    pub status: String, // recording | recorded
+    pub tx_hash: Option<String>,
+    pub recorded_at: Option<DateTime<Utc>>,
+}
+
+/// One employee's pay, in cents
+struct Payslip {
+    employee_uuid: String,
+    account: String,
+    gross_cents: i64,
+    net_cents: i64,
+    employee_taxes_cents: i64,
+    employer_taxes_cents: i64,
+    benefit_deductions_cents: i64,
+    other_deductions_cents: i64,
+    disbursed_cents: i64,
+}
+
+impl Payslip {
+    fn from_compensation(comp: &EmployeeCompensation, account: String, disburse: bool) -> Result<Self, Box<dyn Error>> {
+        let amount = |value: &Option<String>| value.as_deref().map(to_cents).unwrap_or(Ok(0));
+
+        let mut employee_taxes_cents = 0;
+        let mut employer_taxes_cents = 0;
+        for tax in &comp.taxes {
+            if tax.employer {
+                employer_taxes_cents += to_cents(&tax.amount)?;
+            } else {
+                employee_taxes_cents += to_cents(&tax.amount)?;
+            }
+        }
+        let mut benefit_deductions_cents = 0;
+        for benefit in &comp.benefits {
+            benefit_deductions_cents += amount(&benefit.employee_deduction)?;
+        }
+        let mut other_deductions_cents = 0;
+        for deduction in &comp.deductions {
+            other_deductions_cents += to_cents(&deduction.amount)?;
+        }
+
+        let net_cents = amount(&comp.net_pay)?;
+        Ok(Self {
+            employee_uuid: comp.employee_uuid.clone(),
+            account,
+            gross_cents: amount(&comp.gross_pay)?,
+            net_cents,
+            employee_taxes_cents,
+            employer_taxes_cents,
+            benefit_deductions_cents,
+            other_deductions_cents,
+            disbursed_cents: if disburse { net_cents.max(0) } else { 0 },
+        })
+    }
+
+    /// Typed entries under the payroll's key prefix, e.g. "payroll_1a2b..._net"
+    fn store_record(&self, prefix: &str, payroll: &PayrollDates) -> StoreRecord<'_> {
+        let field = |name: &str| format!("{}_{}", prefix, name);
+        StoreRecord {
+            account: &self.account,
+            fields: vec![
+                (field("check_date"), StoreValue::Text(payroll.check_date.to_string())),
+                (field("period_start"), StoreValue::Text(payroll.pay_period_start.to_string())),
+                (field("period_end"), StoreValue::Text(payroll.pay_period_end.to_string())),
+                (field("gross"), StoreValue::Cents(self.gross_cents)),
+                (field("employee_taxes"), StoreValue::Cents(self.employee_taxes_cents)),
+                (field("employer_taxes"), StoreValue::Cents(self.employer_taxes_cents)),
+                (field("benefit_deductions"), StoreValue::Cents(self.benefit_deductions_cents)),
+                (field("other_deductions"), StoreValue::Cents(self.other_deductions_cents)),
+                (field("disbursed"), StoreValue::Cents(self.disbursed_cents)),
+                // Written last: its presence proves the whole transaction landed
+                (field("net"), StoreValue::Cents(self.net_cents)),
+            ],
+        }
+    }
+}
+
+struct PayrollDates {
+    check_date: NaiveDate,
+    pay_period_start: NaiveDate,
+    pay_period_end: NaiveDate,
+}
+
+pub struct PayrollMirror {
+    db: PgPool,
+    gusto: Arc<GustoClient>,
+    unit: Arc<UnitClient>,
+    iroha: Arc<IrohaClient>,
+    employees: Arc<EmployeeSync>,
+    config: PayrollConfig,
+}
+
+impl PayrollMirror {
+    pub fn new(
+        db: PgPool,
+        gusto: Arc<GustoClient>,
+        unit: Arc<UnitClient>,
+        iroha: Arc<IrohaClient>,
+        employees: Arc<EmployeeSync>,
+        config: PayrollConfig,
+    ) -> Self {
+        Self { db, gusto, unit, iroha, employees, config }
+    }
+
+    /// OPT IN (or out) of paying net pay on chain
+    pub async fn set_settings(&self, tenant_id: Uuid, settings: &PayrollSettings) -> Result<PayrollSettings, Box<dyn Error>> {
+        if settings.disburse_net_pay {
+            let treasury = settings
+                .treasury_account_id
+                .as_deref()
+                .ok_or("A treasury account is required to disburse net pay")?;
+            let domain = self.tenant_domain(tenant_id).await?;
+            if treasury.split('@').nth(1) != Some(domain.as_str()) {
+                return Err(format!("Treasury account must belong to domain {}", domain).into());
+            }
+        }
+        if settings.instant_offramp && !settings.disburse_net_pay {
+            return Err("Instant off-ramp needs net pay disbursement".into());
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO payroll_settings (tenant_id, disburse_net_pay, treasury_account_id, instant_offramp)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (tenant_id) DO UPDATE
+            SET disburse_net_pay = EXCLUDED.disburse_net_pay,
+                treasury_account_id = EXCLUDED.treasury_account_id,
+                instant_offramp = EXCLUDED.instant_offramp,
+                updated_at = NOW()
+            "#,
+            tenant_id,
+            settings.disburse_net_pay,
+            settings.treasury_account_id,
+            settings.instant_offramp
+        )
+        .execute(&self.db)
+        .await?;
+
+        PayrollSettings::load(&self.db, tenant_id).await
+    }
+
+    pub async fn runs(&self, tenant_id: Uuid) -> Result<Vec<PayrollRun>, Box<dyn Error>> {
+        let runs = sqlx::query_as!(
+            PayrollRun,
+            r#"
+            SELECT payroll_uuid, pay_period_start, pay_period_end, check_date, employee_count,
+                   gross_cents, net_cents, disbursed_cents, status, tx_hash, recorded_at
+            FROM payroll_runs
+            WHERE tenant_id = $1
+            ORDER BY check_date DESC
+            "#,
+            tenant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+        Ok(runs)
+    }
+
+    /// MIRROR ONE PAYROLL (payroll.processed webhook, or on demand). Safe to repeat.
+    /// 1. Reads the payroll from Gusto and snapshots the payslips
+    /// 2. Records payslips + disbursements in one ledger transaction (once)
+    /// 3. Off-ramps disbursed net pay to employees' Unit accounts (if enabled)
+    pub async fn mirror(&self, tenant_id: Uuid, payroll_uuid: &str) -> Result<PayrollRun, Box<dyn Error>> {
+        let domain = self.tenant_domain(tenant_id).await?;
+        let settings = PayrollSettings::load(&self.db, tenant_id).await?;
+
+        // 1. Snapshot (first time only: retries replay exactly what was decided)
+        let owner = sqlx::query!("SELECT tenant_id FROM payroll_runs WHERE payroll_uuid = $1", payroll_uuid)
+            .fetch_optional(&self.db)
+            .await?;
+        match owner {
+            Some(row) if row.tenant_id != tenant_id => {
+                return Err(format!("Payroll {} belongs to another tenant", payroll_uuid).into());
+            }
+            Some(_) => {}
+            None => self.snapshot(tenant_id, payroll_uuid, &settings).await?,
+        }
+
+        // 2. Ledger
+        let run = self.run(tenant_id, payroll_uuid).await?;
+        if run.status != "recorded" {
+            self.record(tenant_id, &run, &domain, settings.treasury_account_id.as_deref()).await?;
+        }
+
+        // 3. Off-ramp
+        if settings.instant_offramp && run.disbursed_cents > 0 {
+            if let Some(treasury) = &settings.treasury_account_id {
+                self.offramp(tenant_id, payroll_uuid, treasury, run.check_date).await?;
+            }
+        }
+
+        self.run(tenant_id, payroll_uuid).await
+    }
+
+    async fn snapshot(&self, tenant_id: Uuid, payroll_uuid: &str, settings: &PayrollSettings) -> Result<(), Box<dyn Error>> {
+        let company_uuid = sqlx::query!("SELECT gusto_company_uuid FROM tenants WHERE id = $1", tenant_id)
+            .fetch_one(&self.db)
+            .await?
+            .gusto_company_uuid
+            .ok_or("Tenant is not linked to a Gusto company")?;
+
+        let payroll: GustoPayroll = self.gusto.get_payroll(&company_uuid, payroll_uuid).await?;
+        if !payroll.processed {
+            return Err(format!("Payroll {} is not processed yet", payroll_uuid).into());
+        }
+        if payroll.uuid != payroll_uuid {
+            return Err(format!("Gusto returned payroll {} for {}", payroll.uuid, payroll_uuid).into());
+        }
+
+        let disburse = settings.disburse_net_pay && settings.treasury_account_id.is_some();
+        let mut payslips = Vec::new();
+        for comp in &payroll.employee_compensations {
+            let account = self.employee_account(tenant_id, &comp.employee_uuid).await?;
+            payslips.push(Payslip::from_compensation(comp, account, disburse)?);
+        }
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO payroll_runs
+                (payroll_uuid, tenant_id, company_uuid, pay_period_start, pay_period_end, check_date,
+                 employee_count, gross_cents, net_cents, disbursed_cents)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (payroll_uuid) DO NOTHING
+            "#,
+            payroll_uuid,
+            tenant_id,
+            company_uuid,
+            payroll.pay_period.start_date,
+            payroll.pay_period.end_date,
+            payroll.check_date,
+            payslips.len() as i32,
+            payslips.iter().map(|p| p.gross_cents).sum::<i64>(),
+            payslips.iter().map(|p| p.net_cents).sum::<i64>(),
+            payslips.iter().map(|p| p.disbursed_cents).sum::<i64>()
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for p in &payslips {
+            sqlx::query!(
+                r#"
+                INSERT INTO payroll_payslips
+                    (id, payroll_uuid, tenant_id, employee_uuid, onchain_account_id, gross_cents, net_cents,
+                     employee_taxes_cents, employer_taxes_cents, benefit_deductions_cents,
+                     other_deductions_cents, disbursed_cents)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+                ON CONFLICT (payroll_uuid, employee_uuid) DO NOTHING
+                "#,
+                Uuid::new_v4(),
+                payroll_uuid,
+                tenant_id,
+                p.employee_uuid,
+                p.account,
+                p.gross_cents,
+                p.net_cents,
+                p.employee_taxes_cents,
+                p.employer_taxes_cents,
+                p.benefit_deductions_cents,
+                p.other_deductions_cents,
+                p.disbursed_cents
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// The ONE ledger transaction for the payroll
+    async fn record(&self, tenant_id: Uuid, run: &PayrollRun, domain: &str, treasury: Option<&str>) -> Result<(), Box<dyn Error>> {
+        let payslips = self.payslips(tenant_id, &run.payroll_uuid).await?;
+        let store_definition = format!("payslip#{}", domain);
+        let prefix = payroll_key(&run.payroll_uuid);
+
+        // Landed already (we crashed before writing it down)?
+        let landed = match payslips.first() {
+            Some(first) => self
+                .iroha
+                .has_store_key(&first.account, &store_definition, &format!("{}_net", prefix))
+                .await
+                .map_err(|e| e.to_string())?,
+            None => true, // Nobody was paid: nothing to record
+        };
+
+        let tx_hash = if landed {
+            format!("receipt:{}", prefix)
+        } else {
+            let dates = PayrollDates {
+                check_date: run.check_date,
+                pay_period_start: run.pay_period_start,
+                pay_period_end: run.pay_period_end,
+            };
+            let records: Vec<StoreRecord> = payslips.iter().map(|p| p.store_record(&prefix, &dates)).collect();
+
+            let transfers: Vec<(&str, &str, i64)> = match treasury {
+                Some(treasury) => payslips
+                    .iter()
+                    .filter(|p| p.disbursed_cents > 0)
+                    .map(|p| (treasury, p.account.as_str(), p.disbursed_cents))
+                    .collect(),
+                None if run.disbursed_cents > 0 => return Err("Payroll disburses net pay but no treasury is configured".into()),
+                None => Vec::new(),
+            };
+
+            self.iroha
+                .record_with_transfers(&store_definition, &records, &self.config.usd_asset_id, &transfers)
+                .await
+                .map_err(|e| e.to_string())?
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE payroll_runs
+            SET status = 'recorded', tx_hash = COALESCE(tx_hash, $3), recorded_at = NOW()
+            WHERE payroll_uuid = $1 AND tenant_id = $2
+            "#,
+            run.payroll_uuid,
+            tenant_id,
+            tx_hash
+        )
+        .execute(&self.db)
+        .await?;
+
+        println!(
+            "💸 Payroll {} on chain: {} payslip(s), ${:.2} disbursed.",
+            run.payroll_uuid, payslips.len(), run.disbursed_cents as f64 / 100.0
+        );
+        Ok(())
+    }
+
+    /// Book payment tenant Unit account -> employee Unit account, per disbursed
+    /// payslip. Employees (or tenants) without a linked Unit account keep the
+    /// `usd` on chain.
+    async fn offramp(&self, tenant_id: Uuid, payroll_uuid: &str, treasury: &str, check_date: NaiveDate) -> Result<(), Box<dyn Error>> {
+        let from = sqlx::query!(
+            "SELECT unit_account_id FROM fiat_account_links WHERE tenant_id = $1 AND onchain_account_id = $2",
+            tenant_id,
+            treasury
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        let from = match from {
+            Some(row) => row.unit_account_id,
+            None => return Ok(()), // Treasury isn't backed by a Unit account we can pay from
+        };
+
+        let pending = sqlx::query!(
+            r#"
+            SELECT p.employee_uuid, p.disbursed_cents, l.unit_account_id AS "to_unit_account"
+            FROM payroll_payslips p
+            JOIN fiat_account_links l
+              ON l.onchain_account_id = p.onchain_account_id AND l.owner_type = 'user' AND l.tenant_id = p.tenant_id
+            WHERE p.payroll_uuid = $1 AND p.tenant_id = $2 AND p.disbursed_cents > 0
+              AND (p.offramp_status IS NULL OR p.offramp_status IN ('submitting', 'failed'))
+            "#,
+            payroll_uuid,
+            tenant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut failed = 0;
+        for p in &pending {
+            // Mark first, so the book payment's webhooks wait for us (see `payroll_prefunded`).
+            // Only a payment Unit rejected starts a new attempt (and idempotency key).
+            let attempt = sqlx::query!(
+                r#"
+                UPDATE payroll_payslips
+                SET offramp_attempt = offramp_attempt + CASE WHEN offramp_status = 'failed' THEN 1 ELSE 0 END,
+                    offramp_status = 'submitting', offramp_from_unit_account = $4, offramp_to_unit_account = $5
+                WHERE payroll_uuid = $1 AND tenant_id = $2 AND employee_uuid = $3
+                RETURNING offramp_attempt
+                "#,
+                payroll_uuid,
+                tenant_id,
+                p.employee_uuid,
+                from,
+                p.to_unit_account
+            )
+            .fetch_one(&self.db)
+            .await?
+            .offramp_attempt;
+            let idempotency_key = match attempt {
+                0 => format!("payroll-{}-{}", payroll_uuid, p.employee_uuid),
+                n => format!("payroll-{}-{}-retry-{}", payroll_uuid, p.employee_uuid, n),
+            };
+
+            let result = self
+                .unit
+                .create_book_payment(
+                    &from,
+                    &p.to_unit_account,
+                    p.disbursed_cents as u64,
+                    &format!("Payroll {}", check_date),
+                    &idempotency_key,
+                )
+                .await;
+
+            let (status, payment_id, error) = match result {
+                Ok(payment_id) => ("sent", Some(payment_id), None),
+                // Unit definitively refused: nothing moved, safe to try again later
+                Err(e) if e.is_definitive() => {
+                    eprintln!("❌ Payroll off-ramp for employee {} rejected: {}", p.employee_uuid, e);
+                    failed += 1;
+                    ("failed", None, Some(e.to_string()))
+                }
+                // 5xx, 429, 409, timeout etc: the payment may exist. Stay 'submitting' so
+                // its webhooks wait, and the next run re-sends it with the same idempotency key.
+                Err(e) => {
+                    eprintln!("❌ Payroll off-ramp for employee {} unconfirmed: {}", p.employee_uuid, e);
+                    failed += 1;
+                    ("submitting", None, Some(e.to_string()))
+                }
+            };
+            sqlx::query!(
+                r#"
+                UPDATE payroll_payslips
+                SET offramp_status = $4, offramp_payment_id = COALESCE($5, offramp_payment_id), offramp_error = $6
+                WHERE payroll_uuid = $1 AND tenant_id = $2 AND employee_uuid = $3
+                "#,
+                payroll_uuid,
+                tenant_id,
+                p.employee_uuid,
+                status,
+                payment_id,
+                error
+            )
+            .execute(&self.db)
+            .await?;
+        }
+
+        if failed > 0 {
+            return Err(format!("{} payroll off-ramp payment(s) not sent", failed).into());
+        }
+        Ok(())
+    }
+
+    async fn run(&self, tenant_id: Uuid, payroll_uuid: &str) -> Result<PayrollRun, Box<dyn Error>> {
+        let run = sqlx::query_as!(
+            PayrollRun,
+            r#"
+            SELECT payroll_uuid, pay_period_start, pay_period_end, check_date, employee_count,
+                   gross_cents, net_cents, disbursed_cents, status, tx_hash, recorded_at
+            FROM payroll_runs WHERE payroll_uuid = $1 AND tenant_id = $2
+            "#,
+            payroll_uuid,
+            tenant_id
+        )
+        .fetch_one(&self.db)
+        .await?;
+        Ok(run)
+    }
+
+    async fn payslips(&self, tenant_id: Uuid, payroll_uuid: &str) -> Result<Vec<Payslip>, Box<dyn Error>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT employee_uuid, onchain_account_id, gross_cents, net_cents, employee_taxes_cents,
+                   employer_taxes_cents, benefit_deductions_cents, other_deductions_cents, disbursed_cents
+            FROM payroll_payslips WHERE payroll_uuid = $1 AND tenant_id = $2
+            ORDER BY employee_uuid
+            "#,
+            payroll_uuid,
+            tenant_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| Payslip {
+                employee_uuid: r.employee_uuid,
+                account: r.onchain_account_id,
+                gross_cents: r.gross_cents,
+                net_cents: r.net_cents,
+                employee_taxes_cents: r.employee_taxes_cents,
+                employer_taxes_cents: r.employer_taxes_cents,
+                benefit_deductions_cents: r.benefit_deductions_cents,
+                other_deductions_cents: r.other_deductions_cents,
+                disbursed_cents: r.disbursed_cents,
+            })
+            .collect())
+    }
+
+    /// The employee's account, registering it first if the sync hasn't yet
+    async fn employee_account(&self, tenant_id: Uuid, employee_uuid: &str) -> Result<String, Box<dyn Error>> {
+        if let Some(account) = self.known_employee_account(tenant_id, employee_uuid).await? {
+            return Ok(account);
+        }
+        self.employees.sync_employee(tenant_id, employee_uuid).await?;
+        self.known_employee_account(tenant_id, employee_uuid)
+            .await?
+            .ok_or_else(|| format!("Employee {} has no on-chain account", employee_uuid).into())
+    }
+
+    async fn known_employee_account(&self, tenant_id: Uuid, employee_uuid: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT onchain_account_id FROM gusto_employee_accounts
+            WHERE employee_uuid = $1 AND tenant_id = $2 AND status <> 'pending'
+            "#,
+            employee_uuid,
+            tenant_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(row.map(|r| r.onchain_account_id))
+    }
+
+    async fn tenant_domain(&self, tenant_id: Uuid) -> Result<String, Box<dyn Error>> {
+        sqlx::query!("SELECT iroha_domain FROM tenants WHERE id = $1", tenant_id)
+            .fetch_one(&self.db)
+            .await?
+            .iroha_domain
+            .ok_or_else(|| "Tenant has no ledger domain".into())
+    }
+}
+
+/// Payroll uuid -> metadata key prefix ("payroll_1a2b3c4d...")
+fn payroll_key(payroll_uuid: &str) -> String {
+    let id: String = payroll_uuid.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    format!("payroll_{}", id)
+}
+
+/// Book payments that move payroll fiat after the fact. The ledger side is the
+/// payroll transaction (treasury -> employee), so neither leg mints or burns:
+/// returns its hash. While an off-ramp is being submitted on this account we
+/// can't tell yet, so the event waits and is retried.
+pub async fn payroll_prefunded(
+    db: &PgPool,
+    unit_account_id: &str,
+    unit_payment_id: &Option<String>,
+) -> Result<Option<String>, Box<dyn Error>> {
+    if let Some(unit_payment_id) = unit_payment_id {
+        let row = sqlx::query!(
+            r#"
+            SELECT r.tx_hash FROM payroll_payslips p
+            JOIN payroll_runs r ON r.payroll_uuid = p.payroll_uuid
+            WHERE p.offramp_payment_id = $1
+            "#,
+            unit_payment_id
+        )
+        .fetch_optional(db)
+        .await?;
+        if let Some(row) = row {
+            return Ok(row.tx_hash);
+        }
+    }
+
+    let submitting = sqlx::query!(
+        r#"
+        SELECT payroll_uuid, employee_uuid FROM payroll_payslips
+        WHERE offramp_status = 'submitting'
+          AND (offramp_from_unit_account = $1 OR offramp_to_unit_account = $1)
+        LIMIT 1
+        "#,
+        unit_account_id
+    )
+    .fetch_optional(db)
+    .await?;
+    if let Some(row) = submitting {
+        return Err(format!(
+            "Payroll {} off-ramp for employee {} is still being submitted",
+            row.payroll_uuid, row.employee_uuid
+        )
+        .into());
+    }
+
+    Ok(None)
+}
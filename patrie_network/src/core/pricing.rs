@@ -0,0 +1,396 @@
+use crate::core::metering::{UsageMetric, UsageRate};
+use crate::core::tiers::ServiceTier;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::error::Error;
+use uuid::Uuid;
+
+/// Every price we charge is looked up by one of these keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceKey {
+    BasePlatformFee,
+    AdminFeeHealth,
+    #[serde(rename = "admin_fee_401k")]
+    AdminFee401k,
+    AdminFeeCrime,
+    BridgeExitFee,
+}
+
+impl PriceKey {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriceKey::BasePlatformFee => "base_platform_fee",
+            PriceKey::AdminFeeHealth => "admin_fee_health",
+            PriceKey::AdminFee401k => "admin_fee_401k",
+            PriceKey::AdminFeeCrime => "admin_fee_crime",
+            PriceKey::BridgeExitFee => "bridge_exit_fee",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value {
+            "base_platform_fee" => Ok(PriceKey::BasePlatformFee),
+            "admin_fee_health" => Ok(PriceKey::AdminFeeHealth),
+            "admin_fee_401k" => Ok(PriceKey::AdminFee401k),
+            "admin_fee_crime" => Ok(PriceKey::AdminFeeCrime),
+            "bridge_exit_fee" => Ok(PriceKey::BridgeExitFee),
+            other => Err(format!("Unknown price key: {}", other).into()),
+        }
+    }
+}
+
+/// A single price inside a book. `tier = None` applies to every tier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceEntry {
+    pub price_key: PriceKey,
+    pub tier: Option<ServiceTier>,
+    pub amount_cents: i64,
+}
+
+/// A versioned price list. Never edited in place: a price change is a new
+/// version with a new effective date.
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceBook {
+    pub id: Uuid,
+    pub version: i32,
+    pub effective_from: NaiveDate,
+    pub notes: Option<String>,
+    pub entries: Vec<PriceEntry>,
+    pub usage_rates: Vec<UsageRate>, // Included quotas + overage pricing
+}
+
+impl PriceBook {
+    /// The book in force on `date`
+    pub async fn effective_at(db: &PgPool, date: NaiveDate) -> Result<PriceBook, Box<dyn Error>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id FROM price_books
+            WHERE effective_from <= $1
+            ORDER BY effective_from DESC
+            LIMIT 1
+            "#,
+            date
+        )
+        .fetch_optional(db)
+        .await?
+        .ok_or_else(|| format!("No price book in force on {}", date))?;
+
+        Self::get(db, row.id).await
+    }
+
+    /// The book in force today
+    pub async fn current(db: &PgPool) -> Result<PriceBook, Box<dyn Error>> {
+        Self::effective_at(db, Utc::now().date_naive()).await
+    }
+
+    pub async fn get(db: &PgPool, id: Uuid) -> Result<PriceBook, Box<dyn Error>> {
+        let book = sqlx::query!(
+            "SELECT id, version, effective_from, notes FROM price_books WHERE id = $1",
+            id
+        )
+        .fetch_one(db)
+        .await?;
+
+        let rows = sqlx::query!(
+            "SELECT price_key, tier, amount_cents FROM price_book_entries WHERE price_book_id = $1",
+            id
+        )
+        .fetch_all(db)
+        .await?;
+
+        let mut entries = Vec::with_capacity(rows.len());
+        for r in rows {
+            entries.push(PriceEntry {
+                price_key: PriceKey::parse(&r.price_key)?,
+                tier: r.tier.as_deref().map(ServiceTier::parse).transpose()?,
+                amount_cents: r.amount_cents,
+            });
+        }
+
+        let rate_rows = sqlx::query!(
+            r#"
+            SELECT metric, tier, included_quantity, overage_block_size, overage_cents_per_block
+            FROM price_book_usage_rates
+            WHERE price_book_id = $1
+            "#,
+            id
+        )
+        .fetch_all(db)
+        .await?;
+
+        let mut usage_rates = Vec::with_capacity(rate_rows.len());
+        for r in rate_rows {
+            usage_rates.push(UsageRate {
+                metric: UsageMetric::parse(&r.metric)?,
+                tier: r.tier.as_deref().map(ServiceTier::parse).transpose()?,
+                included_quantity: r.included_quantity,
+                overage_block_size: r.overage_block_size,
+                overage_cents_per_block: r.overage_cents_per_block,
+            });
+        }
+
+        Ok(PriceBook {
+            id: book.id,
+            version: book.version,
+            effective_from: book.effective_from,
+            notes: book.notes,
+            entries,
+            usage_rates,
+        })
+    }
+
+    /// Publishes a new version. Takes effect on `effective_from`; invoices for
+    /// earlier periods keep using the book that was in force back then.
+    pub async fn publish(
+        db: &PgPool,
+        effective_from: NaiveDate,
+        notes: Option<String>,
+        entries: &[PriceEntry],
+        usage_rates: &[UsageRate],
+    ) -> Result<PriceBook, Box<dyn Error>> {
+        let id = Uuid::new_v4();
+        let mut tx = db.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO price_books (id, version, effective_from, notes)
+            VALUES ($1, (SELECT COALESCE(MAX(version), 0) + 1 FROM price_books), $2, $3)
+            "#,
+            id,
+            effective_from,
+            notes
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for entry in entries {
+            sqlx::query!(
+                r#"
+                INSERT INTO price_book_entries (price_book_id, price_key, tier, amount_cents)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                id,
+                entry.price_key.as_str(),
+                entry.tier.map(|t| t.as_str()),
+                entry.amount_cents
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for rate in usage_rates {
+            sqlx::query!(
+                r#"
+                INSERT INTO price_book_usage_rates
+                (price_book_id, metric, tier, included_quantity, overage_block_size, overage_cents_per_block)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+                id,
+                rate.metric.as_str(),
+                rate.tier.map(|t| t.as_str()),
+                rate.included_quantity,
+                rate.overage_block_size,
+                rate.overage_cents_per_block
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        println!("💲 Price book published. Effective from {}.", effective_from);
+
+        Self::get(db, id).await
+    }
+
+    /// Tier-specific price first, then the all-tiers price
+    pub fn price_cents(&self, key: PriceKey, tier: ServiceTier) -> Result<i64, Box<dyn Error>> {
+        self.entries
+            .iter()
+            .find(|e| e.price_key == key && e.tier == Some(tier))
+            .or_else(|| self.entries.iter().find(|e| e.price_key == key && e.tier.is_none()))
+            .map(|e| e.amount_cents)
+            .ok_or_else(|| format!("Price book v{} has no price for {}", self.version, key.as_str()).into())
+    }
+
+    pub fn price_usd(&self, key: PriceKey, tier: ServiceTier) -> Result<f64, Box<dyn Error>> {
+        Ok(self.price_cents(key, tier)? as f64 / 100.0)
+    }
+
+    /// Tier-specific quota first, then the all-tiers quota. None = not metered.
+    pub fn usage_rate(&self, metric: UsageMetric, tier: ServiceTier) -> Option<&UsageRate> {
+        self.usage_rates
+            .iter()
+            .find(|r| r.metric == metric && r.tier == Some(tier))
+            .or_else(|| self.usage_rates.iter().find(|r| r.metric == metric && r.tier.is_none()))
+    }
+}
+
+/// A negotiated discount or coupon on a tenant's retained fees
+#[derive(Debug, Clone, Serialize)]
+pub struct Discount {
+    pub id: Uuid,
+    pub code: String,
+    pub description: String,
+    pub percent_off_bps: Option<i32>,  // 1500 = 15%
+    pub amount_off_cents: Option<i64>,
+    pub applies_to: Option<PriceKey>, // None = all retained fees
+}
+
+impl Discount {
+    /// How much to take off a base amount (never more than the base itself)
+    pub fn amount_for(&self, base_cents: i64) -> i64 {
+        let percent = self
+            .percent_off_bps
+            .map(|bps| base_cents * bps as i64 / 10_000)
+            .unwrap_or(0);
+        let fixed = self.amount_off_cents.unwrap_or(0);
+        (percent + fixed).min(base_cents)
+    }
+}
+
+/// Everything needed to price ONE tenant for ONE period:
+/// the price book in force, their negotiated overrides and active discounts.
+pub struct TenantPricing {
+    pub book: PriceBook,
+    pub tier: ServiceTier,
+    pub discounts: Vec<Discount>,
+    overrides: HashMap<PriceKey, i64>,
+}
+
+impl TenantPricing {
+    pub async fn load(
+        db: &PgPool,
+        tenant_id: Uuid,
+        tier: ServiceTier,
+        date: NaiveDate,
+    ) -> Result<TenantPricing, Box<dyn Error>> {
+        let book = PriceBook::effective_at(db, date).await?;
+
+        // Latest override in force on the date wins
+        let override_rows = sqlx::query!(
+            r#"
+            SELECT price_key, amount_cents FROM tenant_price_overrides
+            WHERE tenant_id = $1
+              AND effective_from <= $2
+              AND (effective_until IS NULL OR effective_until > $2)
+            ORDER BY effective_from ASC
+            "#,
+            tenant_id,
+            date
+        )
+        .fetch_all(db)
+        .await?;
+
+        let mut overrides = HashMap::new();
+        for r in override_rows {
+            overrides.insert(PriceKey::parse(&r.price_key)?, r.amount_cents);
+        }
+
+        let discount_rows = sqlx::query!(
+            r#"
+            SELECT id, code, description, percent_off_bps, amount_off_cents, applies_to
+            FROM tenant_discounts
+            WHERE tenant_id = $1
+              AND valid_from <= $2
+              AND (valid_until IS NULL OR valid_until > $2)
+            ORDER BY created_at
+            "#,
+            tenant_id,
+            date
+        )
+        .fetch_all(db)
+        .await?;
+
+        let mut discounts = Vec::with_capacity(discount_rows.len());
+        for r in discount_rows {
+            discounts.push(Discount {
+                id: r.id,
+                code: r.code,
+                description: r.description,
+                percent_off_bps: r.percent_off_bps,
+                amount_off_cents: r.amount_off_cents,
+                applies_to: r.applies_to.as_deref().map(PriceKey::parse).transpose()?,
+            });
+        }
+
+        Ok(TenantPricing { book, tier, discounts, overrides })
+    }
+
+    /// Negotiated price if there is one, otherwise the price book
+    pub fn price_cents(&self, key: PriceKey) -> Result<i64, Box<dyn Error>> {
+        match self.overrides.get(&key) {
+            Some(cents) => Ok(*cents),
+            None => self.book.price_cents(key, self.tier),
+        }
+    }
+}
+
+/// Records a negotiated price for a tenant
+pub async fn add_price_override(
+    db: &PgPool,
+    tenant_id: Uuid,
+    key: PriceKey,
+    amount_cents: i64,
+    effective_from: NaiveDate,
+    effective_until: Option<NaiveDate>,
+) -> Result<Uuid, Box<dyn Error>> {
+    let id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+        INSERT INTO tenant_price_overrides
+        (id, tenant_id, price_key, amount_cents, effective_from, effective_until)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        id,
+        tenant_id,
+        key.as_str(),
+        amount_cents,
+        effective_from,
+        effective_until
+    )
+    .execute(db)
+    .await?;
+    Ok(id)
+}
+
+/// Grants a discount / applies a coupon to a tenant
+#[allow(clippy::too_many_arguments)]
+pub async fn add_discount(
+    db: &PgPool,
+    tenant_id: Uuid,
+    code: &str,
+    description: &str,
+    percent_off_bps: Option<i32>,
+    amount_off_cents: Option<i64>,
+    applies_to: Option<PriceKey>,
+    valid_from: NaiveDate,
+    valid_until: Option<NaiveDate>,
+) -> Result<Uuid, Box<dyn Error>> {
+    if percent_off_bps.is_none() && amount_off_cents.is_none() {
+        return Err("A discount needs a percentage or a fixed amount".into());
+    }
+
+    let id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+        INSERT INTO tenant_discounts
+        (id, tenant_id, code, description, percent_off_bps, amount_off_cents, applies_to, valid_from, valid_until)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        id,
+        tenant_id,
+        code,
+        description,
+        percent_off_bps,
+        amount_off_cents,
+        applies_to.map(|k| k.as_str()),
+        valid_from,
+        valid_until
+    )
+    .execute(db)
+    .await?;
+    Ok(id)
+}
@@ -0,0 +1,428 @@
+use crate::core::invoice::{BillingPeriod, LineCategory};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use uuid::Uuid;
+
+// --- REVENUE REPORTING ---
+// Everything here is computed from what actually happened: finalized invoices
+// (net of credit notes) and the commission transfers indexed from the ledger.
+// `ServiceTier::estimated_profit` stays a sales estimate only.
+
+/// Where on-chain revenue lands
+#[derive(Debug, Clone)]
+pub struct ReportingConfig {
+    pub commission_account_id: String, // Receives insurance commissions (InsuranceBroker)
+    pub bridge_fee_account_id: String, // Receives bridge exit fees
+    pub usd_asset_id: String,          // Only USD transfers count as revenue
+}
+
+impl ReportingConfig {
+    /// Reads REVENUE_COMMISSION_ACCOUNT_ID, REVENUE_BRIDGE_FEE_ACCOUNT_ID and REVENUE_USD_ASSET_ID
+    pub fn from_env() -> Self {
+        let var = |key: &str, default: &str| std::env::var(key).unwrap_or_else(|_| default.to_string());
+        Self {
+            commission_account_id: var("REVENUE_COMMISSION_ACCOUNT_ID", "admin@my_ecosystem"),
+            bridge_fee_account_id: var("REVENUE_BRIDGE_FEE_ACCOUNT_ID", "bridge_fees@my_ecosystem"),
+            usd_asset_id: var("REVENUE_USD_ASSET_ID", "usd#bank"),
+        }
+    }
+}
+
+/// Revenue split for one tenant (or the whole platform) in one period, in cents
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RevenueBreakdown {
+    // Retained (ours)
+    pub platform_fees_cents: i64,
+    pub admin_fees_cents: i64,
+    pub discounts_cents: i64,
+    pub usage_overage_cents: i64,
+    pub adjustments_cents: i64,
+    pub commitment_true_up_cents: i64,
+    pub insurance_commissions_cents: i64,
+    pub bridge_fees_cents: i64,
+    // Forwarded to carriers / plans
+    pub pass_through_cents: i64,
+}
+
+impl RevenueBreakdown {
+    /// Recurring subscription revenue (what MRR is built from)
+    pub fn recurring_cents(&self) -> i64 {
+        self.platform_fees_cents + self.admin_fees_cents + self.discounts_cents + self.pass_through_cents
+    }
+
+    /// What we keep
+    pub fn retained_cents(&self) -> i64 {
+        self.platform_fees_cents
+            + self.admin_fees_cents
+            + self.discounts_cents
+            + self.usage_overage_cents
+            + self.adjustments_cents
+            + self.commitment_true_up_cents
+            + self.insurance_commissions_cents
+            + self.bridge_fees_cents
+    }
+
+    /// Everything that flowed through us
+    pub fn gross_cents(&self) -> i64 {
+        self.retained_cents() + self.pass_through_cents
+    }
+
+    fn add_line(&mut self, category: LineCategory, cents: i64) {
+        match category {
+            LineCategory::PlatformFee => self.platform_fees_cents += cents,
+            LineCategory::PassThroughPremium => self.pass_through_cents += cents,
+            LineCategory::AdminFee => self.admin_fees_cents += cents,
+            LineCategory::Discount => self.discounts_cents += cents,
+            LineCategory::Adjustment => self.adjustments_cents += cents,
+            LineCategory::UsageOverage => self.usage_overage_cents += cents,
+            LineCategory::CommitmentTrueUp => self.commitment_true_up_cents += cents,
+            // Paying with credit is not revenue, the credit note already reduced it
+            LineCategory::AccountCredit => {}
+        }
+    }
+
+    fn merge(&mut self, other: &RevenueBreakdown) {
+        self.platform_fees_cents += other.platform_fees_cents;
+        self.admin_fees_cents += other.admin_fees_cents;
+        self.discounts_cents += other.discounts_cents;
+        self.usage_overage_cents += other.usage_overage_cents;
+        self.adjustments_cents += other.adjustments_cents;
+        self.commitment_true_up_cents += other.commitment_true_up_cents;
+        self.insurance_commissions_cents += other.insurance_commissions_cents;
+        self.bridge_fees_cents += other.bridge_fees_cents;
+        self.pass_through_cents += other.pass_through_cents;
+    }
+}
+
+/// One tenant's revenue for a period
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantRevenue {
+    pub tenant_id: Uuid,
+    pub tier: Option<String>,
+    pub invoiced: bool,
+    pub revenue: RevenueBreakdown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TenantMargin {
+    pub tenant_id: Uuid,
+    pub tier: Option<String>,
+    pub gross_cents: i64,
+    pub pass_through_cents: i64,
+    pub retained_cents: i64,
+    pub margin_pct: f64, // retained / gross
+}
+
+#[derive(Debug, Serialize)]
+pub struct TierMix {
+    pub tier: String,
+    pub tenants: i64,
+    pub mrr_cents: i64,
+}
+
+/// Platform-wide numbers for one period
+#[derive(Debug, Serialize)]
+pub struct RevenueReport {
+    pub period: String,
+    pub mrr_cents: i64,
+    pub retained_mrr_cents: i64,
+    pub arr_cents: i64,
+    pub collected_cents: i64, // Invoices actually paid
+    pub active_tenants: i64,
+    pub new_tenants: i64,
+    pub churned_tenants: i64,
+    pub churn_rate_pct: f64,
+    pub tier_mix: Vec<TierMix>,
+    pub revenue: RevenueBreakdown,
+    pub retained_cents: i64,
+    pub gross_cents: i64,
+}
+
+/// One month of the trend, with the change against the month before
+#[derive(Debug, Serialize)]
+pub struct TrendPoint {
+    pub period: String,
+    pub mrr_cents: i64,
+    pub retained_cents: i64,
+    pub active_tenants: i64,
+    pub churn_rate_pct: f64,
+    pub mrr_change_pct: Option<f64>,
+    pub retained_change_pct: Option<f64>,
+}
+
+pub struct RevenueReporter {
+    db: PgPool,
+    config: ReportingConfig,
+}
+
+impl RevenueReporter {
+    pub fn new(db: PgPool, config: ReportingConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// PLATFORM REPORT
+    /// 1. MRR / ARR from the period's invoices
+    /// 2. New & churned tenants against the previous period
+    /// 3. Tier mix and retained vs. pass-through revenue
+    pub async fn report(&self, period: BillingPeriod) -> Result<RevenueReport, Box<dyn Error>> {
+        let current = self.tenant_revenue(period).await?;
+        let previous = self.tenant_revenue(period.previous()).await?;
+
+        let active: HashSet<Uuid> = current.iter().filter(|t| t.invoiced).map(|t| t.tenant_id).collect();
+        let was_active: HashSet<Uuid> = previous.iter().filter(|t| t.invoiced).map(|t| t.tenant_id).collect();
+        let churned = was_active.difference(&active).count() as i64;
+        let new = active.difference(&was_active).count() as i64;
+
+        let mut revenue = RevenueBreakdown::default();
+        let mut tiers: BTreeMap<String, TierMix> = BTreeMap::new();
+        for t in &current {
+            revenue.merge(&t.revenue);
+            if t.invoiced {
+                let tier = t.tier.clone().unwrap_or_else(|| "unknown".to_string());
+                let mix = tiers.entry(tier.clone()).or_insert(TierMix { tier, tenants: 0, mrr_cents: 0 });
+                mix.tenants += 1;
+                mix.mrr_cents += t.revenue.recurring_cents();
+            }
+        }
+
+        let collected_cents = sqlx::query!(
+            r#"
+            SELECT COALESCE(SUM(total_cents), 0)::BIGINT AS "collected!"
+            FROM invoices
+            WHERE period_start = $1 AND status = 'paid'
+            "#,
+            period.start
+        )
+        .fetch_one(&self.db)
+        .await?
+        .collected;
+
+        let mrr_cents = revenue.recurring_cents();
+        let retained_mrr_cents = mrr_cents - revenue.pass_through_cents;
+
+        Ok(RevenueReport {
+            period: period.to_string(),
+            mrr_cents,
+            retained_mrr_cents,
+            arr_cents: mrr_cents * 12,
+            collected_cents,
+            active_tenants: active.len() as i64,
+            new_tenants: new,
+            churned_tenants: churned,
+            churn_rate_pct: percent(churned, was_active.len() as i64),
+            tier_mix: tiers.into_values().collect(),
+            retained_cents: revenue.retained_cents(),
+            gross_cents: revenue.gross_cents(),
+            revenue,
+        })
+    }
+
+    /// Stores the finished month's report, so month-end numbers stay fixed
+    /// even if credit notes are issued against that month later
+    pub async fn generate_monthly(&self, period: BillingPeriod) -> Result<RevenueReport, Box<dyn Error>> {
+        let report = self.report(period).await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO revenue_reports (period_start, report)
+            VALUES ($1, $2)
+            ON CONFLICT (period_start) DO UPDATE SET report = EXCLUDED.report, generated_at = NOW()
+            "#,
+            period.start,
+            serde_json::to_value(&report)?
+        )
+        .execute(&self.db)
+        .await?;
+
+        println!("📊 Revenue report for {} stored (MRR ${:.2}).", period, report.mrr_cents as f64 / 100.0);
+        Ok(report)
+    }
+
+    /// Margin per tenant, best first
+    pub async fn tenant_margins(&self, period: BillingPeriod) -> Result<Vec<TenantMargin>, Box<dyn Error>> {
+        let mut margins: Vec<TenantMargin> = self
+            .tenant_revenue(period)
+            .await?
+            .into_iter()
+            .map(|t| TenantMargin {
+                tenant_id: t.tenant_id,
+                tier: t.tier,
+                gross_cents: t.revenue.gross_cents(),
+                pass_through_cents: t.revenue.pass_through_cents,
+                retained_cents: t.revenue.retained_cents(),
+                margin_pct: percent(t.revenue.retained_cents(), t.revenue.gross_cents()),
+            })
+            .collect();
+
+        margins.sort_by(|a, b| b.retained_cents.cmp(&a.retained_cents));
+        Ok(margins)
+    }
+
+    /// Month-over-month trend for the `months` periods ending with `end` (oldest first)
+    pub async fn trend(&self, end: BillingPeriod, months: u32) -> Result<Vec<TrendPoint>, Box<dyn Error>> {
+        let mut periods = vec![end];
+        for _ in 1..months.max(1) {
+            let earliest = *periods.last().unwrap();
+            periods.push(earliest.previous());
+        }
+        periods.reverse();
+
+        let mut points: Vec<TrendPoint> = Vec::with_capacity(periods.len());
+        for period in periods {
+            let report = self.report(period).await?;
+            let (mrr_change_pct, retained_change_pct) = match points.last() {
+                Some(prev) => (
+                    change(prev.mrr_cents, report.mrr_cents),
+                    change(prev.retained_cents, report.retained_cents),
+                ),
+                None => (None, None),
+            };
+
+            points.push(TrendPoint {
+                period: report.period,
+                mrr_cents: report.mrr_cents,
+                retained_cents: report.retained_cents,
+                active_tenants: report.active_tenants,
+                churn_rate_pct: report.churn_rate_pct,
+                mrr_change_pct,
+                retained_change_pct,
+            });
+        }
+        Ok(points)
+    }
+
+    /// Per-tenant revenue: invoice lines (net of credit notes) + on-chain commissions
+    async fn tenant_revenue(&self, period: BillingPeriod) -> Result<Vec<TenantRevenue>, Box<dyn Error>> {
+        let mut tenants: HashMap<Uuid, TenantRevenue> = HashMap::new();
+
+        // 1. Invoiced revenue, by category. A line billed upfront for several
+        //    months (contract cycles) counts 1/Nth in each month it pays for.
+        let lines = sqlx::query!(
+            r#"
+            SELECT
+                i.tenant_id,
+                i.tier,
+                li.category,
+                SUM((li.amount_cents - COALESCE(c.credited, 0)) / li.service_months)::BIGINT AS "net_cents!"
+            FROM invoices i
+            JOIN invoice_line_items li ON li.invoice_id = i.id
+            LEFT JOIN (
+                SELECT invoice_line_item_id, SUM(amount_cents) AS credited
+                FROM credit_note_lines
+                GROUP BY invoice_line_item_id
+            ) c ON c.invoice_line_item_id = li.id
+            WHERE i.status <> 'draft'
+              AND i.period_start <= $1
+              AND i.period_start + make_interval(months => li.service_months) > $1
+            GROUP BY i.tenant_id, i.tier, li.category
+            "#,
+            period.start
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        for row in lines {
+            let entry = tenants.entry(row.tenant_id).or_insert_with(|| TenantRevenue {
+                tenant_id: row.tenant_id,
+                tier: row.tier.clone(),
+                invoiced: true,
+                revenue: RevenueBreakdown::default(),
+            });
+            entry.revenue.add_line(LineCategory::parse(&row.category)?, row.net_cents);
+        }
+
+        // 2. On-chain commissions & bridge fees, attributed by the payer's domain
+        let from = period.start.and_hms_opt(0, 0, 0).unwrap();
+        let to = period.end().and_hms_opt(0, 0, 0).unwrap();
+        let transfers = sqlx::query!(
+            r#"
+            SELECT
+                tn.id AS tenant_id,
+                tn.iroha_domain,
+                ci.destination_account_id AS "destination!",
+                COALESCE(SUM(ci.quantity), 0) AS "amount!"
+            FROM chain_instructions ci
+            JOIN chain_transactions t ON t.tx_hash = ci.tx_hash
+            JOIN tenants tn ON tn.iroha_domain = split_part(ci.source_account_id, '@', 2)
+            WHERE ci.kind = 'Transfer'
+              AND ci.destination_account_id = ANY($1)
+              AND ci.asset_definition_id = $2
+              AND t.timestamp >= $3 AND t.timestamp < $4
+            GROUP BY tn.id, tn.iroha_domain, ci.destination_account_id
+            "#,
+            &[self.config.commission_account_id.clone(), self.config.bridge_fee_account_id.clone()],
+            self.config.usd_asset_id,
+            from,
+            to
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        for row in transfers {
+            let entry = tenants.entry(row.tenant_id).or_insert_with(|| TenantRevenue {
+                tenant_id: row.tenant_id,
+                tier: None,
+                invoiced: false,
+                revenue: RevenueBreakdown::default(),
+            });
+            let cents = (row.amount * 100.0).round() as i64;
+            if row.destination == self.config.commission_account_id {
+                entry.revenue.insurance_commissions_cents += cents;
+            } else {
+                entry.revenue.bridge_fees_cents += cents;
+            }
+        }
+
+        Ok(tenants.into_values().collect())
+    }
+}
+
+fn percent(part: i64, whole: i64) -> f64 {
+    if whole == 0 {
+        return 0.0;
+    }
+    (part as f64 / whole as f64 * 10000.0).round() / 100.0
+}
+
+fn change(before: i64, after: i64) -> Option<f64> {
+    if before == 0 {
+        return None;
+    }
+    Some(((after - before) as f64 / before.abs() as f64 * 10000.0).round() / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breakdown_keeps_pass_through_out_of_retained_revenue() {
+        let mut revenue = RevenueBreakdown::default();
+        revenue.add_line(LineCategory::PlatformFee, 9_900);
+        revenue.add_line(LineCategory::PassThroughPremium, 45_000);
+        revenue.add_line(LineCategory::AdminFee, 1_500);
+        revenue.add_line(LineCategory::Discount, -990);
+        revenue.add_line(LineCategory::UsageOverage, 250);
+        revenue.add_line(LineCategory::AccountCredit, -5_000); // Not revenue
+
+        assert_eq!(revenue.recurring_cents(), 9_900 + 45_000 + 1_500 - 990);
+        assert_eq!(revenue.retained_cents(), 9_900 + 1_500 - 990 + 250);
+        assert_eq!(revenue.gross_cents(), revenue.retained_cents() + 45_000);
+
+        let mut platform = RevenueBreakdown::default();
+        platform.merge(&revenue);
+        platform.merge(&revenue);
+        assert_eq!(platform.retained_cents(), 2 * revenue.retained_cents());
+    }
+
+    #[test]
+    fn percentages_round_to_two_places() {
+        assert_eq!(percent(1, 3), 33.33);
+        assert_eq!(percent(5, 0), 0.0);
+        assert_eq!(change(200, 250), Some(25.0));
+        assert_eq!(change(-100, -50), Some(50.0));
+        assert_eq!(change(0, 100), None); // No baseline, no growth rate
+    }
+}
@@ -0,0 +1,598 @@
+use crate::core::fiat_banking::{FiatAsset, TransactionFilter, UnitClient};
+use crate::ledger::client::IrohaClient;
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::{Signer, SigningKey};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- PROOF OF RESERVES ---
+// Every `usd` unit on the private network must be backed by a dollar in a
+// Unit deposit account. The reconciliation job compares the two totals,
+// explains any gap down to single deposits/mints, and publishes a signed
+// report whose Merkle root commits to every holder's balance.
+//
+// MERKLE TREE (so holders can check it without us):
+//   leaf = SHA256(0x00 || report_id (16 bytes) || len(account) as u32 BE || account || balance_cents as i64 BE)
+//   node = SHA256(0x01 || left || right)
+// Leaves are sorted by account id; an odd node at the end of a level moves up unchanged.
+
+/// Deposits younger than this may still have their webhook in flight
+const WEBHOOK_GRACE_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct ReserveConfig {
+    pub asset_definition_id: String,
+    pub window_days: i64,
+    signing_key: Option<SigningKey>,
+}
+
+impl ReserveConfig {
+    /// Reads RECONCILIATION_WINDOW_DAYS (35) and
+    /// RESERVES_SIGNING_KEY (hex ed25519 seed, 32 bytes)
+    pub fn from_env(asset: &FiatAsset) -> Result<Self, Box<dyn Error>> {
+        let signing_key = match std::env::var("RESERVES_SIGNING_KEY") {
+            Ok(seed) if !seed.is_empty() => {
+                let bytes: [u8; 32] = hex::decode(seed.trim())?
+                    .try_into()
+                    .map_err(|_| "RESERVES_SIGNING_KEY must be 32 bytes of hex")?;
+                Some(SigningKey::from_bytes(&bytes))
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            asset_definition_id: asset.usd_asset_id.clone(),
+            window_days: std::env::var("RECONCILIATION_WINDOW_DAYS").ok().and_then(|v| v.parse().ok()).unwrap_or(35),
+            signing_key,
+        })
+    }
+}
+
+/// What gets signed. Field order is fixed, so the JSON bytes are reproducible.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReserveAttestation {
+    pub report_id: Uuid,
+    pub asset_definition_id: String,
+    pub as_of: DateTime<Utc>,
+    pub onchain_supply_cents: i64,
+    pub reserve_cents: i64,
+    pub holder_count: i32,
+    pub merkle_root: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Discrepancy {
+    pub kind: String, // unminted_deposit | unburned_withdrawal | unbacked_mint | unbacked_burn | balance_drift | unit_unavailable
+    pub unit_account_id: Option<String>,
+    pub unit_transaction_id: Option<String>,
+    pub fiat_entry_id: Option<Uuid>,
+    pub amount_cents: i64,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReserveReport {
+    pub id: Uuid,
+    pub asset_definition_id: String,
+    pub as_of: DateTime<Utc>,
+    pub onchain_supply_cents: i64,
+    pub reserve_cents: i64,
+    pub difference_cents: i64,
+    pub status: String, // balanced | discrepancy
+    pub holder_count: i32,
+    pub merkle_root: String,
+    pub signed_payload: String, // The exact JSON bytes the signature covers
+    pub signature: String,
+    pub public_key: String,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+/// One step from a leaf up to the root
+#[derive(Debug, Serialize)]
+pub struct ProofStep {
+    pub sibling: String, // hex
+    pub side: String,    // "left" | "right": where the sibling sits
+}
+
+#[derive(Debug, Serialize)]
+pub struct InclusionProof {
+    pub report_id: Uuid,
+    pub account_id: String,
+    pub balance_cents: i64,
+    pub leaf: String,
+    pub steps: Vec<ProofStep>,
+    pub merkle_root: String,
+}
+
+pub struct ReserveReconciler {
+    db: PgPool,
+    unit: Arc<UnitClient>,
+    iroha: Arc<IrohaClient>,
+    config: ReserveConfig,
+}
+
+impl ReserveReconciler {
+    pub fn new(db: PgPool, unit: Arc<UnitClient>, iroha: Arc<IrohaClient>, config: ReserveConfig) -> Self {
+        Self { db, unit, iroha, config }
+    }
+
+    /// RECONCILE & PUBLISH
+    /// 1. On-chain: every holder of the asset (the supply is their sum)
+    /// 2. Unit: the balance of every linked deposit account
+    /// 3. Drill-down per account: deposits never minted, mints with no deposit, drift
+    /// 4. Merkle root over holders, signed attestation, stored with its leaves
+    pub async fn run(&self) -> Result<ReserveReport, Box<dyn Error>> {
+        let signing_key = self
+            .config
+            .signing_key
+            .as_ref()
+            .ok_or("RESERVES_SIGNING_KEY is not configured")?;
+        let asset = &self.config.asset_definition_id;
+        let report_id = Uuid::new_v4();
+        let as_of = Utc::now();
+
+        // 1. On-chain
+        let mut holders = self.iroha.asset_holders_cents(asset).await.map_err(|e| e.to_string())?;
+        holders.sort_by(|a, b| a.0.cmp(&b.0));
+        let onchain_supply_cents: i64 = holders.iter().map(|(_, cents)| cents).sum();
+
+        // 2 & 3. Unit, account by account
+        let links = sqlx::query!("SELECT unit_account_id FROM fiat_account_links ORDER BY unit_account_id")
+            .fetch_all(&self.db)
+            .await?;
+
+        let mut reserve_cents = 0;
+        let mut discrepancies = Vec::new();
+        for link in &links {
+            match self.reconcile_account(&link.unit_account_id, as_of).await {
+                Ok((balance, mut found)) => {
+                    reserve_cents += balance;
+                    discrepancies.append(&mut found);
+                }
+                // Can't see the account: its money can't count as reserves
+                Err(e) => discrepancies.push(Discrepancy {
+                    kind: "unit_unavailable".to_string(),
+                    unit_account_id: Some(link.unit_account_id.clone()),
+                    unit_transaction_id: None,
+                    fiat_entry_id: None,
+                    amount_cents: 0,
+                    detail: format!("Could not read account from Unit: {}", e),
+                }),
+            }
+        }
+
+        // 4. Commit to every balance & sign
+        let leaves: Vec<[u8; 32]> = holders
+            .iter()
+            .map(|(account, cents)| leaf_hash(report_id, account, *cents))
+            .collect();
+        let root = hex::encode(merkle_root(&leaves));
+
+        let attestation = ReserveAttestation {
+            report_id,
+            asset_definition_id: asset.clone(),
+            as_of,
+            onchain_supply_cents,
+            reserve_cents,
+            holder_count: holders.len() as i32,
+            merkle_root: root.clone(),
+        };
+        let payload = serde_json::to_vec(&attestation)?;
+        let signature = hex::encode(signing_key.sign(&payload).to_bytes());
+        let public_key = hex::encode(signing_key.verifying_key().to_bytes());
+
+        let difference_cents = onchain_supply_cents - reserve_cents;
+        let status = if difference_cents == 0 && discrepancies.is_empty() { "balanced" } else { "discrepancy" };
+        let signed_payload = String::from_utf8(payload)?;
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO reserve_reports
+            (id, asset_definition_id, as_of, onchain_supply_cents, reserve_cents, difference_cents, status,
+             holder_count, merkle_root, signed_payload, signature, public_key)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            "#,
+            report_id,
+            asset,
+            as_of,
+            onchain_supply_cents,
+            reserve_cents,
+            difference_cents,
+            status,
+            holders.len() as i32,
+            root,
+            signed_payload,
+            signature,
+            public_key
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for (index, (account, cents)) in holders.iter().enumerate() {
+            sqlx::query!(
+                "INSERT INTO reserve_report_leaves (report_id, leaf_index, account_id, balance_cents) VALUES ($1, $2, $3, $4)",
+                report_id,
+                index as i32,
+                account,
+                cents
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        for d in &discrepancies {
+            sqlx::query!(
+                r#"
+                INSERT INTO reserve_discrepancies
+                (id, report_id, kind, unit_account_id, unit_transaction_id, fiat_entry_id, amount_cents, detail)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+                Uuid::new_v4(),
+                report_id,
+                d.kind,
+                d.unit_account_id,
+                d.unit_transaction_id,
+                d.fiat_entry_id,
+                d.amount_cents,
+                d.detail
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        if status == "balanced" {
+            println!("🏦 Reserves balanced: ${:.2} on-chain, fully backed.", onchain_supply_cents as f64 / 100.0);
+        } else {
+            eprintln!(
+                "CRITICAL: Reserve discrepancy for {}: supply ${:.2} vs reserves ${:.2}, {} unmatched item(s). Report {}.",
+                asset, onchain_supply_cents as f64 / 100.0, reserve_cents as f64 / 100.0, discrepancies.len(), report_id
+            );
+        }
+
+        Ok(ReserveReport {
+            id: report_id,
+            asset_definition_id: asset.clone(),
+            as_of,
+            onchain_supply_cents,
+            reserve_cents,
+            difference_cents,
+            status: status.to_string(),
+            holder_count: holders.len() as i32,
+            merkle_root: root,
+            signed_payload,
+            signature,
+            public_key,
+            discrepancies,
+        })
+    }
+
+    /// Unit balance of one account, plus everything that doesn't line up on it
+    async fn reconcile_account(
+        &self,
+        unit_account_id: &str,
+        as_of: DateTime<Utc>,
+    ) -> Result<(i64, Vec<Discrepancy>), Box<dyn Error>> {
+        let balance = self.unit.get_balance(unit_account_id).await?.balance_cents;
+        let since = as_of - Duration::days(self.config.window_days);
+        let settled_before = as_of - Duration::minutes(WEBHOOK_GRACE_MINUTES);
+
+        let transactions = self
+            .unit
+            .all_transactions(unit_account_id, &TransactionFilter { since: Some(since), until: Some(settled_before) })
+            .await?;
+        let entries = sqlx::query!(
+            r#"
+            SELECT id, unit_transaction_id, action, amount_cents, created_at
+            FROM fiat_ledger_entries
+            WHERE unit_account_id = $1
+            "#,
+            unit_account_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut discrepancies = Vec::new();
+        let entry_by_tx: HashMap<&str, _> = entries.iter().map(|e| (e.unit_transaction_id.as_str(), e)).collect();
+        let unit_tx_ids: HashSet<&str> = transactions.iter().map(|t| t.id.as_str()).collect();
+
+        // Money moved in Unit, nothing happened on-chain (or the amount differs)
+        for t in &transactions {
+            if t.attributes.amount <= 0 {
+                continue;
+            }
+            match entry_by_tx.get(t.id.as_str()) {
+                None => {
+                    let kind = if t.attributes.direction == "Credit" { "unminted_deposit" } else { "unburned_withdrawal" };
+                    discrepancies.push(Discrepancy {
+                        kind: kind.to_string(),
+                        unit_account_id: Some(unit_account_id.to_string()),
+                        unit_transaction_id: Some(t.id.clone()),
+                        fiat_entry_id: None,
+                        amount_cents: t.attributes.amount,
+                        detail: format!("{} of ${:.2} has no ledger entry", t.kind, t.attributes.amount as f64 / 100.0),
+                    });
+                }
+                Some(entry) if entry.amount_cents != t.attributes.amount => {
+                    discrepancies.push(Discrepancy {
+                        kind: if entry.action == "mint" { "unbacked_mint" } else { "unbacked_burn" }.to_string(),
+                        unit_account_id: Some(unit_account_id.to_string()),
+                        unit_transaction_id: Some(t.id.clone()),
+                        fiat_entry_id: Some(entry.id),
+                        amount_cents: entry.amount_cents - t.attributes.amount,
+                        detail: format!(
+                            "{} of ${:.2} in Unit vs ${:.2} on-chain",
+                            entry.action, t.attributes.amount as f64 / 100.0, entry.amount_cents as f64 / 100.0
+                        ),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+
+        // Minted/burned on-chain, but Unit has no such transaction
+        for entry in &entries {
+            let in_window = entry.created_at >= since && entry.created_at < settled_before;
+            if in_window && !unit_tx_ids.contains(entry.unit_transaction_id.as_str()) {
+                discrepancies.push(Discrepancy {
+                    kind: if entry.action == "mint" { "unbacked_mint" } else { "unbacked_burn" }.to_string(),
+                    unit_account_id: Some(unit_account_id.to_string()),
+                    unit_transaction_id: Some(entry.unit_transaction_id.clone()),
+                    fiat_entry_id: Some(entry.id),
+                    amount_cents: entry.amount_cents,
+                    detail: format!("{} of ${:.2} matches no Unit transaction", entry.action, entry.amount_cents as f64 / 100.0),
+                });
+            }
+        }
+
+        // All-time: what we minted net of burns should equal what sits in Unit
+        // (accounts linked after they already held money show up here too)
+        let net_minted: i64 = entries
+            .iter()
+            .map(|e| if e.action == "mint" { e.amount_cents } else { -e.amount_cents })
+            .sum();
+        if net_minted != balance {
+            discrepancies.push(Discrepancy {
+                kind: "balance_drift".to_string(),
+                unit_account_id: Some(unit_account_id.to_string()),
+                unit_transaction_id: None,
+                fiat_entry_id: None,
+                amount_cents: net_minted - balance,
+                detail: format!(
+                    "Net minted ${:.2} vs Unit balance ${:.2}",
+                    net_minted as f64 / 100.0, balance as f64 / 100.0
+                ),
+            });
+        }
+
+        Ok((balance, discrepancies))
+    }
+
+    /// The most recent report for the asset
+    pub async fn latest(&self) -> Result<ReserveReport, Box<dyn Error>> {
+        let row = sqlx::query!(
+            "SELECT id FROM reserve_reports WHERE asset_definition_id = $1 ORDER BY as_of DESC LIMIT 1",
+            self.config.asset_definition_id
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .ok_or("No reserve report has been published yet")?;
+
+        self.get(row.id).await
+    }
+
+    pub async fn get(&self, report_id: Uuid) -> Result<ReserveReport, Box<dyn Error>> {
+        let r = sqlx::query!(
+            r#"
+            SELECT id, asset_definition_id, as_of, onchain_supply_cents, reserve_cents, difference_cents, status,
+                   holder_count, merkle_root, signed_payload, signature, public_key
+            FROM reserve_reports WHERE id = $1
+            "#,
+            report_id
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        let discrepancies = sqlx::query!(
+            r#"
+            SELECT kind, unit_account_id, unit_transaction_id, fiat_entry_id, amount_cents, detail
+            FROM reserve_discrepancies WHERE report_id = $1
+            ORDER BY kind, unit_account_id
+            "#,
+            report_id
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|d| Discrepancy {
+            kind: d.kind,
+            unit_account_id: d.unit_account_id,
+            unit_transaction_id: d.unit_transaction_id,
+            fiat_entry_id: d.fiat_entry_id,
+            amount_cents: d.amount_cents,
+            detail: d.detail,
+        })
+        .collect();
+
+        Ok(ReserveReport {
+            id: r.id,
+            asset_definition_id: r.asset_definition_id,
+            as_of: r.as_of,
+            onchain_supply_cents: r.onchain_supply_cents,
+            reserve_cents: r.reserve_cents,
+            difference_cents: r.difference_cents,
+            status: r.status,
+            holder_count: r.holder_count,
+            merkle_root: r.merkle_root,
+            signed_payload: r.signed_payload,
+            signature: r.signature,
+            public_key: r.public_key,
+            discrepancies,
+        })
+    }
+
+    /// INCLUSION PROOF: lets a holder check their balance is in the signed root
+    pub async fn proof(&self, report_id: Uuid, account_id: &str) -> Result<InclusionProof, Box<dyn Error>> {
+        let root = sqlx::query!("SELECT merkle_root FROM reserve_reports WHERE id = $1", report_id)
+            .fetch_one(&self.db)
+            .await?
+            .merkle_root;
+        let leaves = sqlx::query!(
+            "SELECT account_id, balance_cents FROM reserve_report_leaves WHERE report_id = $1 ORDER BY leaf_index",
+            report_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let index = leaves
+            .iter()
+            .position(|l| l.account_id == account_id)
+            .ok_or("Account held no balance in this report")?;
+        let hashes: Vec<[u8; 32]> = leaves
+            .iter()
+            .map(|l| leaf_hash(report_id, &l.account_id, l.balance_cents))
+            .collect();
+
+        Ok(InclusionProof {
+            report_id,
+            account_id: account_id.to_string(),
+            balance_cents: leaves[index].balance_cents,
+            leaf: hex::encode(hashes[index]),
+            steps: merkle_path(&hashes, index),
+            merkle_root: root,
+        })
+    }
+}
+
+// --- Merkle tree ---
+
+pub fn leaf_hash(report_id: Uuid, account_id: &str, balance_cents: i64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0x00]);
+    hasher.update(report_id.as_bytes());
+    hasher.update((account_id.len() as u32).to_be_bytes());
+    hasher.update(account_id.as_bytes());
+    hasher.update(balance_cents.to_be_bytes());
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([0x01]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Next level up. An odd node at the end moves up unchanged.
+fn parent_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return Sha256::digest(b"").into();
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = parent_level(&level);
+    }
+    level[0]
+}
+
+fn merkle_path(leaves: &[[u8; 32]], mut index: usize) -> Vec<ProofStep> {
+    let mut steps = Vec::new();
+    let mut level = leaves.to_vec();
+
+    while level.len() > 1 {
+        let sibling = if index % 2 == 0 { index + 1 } else { index - 1 };
+        if sibling < level.len() {
+            steps.push(ProofStep {
+                sibling: hex::encode(level[sibling]),
+                side: if sibling < index { "left" } else { "right" }.to_string(),
+            });
+        }
+        level = parent_level(&level);
+        index /= 2;
+    }
+    steps
+}
+
+/// What a holder runs: does `leaf` (hex) + `steps` lead to `root` (hex)?
+pub fn verify_inclusion(leaf: &str, steps: &[ProofStep], root: &str) -> bool {
+    let mut hash: [u8; 32] = match hex::decode(leaf).ok().and_then(|b| b.try_into().ok()) {
+        Some(hash) => hash,
+        None => return false,
+    };
+    for step in steps {
+        let sibling: [u8; 32] = match hex::decode(&step.sibling).ok().and_then(|b| b.try_into().ok()) {
+            Some(sibling) => sibling,
+            None => return false,
+        };
+        hash = match step.side.as_str() {
+            "left" => node_hash(&sibling, &hash),
+            "right" => node_hash(&hash, &sibling),
+            _ => return false,
+        };
+    }
+    hex::encode(hash) == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holders() -> Vec<(String, i64)> {
+        // Odd count: the last leaf moves up a level unpaired
+        (1..=5).map(|n| (format!("user{}@tenant", n), n * 1_000)).collect()
+    }
+
+    #[test]
+    fn every_holder_proves_inclusion() {
+        let report_id = Uuid::new_v4();
+        let leaves: Vec<[u8; 32]> = holders().iter().map(|(a, c)| leaf_hash(report_id, a, *c)).collect();
+        let root = hex::encode(merkle_root(&leaves));
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let steps = merkle_path(&leaves, index);
+            assert!(verify_inclusion(&hex::encode(leaf), &steps, &root), "leaf {} should verify", index);
+        }
+    }
+
+    #[test]
+    fn tampered_balance_fails_verification() {
+        let report_id = Uuid::new_v4();
+        let holders = holders();
+        let leaves: Vec<[u8; 32]> = holders.iter().map(|(a, c)| leaf_hash(report_id, a, *c)).collect();
+        let root = hex::encode(merkle_root(&leaves));
+
+        let (account, cents) = &holders[2];
+        let steps = merkle_path(&leaves, 2);
+        let tampered = leaf_hash(report_id, account, cents + 1);
+        assert!(!verify_inclusion(&hex::encode(tampered), &steps, &root));
+
+        // Same balance under another report doesn't verify either
+        let replayed = leaf_hash(Uuid::new_v4(), account, *cents);
+        assert!(!verify_inclusion(&hex::encode(replayed), &steps, &root));
+    }
+
+    #[test]
+    fn single_holder_is_its_own_root() {
+        let leaf = leaf_hash(Uuid::new_v4(), "only@tenant", 42);
+        assert!(merkle_path(&[leaf], 0).is_empty());
+        assert!(verify_inclusion(&hex::encode(leaf), &[], &hex::encode(merkle_root(&[leaf]))));
+    }
+}
@@ -0,0 +1,438 @@
+use crate::core::cards::{CardConfig, CARD_TRANSACTION_TYPES};
+use crate::core::explorer_indexer::check_indexer_health;
+use crate::core::fiat_banking::{FiatAsset, PageRequest, TransactionFilter, UnitClient};
+use crate::core::invoice::BillingPeriod;
+use crate::ledger::client::IrohaClient;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// --- ACCOUNT STATEMENTS ---
+// One statement per on-chain account and month:
+//   wallet section:           `usd` movements from the explorer index
+//   deposit account sections: every Unit account mirrored by the wallet
+// Statements are generated once the month is over and stored as-is, so a
+// statement downloaded twice is the same statement.
+
+#[derive(Debug, Clone)]
+pub struct StatementConfig {
+    pub usd_asset_id: String,
+    pub fee_accounts: Vec<String>, // USD sent here shows up as a fee
+    pub card_hold_account_id: String,
+    pub max_index_lag_blocks: u64,
+}
+
+impl StatementConfig {
+    /// Reads the revenue accounts (REVENUE_COMMISSION_ACCOUNT_ID,
+    /// REVENUE_BRIDGE_FEE_ACCOUNT_ID, ONCHAIN_REVENUE_ACCOUNT_ID), CARD_HOLD_ACCOUNT_ID
+    /// and INDEXER_MAX_LAG_BLOCKS
+    pub fn from_env(asset: &FiatAsset) -> Self {
+        let var = |key: &str, default: &str| std::env::var(key).unwrap_or_else(|_| default.to_string());
+        Self {
+            usd_asset_id: asset.usd_asset_id.clone(),
+            fee_accounts: vec![
+                var("REVENUE_COMMISSION_ACCOUNT_ID", "admin@my_ecosystem"),
+                var("REVENUE_BRIDGE_FEE_ACCOUNT_ID", "bridge_fees@my_ecosystem"),
+                var("ONCHAIN_REVENUE_ACCOUNT_ID", "revenue@my_ecosystem"),
+            ],
+            card_hold_account_id: CardConfig::from_env(asset).hold_account_id,
+            max_index_lag_blocks: var("INDEXER_MAX_LAG_BLOCKS", "20").parse().unwrap_or(20),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementCategory {
+    Deposit,     // Money in from the bank (mint / Unit credit)
+    Withdrawal,  // Money out to the bank (burn / Unit debit)
+    TransferIn,  // On-chain, from another account
+    TransferOut, // On-chain, to another account
+    Card,        // Card purchases & refunds
+    Fee,
+}
+
+impl StatementCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatementCategory::Deposit => "deposit",
+            StatementCategory::Withdrawal => "withdrawal",
+            StatementCategory::TransferIn => "transfer_in",
+            StatementCategory::TransferOut => "transfer_out",
+            StatementCategory::Card => "card",
+            StatementCategory::Fee => "fee",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementLine {
+    pub posted_at: DateTime<Utc>,
+    pub category: StatementCategory,
+    pub description: String,
+    pub amount_cents: i64,  // Signed: negative = money out
+    pub balance_cents: i64, // After this line
+    pub reference: String,  // Transaction hash or Unit transaction id
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementSection {
+    pub kind: String,       // "wallet" | "deposit_account"
+    pub account_id: String, // On-chain account or Unit account id
+    pub opening_balance_cents: i64,
+    pub closing_balance_cents: i64,
+    pub credits_cents: i64,
+    pub debits_cents: i64, // Excluding fees
+    pub fees_cents: i64,
+    pub lines: Vec<StatementLine>,
+}
+
+impl StatementSection {
+    fn new(kind: &str, account_id: &str, opening_balance_cents: i64, lines: Vec<StatementLine>) -> Self {
+        let closing_balance_cents = lines.last().map_or(opening_balance_cents, |l| l.balance_cents);
+        let fees_cents = lines.iter().filter(|l| l.category == StatementCategory::Fee).map(|l| -l.amount_cents).sum();
+        let credits_cents = lines.iter().filter(|l| l.amount_cents > 0).map(|l| l.amount_cents).sum();
+        let debits_cents = lines
+            .iter()
+            .filter(|l| l.amount_cents < 0 && l.category != StatementCategory::Fee)
+            .map(|l| -l.amount_cents)
+            .sum();
+        Self {
+            kind: kind.to_string(),
+            account_id: account_id.to_string(),
+            opening_balance_cents,
+            closing_balance_cents,
+            credits_cents,
+            debits_cents,
+            fees_cents,
+            lines,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Statement {
+    pub id: Uuid,
+    pub onchain_account_id: String,
+    pub tenant_id: Option<Uuid>,
+    pub period: BillingPeriod,
+    pub asset_definition_id: String,
+    pub generated_at: DateTime<Utc>,
+    pub sections: Vec<StatementSection>, // Wallet first
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatementSummary {
+    pub id: Uuid,
+    pub period: String, // "2024-03"
+    pub opening_balance_cents: i64,
+    pub closing_balance_cents: i64,
+    pub generated_at: DateTime<Utc>,
+}
+
+pub struct StatementService {
+    db: PgPool,
+    unit: Arc<UnitClient>,
+    iroha: Arc<IrohaClient>,
+    config: StatementConfig,
+}
+
+impl StatementService {
+    pub fn new(db: PgPool, unit: Arc<UnitClient>, iroha: Arc<IrohaClient>, config: StatementConfig) -> Self {
+        Self { db, unit, iroha, config }
+    }
+
+    /// Statements already generated for an account, newest first
+    pub async fn list(&self, onchain_account_id: &str) -> Result<Vec<StatementSummary>, Box<dyn Error>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, period, opening_balance_cents, closing_balance_cents, generated_at
+            FROM account_statements
+            WHERE onchain_account_id = $1
+            ORDER BY period DESC
+            "#,
+            onchain_account_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| StatementSummary {
+                id: r.id,
+                period: BillingPeriod::containing(r.period).to_string(),
+                opening_balance_cents: r.opening_balance_cents,
+                closing_balance_cents: r.closing_balance_cents,
+                generated_at: r.generated_at,
+            })
+            .collect())
+    }
+
+    /// THE STATEMENT for one month: the stored one, or generated now.
+    /// 1. Only months that are over (a statement never changes once issued)
+    /// 2. The explorer index must have caught up with the chain
+    /// 3. Wallet section from the index, one section per linked Unit account
+    pub async fn statement(&self, onchain_account_id: &str, period: BillingPeriod) -> Result<Statement, Box<dyn Error>> {
+        if let Some(existing) = self.stored(onchain_account_id, period).await? {
+            return Ok(existing);
+        }
+        if period.end() > Utc::now().date_naive() {
+            return Err(format!("The statement for {} is available once the month is over", period).into());
+        }
+        check_indexer_health(&self.db, &self.iroha, self.config.max_index_lag_blocks).await?;
+
+        let domain = onchain_account_id.split('@').nth(1).ok_or("Invalid account id")?;
+        let tenant_id = sqlx::query!("SELECT id FROM tenants WHERE iroha_domain = $1", domain)
+            .fetch_optional(&self.db)
+            .await?
+            .map(|r| r.id);
+
+        let mut sections = vec![self.wallet_section(onchain_account_id, period).await?];
+        let links = sqlx::query!(
+            "SELECT unit_account_id FROM fiat_account_links WHERE onchain_account_id = $1 ORDER BY unit_account_id",
+            onchain_account_id
+        )
+        .fetch_all(&self.db)
+        .await?;
+        for link in &links {
+            sections.push(self.deposit_section(&link.unit_account_id, period).await?);
+        }
+
+        let statement = Statement {
+            id: Uuid::new_v4(),
+            onchain_account_id: onchain_account_id.to_string(),
+            tenant_id,
+            period,
+            asset_definition_id: self.config.usd_asset_id.clone(),
+            generated_at: Utc::now(),
+            sections,
+        };
+
+        let wallet = &statement.sections[0];
+        sqlx::query!(
+            r#"
+            INSERT INTO account_statements
+            (id, onchain_account_id, tenant_id, period, asset_definition_id, opening_balance_cents,
+             closing_balance_cents, content, generated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (onchain_account_id, period) DO NOTHING
+            "#,
+            statement.id,
+            statement.onchain_account_id,
+            statement.tenant_id,
+            period.start,
+            statement.asset_definition_id,
+            wallet.opening_balance_cents,
+            wallet.closing_balance_cents,
+            serde_json::to_value(&statement)?,
+            statement.generated_at
+        )
+        .execute(&self.db)
+        .await?;
+
+        println!("🧾 Statement {} generated for {}.", period, onchain_account_id);
+
+        // Ours, or the one a concurrent request stored first
+        self.stored(onchain_account_id, period)
+            .await?
+            .ok_or_else(|| "Statement was generated but could not be stored".into())
+    }
+
+    /// MONTH END (statements job): a statement for every account that
+    /// mirrors a Unit account
+    pub async fn generate_all(&self, period: BillingPeriod) -> Result<(), Box<dyn Error>> {
+        let accounts = sqlx::query!("SELECT DISTINCT onchain_account_id FROM fiat_account_links ORDER BY onchain_account_id")
+            .fetch_all(&self.db)
+            .await?;
+
+        let mut failed = 0;
+        for account in &accounts {
+            if let Err(e) = self.statement(&account.onchain_account_id, period).await {
+                eprintln!("❌ Statement {} for {} failed: {}", period, account.onchain_account_id, e);
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            return Err(format!("{} of {} statements for {} failed", failed, accounts.len(), period).into());
+        }
+        Ok(())
+    }
+
+    async fn stored(&self, onchain_account_id: &str, period: BillingPeriod) -> Result<Option<Statement>, Box<dyn Error>> {
+        let row = sqlx::query!(
+            "SELECT content FROM account_statements WHERE onchain_account_id = $1 AND period = $2",
+            onchain_account_id,
+            period.start
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        match row {
+            Some(r) => Ok(Some(serde_json::from_value(r.content)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// On-chain `usd` movements of the account, from the explorer index.
+    /// The opening balance is everything indexed before the month.
+    async fn wallet_section(&self, account: &str, period: BillingPeriod) -> Result<StatementSection, Box<dyn Error>> {
+        let from = period.start.and_hms_opt(0, 0, 0).unwrap();
+        let to = period.end().and_hms_opt(0, 0, 0).unwrap();
+        let asset = &self.config.usd_asset_id;
+
+        let opening = sqlx::query!(
+            r#"
+            SELECT COALESCE(SUM(
+                CASE WHEN i.destination_account_id = $2 THEN i.quantity ELSE 0 END
+              - CASE WHEN i.source_account_id = $2 THEN i.quantity ELSE 0 END
+            ), 0) AS "net!"
+            FROM chain_instructions i
+            JOIN chain_transactions t ON t.tx_hash = i.tx_hash
+            WHERE i.asset_definition_id = $1
+              AND (i.source_account_id = $2 OR i.destination_account_id = $2)
+              AND t.timestamp < $3
+            "#,
+            asset,
+            account,
+            from
+        )
+        .fetch_one(&self.db)
+        .await?;
+        let opening_cents = to_cents(opening.net);
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT i.tx_hash, i.kind, i.source_account_id, i.destination_account_id,
+                   i.quantity AS "quantity!", t.timestamp AS "timestamp!", f.transaction_type AS "fiat_type?"
+            FROM chain_instructions i
+            JOIN chain_transactions t ON t.tx_hash = i.tx_hash
+            LEFT JOIN fiat_ledger_entries f ON f.tx_hash = i.tx_hash
+            WHERE i.asset_definition_id = $1
+              AND (i.source_account_id = $2 OR i.destination_account_id = $2)
+              AND i.quantity IS NOT NULL
+              AND t.timestamp >= $3 AND t.timestamp < $4
+            ORDER BY t.timestamp, i.tx_hash, i.position
+            "#,
+            asset,
+            account,
+            from,
+            to
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut balance = opening_cents;
+        let mut lines = Vec::new();
+        for row in rows {
+            let cents = to_cents(row.quantity);
+            let incoming = row.destination_account_id.as_deref() == Some(account);
+            let outgoing = row.source_account_id.as_deref() == Some(account);
+            if incoming == outgoing {
+                continue; // To itself: no movement
+            }
+
+            let counterparty = if incoming { &row.source_account_id } else { &row.destination_account_id };
+            let (category, description) = match (row.kind.as_str(), incoming) {
+                ("Mint", _) => (StatementCategory::Deposit, bank_description("Bank deposit", &row.fiat_type)),
+                ("Burn", _) => (StatementCategory::Withdrawal, bank_description("Bank withdrawal", &row.fiat_type)),
+                _ if counterparty.as_deref() == Some(self.config.card_hold_account_id.as_str()) => {
+                    let label = if incoming { "Card hold released" } else { "Card hold" };
+                    (StatementCategory::Card, label.to_string())
+                }
+                (_, false) if counterparty.as_ref().map_or(false, |c| self.config.fee_accounts.contains(c)) => {
+                    (StatementCategory::Fee, "Platform fee".to_string())
+                }
+                (_, true) => (
+                    StatementCategory::TransferIn,
+                    format!("Transfer from {}", counterparty.as_deref().unwrap_or("unknown")),
+                ),
+                (_, false) => (
+                    StatementCategory::TransferOut,
+                    format!("Transfer to {}", counterparty.as_deref().unwrap_or("unknown")),
+                ),
+            };
+
+            let amount_cents = if incoming { cents } else { -cents };
+            balance += amount_cents;
+            lines.push(StatementLine {
+                posted_at: utc(row.timestamp),
+                category,
+                description,
+                amount_cents,
+                balance_cents: balance,
+                reference: row.tx_hash,
+            });
+        }
+
+        Ok(StatementSection::new("wallet", account, opening_cents, lines))
+    }
+
+    /// The Unit deposit account for the month. Unit reports the balance after
+    /// every transaction; the opening balance is the one after the last
+    /// transaction before the month.
+    async fn deposit_section(&self, unit_account_id: &str, period: BillingPeriod) -> Result<StatementSection, Box<dyn Error>> {
+        let from = utc(period.start.and_hms_opt(0, 0, 0).unwrap());
+        let to = utc(period.end().and_hms_opt(0, 0, 0).unwrap());
+
+        let before = self
+            .unit
+            .list_transactions(
+                unit_account_id,
+                &TransactionFilter { since: None, until: Some(from) },
+                PageRequest { limit: 1, offset: 0 },
+            )
+            .await?;
+        let opening_cents = before.items.first().map_or(0, |t| t.attributes.balance);
+
+        let mut transactions = self
+            .unit
+            .all_transactions(unit_account_id, &TransactionFilter { since: Some(from), until: Some(to) })
+            .await?;
+        transactions.sort_by_key(|t| t.attributes.created_at);
+
+        let lines = transactions
+            .into_iter()
+            .filter(|t| t.attributes.created_at >= from && t.attributes.created_at < to)
+            .map(|t| {
+                let credit = t.attributes.direction == "Credit";
+                let category = match t.kind.as_str() {
+                    "feeTransaction" => StatementCategory::Fee,
+                    kind if CARD_TRANSACTION_TYPES.contains(&kind) => StatementCategory::Card,
+                    _ if credit => StatementCategory::Deposit,
+                    _ => StatementCategory::Withdrawal,
+                };
+                StatementLine {
+                    posted_at: t.attributes.created_at,
+                    category,
+                    description: t.attributes.description.clone().unwrap_or(t.attributes.summary.clone()),
+                    amount_cents: if credit { t.attributes.amount } else { -t.attributes.amount },
+                    balance_cents: t.attributes.balance,
+                    reference: t.id,
+                }
+            })
+            .collect();
+
+        Ok(StatementSection::new("deposit_account", unit_account_id, opening_cents, lines))
+    }
+}
+
+/// Index quantities are dollars
+fn to_cents(quantity: f64) -> i64 {
+    (quantity * 100.0).round() as i64
+}
+
+fn utc(timestamp: NaiveDateTime) -> DateTime<Utc> {
+    DateTime::from_naive_utc_and_offset(timestamp, Utc)
+}
+
+/// "Bank deposit (cashDepositTransaction)" when we know the Unit transaction
+fn bank_description(label: &str, fiat_type: &Option<String>) -> String {
+    match fiat_type {
+        Some(kind) => format!("{} ({})", label, kind),
+        None => label.to_string(),
+    }
+}
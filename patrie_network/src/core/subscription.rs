@@ -0,0 +1,326 @@
+use crate::core::adjustments::BillingAdjustment;
+use crate::core::benefit_catalog::{BenefitCatalog, HEALTH, RETIREMENT};
+use crate::core::billing_engine::recurring_lines;
+use crate::core::gusto::{update_wholesale_cost, GustoClient};
+use crate::core::invoice::{BillingPeriod, Invoice, InvoiceStatus};
+use crate::core::pricing::{PriceBook, TenantPricing};
+use crate::core::tiers::ServiceTier;
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::error::Error;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// A tenant's row in `subscription_settings` (The Store), plus its deposit account
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionSettings {
+    pub tenant_id: Uuid,
+    pub tier: ServiceTier,
+    pub unit_deposit_account_id: Option<String>,
+    pub health_active: bool,
+    pub health_cost_wholesale: f64,
+    pub retirement_active: bool,
+    pub retirement_cost_wholesale: f64,
+    pub crime_active: bool,
+    pub crime_cost_wholesale: f64,
+    pub onchain_settlement: bool,            // Pay invoices from the on-chain treasury first
+    pub treasury_account_id: Option<String>, // e.g. "treasury@tesla_supply_chain"
+}
+
+impl SubscriptionSettings {
+    pub async fn load(db: &PgPool, tenant_id: Uuid) -> Result<SubscriptionSettings, Box<dyn Error>> {
+        let rec = sqlx::query!(
+            r#"
+            SELECT
+                t.unit_deposit_account_id,
+                s.tier,
+                s.health_active, s.health_cost_wholesale,
+                s.retirement_active, s.retirement_cost_wholesale,
+                s.crime_active, s.crime_cost_wholesale,
+                s.onchain_settlement, s.treasury_account_id
+            FROM tenants t
+            JOIN subscription_settings s ON t.id = s.tenant_id
+            WHERE t.id = $1
+            "#,
+            tenant_id
+        )
+        .fetch_one(db)
+        .await?;
+
+        Ok(SubscriptionSettings {
+            tenant_id,
+            tier: ServiceTier::parse(&rec.tier)?,
+            unit_deposit_account_id: rec.unit_deposit_account_id,
+            health_active: rec.health_active.unwrap_or(false),
+            health_cost_wholesale: rec.health_cost_wholesale.unwrap_or_default(),
+            retirement_active: rec.retirement_active.unwrap_or(false),
+            retirement_cost_wholesale: rec.retirement_cost_wholesale.unwrap_or_default(),
+            crime_active: rec.crime_active.unwrap_or(false),
+            crime_cost_wholesale: rec.crime_cost_wholesale.unwrap_or_default(),
+            onchain_settlement: rec.onchain_settlement,
+            treasury_account_id: rec.treasury_account_id,
+        })
+    }
+
+    async fn save(&self, db: &PgPool) -> Result<(), Box<dyn Error>> {
+        sqlx::query!(
+            r#"
+            UPDATE subscription_settings
+            SET tier = $2, health_active = $3, retirement_active = $4, crime_active = $5
+            WHERE tenant_id = $1
+            "#,
+            self.tenant_id,
+            self.tier.as_str(),
+            self.health_active,
+            self.retirement_active,
+            self.crime_active
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+}
+
+/// The outcome of a tier change, returned to the API caller
+#[derive(Debug, Serialize)]
+pub struct TierChange {
+    pub tenant_id: Uuid,
+    pub from: ServiceTier,
+    pub to: ServiceTier,
+    pub proration: Option<BillingAdjustment>, // Charge (+) or credit (-) on the next invoice
+}
+
+pub struct SubscriptionManager {
+    db: PgPool,
+    gusto: Arc<GustoClient>,
+    benefits: Arc<BenefitCatalog>,
+    billing_tz: Tz, // The billing job's clock (JOB_BILLING_TZ): which month "today" is in
+}
+
+impl SubscriptionManager {
+    pub fn new(db: PgPool, gusto: Arc<GustoClient>, benefits: Arc<BenefitCatalog>, billing_tz: Tz) -> Self {
+        Self { db, gusto, benefits, billing_tz }
+    }
+
+    /// UPGRADE / DOWNGRADE
+    /// 1. Turns the tier's Gusto benefits on/off
+    /// 2. Saves the new tier to `subscription_settings` (Gusto is put back if that fails)
+    /// 3. Books a prorated charge/credit for the rest of the current month
+    pub async fn change_tier(&self, tenant_id: Uuid, new_tier: ServiceTier) -> Result<TierChange, Box<dyn Error>> {
+        let old = SubscriptionSettings::load(&self.db, tenant_id).await?;
+        if old.tier == new_tier {
+            return Err(format!("Tenant is already on {}", new_tier).into());
+        }
+
+        let prices = PriceBook::current(&self.db).await?;
+        let config = new_tier.get_config(&prices)?;
+
+        // 1. Sync Gusto benefits with the new tier (which products a tier
+        // includes is configuration: see core/benefit_catalog.rs)
+        let mut new = old.clone();
+        new.tier = new_tier;
+        new.crime_active = config.includes_crime_ins; // Crime insurance is not a Gusto benefit
+
+        let active = match self.benefits.apply_tier(tenant_id, new_tier).await {
+            Ok(active) => active,
+            Err(e) => {
+                self.restore_benefits(tenant_id, old.tier).await; // Some products may have switched already
+                return Err(e);
+            }
+        };
+        new.health_active = active.iter().any(|p| p == HEALTH);
+        new.retirement_active = active.iter().any(|p| p == RETIREMENT);
+
+        // 2. Save. Gusto has already changed: a failed save puts it back, so
+        // Gusto and the tier we bill never disagree
+        if let Err(e) = new.save(&self.db).await {
+            self.restore_benefits(tenant_id, old.tier).await;
+            return Err(e);
+        }
+
+        // 3. Proration for the rest of this month
+        let today = Utc::now().with_timezone(&self.billing_tz).date_naive();
+        let proration = self.prorate(&old, &new, today).await?;
+
+        println!("🔀 Tenant {} moved from {} to {}.", tenant_id, old.tier, new_tier);
+
+        Ok(TierChange { tenant_id, from: old.tier, to: new_tier, proration })
+    }
+
+    /// Best effort: Gusto back to what `tier` includes, after a failed tier change
+    async fn restore_benefits(&self, tenant_id: Uuid, tier: ServiceTier) {
+        if let Err(e) = self.benefits.apply_tier(tenant_id, tier).await {
+            eprintln!("❌ Tenant {}: Gusto benefits could not be rolled back to {}: {}", tenant_id, tier, e);
+        }
+    }
+
+    /// The month was billed in advance at the OLD tier. For the days left we
+    /// charge the difference (upgrade) or credit it back (downgrade).
+    /// `today` is on the billing clock, so the month matches the invoice's.
+    async fn prorate(
+        &self,
+        old: &SubscriptionSettings,
+        new: &SubscriptionSettings,
+        today: NaiveDate,
+    ) -> Result<Option<BillingAdjustment>, Box<dyn Error>> {
+        let period = BillingPeriod::containing(today);
+
+        // No invoice yet, or a draft: it will be calculated at the NEW tier,
+        // so there is nothing billed at the old one to correct
+        match Invoice::find(&self.db, old.tenant_id, period).await? {
+            Some(invoice) if invoice.status != InvoiceStatus::Draft => {}
+            _ => return Ok(None),
+        }
+
+        let days_in_period = (period.end() - period.start).num_days();
+        let days_remaining = (period.end() - today).num_days();
+
+        let old_pricing = TenantPricing::load(&self.db, old.tenant_id, old.tier, period.start).await?;
+        let new_pricing = TenantPricing::load(&self.db, new.tenant_id, new.tier, period.start).await?;
+
+        let old_monthly: i64 = recurring_lines(&old_pricing, old)?.iter().map(|l| l.amount_cents).sum();
+        let new_monthly: i64 = recurring_lines(&new_pricing, new)?.iter().map(|l| l.amount_cents).sum();
+
+        let amount = (new_monthly - old_monthly) * days_remaining / days_in_period;
+        if amount == 0 {
+            return Ok(None);
+        }
+
+        let description = format!(
+            "Proration: {} -> {} ({} of {} days)",
+            old.tier, new.tier, days_remaining, days_in_period
+        );
+        Ok(Some(BillingAdjustment::create(&self.db, old.tenant_id, &description, amount).await?))
+    }
+
+    /// ON-CHAIN SETTLEMENT OPT-IN
+    /// The treasury must live in the tenant's own domain. The tenant also has to
+    /// grant our platform account permission to transfer its USD asset;
+    /// without it the transfer is rejected and the invoice goes to dunning.
+    pub async fn set_onchain_settlement(
+        &self,
+        tenant_id: Uuid,
+        enabled: bool,
+        treasury_account_id: Option<&str>,
+    ) -> Result<SubscriptionSettings, Box<dyn Error>> {
+        if enabled {
+            let treasury = treasury_account_id.ok_or("A treasury account is required to settle on-chain")?;
+            let domain = sqlx::query!("SELECT iroha_domain FROM tenants WHERE id = $1", tenant_id)
+                .fetch_one(&self.db)
+                .await?
+                .iroha_domain
+                .ok_or("Tenant has no ledger domain")?;
+
+            if treasury.split('@').nth(1) != Some(domain.as_str()) {
+                return Err(format!("Treasury account must belong to domain {}", domain).into());
+            }
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE subscription_settings
+            SET onchain_settlement = $2, treasury_account_id = COALESCE($3, treasury_account_id)
+            WHERE tenant_id = $1
+            "#,
+            tenant_id,
+            enabled,
+            treasury_account_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        SubscriptionSettings::load(&self.db, tenant_id).await
+    }
+
+    /// WHOLESALE COST SYNC
+    /// Pulls what each active Gusto benefit actually costs into `subscription_settings`,
+    /// so the pass-through lines on the next invoice match the carriers' bills.
+    pub async fn sync_wholesale_costs(&self) -> Result<(), Box<dyn Error>> {
+        let tenants = sqlx::query!(
+            r#"
+            SELECT s.tenant_id
+            FROM subscription_settings s
+            JOIN tenants t ON t.id = s.tenant_id
+            WHERE t.gusto_company_uuid IS NOT NULL
+              AND EXISTS (SELECT 1 FROM tenant_benefits b WHERE b.tenant_id = s.tenant_id AND b.active)
+            "#
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut failures = 0;
+        for row in tenants {
+            if let Err(e) = self.sync_tenant_costs(row.tenant_id).await {
+                eprintln!("❌ Wholesale cost sync failed for tenant {}: {}", row.tenant_id, e);
+                failures += 1;
+            }
+        }
+
+        if failures > 0 {
+            return Err(format!("{} tenant(s) failed wholesale cost sync", failures).into());
+        }
+        Ok(())
+    }
+
+    /// One tenant (also run when Gusto reports a benefit change)
+    pub async fn sync_tenant_costs(&self, tenant_id: Uuid) -> Result<(), Box<dyn Error>> {
+        let settings = SubscriptionSettings::load(&self.db, tenant_id).await?;
+        let benefits = self.benefits.sync_costs(tenant_id).await?;
+
+        // Only health & 401(k) are billed as pass-through premiums
+        let cost = |product: &str, fallback: f64| {
+            benefits
+                .iter()
+                .find(|b| b.product == product && b.active)
+                .map(|b| b.monthly_employer_cost)
+                .unwrap_or(fallback)
+        };
+        let health = cost(HEALTH, settings.health_cost_wholesale);
+        let retirement = cost(RETIREMENT, settings.retirement_cost_wholesale);
+
+        update_wholesale_cost(&self.db, tenant_id, health, retirement).await?;
+        Ok(())
+    }
+
+    /// OFFBOARDING
+    /// 1. Turns off the tenant's Gusto benefits (we stop paying for them)
+    /// 2. Revokes our access to their Gusto company
+    /// 3. Marks the tenant offboarded (the API rejects writes from then on)
+    pub async fn offboard(&self, tenant_id: Uuid) -> Result<(), Box<dyn Error>> {
+        let company_uuid = sqlx::query!("SELECT gusto_company_uuid FROM tenants WHERE id = $1", tenant_id)
+            .fetch_one(&self.db)
+            .await?
+            .gusto_company_uuid;
+
+        if let Some(company_uuid) = &company_uuid {
+            // 1. Benefits first: after step 2 we can no longer touch them
+            self.benefits.deactivate_all(tenant_id, company_uuid).await?;
+
+            // 2. Revoke
+            self.gusto.revoke_company_access(company_uuid).await?;
+        }
+
+        // 3. Close the tenant
+        sqlx::query!(
+            r#"
+            UPDATE subscription_settings
+            SET health_active = FALSE, retirement_active = FALSE
+            WHERE tenant_id = $1
+            "#,
+            tenant_id
+        )
+        .execute(&self.db)
+        .await?;
+        sqlx::query!(
+            "UPDATE tenants SET status = 'offboarded', offboarded_at = NOW() WHERE id = $1",
+            tenant_id
+        )
+        .execute(&self.db)
+        .await?;
+
+        println!("👋 Tenant {} offboarded.", tenant_id);
+        Ok(())
+    }
+}
@@ -1,12 +1,11 @@
+use crate::core::pricing::{PriceBook, PriceKey};
 use serde::{Deserialize, Serialize};
+use std::error::Error;
 use std::fmt;
 
-// --- CONSTANTS (The Price List) ---
-pub const BASE_PLATFORM_FEE: f64 = 2500.00;
-pub const ADMIN_FEE_HEALTH: f64 = 50.00;
-pub const ADMIN_FEE_401K: f64 = 50.00;
-pub const ADMIN_FEE_CRIME: f64 = 50.00;
-pub const BRIDGE_EXIT_FEE_USD: f64 = 10.00;
+// --- THE PRICE LIST ---
+// Prices live in the `price_books` table (see core/pricing.rs), so a price
+// change is a new price book version instead of a redeploy.
 
 /// The Official Tiers of the Platform
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -38,12 +37,32 @@ pub struct TierConfiguration {
 }
 
 impl ServiceTier {
-    /// Returns the configuration for a specific tier
-    pub fn get_config(&self) -> TierConfiguration {
+    /// Stable code stored in `subscription_settings.tier` and price books
+    pub fn as_str(&self) -> &'static str {
         match self {
+            ServiceTier::Starter => "starter",
+            ServiceTier::Professional => "professional",
+            ServiceTier::Enterprise => "enterprise",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        match value.to_lowercase().as_str() {
+            "starter" => Ok(ServiceTier::Starter),
+            "professional" => Ok(ServiceTier::Professional),
+            "enterprise" => Ok(ServiceTier::Enterprise),
+            other => Err(format!("Unknown service tier: {}", other).into()),
+        }
+    }
+
+    /// Returns the configuration for a specific tier, priced from `prices`
+    pub fn get_config(&self, prices: &PriceBook) -> Result<TierConfiguration, Box<dyn Error>> {
+        let base_price = prices.price_usd(PriceKey::BasePlatformFee, *self)?;
+
+        Ok(match self {
             ServiceTier::Starter => TierConfiguration {
                 tier_name: self.to_string(),
-                base_price: BASE_PLATFORM_FEE,
+                base_price,
                 includes_health: false,
                 includes_401k: false,
                 includes_crime_ins: false,
@@ -51,7 +70,7 @@ impl ServiceTier {
             },
             ServiceTier::Professional => TierConfiguration {
                 tier_name: self.to_string(),
-                base_price: BASE_PLATFORM_FEE,
+                base_price,
                 includes_health: true, // Auto-enables Health Store Item
                 includes_401k: false,
                 includes_crime_ins: false,
@@ -59,21 +78,28 @@ impl ServiceTier {
             },
             ServiceTier::Enterprise => TierConfiguration {
                 tier_name: self.to_string(),
-                base_price: BASE_PLATFORM_FEE,
+                base_price,
                 includes_health: true,
                 includes_401k: true,
                 includes_crime_ins: true,
                 description: "Full Compliance Stack: Health, 401k, Fraud Protection".to_string(),
             },
-        }
+        })
     }
 
     /// Helper: Calculate estimated revenue for YOU (excluding pass-through costs)
-    pub fn estimated_profit(&self) -> f64 {
-        match self {
-            ServiceTier::Starter => BASE_PLATFORM_FEE,
-            ServiceTier::Professional => BASE_PLATFORM_FEE + ADMIN_FEE_HEALTH,
-            ServiceTier::Enterprise => BASE_PLATFORM_FEE + ADMIN_FEE_HEALTH + ADMIN_FEE_401K + ADMIN_FEE_CRIME,
-        }
+    pub fn estimated_profit(&self, prices: &PriceBook) -> Result<f64, Box<dyn Error>> {
+        let price = |key| prices.price_usd(key, *self);
+
+        Ok(match self {
+            ServiceTier::Starter => price(PriceKey::BasePlatformFee)?,
+            ServiceTier::Professional => price(PriceKey::BasePlatformFee)? + price(PriceKey::AdminFeeHealth)?,
+            ServiceTier::Enterprise => {
+                price(PriceKey::BasePlatformFee)?
+                    + price(PriceKey::AdminFeeHealth)?
+                    + price(PriceKey::AdminFee401k)?
+                    + price(PriceKey::AdminFeeCrime)?
+            }
+        })
     }
 }
\ No newline at end of file
@@ -1,35 +1,211 @@
+use crate::core::ach::AchService;
 use crate::core::billing_engine::BillingEngine;
+use crate::core::cards::CardService;
+use crate::core::cash_deposits::expire_stale_barcodes;
+use crate::core::contracts::process_contract_renewals;
+use crate::core::dunning::DunningService;
+use crate::core::employee_sync::EmployeeSync;
+use crate::core::explorer_indexer::check_indexer_health;
+use crate::core::fiat_events::FiatEventProcessor;
+use crate::core::gusto_events::GustoEventProcessor;
+use crate::core::invoice::BillingPeriod;
+use crate::core::jobs::{JobName, JobRegistry, JobSchedule, JobTrigger};
+use crate::core::notifications::NotificationSender;
+use crate::core::reporting::RevenueReporter;
+use crate::core::reserves::ReserveReconciler;
+use crate::core::statements::StatementService;
+use crate::core::subscription::SubscriptionManager;
+use crate::ledger::client::IrohaClient;
+use sqlx::PgPool;
 use std::sync::Arc;
 use tokio_cron_scheduler::{Job, JobScheduler};
 use std::error::Error;
 
-pub async fn start_cron_service(
-    billing_engine: Arc<BillingEngine>
-) -> Result<JobScheduler, Box<dyn Error>> {
-
-    let mut sched = JobScheduler::new().await?;
-
-    // CRON EXPRESSION: "sec min hour day_of_month month day_of_week"
-    // "0 0 9 1 * *" = At 09:00:00 AM, on the 1st day of every month.
-    let cron_schedule = "0 0 9 1 * *"; 
-
-    sched.add(
-        Job::new_async(cron_schedule, move |_uuid, _l| {
-            // Clone the Arc so we can move it into the async block
-            let engine = billing_engine.clone();
-            
-            Box::pin(async move {
-                if let Err(e) = engine.process_all_tenants().await {
-                    eprintln!("CRITICAL: Billing Job Failed: {}", e);
-                }
-            })
-        })?
-    ).await?;
+/// Registers every background job. Schedules & timezones come from JOB_* env vars
+/// (see core/jobs.rs), so changing when a job runs never needs a redeploy.
+pub fn build_job_registry(
+    db: PgPool,
+    billing_engine: Arc<BillingEngine>,
+    dunning: Arc<DunningService>,
+    subscriptions: Arc<SubscriptionManager>,
+    reporter: Arc<RevenueReporter>,
+    fiat_events: Arc<FiatEventProcessor>,
+    reserves: Arc<ReserveReconciler>,
+    ach: Arc<AchService>,
+    cards: Arc<CardService>,
+    statements: Arc<StatementService>,
+    gusto_events: Arc<GustoEventProcessor>,
+    employees: Arc<EmployeeSync>,
+    notifications: Arc<NotificationSender>,
+    iroha: Arc<IrohaClient>,
+) -> Result<JobRegistry, Box<dyn Error>> {
+    let mut registry = JobRegistry::new(db.clone());
+
+    // Month-based jobs work out "this month" on their own clock (JOB_<NAME>_TZ), not
+    // UTC: 9pm on Oct 31 in New York is already November in UTC
+    let billing_tz = JobSchedule::from_env(JobName::Billing)?.tz()?;
+    let report_tz = JobSchedule::from_env(JobName::RevenueReport)?.tz()?;
+    let statements_tz = JobSchedule::from_env(JobName::Statements)?.tz()?;
+
+    // 1. Monthly invoices
+    registry.register(JobName::Billing, move || {
+        let engine = billing_engine.clone();
+        async move {
+            engine
+                .process_all_tenants(BillingPeriod::current_in(billing_tz))
+                .await
+                .map_err(|e| e.to_string())
+        }
+    })?;
+
+    // 2. Gusto benefit costs, before they land on an invoice
+    registry.register(JobName::WholesaleCostSync, move || {
+        let subscriptions = subscriptions.clone();
+        async move { subscriptions.sync_wholesale_costs().await.map_err(|e| e.to_string()) }
+    })?;
+
+    // 3. Usage metering reads the explorer index: make sure it keeps up
+    let max_lag_blocks = std::env::var("INDEXER_MAX_LAG_BLOCKS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(20);
+    let indexer_db = db.clone();
+    registry.register(JobName::IndexerHealth, move || {
+        let db = indexer_db.clone();
+        let iroha = iroha.clone();
+        async move {
+            check_indexer_health(&db, &iroha, max_lag_blocks)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+    })?;
+
+    // 4. Retries failed charges & suspends
+    registry.register(JobName::Dunning, move || {
+        let dunning = dunning.clone();
+        async move { dunning.run().await.map_err(|e| e.to_string()) }
+    })?;
+
+    // 5. Contract notices, renewals & expiry
+    let contracts_db = db.clone();
+    registry.register(JobName::ContractRenewals, move || {
+        let db = contracts_db.clone();
+        async move { process_contract_renewals(&db).await.map_err(|e| e.to_string()) }
+    })?;
+
+    // 6. Snapshot of the month that just ended
+    registry.register(JobName::RevenueReport, move || {
+        let reporter = reporter.clone();
+        async move {
+            reporter
+                .generate_monthly(BillingPeriod::current_in(report_tz).previous())
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+    })?;
+
+    // 7. Unit events that could not be applied when they arrived
+    registry.register(JobName::FiatEvents, move || {
+        let fiat_events = fiat_events.clone();
+        async move { fiat_events.process_pending().await.map_err(|e| e.to_string()) }
+    })?;
+
+    // 8. Cash barcodes nobody used
+    let cash_db = db.clone();
+    registry.register(JobName::CashBarcodes, move || {
+        let db = cash_db.clone();
+        async move { expire_stale_barcodes(&db).await.map(|_| ()).map_err(|e| e.to_string()) }
+    })?;
+
+    // 9. Is every on-chain dollar backed? (signed proof of reserves)
+    registry.register(JobName::Reserves, move || {
+        let reserves = reserves.clone();
+        async move {
+            match reserves.run().await {
+                Ok(report) if report.status == "balanced" => Ok(()),
+                Ok(report) => Err(format!("Reserve discrepancy, see report {}", report.id)),
+                Err(e) => Err(e.to_string()),
+            }
+        }
+    })?;
+
+    // 10. ACH payments the webhook has not moved along
+    registry.register(JobName::AchPayments, move || {
+        let ach = ach.clone();
+        async move { ach.sync_open().await.map_err(|e| e.to_string()) }
+    })?;
+
+    // 11. Card holds whose authorization went quiet
+    registry.register(JobName::CardHolds, move || {
+        let cards = cards.clone();
+        async move { cards.release_stale_holds().await.map_err(|e| e.to_string()) }
+    })?;
+
+    // 12. Statements for the month that just ended
+    registry.register(JobName::Statements, move || {
+        let statements = statements.clone();
+        async move {
+            statements
+                .generate_all(BillingPeriod::current_in(statements_tz).previous())
+                .await
+                .map_err(|e| e.to_string())
+        }
+    })?;
+
+    // 13. Gusto events that didn't apply on arrival
+    registry.register(JobName::GustoEvents, move || {
+        let gusto_events = gusto_events.clone();
+        async move { gusto_events.process_pending().await.map_err(|e| e.to_string()) }
+    })?;
+
+    // 14. Catch employee changes no webhook told us about
+    registry.register(JobName::EmployeeSync, move || {
+        let employees = employees.clone();
+        async move { employees.sync_all().await.map_err(|e| e.to_string()) }
+    })?;
+
+    // 15. Dunning, renewal & suspension emails waiting in the outbox
+    registry.register(JobName::Notifications, move || {
+        let notifications = notifications.clone();
+        async move { notifications.deliver_pending().await.map_err(|e| e.to_string()) }
+    })?;
+
+    Ok(registry)
+}
+
+pub async fn start_cron_service(registry: Arc<JobRegistry>) -> Result<JobScheduler, Box<dyn Error>> {
+    let sched = JobScheduler::new().await?;
+
+    for (name, job) in registry.jobs() {
+        if !job.schedule.enabled {
+            println!("⏸️  Job {} disabled.", name.as_str());
+            continue;
+        }
+
+        let name = *name;
+        let registry = registry.clone();
+        sched.add(
+            Job::new_async_tz(job.schedule.cron.as_str(), job.schedule.tz()?, move |_uuid, _l| {
+                // Clone the Arc so we can move it into the async block
+                let registry = registry.clone();
+
+                Box::pin(async move {
+                    if let Err(e) = registry.run(name, JobTrigger::Schedule, None).await {
+                        eprintln!("CRITICAL: Job {} could not run: {}", name.as_str(), e);
+                    }
+                })
+            })?
+        ).await?;
+
+        println!("⏰ Job {} scheduled: \"{}\" ({}).", name.as_str(), job.schedule.cron, job.schedule.timezone);
+    }
 
     // Start the scheduler in the background
     sched.start().await?;
 
-    println!("⏰ Cron Scheduler Started. Next Billing: 1st of Month at 9am.");
+    println!("⏰ Cron Scheduler Started.");
 
     Ok(sched)
-}
\ No newline at end of file
+}
@@ -0,0 +1,623 @@
+use crate::sandbox::ledger::{SandboxInstruction, SandboxLedger};
+use eyre::{eyre, Result};
+use iroha_client::client::{Client, ClientConfig, ClientQueryError};
+use iroha_data_model::prelude::*;
+use std::str::FromStr;
+
+/// Account metadata holding what a domain freeze removed (keys, permission tokens)
+const FROZEN_ACCESS_KEY: &str = "frozen_access";
+
+/// Typed value in a Store asset's metadata
+pub enum StoreValue {
+    Text(String),
+    Cents(i64), // Stored as a numeric with 2 decimals
+}
+
+/// Entries written under one account's Store asset
+pub struct StoreRecord<'a> {
+    pub account: &'a str,
+    pub fields: Vec<(String, StoreValue)>,
+}
+
+/// Our connection to the PRIVATE network, signing as the platform admin account.
+/// Every write waits for its block to commit: an `Ok` hash means the
+/// instructions are on the ledger, a transaction the peers reject (e.g. a
+/// missing permission) is an `Err`. Callers mark things paid/recorded on `Ok`.
+pub struct IrohaClient {
+    backend: Backend,
+}
+
+enum Backend {
+    Live(Client),
+    Sandbox(SandboxLedger), // APP_MODE=sandbox: in-memory ledger, no peer needed
+}
+
+impl IrohaClient {
+    /// Reads IROHA_API_URL, IROHA_ACCOUNT_ID, IROHA_PUBLIC_KEY and IROHA_PRIVATE_KEY
+    pub fn from_env() -> Result<Self> {
+        let api_url = std::env::var("IROHA_API_URL")?.parse()?;
+        let account_id = AccountId::from_str(&std::env::var("IROHA_ACCOUNT_ID")?)?;
+        let key_pair = KeyPair::new(
+            PublicKey::from_str(&std::env::var("IROHA_PUBLIC_KEY")?)?,
+            PrivateKey::from_str(&std::env::var("IROHA_PRIVATE_KEY")?)?,
+        )?;
+
+        let config = ClientConfig::new(api_url, account_id, key_pair);
+        Ok(Self { backend: Backend::Live(Client::new(config)) })
+    }
+
+    /// A fresh in-memory ledger (sandbox mode) with the platform's accounts registered
+    pub fn sandbox(platform_accounts: &[String]) -> Self {
+        Self { backend: Backend::Sandbox(SandboxLedger::with_accounts(platform_accounts)) }
+    }
+
+    /// The underlying Iroha client, for building custom transactions.
+    /// Not available in sandbox mode.
+    pub fn raw(&self) -> Result<&Client> {
+        match &self.backend {
+            Backend::Live(client) => Ok(client),
+            Backend::Sandbox(_) => Err(eyre!("No Iroha peer in sandbox mode")),
+        }
+    }
+
+    /// Current height of the chain, as reported by the peer
+    pub async fn block_height(&self) -> Result<u64> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => return Ok(ledger.block_height()),
+        };
+        let status = client.get_status().await?;
+        Ok(status.blocks)
+    }
+
+    /// Balance of a USD-style numeric asset, in cents
+    pub async fn balance_cents(&self, account: &str, asset_definition: &str) -> Result<i64> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => return Ok(ledger.balance_cents(account, asset_definition)),
+        };
+        let asset_id = AssetId::new(asset_definition.parse()?, AccountId::from_str(account)?);
+        let quantity = match client.request(FindAssetQuantityById::new(asset_id)).await {
+            Ok(q) => q,
+            Err(_) => return Ok(0), // No asset yet = empty balance
+        };
+        let amount: f64 = quantity.to_string().parse()?;
+        Ok((amount * 100.0).round() as i64)
+    }
+
+    /// Every account holding `asset_definition`, with its balance in cents
+    pub async fn asset_holders_cents(&self, asset_definition: &str) -> Result<Vec<(String, i64)>> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => return Ok(ledger.holders(asset_definition)),
+        };
+        let definition_id: AssetDefinitionId = asset_definition.parse()?;
+        let assets = client.request(FindAssetsByAssetDefinitionId::new(definition_id)).await?;
+
+        let mut holders = Vec::new();
+        for asset in assets {
+            let amount: f64 = asset.value().to_string().parse()?;
+            let cents = (amount * 100.0).round() as i64;
+            if cents != 0 {
+                holders.push((asset.id().account_id().to_string(), cents));
+            }
+        }
+        Ok(holders)
+    }
+
+    /// PAY AN INVOICE ON-CHAIN
+    /// ONE transaction: the USD transfer AND a receipt under `receipt_key` in the
+    /// revenue account's metadata. Either both land or neither does, so the
+    /// receipt is proof the invoice was paid. Returns the hash of the
+    /// COMMITTED transaction.
+    pub async fn settle_invoice(
+        &self,
+        from_account: &str,
+        to_account: &str,
+        asset_definition: &str,
+        amount_cents: i64,
+        receipt_key: &str,
+    ) -> Result<String> {
+        self.transfer_with_receipt(from_account, to_account, asset_definition, amount_cents, receipt_key).await
+    }
+
+    /// USD transfer with a receipt on the RECEIVING account, in one transaction
+    pub async fn transfer_with_receipt(
+        &self,
+        from_account: &str,
+        to_account: &str,
+        asset_definition: &str,
+        amount_cents: i64,
+        receipt_key: &str,
+    ) -> Result<String> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => {
+                return ledger.submit(vec![
+                    SandboxInstruction::Transfer { from: from_account, to: to_account, asset: asset_definition, cents: amount_cents },
+                    SandboxInstruction::SetKeyValue { account: to_account, key: receipt_key, value: from_account },
+                ])
+            }
+        };
+
+        let from = AccountId::from_str(from_account)?;
+        let to = AccountId::from_str(to_account)?;
+        let asset_id = AssetId::new(asset_definition.parse()?, from.clone());
+
+        let transfer = Transfer::asset_numeric(asset_id, Numeric::new(amount_cents as u128, 2), to.clone());
+        let receipt = SetKeyValue::account(to, receipt_key.parse()?, from_account.to_string().into());
+
+        let transaction = client.build_transaction(vec![transfer.into(), receipt.into()], None);
+        let hash = client.submit_transaction_blocking(transaction).await?;
+        Ok(hash.to_string())
+    }
+
+    /// Whether the receipt for `receipt_key` exists on the revenue account
+    pub async fn has_settlement_receipt(&self, revenue_account: &str, receipt_key: &str) -> Result<bool> {
+        self.has_receipt(revenue_account, receipt_key).await
+    }
+
+    /// Whether `account` carries a receipt under `receipt_key` in its metadata
+    pub async fn has_receipt(&self, account: &str, receipt_key: &str) -> Result<bool> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => return Ok(ledger.has_key(account, receipt_key)),
+        };
+        let account = client.request(FindAccountById::new(AccountId::from_str(account)?)).await?;
+        Ok(account.metadata().get(&receipt_key.parse()?).is_some())
+    }
+
+    /// FIAT IN: mints USD cents to `account`, with a receipt on the same account
+    /// in the SAME transaction (so a redelivered deposit can never mint twice).
+    pub async fn mint_with_receipt(
+        &self,
+        account: &str,
+        asset_definition: &str,
+        amount_cents: i64,
+        receipt_key: &str,
+    ) -> Result<String> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => {
+                return ledger.submit(vec![
+                    SandboxInstruction::Mint { account, asset: asset_definition, cents: amount_cents },
+                    SandboxInstruction::SetKeyValue { account, key: receipt_key, value: "mint" },
+                ])
+            }
+        };
+
+        let account_id = AccountId::from_str(account)?;
+        let asset_id = AssetId::new(asset_definition.parse()?, account_id.clone());
+
+        let mint = Mint::asset_numeric(Numeric::new(amount_cents as u128, 2), asset_id);
+        let receipt = SetKeyValue::account(account_id, receipt_key.parse()?, "mint".to_string().into());
+
+        let transaction = client.build_transaction(vec![mint.into(), receipt.into()], None);
+        let hash = client.submit_transaction_blocking(transaction).await?;
+        Ok(hash.to_string())
+    }
+
+    /// FIAT OUT: burns USD cents from `account`, with a receipt (see `mint_with_receipt`)
+    pub async fn burn_with_receipt(
+        &self,
+        account: &str,
+        asset_definition: &str,
+        amount_cents: i64,
+        receipt_key: &str,
+    ) -> Result<String> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => {
+                return ledger.submit(vec![
+                    SandboxInstruction::Burn { account, asset: asset_definition, cents: amount_cents },
+                    SandboxInstruction::SetKeyValue { account, key: receipt_key, value: "burn" },
+                ])
+            }
+        };
+
+        let account_id = AccountId::from_str(account)?;
+        let asset_id = AssetId::new(asset_definition.parse()?, account_id.clone());
+
+        let burn = Burn::asset_numeric(Numeric::new(amount_cents as u128, 2), asset_id);
+        let receipt = SetKeyValue::account(account_id, receipt_key.parse()?, "burn".to_string().into());
+
+        let transaction = client.build_transaction(vec![burn.into(), receipt.into()], None);
+        let hash = client.submit_transaction_blocking(transaction).await?;
+        Ok(hash.to_string())
+    }
+
+    /// CARD CAPTURE, in one transaction with a receipt on `hold_account`:
+    /// burns what the merchant captured out of the held USD (and out of `account`
+    /// if the capture is larger than the hold, e.g. tips), and hands back
+    /// whatever was held but not captured.
+    pub async fn capture_hold(
+        &self,
+        hold_account: &str,
+        account: &str,
+        asset_definition: &str,
+        held_cents: i64,
+        captured_cents: i64,
+        receipt_key: &str,
+    ) -> Result<String> {
+        let from_hold = captured_cents.min(held_cents);
+        let extra = captured_cents - from_hold;
+        let released = held_cents - from_hold;
+
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => {
+                let mut instructions = Vec::new();
+                if from_hold > 0 {
+                    instructions.push(SandboxInstruction::Burn { account: hold_account, asset: asset_definition, cents: from_hold });
+                }
+                if extra > 0 {
+                    instructions.push(SandboxInstruction::Burn { account, asset: asset_definition, cents: extra });
+                }
+                if released > 0 {
+                    instructions.push(SandboxInstruction::Transfer { from: hold_account, to: account, asset: asset_definition, cents: released });
+                }
+                instructions.push(SandboxInstruction::SetKeyValue { account: hold_account, key: receipt_key, value: account });
+                return ledger.submit(instructions);
+            }
+        };
+
+        let hold_id = AccountId::from_str(hold_account)?;
+        let account_id = AccountId::from_str(account)?;
+        let definition: AssetDefinitionId = asset_definition.parse()?;
+        let held_asset = AssetId::new(definition.clone(), hold_id.clone());
+
+        let mut instructions: Vec<InstructionBox> = Vec::new();
+        if from_hold > 0 {
+            instructions.push(Burn::asset_numeric(Numeric::new(from_hold as u128, 2), held_asset.clone()).into());
+        }
+        if extra > 0 {
+            let own_asset = AssetId::new(definition, account_id.clone());
+            instructions.push(Burn::asset_numeric(Numeric::new(extra as u128, 2), own_asset).into());
+        }
+        if released > 0 {
+            instructions.push(Transfer::asset_numeric(held_asset, Numeric::new(released as u128, 2), account_id).into());
+        }
+        instructions.push(SetKeyValue::account(hold_id, receipt_key.parse()?, account.to_string().into()).into());
+
+        let transaction = client.build_transaction(instructions, None);
+        let hash = client.submit_transaction_blocking(transaction).await?;
+        Ok(hash.to_string())
+    }
+
+    /// Registers a tenant's domain, owned by the platform account, with
+    /// `metadata` set in the SAME transaction
+    pub async fn register_domain(&self, domain: &str, metadata: &[(&str, &str)]) -> Result<String> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => {
+                let mut instructions = vec![SandboxInstruction::RegisterDomain { domain }];
+                for (key, value) in metadata {
+                    instructions.push(SandboxInstruction::SetDomainKeyValue { domain, key, value });
+                }
+                return ledger.submit(instructions);
+            }
+        };
+
+        let domain_id: DomainId = domain.parse()?;
+        let mut instructions: Vec<InstructionBox> = vec![Register::domain(Domain::new(domain_id.clone())).into()];
+        for (key, value) in metadata {
+            instructions.push(SetKeyValue::domain(domain_id.clone(), key.parse()?, value.to_string().into()).into());
+        }
+
+        let transaction = client.build_transaction(instructions, None);
+        let hash = client.submit_transaction_blocking(transaction).await?;
+        Ok(hash.to_string())
+    }
+
+    /// Whether `domain` is registered. Only "not found" is `false`: any other
+    /// query failure is an error, so a flaky peer never looks like a missing domain.
+    pub async fn has_domain(&self, domain: &str) -> Result<bool> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => return Ok(ledger.has_domain(domain)),
+        };
+        match client.request(FindDomainById::new(domain.parse()?)).await {
+            Ok(_) => Ok(true),
+            Err(e) if is_not_found(&e) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Registers `account` with `metadata` set in the SAME transaction. The
+    /// platform key is its signatory until the owner claims the account.
+    pub async fn register_account(&self, account: &str, metadata: &[(&str, &str)]) -> Result<String> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => {
+                let mut instructions = vec![SandboxInstruction::RegisterAccount { account }];
+                for (key, value) in metadata {
+                    instructions.push(SandboxInstruction::SetKeyValue { account, key, value });
+                }
+                return ledger.submit(instructions);
+            }
+        };
+
+        let account_id = AccountId::from_str(account)?;
+        let mut instructions: Vec<InstructionBox> =
+            vec![Register::account(Account::new(account_id.clone(), client.key_pair.public_key().clone())).into()];
+        for (key, value) in metadata {
+            instructions.push(SetKeyValue::account(account_id.clone(), key.parse()?, value.to_string().into()).into());
+        }
+
+        let transaction = client.build_transaction(instructions, None);
+        let hash = client.submit_transaction_blocking(transaction).await?;
+        Ok(hash.to_string())
+    }
+
+    /// One metadata entry on an account (e.g. "status" = "terminated")
+    pub async fn set_account_metadata(&self, account: &str, key: &str, value: &str) -> Result<String> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => return ledger.submit(vec![SandboxInstruction::SetKeyValue { account, key, value }]),
+        };
+
+        let set = SetKeyValue::account(AccountId::from_str(account)?, key.parse()?, value.to_string().into());
+        let hash = client.submit_blocking(set).await?;
+        Ok(hash.to_string())
+    }
+
+    /// RECORDS + PAYMENTS, ATOMICALLY (the InsuranceBroker pattern)
+    /// Writes every record into the accounts' `store_definition` asset and makes
+    /// every (from, to, cents) transfer of `asset_definition` in ONE transaction:
+    /// all of it lands or none of it does. Registers the Store definition first
+    /// if the domain doesn't have it yet.
+    pub async fn record_with_transfers(
+        &self,
+        store_definition: &str,
+        records: &[StoreRecord<'_>],
+        asset_definition: &str,
+        transfers: &[(&str, &str, i64)],
+    ) -> Result<String> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => {
+                let fields: Vec<(&str, String, String)> = records
+                    .iter()
+                    .flat_map(|r| {
+                        r.fields.iter().map(move |(key, value)| {
+                            let value = match value {
+                                StoreValue::Text(text) => text.clone(),
+                                StoreValue::Cents(cents) => format!("{}.{:02}", cents / 100, cents % 100),
+                            };
+                            (r.account, key.clone(), value)
+                        })
+                    })
+                    .collect();
+
+                let mut instructions = Vec::new();
+                for (from, to, cents) in transfers {
+                    instructions.push(SandboxInstruction::Transfer { from, to, asset: asset_definition, cents: *cents });
+                }
+                for (account, key, value) in &fields {
+                    instructions.push(SandboxInstruction::SetAssetKeyValue { account, asset: store_definition, key, value });
+                }
+                return ledger.submit(instructions);
+            }
+        };
+
+        let definition_id: AssetDefinitionId = store_definition.parse()?;
+        let mut instructions: Vec<InstructionBox> = Vec::new();
+        if client.request(FindAssetDefinitionById::new(definition_id.clone())).await.is_err() {
+            instructions.push(Register::asset_definition(AssetDefinition::store(definition_id.clone())).into());
+        }
+
+        // Payments first: if one of them bounces, no record claims it was paid
+        let currency: AssetDefinitionId = asset_definition.parse()?;
+        for (from, to, cents) in transfers {
+            let source = AssetId::new(currency.clone(), AccountId::from_str(from)?);
+            instructions.push(Transfer::asset_numeric(source, Numeric::new(*cents as u128, 2), AccountId::from_str(to)?).into());
+        }
+
+        for record in records {
+            let asset_id = AssetId::new(definition_id.clone(), AccountId::from_str(record.account)?);
+            for (key, value) in &record.fields {
+                let value: MetadataValueBox = match value {
+                    StoreValue::Text(text) => text.clone().into(),
+                    StoreValue::Cents(cents) => Numeric::new(*cents as u128, 2).into(),
+                };
+                instructions.push(SetKeyValue::asset(asset_id.clone(), key.parse()?, value).into());
+            }
+        }
+
+        let transaction = client.build_transaction(instructions, None);
+        let hash = client.submit_transaction_blocking(transaction).await?;
+        Ok(hash.to_string())
+    }
+
+    /// Whether `account`'s `store_definition` asset has an entry under `key`
+    pub async fn has_store_key(&self, account: &str, store_definition: &str, key: &str) -> Result<bool> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => return Ok(ledger.has_asset_key(account, store_definition, key)),
+        };
+        let asset_id = AssetId::new(store_definition.parse()?, AccountId::from_str(account)?);
+        let asset = match client.request(FindAssetById::new(asset_id)).await {
+            Ok(asset) => asset,
+            Err(_) => return Ok(false), // No Store asset yet = nothing recorded
+        };
+        match asset.value() {
+            AssetValue::Store(metadata) => Ok(metadata.get(&key.parse()?).is_some()),
+            _ => Ok(false),
+        }
+    }
+
+    /// FREEZE A TENANT'S DOMAIN (non-payment)
+    /// ONE transaction, for every account in the domain: the tenant's keys are
+    /// removed as signatories and its transfer/mint/burn permission tokens are
+    /// revoked, so only the platform key can move the domain's assets (dunning
+    /// can still collect from the treasury). What was removed is stashed in the
+    /// account's `FROZEN_ACCESS_KEY` metadata for `unfreeze_domain` to put back.
+    pub async fn freeze_domain(&self, domain: &str) -> Result<()> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            // Every sandbox write is signed by the platform: no tenant keys to remove
+            Backend::Sandbox(ledger) => return ledger.set_domain_frozen(domain, true).map(|_| ()),
+        };
+        let domain_id: DomainId = domain.parse()?;
+        let platform_key = client.key_pair.public_key().clone();
+        let stash_key: Name = FROZEN_ACCESS_KEY.parse()?;
+
+        let mut instructions: Vec<InstructionBox> = Vec::new();
+        for account in client.request(FindAccountsByDomainId::new(domain_id.clone())).await? {
+            instructions.extend(freeze_instructions(client, &account).await?);
+        }
+        instructions.push(SetKeyValue::domain(domain_id, "status".parse()?, "frozen".to_string().into()).into());
+
+        let transaction = client.build_transaction(instructions, None);
+        client.submit_transaction_blocking(transaction).await?;
+        println!("🧊 Domain {} frozen.", domain);
+        Ok(())
+    }
+
+    /// Gives every account in the domain back the keys and tokens
+    /// `freeze_domain` took, in ONE transaction
+    pub async fn unfreeze_domain(&self, domain: &str) -> Result<()> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => return ledger.set_domain_frozen(domain, false).map(|_| ()),
+        };
+        let domain_id: DomainId = domain.parse()?;
+
+        let mut instructions: Vec<InstructionBox> = Vec::new();
+        for account in client.request(FindAccountsByDomainId::new(domain_id.clone())).await? {
+            instructions.extend(unfreeze_instructions(client, &account)?);
+        }
+        instructions.push(SetKeyValue::domain(domain_id, "status".parse()?, "active".to_string().into()).into());
+
+        let transaction = client.build_transaction(instructions, None);
+        client.submit_transaction_blocking(transaction).await?;
+        println!("🔥 Domain {} unfrozen.", domain);
+        Ok(())
+    }
+
+    /// FREEZE ONE ACCOUNT (e.g. a terminated employee): like `freeze_domain`,
+    /// for a single account, with `key` = `value` set in the SAME transaction
+    pub async fn freeze_account(&self, account: &str, key: &str, value: &str) -> Result<String> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            // Every sandbox write is signed by the platform: no account keys to remove
+            Backend::Sandbox(ledger) => return ledger.submit(vec![SandboxInstruction::SetKeyValue { account, key, value }]),
+        };
+        let account_id = AccountId::from_str(account)?;
+        let current = client.request(FindAccountById::new(account_id.clone())).await?;
+
+        let mut instructions = freeze_instructions(client, &current).await?;
+        instructions.push(SetKeyValue::account(account_id, key.parse()?, value.to_string().into()).into());
+
+        let transaction = client.build_transaction(instructions, None);
+        let hash = client.submit_transaction_blocking(transaction).await?;
+        Ok(hash.to_string())
+    }
+
+    /// Gives one account back what `freeze_account` took, with `key` = `value`
+    pub async fn unfreeze_account(&self, account: &str, key: &str, value: &str) -> Result<String> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => return ledger.submit(vec![SandboxInstruction::SetKeyValue { account, key, value }]),
+        };
+        let account_id = AccountId::from_str(account)?;
+        let current = client.request(FindAccountById::new(account_id.clone())).await?;
+
+        let mut instructions = unfreeze_instructions(client, &current)?;
+        instructions.push(SetKeyValue::account(account_id, key.parse()?, value.to_string().into()).into());
+
+        let transaction = client.build_transaction(instructions, None);
+        let hash = client.submit_transaction_blocking(transaction).await?;
+        Ok(hash.to_string())
+    }
+
+    /// Whether `account` is registered. Only "not found" is `false`.
+    pub async fn has_account(&self, account: &str) -> Result<bool> {
+        let client = match &self.backend {
+            Backend::Live(client) => client,
+            Backend::Sandbox(ledger) => return Ok(ledger.has_account(account)),
+        };
+        match client.request(FindAccountById::new(AccountId::from_str(account)?)).await {
+            Ok(_) => Ok(true),
+            Err(e) if is_not_found(&e) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Strips one account's own keys and asset-moving tokens (the platform key
+/// stays or joins) and stashes them under `FROZEN_ACCESS_KEY`. Nothing if it is
+/// frozen already: the first stash is the one to restore.
+async fn freeze_instructions(client: &Client, account: &Account) -> Result<Vec<InstructionBox>> {
+    let platform_key = client.key_pair.public_key().clone();
+    let stash_key: Name = FROZEN_ACCESS_KEY.parse()?;
+    if account.metadata().get(&stash_key).is_some() {
+        return Ok(Vec::new());
+    }
+
+    let account_id = account.id().clone();
+    let own_keys: Vec<PublicKey> = account.signatories().filter(|k| **k != platform_key).cloned().collect();
+    let tokens: Vec<PermissionToken> = client
+        .request(FindPermissionTokensByAccountId::new(account_id.clone()))
+        .await?
+        .into_iter()
+        .filter(|token| moves_assets(token))
+        .collect();
+
+    let mut instructions: Vec<InstructionBox> = Vec::new();
+    // An account always needs a signatory: ours stays (or joins) while frozen
+    let platform_added = !account.signatories().any(|k| *k == platform_key);
+    if platform_added {
+        instructions.push(Mint::account_public_key(platform_key, account_id.clone()).into());
+    }
+    for key in &own_keys {
+        instructions.push(Burn::account_public_key(key.clone(), account_id.clone()).into());
+    }
+    for token in &tokens {
+        instructions.push(Revoke::permission(token.clone(), account_id.clone()).into());
+    }
+    let stash = serde_json::json!({
+        "signatories": own_keys.iter().map(|k| k.to_string()).collect::<Vec<_>>(),
+        "permissions": tokens,
+        "platform_added": platform_added,
+    });
+    instructions.push(SetKeyValue::account(account_id, stash_key, stash.to_string().into()).into());
+    Ok(instructions)
+}
+
+/// Puts back what `freeze_instructions` took (nothing if the account isn't frozen)
+fn unfreeze_instructions(client: &Client, account: &Account) -> Result<Vec<InstructionBox>> {
+    let platform_key = client.key_pair.public_key().clone();
+    let stash_key: Name = FROZEN_ACCESS_KEY.parse()?;
+    let stash: serde_json::Value = match account.metadata().get(&stash_key) {
+        Some(value) => serde_json::from_str(&value.to_string())?,
+        None => return Ok(Vec::new()),
+    };
+
+    let account_id = account.id().clone();
+    let mut instructions: Vec<InstructionBox> = Vec::new();
+    for key in stash["signatories"].as_array().into_iter().flatten() {
+        let key = PublicKey::from_str(key.as_str().ok_or_else(|| eyre!("Bad frozen signatory"))?)?;
+        instructions.push(Mint::account_public_key(key, account_id.clone()).into());
+    }
+    let tokens: Vec<PermissionToken> = serde_json::from_value(stash["permissions"].clone())?;
+    for token in tokens {
+        instructions.push(Grant::permission(token, account_id.clone()).into());
+    }
+    if stash["platform_added"].as_bool() == Some(true) {
+        instructions.push(Burn::account_public_key(platform_key, account_id.clone()).into());
+    }
+    instructions.push(RemoveKeyValue::account(account_id, stash_key).into());
+    Ok(instructions)
+}
+
+/// Permission tokens that let an account move assets (its own or others')
+fn moves_assets(token: &PermissionToken) -> bool {
+    let definition = token.definition_id().to_string();
+    ["CanTransfer", "CanMint", "CanBurn"].iter().any(|prefix| definition.starts_with(prefix))
+}
+
+/// The peer answered the query, and the answer is "no such entity"
+fn is_not_found(e: &ClientQueryError) -> bool {
+    matches!(e, ClientQueryError::Validation(ValidationFail::QueryFailed(QueryExecutionFail::Find(_))))
+}
@@ -0,0 +1,7 @@
+// The platform as a library: main.rs wires the services together and starts
+// the server, test/ drives the same services end to end.
+pub mod api;
+pub mod core;
+pub mod cron;
+pub mod ledger;
+pub mod sandbox;
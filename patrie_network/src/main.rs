@@ -1,21 +1,235 @@
-mod cron; // Register the new module
+use patrie_network::{api, cron, sandbox};
+use patrie_network::core::ach::{AchConfig, AchService};
+use patrie_network::core::benefit_catalog::{BenefitCatalog, BenefitCatalogConfig};
+use patrie_network::core::billing_engine::{BillingEngine, OnChainSettlement};
+use patrie_network::core::cards::{CardConfig, CardService};
+use patrie_network::core::cash_deposits::{CashConfig, CashDepositService};
+use patrie_network::core::credit_notes::CreditNoteService;
+use patrie_network::core::dunning::{DunningPolicy, DunningService};
+use patrie_network::core::employee_sync::EmployeeSync;
+use patrie_network::core::explorer_indexer::ExplorerIndexer;
+use patrie_network::core::fiat_banking::{FiatAsset, UnitClient};
+use patrie_network::core::fiat_events::{FiatConfig, FiatEventProcessor};
+use patrie_network::core::gusto::{GustoClient, GustoOAuth};
+use patrie_network::core::gusto_events::{GustoEventProcessor, GustoWebhookConfig};
+use patrie_network::core::gusto_tokens::{GustoTokenStore, TokenCipher};
+use patrie_network::core::invoice::BillingPeriod;
+use patrie_network::core::jobs::{JobName, JobSchedule};
+use patrie_network::core::metering::MeteringConfig;
+use patrie_network::core::notifications::{MailConfig, NotificationSender};
+use patrie_network::core::onboarding::TenantOnboarding;
+use patrie_network::core::payroll::{PayrollConfig, PayrollMirror};
+use patrie_network::core::reporting::{ReportingConfig, RevenueReporter};
+use patrie_network::core::reserves::{ReserveConfig, ReserveReconciler};
+use patrie_network::core::statements::{StatementConfig, StatementService};
+use patrie_network::core::subscription::SubscriptionManager;
+use patrie_network::ledger::client::IrohaClient;
+use patrie_network::sandbox::RuntimeMode;
+use actix_web::{web, App, HttpServer};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
     // 1. Setup Database & Clients
-    let db_pool = PgPoolOptions::new().connect("postgres://...").await.unwrap();
-    let unit_client = UnitClient::new("...".to_string());
+    dotenv::dotenv().ok(); // .env, if present (see it for every setting)
+    let mode = RuntimeMode::from_env().expect("Invalid APP_MODE");
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL missing");
+    let db_pool = PgPoolOptions::new()
+        .connect(&database_url)
+        .await
+        .expect("Failed to connect to DATABASE_URL");
+    // Sandbox: a local Postgres (docker-compose) brought up to date on start
+    if mode == RuntimeMode::Sandbox {
+        sqlx::migrate!("./migrations").run(&db_pool).await.expect("Failed to run migrations");
+    }
+    let token_cipher = match mode {
+        RuntimeMode::Live => TokenCipher::from_env().expect("Invalid GUSTO_TOKEN_KEY"),
+        RuntimeMode::Sandbox => TokenCipher::ephemeral(),
+    };
+    let gusto_tokens = GustoTokenStore::new(db_pool.clone(), token_cipher.clone());
+    // Our LLC's Unit account that subscription payments are booked into
+    let unit_revenue_account_id =
+        std::env::var("UNIT_REVENUE_ACCOUNT_ID").unwrap_or_else(|_| "YOUR_LLC_REVENUE_ACCOUNT_ID".to_string());
+    // The on-chain USD every fiat service mints, burns and reports
+    let fiat_asset = FiatAsset::from_env();
+    let (unit_client, gusto_client, iroha_client) = match mode {
+        RuntimeMode::Live => (
+            UnitClient::new("...".to_string()),
+            GustoClient::new("...".to_string(), GustoOAuth::from_env(), gusto_tokens.clone()),
+            IrohaClient::from_env().expect("Iroha config missing (IROHA_* env vars)"),
+        ),
+        // Offline: local Unit & Gusto simulators, in-memory ledger
+        RuntimeMode::Sandbox => {
+            let port = std::env::var("SANDBOX_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(3900);
+            // The platform accounts the app is configured with exist from the start
+            let platform = sandbox::PlatformAccounts {
+                unit_accounts: vec![unit_revenue_account_id.clone(), AchConfig::from_env(&fiat_asset).verification_account_id],
+                ledger_accounts: vec![
+                    CardConfig::from_env(&fiat_asset).hold_account_id,
+                    CashConfig::from_env(&fiat_asset).hold_account_id,
+                    OnChainSettlement::from_env().revenue_account_id,
+                ],
+            };
+            let base_url = sandbox::start_simulators(port, &platform).expect("Failed to start sandbox simulators");
+            (
+                UnitClient::with_base_url("sandbox".to_string(), format!("{}/unit", base_url)),
+                GustoClient::with_base_url(
+                    "sandbox".to_string(),
+                    format!("{}/gusto", base_url),
+                    GustoOAuth::from_env(),
+                    gusto_tokens.clone(),
+                ),
+                IrohaClient::sandbox(&platform.ledger_accounts),
+            )
+        }
+    };
+    let unit_client = Arc::new(unit_client);
+    let gusto_client = Arc::new(gusto_client);
+    let iroha_client = Arc::new(iroha_client);
+    println!("🌐 Running in {} mode.", mode.as_str());
+
+    // Plaintext Gusto tokens from before encrypted storage
+    if mode == RuntimeMode::Live {
+        match gusto_tokens.import_plaintext_tokens().await {
+            Ok(0) => {}
+            Ok(n) => println!("🔐 Encrypted {} legacy Gusto token(s).", n),
+            Err(e) => eprintln!("❌ Gusto token import failed: {}", e),
+        }
+    }
     
     // 2. Create the Billing Engine
     let billing_engine = Arc::new(BillingEngine::new(
         db_pool.clone(),
-        unit_client,
-        "YOUR_LLC_REVENUE_ACCOUNT_ID".to_string()
+        unit_client.clone(),
+        iroha_client.clone(),
+        unit_revenue_account_id.clone(),
+        OnChainSettlement::from_env(),
+        MeteringConfig::from_env(&fiat_asset),
     ));
 
+    let onboarding = Arc::new(TenantOnboarding::new(
+        db_pool.clone(),
+        gusto_client.clone(),
+        unit_client.clone(),
+        iroha_client.clone(),
+    ));
+    let benefits = Arc::new(BenefitCatalog::new(db_pool.clone(), gusto_client.clone(), BenefitCatalogConfig::from_env()));
+    let billing_tz = JobSchedule::from_env(JobName::Billing)
+        .and_then(|schedule| schedule.tz())
+        .expect("Invalid JOB_BILLING_TZ");
+    let subscriptions =
+        Arc::new(SubscriptionManager::new(db_pool.clone(), gusto_client.clone(), benefits.clone(), billing_tz));
+    let employees = Arc::new(EmployeeSync::new(db_pool.clone(), gusto_client.clone(), iroha_client.clone()));
+    let payroll = Arc::new(PayrollMirror::new(
+        db_pool.clone(),
+        gusto_client.clone(),
+        unit_client.clone(),
+        iroha_client.clone(),
+        employees.clone(),
+        PayrollConfig::new(&fiat_asset),
+    ));
+    let gusto_events = Arc::new(GustoEventProcessor::new(
+        db_pool.clone(),
+        subscriptions.clone(),
+        employees.clone(),
+        payroll.clone(),
+        GustoWebhookConfig::from_env(),
+        token_cipher.clone(),
+    ));
+    let dunning = Arc::new(DunningService::new(
+        db_pool.clone(),
+        billing_engine.clone(),
+        iroha_client.clone(),
+        DunningPolicy::from_env(),
+    ));
+    let notifications = Arc::new(NotificationSender::new(db_pool.clone(), MailConfig::from_env()));
+    let credit_notes = Arc::new(CreditNoteService::new(db_pool.clone(), billing_engine.clone()));
+    let reporter = Arc::new(RevenueReporter::new(db_pool.clone(), ReportingConfig::from_env()));
+    let fiat_events = Arc::new(FiatEventProcessor::new(
+        db_pool.clone(),
+        unit_client.clone(),
+        iroha_client.clone(),
+        FiatConfig::from_env(&fiat_asset),
+    ));
+    let ach = Arc::new(AchService::new(
+        db_pool.clone(),
+        unit_client.clone(),
+        iroha_client.clone(),
+        AchConfig::from_env(&fiat_asset),
+    ));
+    let cash_deposits = Arc::new(CashDepositService::new(
+        db_pool.clone(),
+        unit_client.clone(),
+        iroha_client.clone(),
+        ach.clone(),
+        CashConfig::from_env(&fiat_asset),
+    ));
+    let cards = Arc::new(CardService::new(
+        db_pool.clone(),
+        unit_client.clone(),
+        iroha_client.clone(),
+        CardConfig::from_env(&fiat_asset),
+    ));
+    let statements = Arc::new(StatementService::new(
+        db_pool.clone(),
+        unit_client.clone(),
+        iroha_client.clone(),
+        StatementConfig::from_env(&fiat_asset),
+    ));
+    let reserves = Arc::new(ReserveReconciler::new(
+        db_pool.clone(),
+        unit_client.clone(),
+        iroha_client.clone(),
+        ReserveConfig::from_env(&fiat_asset).expect("Invalid RESERVES_SIGNING_KEY"),
+    ));
+
+    // CLI: `patrie_network billing-preview [YYYY-MM]`
+    // Prints the dry-run invoices for every tenant and exits without moving money.
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("billing-preview") {
+        let period = match args.get(2) {
+            Some(p) => BillingPeriod::parse(p).expect("Invalid period, expected YYYY-MM"),
+            None => BillingPeriod::current().next(),
+        };
+        match billing_engine.preview_all_tenants(period).await {
+            Ok(previews) => println!("{}", serde_json::to_string_pretty(&previews).unwrap()),
+            Err(e) => eprintln!("❌ Billing preview failed: {}", e),
+        }
+        return Ok(());
+    }
+
     // 3. Start the Cron Service
+    // A bad schedule or timezone is a config error: refuse to start.
+    let jobs = Arc::new(
+        cron::build_job_registry(
+            db_pool.clone(),
+            billing_engine.clone(),
+            dunning.clone(),
+            subscriptions.clone(),
+            reporter.clone(),
+            fiat_events.clone(),
+            reserves.clone(),
+            ach.clone(),
+            cards.clone(),
+            statements.clone(),
+            gusto_events.clone(),
+            employees.clone(),
+            notifications.clone(),
+            iroha_client.clone(),
+        )
+        .expect("Invalid job configuration (JOB_* env vars)"),
+    );
+
+    // Metering, revenue reports and statements read the explorer index.
+    // The sandbox ledger has no block stream: nothing to index there.
+    if mode == RuntimeMode::Live {
+        let indexer = ExplorerIndexer::new(db_pool.clone(), iroha_client.clone());
+        tokio::spawn(async move { indexer.start_syncing().await });
+    }
+
     // We handle the error here so the app doesn't crash if the scheduler fails
-    match cron::start_cron_service(billing_engine.clone()).await {
+    match cron::start_cron_service(jobs.clone()).await {
         Ok(_) => println!("✅ Background jobs running..."),
         Err(e) => eprintln!("❌ Failed to start cron: {}", e),
     }
@@ -23,7 +237,26 @@ async fn main() -> std::io::Result<()> {
     // 4. Start Web Server
     HttpServer::new(move || {
         App::new()
-            // ... your routes ...
+            .app_data(web::Data::new(db_pool.clone()))
+            .app_data(web::Data::new(billing_engine.clone()))
+            .app_data(web::Data::new(onboarding.clone()))
+            .app_data(web::Data::new(subscriptions.clone()))
+            .app_data(web::Data::new(benefits.clone()))
+            .app_data(web::Data::new(dunning.clone()))
+            .app_data(web::Data::new(credit_notes.clone()))
+            .app_data(web::Data::new(reporter.clone()))
+            .app_data(web::Data::new(jobs.clone()))
+            .app_data(web::Data::new(fiat_events.clone()))
+            .app_data(web::Data::new(gusto_events.clone()))
+            .app_data(web::Data::new(employees.clone()))
+            .app_data(web::Data::new(payroll.clone()))
+            .app_data(web::Data::new(cash_deposits.clone()))
+            .app_data(web::Data::new(ach.clone()))
+            .app_data(web::Data::new(cards.clone()))
+            .app_data(web::Data::new(statements.clone()))
+            .app_data(web::Data::new(reserves.clone()))
+            .app_data(web::Data::new(iroha_client.clone()))
+            .configure(api::routes::config)
     })
     .bind(("127.0.0.1", 3000))?
     .run()
@@ -0,0 +1,428 @@
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+// --- GUSTO SIMULATOR ---
+// Companies (with OAuth token refresh & revocation), the benefit catalog,
+// company benefits (with Gusto's optimistic `version` check) and enrollments.
+// Every company has the same three employees, so benefit costs are
+// predictable: see `BENEFITS` for the employer contribution per employee per month.
+
+const EMPLOYEES_PER_COMPANY: usize = 3;
+const TOKEN_LIFETIME_SECS: i64 = 7200;
+
+/// benefit_type, name, healthcare, retirement, employer contribution
+const BENEFITS: [(u32, &str, bool, bool, &str); 6] = [
+    (1, "Medical Insurance", true, false, "412.50"),
+    (2, "Dental Insurance", true, false, "38.00"),
+    (3, "Vision Insurance", true, false, "9.50"),
+    (5, "Commuter Benefits (Transit)", false, false, "0.00"),
+    (6, "Health Savings Account", true, false, "50.00"),
+    (105, "401(k)", false, true, "150.00"),
+];
+
+pub struct GustoSandbox {
+    base_url: String,
+    state: Mutex<GustoState>,
+}
+
+impl GustoSandbox {
+    pub fn new(base_url: String) -> Self {
+        Self { base_url, state: Mutex::new(GustoState::default()) }
+    }
+}
+
+#[derive(Default)]
+struct GustoState {
+    counter: u64,
+    companies: BTreeMap<String, SandboxCompany>,
+    benefits: BTreeMap<String, SandboxBenefit>,
+}
+
+impl GustoState {
+    /// Gusto ids are UUIDs: keep the shape, but count
+    fn next_uuid(&mut self) -> String {
+        let n = super::next_id(&mut self.counter, 0);
+        format!("00000000-0000-4000-8000-{:0>12}", n)
+    }
+}
+
+struct SandboxCompany {
+    name: String,
+    access_token: String,
+    refresh_token: String, // Single use: rotated on every refresh, cleared on revoke
+}
+
+struct SandboxBenefit {
+    uuid: String,
+    company_uuid: String,
+    benefit_type: String,
+    description: String,
+    active: bool,
+    version: u64,
+}
+
+impl SandboxBenefit {
+    fn json(&self) -> Value {
+        json!({
+            "uuid": self.uuid,
+            "company_uuid": self.company_uuid,
+            "benefit_type": self.benefit_type,
+            "description": self.description,
+            "active": self.active,
+            "version": format!("v{}", self.version)
+        })
+    }
+
+    /// Employer contribution per employee per month
+    fn employer_contribution(&self) -> &'static str {
+        BENEFITS
+            .iter()
+            .find(|(benefit_type, ..)| benefit_type.to_string() == self.benefit_type)
+            .map(|(.., contribution)| *contribution)
+            .unwrap_or("0.00")
+    }
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/gusto")
+            .service(create_company)
+            .service(oauth_token)
+            .service(oauth_revoke)
+            .service(list_benefits)
+            .service(create_benefit)
+            .service(list_company_benefits)
+            .service(get_benefit)
+            .service(update_benefit)
+            .service(employee_benefits)
+            .service(list_employees)
+            .service(get_employee)
+            .service(get_payroll)
+            .service(create_flow),
+    );
+}
+
+#[post("/v1/partner_managed_companies")]
+async fn create_company(body: web::Json<Value>, sandbox: web::Data<GustoSandbox>) -> impl Responder {
+    let name = body["company"]["name"].as_str().unwrap_or_default().to_string();
+    let admin_email = body["user"]["email"].as_str().unwrap_or_default().to_string();
+    if name.is_empty() || admin_email.is_empty() {
+        return gusto_error(422, "company.name and user.email are required");
+    }
+
+    let mut state = sandbox.state.lock().unwrap();
+    let uuid = state.next_uuid();
+    let access_token = format!("sandbox-access-{}", uuid);
+    let refresh_token = format!("sandbox-refresh-{}", uuid);
+    state.companies.insert(
+        uuid.clone(),
+        SandboxCompany { name, access_token: access_token.clone(), refresh_token: refresh_token.clone() },
+    );
+
+    HttpResponse::Created().json(json!({
+        "company_uuid": uuid,
+        "access_token": access_token,
+        "refresh_token": refresh_token,
+        "expires_in": TOKEN_LIFETIME_SECS
+    }))
+}
+
+#[post("/oauth/token")]
+async fn oauth_token(body: web::Json<Value>, sandbox: web::Data<GustoSandbox>) -> impl Responder {
+    if body["grant_type"].as_str() != Some("refresh_token") {
+        return gusto_error(400, "Only grant_type=refresh_token is simulated");
+    }
+    let presented = body["refresh_token"].as_str().unwrap_or_default();
+
+    let mut state = sandbox.state.lock().unwrap();
+    let n = super::next_id(&mut state.counter, 0);
+    let company = match state.companies.values_mut().find(|c| !c.refresh_token.is_empty() && c.refresh_token == presented) {
+        Some(company) => company,
+        None => return gusto_error(401, "invalid_grant"),
+    };
+
+    company.access_token = format!("sandbox-access-r{}", n);
+    company.refresh_token = format!("sandbox-refresh-r{}", n);
+
+    HttpResponse::Ok().json(json!({
+        "access_token": company.access_token,
+        "refresh_token": company.refresh_token,
+        "token_type": "bearer",
+        "expires_in": TOKEN_LIFETIME_SECS
+    }))
+}
+
+#[post("/oauth/revoke")]
+async fn oauth_revoke(body: web::Json<Value>, sandbox: web::Data<GustoSandbox>) -> impl Responder {
+    let presented = body["token"].as_str().unwrap_or_default();
+
+    let mut state = sandbox.state.lock().unwrap();
+    // Revoking either token kills both, like Gusto
+    if let Some(company) = state
+        .companies
+        .values_mut()
+        .find(|c| !presented.is_empty() && (c.refresh_token == presented || c.access_token == presented))
+    {
+        company.access_token.clear();
+        company.refresh_token.clear();
+    }
+    HttpResponse::Ok().json(json!({}))
+}
+
+/// Gusto's catalog is the same for everyone: no company token needed
+#[get("/v1/benefits")]
+async fn list_benefits() -> impl Responder {
+    let benefits: Vec<Value> = BENEFITS
+        .iter()
+        .map(|(benefit_type, name, healthcare, retirement, _)| {
+            json!({
+                "benefit_type": benefit_type,
+                "name": name,
+                "description": format!("Deductions and contributions for {}", name),
+                "pretax": true,
+                "posttax": false,
+                "healthcare": healthcare,
+                "retirement": retirement
+            })
+        })
+        .collect();
+    HttpResponse::Ok().json(benefits)
+}
+
+#[post("/v1/companies/{uuid}/company_benefits")]
+async fn create_benefit(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<Value>,
+    sandbox: web::Data<GustoSandbox>,
+) -> impl Responder {
+    let company_uuid = path.into_inner();
+    let mut state = sandbox.state.lock().unwrap();
+    if let Err(resp) = authorize(&req, &state, &company_uuid) {
+        return resp;
+    }
+
+    let benefit = SandboxBenefit {
+        uuid: state.next_uuid(),
+        company_uuid,
+        benefit_type: match &body["benefit_type"] {
+            Value::Number(n) => n.to_string(),
+            other => other.as_str().unwrap_or_default().to_string(),
+        },
+        description: body["description"].as_str().unwrap_or_default().to_string(),
+        active: body["active"].as_bool().unwrap_or(true),
+        version: 1,
+    };
+    let json = benefit.json();
+    state.benefits.insert(benefit.uuid.clone(), benefit);
+
+    HttpResponse::Created().json(json)
+}
+
+#[get("/v1/companies/{uuid}/company_benefits")]
+async fn list_company_benefits(req: HttpRequest, path: web::Path<String>, sandbox: web::Data<GustoSandbox>) -> impl Responder {
+    let company_uuid = path.into_inner();
+    let state = sandbox.state.lock().unwrap();
+    if let Err(resp) = authorize(&req, &state, &company_uuid) {
+        return resp;
+    }
+
+    let benefits: Vec<Value> = state
+        .benefits
+        .values()
+        .filter(|b| b.company_uuid == company_uuid)
+        .map(SandboxBenefit::json)
+        .collect();
+    HttpResponse::Ok().json(benefits)
+}
+
+#[get("/v1/company_benefits/{uuid}")]
+async fn get_benefit(req: HttpRequest, path: web::Path<String>, sandbox: web::Data<GustoSandbox>) -> impl Responder {
+    let state = sandbox.state.lock().unwrap();
+    let benefit = match state.benefits.get(&path.into_inner()) {
+        Some(benefit) => benefit,
+        None => return gusto_error(404, "Company benefit not found"),
+    };
+    if let Err(resp) = authorize(&req, &state, &benefit.company_uuid) {
+        return resp;
+    }
+    HttpResponse::Ok().json(benefit.json())
+}
+
+#[put("/v1/company_benefits/{uuid}")]
+async fn update_benefit(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<Value>,
+    sandbox: web::Data<GustoSandbox>,
+) -> impl Responder {
+    let mut state = sandbox.state.lock().unwrap();
+    let uuid = path.into_inner();
+    let company_uuid = match state.benefits.get(&uuid) {
+        Some(benefit) => benefit.company_uuid.clone(),
+        None => return gusto_error(404, "Company benefit not found"),
+    };
+    if let Err(resp) = authorize(&req, &state, &company_uuid) {
+        return resp;
+    }
+
+    let benefit = state.benefits.get_mut(&uuid).unwrap();
+    // Same rule as Gusto: stale versions are rejected
+    if body["version"].as_str() != Some(format!("v{}", benefit.version).as_str()) {
+        return gusto_error(409, "The resource you are attempting to update has been modified");
+    }
+    if let Some(active) = body["active"].as_bool() {
+        benefit.active = active;
+    }
+    if let Some(description) = body["description"].as_str() {
+        benefit.description = description.to_string();
+    }
+    benefit.version += 1;
+
+    HttpResponse::Ok().json(benefit.json())
+}
+
+#[get("/v1/company_benefits/{uuid}/employee_benefits")]
+async fn employee_benefits(req: HttpRequest, path: web::Path<String>, sandbox: web::Data<GustoSandbox>) -> impl Responder {
+    let state = sandbox.state.lock().unwrap();
+    let benefit = match state.benefits.get(&path.into_inner()) {
+        Some(benefit) => benefit,
+        None => return gusto_error(404, "Company benefit not found"),
+    };
+    if let Err(resp) = authorize(&req, &state, &benefit.company_uuid) {
+        return resp;
+    }
+
+    let enrollments: Vec<Value> = (1..=EMPLOYEES_PER_COMPANY)
+        .map(|n| json!({
+            "uuid": format!("{}-e{}", benefit.uuid, n),
+            "company_benefit_uuid": benefit.uuid,
+            "active": benefit.active,
+            "company_contribution": benefit.employer_contribution()
+        }))
+        .collect();
+
+    HttpResponse::Ok().json(enrollments)
+}
+
+/// The company's fixed roster: "<company uuid>-emp<n>"
+fn sandbox_employee(company_uuid: &str, n: usize) -> Value {
+    const NAMES: [(&str, &str); EMPLOYEES_PER_COMPANY] = [("Ada", "Lovelace"), ("Grace", "Hopper"), ("Alan", "Turing")];
+    let (first_name, last_name) = NAMES[n - 1];
+    json!({
+        "uuid": format!("{}-emp{}", company_uuid, n),
+        "company_uuid": company_uuid,
+        "first_name": first_name,
+        "last_name": last_name,
+        "email": format!("{}.{}@example.com", first_name.to_lowercase(), last_name.to_lowercase()),
+        "terminated": false
+    })
+}
+
+#[get("/v1/companies/{uuid}/employees")]
+async fn list_employees(req: HttpRequest, path: web::Path<String>, sandbox: web::Data<GustoSandbox>) -> impl Responder {
+    let company_uuid = path.into_inner();
+    let state = sandbox.state.lock().unwrap();
+    if let Err(resp) = authorize(&req, &state, &company_uuid) {
+        return resp;
+    }
+
+    // Everyone fits on page 1
+    let page = req.query_string().split('&').find_map(|p| p.strip_prefix("page=")).unwrap_or("1");
+    let employees: Vec<Value> = match page {
+        "1" => (1..=EMPLOYEES_PER_COMPANY).map(|n| sandbox_employee(&company_uuid, n)).collect(),
+        _ => Vec::new(),
+    };
+    HttpResponse::Ok().json(employees)
+}
+
+#[get("/v1/employees/{uuid}")]
+async fn get_employee(req: HttpRequest, path: web::Path<String>, sandbox: web::Data<GustoSandbox>) -> impl Responder {
+    let employee_uuid = path.into_inner();
+    let (company_uuid, n) = match employee_uuid.rsplit_once("-emp").and_then(|(c, n)| Some((c, n.parse::<usize>().ok()?))) {
+        Some((company_uuid, n)) if (1..=EMPLOYEES_PER_COMPANY).contains(&n) => (company_uuid.to_string(), n),
+        _ => return gusto_error(404, "Employee not found"),
+    };
+
+    let state = sandbox.state.lock().unwrap();
+    if let Err(resp) = authorize(&req, &state, &company_uuid) {
+        return resp;
+    }
+    HttpResponse::Ok().json(sandbox_employee(&company_uuid, n))
+}
+
+/// Any payroll uuid is a processed semi-monthly payroll for the fixed roster:
+/// $2,500.00 gross, $437.50 withheld, $100.00 into the 401(k), $1,962.50 net
+#[get("/v1/companies/{company}/payrolls/{payroll}")]
+async fn get_payroll(req: HttpRequest, path: web::Path<(String, String)>, sandbox: web::Data<GustoSandbox>) -> impl Responder {
+    let (company_uuid, payroll_uuid) = path.into_inner();
+    let state = sandbox.state.lock().unwrap();
+    if let Err(resp) = authorize(&req, &state, &company_uuid) {
+        return resp;
+    }
+
+    let compensations: Vec<Value> = (1..=EMPLOYEES_PER_COMPANY)
+        .map(|n| json!({
+            "employee_uuid": format!("{}-emp{}", company_uuid, n),
+            "gross_pay": "2500.00",
+            "net_pay": "1962.50",
+            "payment_method": "Direct Deposit",
+            "taxes": [
+                { "name": "Federal Income Tax", "employer": false, "amount": "246.25" },
+                { "name": "Social Security", "employer": false, "amount": "155.00" },
+                { "name": "Medicare", "employer": false, "amount": "36.25" },
+                { "name": "Social Security", "employer": true, "amount": "155.00" },
+                { "name": "Medicare", "employer": true, "amount": "36.25" }
+            ],
+            "benefits": [
+                { "name": "401(k)", "employee_deduction": "100.00", "company_contribution": "50.00" }
+            ],
+            "deductions": []
+        }))
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "payroll_uuid": payroll_uuid,
+        "company_uuid": company_uuid,
+        "processed": true,
+        "check_date": "2024-03-15",
+        "pay_period": { "start_date": "2024-03-01", "end_date": "2024-03-15" },
+        "employee_compensations": compensations
+    }))
+}
+
+#[post("/v1/companies/{uuid}/flows")]
+async fn create_flow(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<Value>,
+    sandbox: web::Data<GustoSandbox>,
+) -> impl Responder {
+    let company_uuid = path.into_inner();
+    let state = sandbox.state.lock().unwrap();
+    if let Err(resp) = authorize(&req, &state, &company_uuid) {
+        return resp;
+    }
+
+    let flow_type = body["flow_type"].as_str().unwrap_or("company_onboarding");
+    HttpResponse::Created().json(json!({
+        "url": format!("{}/gusto/flows/{}?company={}", sandbox.base_url, flow_type, company_uuid)
+    }))
+}
+
+/// Company endpoints need that company's access token
+fn authorize(req: &HttpRequest, state: &GustoState, company_uuid: &str) -> Result<(), HttpResponse> {
+    let company = state.companies.get(company_uuid).ok_or_else(|| gusto_error(404, "Company not found"))?;
+    let expected = format!("Bearer {}", company.access_token);
+    match req.headers().get("Authorization").and_then(|v| v.to_str().ok()) {
+        Some(header) if !company.access_token.is_empty() && header == expected => Ok(()),
+        _ => Err(gusto_error(401, &format!("Invalid access token for company {}", company.name))),
+    }
+}
+
+fn gusto_error(status: u16, message: &str) -> HttpResponse {
+    let code = actix_web::http::StatusCode::from_u16(status).unwrap_or(actix_web::http::StatusCode::BAD_REQUEST);
+    HttpResponse::build(code).json(json!({ "errors": [{ "category": "sandbox", "message": message }] }))
+}
@@ -0,0 +1,163 @@
+use eyre::{eyre, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+
+// --- LEDGER SIMULATOR ---
+// In-memory stand-in for the Iroha peer behind `IrohaClient`. Every submitted
+// transaction is its own block, applied all-or-nothing like the real thing.
+// Transaction hashes are "sandbox-tx-<height>".
+
+#[derive(Default)]
+pub struct SandboxLedger {
+    state: Mutex<LedgerState>,
+}
+
+#[derive(Default, Clone)]
+struct LedgerState {
+    height: u64,
+    balances: BTreeMap<(String, String), i64>,            // (account, asset) -> cents
+    metadata: BTreeMap<String, BTreeMap<String, String>>, // account -> key -> value
+    accounts: BTreeSet<String>,                            // Explicitly registered accounts
+    domains: BTreeMap<String, BTreeMap<String, String>>,   // Registered domains -> metadata
+    store_assets: BTreeMap<(String, String), BTreeMap<String, String>>, // (account, asset) -> key -> value
+    frozen_domains: BTreeSet<String>, // Live freezes strip tenant keys; sandbox writes are all the platform's, so only the flag
+}
+
+/// One instruction of a sandbox transaction
+pub enum SandboxInstruction<'a> {
+    RegisterDomain { domain: &'a str },
+    SetDomainKeyValue { domain: &'a str, key: &'a str, value: &'a str },
+    RegisterAccount { account: &'a str },
+    Mint { account: &'a str, asset: &'a str, cents: i64 },
+    Burn { account: &'a str, asset: &'a str, cents: i64 },
+    Transfer { from: &'a str, to: &'a str, asset: &'a str, cents: i64 },
+    SetKeyValue { account: &'a str, key: &'a str, value: &'a str },
+    SetAssetKeyValue { account: &'a str, asset: &'a str, key: &'a str, value: &'a str },
+}
+
+impl SandboxLedger {
+    /// A ledger where `accounts` are already registered (the platform's own)
+    pub fn with_accounts(accounts: &[String]) -> Self {
+        let ledger = Self::default();
+        ledger
+            .state
+            .lock()
+            .unwrap()
+            .accounts
+            .extend(accounts.iter().filter(|a| !a.is_empty()).cloned());
+        ledger
+    }
+
+    pub fn block_height(&self) -> u64 {
+        self.state.lock().unwrap().height
+    }
+
+    pub fn balance_cents(&self, account: &str, asset: &str) -> i64 {
+        let state = self.state.lock().unwrap();
+        state.balances.get(&(account.to_string(), asset.to_string())).copied().unwrap_or(0)
+    }
+
+    /// Every non-zero balance of `asset`
+    pub fn holders(&self, asset: &str) -> Vec<(String, i64)> {
+        let state = self.state.lock().unwrap();
+        state
+            .balances
+            .iter()
+            .filter(|((_, a), cents)| a == asset && **cents != 0)
+            .map(|((account, _), cents)| (account.clone(), *cents))
+            .collect()
+    }
+
+    pub fn has_key(&self, account: &str, key: &str) -> bool {
+        let state = self.state.lock().unwrap();
+        state.metadata.get(account).map_or(false, |m| m.contains_key(key))
+    }
+
+    pub fn has_account(&self, account: &str) -> bool {
+        self.state.lock().unwrap().accounts.contains(account)
+    }
+
+    pub fn has_domain(&self, domain: &str) -> bool {
+        self.state.lock().unwrap().domains.contains_key(domain)
+    }
+
+    pub fn has_asset_key(&self, account: &str, asset: &str, key: &str) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .store_assets
+            .get(&(account.to_string(), asset.to_string()))
+            .map_or(false, |m| m.contains_key(key))
+    }
+
+    pub fn set_domain_frozen(&self, domain: &str, frozen: bool) -> Result<String> {
+        let mut state = self.state.lock().unwrap();
+        if frozen {
+            state.frozen_domains.insert(domain.to_string());
+        } else {
+            state.frozen_domains.remove(domain);
+        }
+        state.height += 1;
+        Ok(format!("sandbox-tx-{}", state.height))
+    }
+
+    /// Applies every instruction or none of them. Returns the transaction hash.
+    pub fn submit(&self, instructions: Vec<SandboxInstruction>) -> Result<String> {
+        let mut state = self.state.lock().unwrap();
+        let mut next = state.clone();
+
+        for isi in instructions {
+            match isi {
+                SandboxInstruction::RegisterDomain { domain } => {
+                    if next.domains.insert(domain.to_string(), BTreeMap::new()).is_some() {
+                        return Err(eyre!("Domain {} already exists", domain));
+                    }
+                }
+                SandboxInstruction::SetDomainKeyValue { domain, key, value } => {
+                    next.domains
+                        .get_mut(domain)
+                        .ok_or_else(|| eyre!("Domain {} does not exist", domain))?
+                        .insert(key.to_string(), value.to_string());
+                }
+                SandboxInstruction::RegisterAccount { account } => {
+                    if !next.accounts.insert(account.to_string()) {
+                        return Err(eyre!("Account {} already exists", account));
+                    }
+                }
+                SandboxInstruction::Mint { account, asset, cents } => {
+                    *next.balances.entry((account.to_string(), asset.to_string())).or_insert(0) += cents;
+                }
+                SandboxInstruction::Burn { account, asset, cents } => {
+                    next.debit(account, asset, cents)?;
+                }
+                SandboxInstruction::Transfer { from, to, asset, cents } => {
+                    next.debit(from, asset, cents)?;
+                    *next.balances.entry((to.to_string(), asset.to_string())).or_insert(0) += cents;
+                }
+                SandboxInstruction::SetKeyValue { account, key, value } => {
+                    next.metadata.entry(account.to_string()).or_default().insert(key.to_string(), value.to_string());
+                }
+                SandboxInstruction::SetAssetKeyValue { account, asset, key, value } => {
+                    next.store_assets
+                        .entry((account.to_string(), asset.to_string()))
+                        .or_default()
+                        .insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+
+        next.height += 1;
+        *state = next;
+        Ok(format!("sandbox-tx-{}", state.height))
+    }
+}
+
+impl LedgerState {
+    fn debit(&mut self, account: &str, asset: &str, cents: i64) -> Result<()> {
+        let balance = self.balances.entry((account.to_string(), asset.to_string())).or_insert(0);
+        if *balance < cents {
+            return Err(eyre!("Not enough {} on {}: {} < {} cents", asset, account, balance, cents));
+        }
+        *balance -= cents;
+        Ok(())
+    }
+}
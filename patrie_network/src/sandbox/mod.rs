@@ -0,0 +1,76 @@
+pub mod gusto;
+pub mod ledger;
+pub mod unit;
+
+use actix_web::{web, App, HttpServer};
+use std::error::Error;
+
+// --- SANDBOX MODE ---
+// APP_MODE=sandbox swaps Unit, Gusto and Iroha for local stand-ins so onboarding
+// and billing run end-to-end offline. Unit & Gusto are faked over HTTP (the real
+// clients just point at us), the ledger is simulated in-process. Ids are
+// counters, so the same script always produces the same ids.
+
+/// Which outside world the app talks to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuntimeMode {
+    Live,
+    Sandbox,
+}
+
+impl RuntimeMode {
+    /// Reads APP_MODE ("live" by default)
+    pub fn from_env() -> Result<Self, Box<dyn Error>> {
+        match std::env::var("APP_MODE").unwrap_or_else(|_| "live".to_string()).as_str() {
+            "live" => Ok(RuntimeMode::Live),
+            "sandbox" => Ok(RuntimeMode::Sandbox),
+            other => Err(format!("Unknown APP_MODE '{}': expected live or sandbox", other).into()),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuntimeMode::Live => "live",
+            RuntimeMode::Sandbox => "sandbox",
+        }
+    }
+}
+
+/// The platform's own accounts, as the app is configured. The simulators
+/// create them up front: live, they exist before the app ever starts.
+#[derive(Debug, Clone, Default)]
+pub struct PlatformAccounts {
+    pub unit_accounts: Vec<String>,   // e.g. revenue, micro-deposit verification
+    pub ledger_accounts: Vec<String>, // e.g. card & cash holds, on-chain revenue
+}
+
+/// Starts the Unit & Gusto simulators on 127.0.0.1:`port` in the background.
+/// Returns their base URL: Unit lives under `/unit`, Gusto under `/gusto`.
+pub fn start_simulators(port: u16, platform: &PlatformAccounts) -> Result<String, Box<dyn Error>> {
+    let base_url = format!("http://127.0.0.1:{}", port);
+
+    let unit_state = web::Data::new(unit::UnitSandbox::with_platform_accounts(&platform.unit_accounts));
+    let gusto_state = web::Data::new(gusto::GustoSandbox::new(base_url.clone()));
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(unit_state.clone())
+            .app_data(gusto_state.clone())
+            .configure(unit::config)
+            .configure(gusto::config)
+    })
+    .workers(1)
+    .bind(("127.0.0.1", port))?
+    .run();
+
+    tokio::spawn(server);
+    println!("🧪 Sandbox simulators listening on {} (Unit: /unit, Gusto: /gusto).", base_url);
+
+    Ok(base_url)
+}
+
+/// Sandbox ids: a per-kind counter, e.g. `next_id(&mut n, 10_000)` -> "10001"
+pub(crate) fn next_id(counter: &mut u64, base: u64) -> String {
+    *counter += 1;
+    (base + *counter).to_string()
+}
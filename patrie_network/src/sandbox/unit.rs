@@ -0,0 +1,856 @@
+use actix_web::{get, patch, post, web, HttpResponse, Responder};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+// --- UNIT SIMULATOR ---
+// Just enough of Unit's JSON:API for our client: applications are approved on
+// the spot, book payments move money instantly (or come back "Rejected"),
+// ACH payments sit in "Pending" until POST /unit/sandbox/ach-payments/{id}/settle
+// or /return. Fund accounts with POST /unit/sandbox/received-ach or
+// /unit/sandbox/cash-deposit. Card activity comes from
+// /unit/sandbox/card-authorizations (then .../{id}/capture or .../{id}/cancel).
+
+#[derive(Default)]
+pub struct UnitSandbox {
+    state: Mutex<UnitState>,
+}
+
+impl UnitSandbox {
+    /// Opens the platform's accounts under their configured ids, held by one
+    /// business customer. They start empty: fund them with /unit/sandbox/received-ach.
+    pub fn with_platform_accounts(account_ids: &[String]) -> Self {
+        let mut state = UnitState::default();
+        let customer_id = state.next_id();
+        state.customers.insert(
+            customer_id.clone(),
+            json!({
+                "type": "businessCustomer",
+                "id": customer_id,
+                "attributes": {
+                    "createdAt": Utc::now().to_rfc3339(),
+                    "name": "Platform",
+                    "status": "Active",
+                    "tags": {}
+                }
+            }),
+        );
+
+        for id in account_ids.iter().filter(|id| !id.is_empty()) {
+            state.accounts.insert(
+                id.clone(),
+                SandboxAccount {
+                    id: id.clone(),
+                    customer_id: customer_id.clone(),
+                    deposit_product: "checking".to_string(),
+                    balance: 0,
+                    hold: 0,
+                    tags: json!({}),
+                    created_at: Utc::now().to_rfc3339(),
+                },
+            );
+        }
+
+        Self { state: Mutex::new(state) }
+    }
+}
+
+#[derive(Default)]
+struct UnitState {
+    counter: u64,
+    applications: BTreeMap<String, Value>,
+    customers: BTreeMap<String, Value>,
+    accounts: BTreeMap<String, SandboxAccount>,
+    payments: BTreeMap<String, Value>,
+    counterparties: BTreeMap<String, Value>,
+    cards: BTreeMap<String, Value>,
+    authorizations: BTreeMap<String, Value>,
+    transactions: Vec<SandboxTransaction>,
+    idempotency: HashMap<String, Value>, // idempotencyKey -> resource we returned
+}
+
+struct SandboxAccount {
+    id: String,
+    customer_id: String,
+    deposit_product: String,
+    balance: i64,
+    hold: i64, // Open card authorizations
+    tags: Value,
+    created_at: String,
+}
+
+impl SandboxAccount {
+    fn resource(&self) -> Value {
+        json!({
+            "type": "depositAccount",
+            "id": self.id,
+            "attributes": {
+                "name": format!("Sandbox account {}", self.id),
+                "status": "Open",
+                "depositProduct": self.deposit_product,
+                "routingNumber": "812345678",
+                "accountNumber": format!("10000{}", self.id),
+                "currency": "USD",
+                "balance": self.balance,
+                "hold": self.hold,
+                "available": self.balance - self.hold,
+                "createdAt": self.created_at,
+                "tags": self.tags
+            },
+            "relationships": { "customer": { "data": { "type": "customer", "id": self.customer_id } } }
+        })
+    }
+}
+
+struct SandboxTransaction {
+    id: String,
+    kind: &'static str,
+    account_id: String,
+    direction: &'static str,
+    amount: i64,
+    balance: i64,
+    summary: String,
+    payment_id: Option<String>,
+    card_id: Option<String>,
+    authorization_id: Option<String>,
+    created_at: String,
+}
+
+impl SandboxTransaction {
+    fn resource(&self) -> Value {
+        let mut relationships = json!({ "account": { "data": { "type": "account", "id": self.account_id } } });
+        if let Some(payment_id) = &self.payment_id {
+            relationships["payment"] = json!({ "data": { "type": "payment", "id": payment_id } });
+        }
+        if let Some(card_id) = &self.card_id {
+            relationships["card"] = json!({ "data": { "type": "card", "id": card_id } });
+        }
+        if let Some(authorization_id) = &self.authorization_id {
+            relationships["authorization"] = json!({ "data": { "type": "authorization", "id": authorization_id } });
+        }
+        json!({
+            "type": self.kind,
+            "id": self.id,
+            "attributes": {
+                "createdAt": self.created_at,
+                "amount": self.amount,
+                "direction": self.direction,
+                "balance": self.balance,
+                "summary": self.summary
+            },
+            "relationships": relationships
+        })
+    }
+}
+
+impl UnitState {
+    fn next_id(&mut self) -> String {
+        super::next_id(&mut self.counter, 10_000)
+    }
+
+    /// Moves money on one account and records the transaction
+    fn post(
+        &mut self,
+        account_id: &str,
+        kind: &'static str,
+        direction: &'static str,
+        amount: i64,
+        summary: &str,
+        payment_id: Option<String>,
+    ) {
+        let id = self.next_id();
+        let account = self.accounts.get_mut(account_id).expect("account checked by caller");
+        account.balance += if direction == "Credit" { amount } else { -amount };
+        let balance = account.balance;
+
+        self.transactions.push(SandboxTransaction {
+            id,
+            kind,
+            account_id: account_id.to_string(),
+            direction,
+            amount,
+            balance,
+            summary: summary.to_string(),
+            payment_id,
+            card_id: None,
+            authorization_id: None,
+            created_at: Utc::now().to_rfc3339(),
+        });
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PageQuery {
+    #[serde(rename = "page[limit]")]
+    pub limit: Option<usize>,
+    #[serde(rename = "page[offset]")]
+    pub offset: Option<usize>,
+    #[serde(rename = "filter[accountId]")]
+    pub account_id: Option<String>,
+    #[serde(rename = "filter[customerId]")]
+    pub customer_id: Option<String>,
+    #[serde(rename = "filter[cardId]")]
+    pub card_id: Option<String>,
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/unit")
+            .service(create_application)
+            .service(get_application)
+            .service(get_customer)
+            .service(list_customers)
+            .service(create_account)
+            .service(get_account)
+            .service(create_payment)
+            .service(get_payment)
+            .service(create_counterparty)
+            .service(list_counterparties)
+            .service(list_transactions)
+            .service(get_transaction)
+            .service(create_card)
+            .service(get_card)
+            .service(update_card)
+            .service(freeze_card)
+            .service(unfreeze_card)
+            .service(close_card)
+            .service(get_authorization)
+            .service(create_barcode)
+            .service(simulate_received_ach)
+            .service(simulate_ach_settled)
+            .service(simulate_ach_returned)
+            .service(simulate_cash_deposit)
+            .service(simulate_card_authorization)
+            .service(simulate_card_capture)
+            .service(simulate_card_cancel),
+    );
+}
+
+// --- Customers ---
+
+#[post("/applications")]
+async fn create_application(body: web::Bytes, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let data = match parse(&body) {
+        Ok(data) => data,
+        Err(resp) => return resp,
+    };
+    let mut state = sandbox.state.lock().unwrap();
+    if let Some(existing) = replay(&state, &data) {
+        return document(existing);
+    }
+
+    let kind = data["type"].as_str().unwrap_or_default();
+    let customer_kind = match kind {
+        "individualApplication" => "individualCustomer",
+        "businessApplication" => "businessCustomer",
+        other => return unit_error(400, &format!("Unsupported application type '{}'", other)),
+    };
+
+    // Sandbox KYC/KYB: everyone is approved immediately
+    let attributes = &data["attributes"];
+    let customer_id = state.next_id();
+    let customer = json!({
+        "type": customer_kind,
+        "id": customer_id,
+        "attributes": {
+            "createdAt": Utc::now().to_rfc3339(),
+            "email": attributes["email"].as_str().or(attributes["contact"]["email"].as_str()),
+            "name": attributes["name"],
+            "fullName": attributes["fullName"],
+            "status": "Active",
+            "tags": if attributes["tags"].is_object() { attributes["tags"].clone() } else { json!({}) }
+        }
+    });
+    state.customers.insert(customer_id.clone(), customer);
+
+    let application_id = state.next_id();
+    let application = json!({
+        "type": kind,
+        "id": application_id,
+        "attributes": { "status": "Approved", "createdAt": Utc::now().to_rfc3339() },
+        "relationships": { "customer": { "data": { "type": "customer", "id": customer_id } } }
+    });
+    state.applications.insert(application_id, application.clone());
+    remember(&mut state, &data, &application);
+
+    document(application)
+}
+
+#[get("/applications/{id}")]
+async fn get_application(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let state = sandbox.state.lock().unwrap();
+    found(state.applications.get(&path.into_inner()).cloned(), "Application")
+}
+
+#[get("/customers/{id}")]
+async fn get_customer(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let state = sandbox.state.lock().unwrap();
+    found(state.customers.get(&path.into_inner()).cloned(), "Customer")
+}
+
+#[get("/customers")]
+async fn list_customers(query: web::Query<PageQuery>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let state = sandbox.state.lock().unwrap();
+    page(state.customers.values().cloned().collect(), &query)
+}
+
+// --- Accounts ---
+
+#[post("/accounts")]
+async fn create_account(body: web::Bytes, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let data = match parse(&body) {
+        Ok(data) => data,
+        Err(resp) => return resp,
+    };
+    let mut state = sandbox.state.lock().unwrap();
+    if let Some(existing) = replay(&state, &data) {
+        return document(existing);
+    }
+
+    let customer_id = data["relationships"]["customer"]["data"]["id"].as_str().unwrap_or_default().to_string();
+    if !state.customers.contains_key(&customer_id) {
+        return unit_error(404, "Customer not found");
+    }
+
+    let account = SandboxAccount {
+        id: state.next_id(),
+        customer_id,
+        deposit_product: data["attributes"]["depositProduct"].as_str().unwrap_or("checking").to_string(),
+        balance: 0,
+        hold: 0,
+        tags: data["attributes"]["tags"].clone(),
+        created_at: Utc::now().to_rfc3339(),
+    };
+    let resource = account.resource();
+    state.accounts.insert(account.id.clone(), account);
+    remember(&mut state, &data, &resource);
+
+    document(resource)
+}
+
+#[get("/accounts/{id}")]
+async fn get_account(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let state = sandbox.state.lock().unwrap();
+    found(state.accounts.get(&path.into_inner()).map(SandboxAccount::resource), "Account")
+}
+
+// --- Payments ---
+
+#[post("/payments")]
+async fn create_payment(body: web::Bytes, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let data = match parse(&body) {
+        Ok(data) => data,
+        Err(resp) => return resp,
+    };
+    let mut state = sandbox.state.lock().unwrap();
+    if let Some(existing) = replay(&state, &data) {
+        return document(existing);
+    }
+
+    let attributes = &data["attributes"];
+    let amount = attributes["amount"].as_i64().unwrap_or(0);
+    let description = attributes["description"].as_str().unwrap_or_default().to_string();
+    let account_id = data["relationships"]["account"]["data"]["id"].as_str().unwrap_or_default().to_string();
+    if amount <= 0 {
+        return unit_error(400, "amount must be positive");
+    }
+    if !state.accounts.contains_key(&account_id) {
+        return unit_error(404, "Account not found");
+    }
+
+    let payment_id = state.next_id();
+    let kind = data["type"].as_str().unwrap_or_default().to_string();
+    let (status, reason, direction) = match kind.as_str() {
+        "bookPayment" => {
+            let to = data["relationships"]["counterpartyAccount"]["data"]["id"].as_str().unwrap_or_default().to_string();
+            if !state.accounts.contains_key(&to) {
+                return unit_error(404, "Counterparty account not found");
+            }
+            if state.accounts[&account_id].balance < amount {
+                ("Rejected", Some("InsufficientFunds"), None)
+            } else {
+                state.post(&account_id, "bookTransaction", "Debit", amount, &description, Some(payment_id.clone()));
+                state.post(&to, "bookTransaction", "Credit", amount, &description, Some(payment_id.clone()));
+                ("Sent", None, None)
+            }
+        }
+        "achPayment" => {
+            let counterparty = data["relationships"]["counterparty"]["data"]["id"].as_str().unwrap_or_default();
+            if !state.counterparties.contains_key(counterparty) {
+                return unit_error(404, "Counterparty not found");
+            }
+            // Credit pushes money out, Debit pulls it in
+            match attributes["direction"].as_str() {
+                Some("Credit") if state.accounts[&account_id].balance < amount => ("Rejected", Some("InsufficientFunds"), Some("Credit")),
+                Some("Credit") => {
+                    state.post(&account_id, "originatedAchTransaction", "Debit", amount, &description, Some(payment_id.clone()));
+                    ("Pending", None, Some("Credit"))
+                }
+                Some("Debit") => {
+                    state.post(&account_id, "originatedAchTransaction", "Credit", amount, &description, Some(payment_id.clone()));
+                    ("Pending", None, Some("Debit"))
+                }
+                _ => return unit_error(400, "direction must be Credit or Debit"),
+            }
+        }
+        other => return unit_error(400, &format!("Unsupported payment type '{}'", other)),
+    };
+
+    let payment = json!({
+        "type": kind,
+        "id": payment_id,
+        "attributes": {
+            "status": status,
+            "amount": amount,
+            "description": description,
+            "direction": direction,
+            "reason": reason,
+            "createdAt": Utc::now().to_rfc3339()
+        },
+        "relationships": data["relationships"]
+    });
+    state.payments.insert(payment_id, payment.clone());
+    remember(&mut state, &data, &payment);
+
+    document(payment)
+}
+
+#[get("/payments/{id}")]
+async fn get_payment(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let state = sandbox.state.lock().unwrap();
+    found(state.payments.get(&path.into_inner()).cloned(), "Payment")
+}
+
+// --- Counterparties ---
+
+#[post("/counterparties")]
+async fn create_counterparty(body: web::Bytes, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let data = match parse(&body) {
+        Ok(data) => data,
+        Err(resp) => return resp,
+    };
+    let mut state = sandbox.state.lock().unwrap();
+    if let Some(existing) = replay(&state, &data) {
+        return document(existing);
+    }
+
+    let mut attributes = data["attributes"].clone();
+    let account_number = attributes["accountNumber"].as_str().unwrap_or_default().to_string();
+    let last4: String = account_number.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect();
+    attributes["accountNumber"] = json!(format!("****{}", last4));
+    attributes["createdAt"] = json!(Utc::now().to_rfc3339());
+    if let Some(map) = attributes.as_object_mut() {
+        map.remove("idempotencyKey");
+    }
+
+    let counterparty = json!({
+        "type": "achCounterparty",
+        "id": state.next_id(),
+        "attributes": attributes,
+        "relationships": data["relationships"]
+    });
+    state.counterparties.insert(counterparty["id"].as_str().unwrap().to_string(), counterparty.clone());
+    remember(&mut state, &data, &counterparty);
+
+    document(counterparty)
+}
+
+#[get("/counterparties")]
+async fn list_counterparties(query: web::Query<PageQuery>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let state = sandbox.state.lock().unwrap();
+    let items = state
+        .counterparties
+        .values()
+        .filter(|c| match &query.customer_id {
+            Some(customer) => c["relationships"]["customer"]["data"]["id"].as_str() == Some(customer.as_str()),
+            None => true,
+        })
+        .cloned()
+        .collect();
+    page(items, &query)
+}
+
+// --- Transactions ---
+
+#[get("/transactions")]
+async fn list_transactions(query: web::Query<PageQuery>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let state = sandbox.state.lock().unwrap();
+    // Newest first, like Unit
+    let items = state
+        .transactions
+        .iter()
+        .rev()
+        .filter(|t| query.account_id.as_deref().map_or(true, |a| t.account_id == a))
+        .filter(|t| query.card_id.is_none() || t.card_id == query.card_id)
+        .map(SandboxTransaction::resource)
+        .collect();
+    page(items, &query)
+}
+
+#[get("/accounts/{account_id}/transactions/{id}")]
+async fn get_transaction(path: web::Path<(String, String)>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let (account_id, id) = path.into_inner();
+    let state = sandbox.state.lock().unwrap();
+    let transaction = state
+        .transactions
+        .iter()
+        .find(|t| t.id == id && t.account_id == account_id)
+        .map(SandboxTransaction::resource);
+    found(transaction, "Transaction")
+}
+
+// --- Cards ---
+
+#[post("/cards")]
+async fn create_card(body: web::Bytes, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let data = match parse(&body) {
+        Ok(data) => data,
+        Err(resp) => return resp,
+    };
+    let mut state = sandbox.state.lock().unwrap();
+    if let Some(existing) = replay(&state, &data) {
+        return document(existing);
+    }
+
+    let account_id = data["relationships"]["account"]["data"]["id"].as_str().unwrap_or_default().to_string();
+    let customer_id = match state.accounts.get(&account_id) {
+        Some(account) => account.customer_id.clone(),
+        None => return unit_error(404, "Account not found"),
+    };
+
+    let id = state.next_id();
+    let expiration = Utc::now() + chrono::Duration::days(3 * 365);
+    let card = json!({
+        "type": data["type"],
+        "id": id,
+        "attributes": {
+            "status": "Active",
+            "last4Digits": format!("{:0>4}", &id[id.len().saturating_sub(4)..]),
+            "expirationDate": expiration.format("%Y-%m").to_string(),
+            "limits": data["attributes"]["limits"],
+            "createdAt": Utc::now().to_rfc3339()
+        },
+        "relationships": {
+            "account": { "data": { "type": "depositAccount", "id": account_id } },
+            "customer": { "data": { "type": "customer", "id": customer_id } }
+        }
+    });
+    state.cards.insert(id, card.clone());
+    remember(&mut state, &data, &card);
+
+    document(card)
+}
+
+#[get("/cards/{id}")]
+async fn get_card(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let state = sandbox.state.lock().unwrap();
+    found(state.cards.get(&path.into_inner()).cloned(), "Card")
+}
+
+#[patch("/cards/{id}")]
+async fn update_card(path: web::Path<String>, body: web::Bytes, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let data = match parse(&body) {
+        Ok(data) => data,
+        Err(resp) => return resp,
+    };
+    let mut state = sandbox.state.lock().unwrap();
+    let card = match state.cards.get_mut(&path.into_inner()) {
+        Some(card) => card,
+        None => return unit_error(404, "Card not found"),
+    };
+    if let Some(limits) = data["attributes"].get("limits") {
+        card["attributes"]["limits"] = limits.clone();
+    }
+    document(card.clone())
+}
+
+#[post("/cards/{id}/freeze")]
+async fn freeze_card(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    set_card_status(&sandbox, &path.into_inner(), "Frozen")
+}
+
+#[post("/cards/{id}/unfreeze")]
+async fn unfreeze_card(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    set_card_status(&sandbox, &path.into_inner(), "Active")
+}
+
+#[post("/cards/{id}/close")]
+async fn close_card(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    set_card_status(&sandbox, &path.into_inner(), "ClosedByCustomer")
+}
+
+fn set_card_status(sandbox: &UnitSandbox, card_id: &str, status: &str) -> HttpResponse {
+    let mut state = sandbox.state.lock().unwrap();
+    let card = match state.cards.get_mut(card_id) {
+        Some(card) => card,
+        None => return unit_error(404, "Card not found"),
+    };
+    if card["attributes"]["status"] == "ClosedByCustomer" {
+        return unit_error(400, "Card is closed");
+    }
+    card["attributes"]["status"] = json!(status);
+    document(card.clone())
+}
+
+#[get("/authorizations/{id}")]
+async fn get_authorization(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let state = sandbox.state.lock().unwrap();
+    found(state.authorizations.get(&path.into_inner()).cloned(), "Authorization")
+}
+
+/// Sandbox only: a merchant asks to charge a card. Declined if the card is
+/// not active or the account's available balance is too low.
+#[post("/sandbox/card-authorizations")]
+async fn simulate_card_authorization(body: web::Bytes, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let data = match parse(&body) {
+        Ok(data) => data,
+        Err(resp) => return resp,
+    };
+    let mut state = sandbox.state.lock().unwrap();
+    let card_id = data["relationships"]["card"]["data"]["id"].as_str().unwrap_or_default().to_string();
+    let amount = data["attributes"]["amount"].as_i64().unwrap_or(0);
+    let card = match state.cards.get(&card_id) {
+        Some(card) => card.clone(),
+        None => return unit_error(404, "Card not found"),
+    };
+    if amount <= 0 {
+        return unit_error(400, "amount must be positive");
+    }
+
+    let account_id = card["relationships"]["account"]["data"]["id"].as_str().unwrap_or_default().to_string();
+    let account = state.accounts.get_mut(&account_id).expect("card accounts exist");
+    let status = if card["attributes"]["status"] != "Active" || account.balance - account.hold < amount {
+        "Declined"
+    } else {
+        account.hold += amount;
+        "Authorized"
+    };
+
+    let id = state.next_id();
+    let authorization = json!({
+        "type": "authorization",
+        "id": id,
+        "attributes": {
+            "amount": amount,
+            "status": status,
+            "merchant": { "name": data["attributes"]["merchant"].as_str().unwrap_or("Sandbox Merchant"), "category": "5999" },
+            "createdAt": Utc::now().to_rfc3339()
+        },
+        "relationships": {
+            "account": { "data": { "type": "depositAccount", "id": account_id } },
+            "card": { "data": { "type": "card", "id": card_id } }
+        }
+    });
+    state.authorizations.insert(id, authorization.clone());
+    document(authorization)
+}
+
+/// Sandbox only: the merchant captures (an amount other than authorized is fine)
+#[post("/sandbox/card-authorizations/{id}/capture")]
+async fn simulate_card_capture(path: web::Path<String>, body: web::Bytes, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let authorization_id = path.into_inner();
+    let data = parse(&body).unwrap_or(Value::Null);
+    let mut state = sandbox.state.lock().unwrap();
+    let authorization = match state.authorizations.get(&authorization_id) {
+        Some(a) if a["attributes"]["status"] == "Authorized" => a.clone(),
+        _ => return unit_error(400, "No open authorization with that id"),
+    };
+
+    let held = authorization["attributes"]["amount"].as_i64().unwrap_or(0);
+    let amount = data["attributes"]["amount"].as_i64().unwrap_or(held);
+    let account_id = authorization["relationships"]["account"]["data"]["id"].as_str().unwrap_or_default().to_string();
+    let card_id = authorization["relationships"]["card"]["data"]["id"].as_str().map(String::from);
+    let merchant = authorization["attributes"]["merchant"]["name"].as_str().unwrap_or_default().to_string();
+
+    if let Some(account) = state.accounts.get_mut(&account_id) {
+        account.hold -= held;
+    }
+    state.post(&account_id, "purchaseTransaction", "Debit", amount, &format!("Purchase from {}", merchant), None);
+    if let Some(transaction) = state.transactions.last_mut() {
+        transaction.card_id = card_id;
+        transaction.authorization_id = Some(authorization_id.clone());
+    }
+    if let Some(a) = state.authorizations.get_mut(&authorization_id) {
+        a["attributes"]["status"] = json!("Completed");
+    }
+
+    let transaction = state.transactions.last().map(SandboxTransaction::resource).unwrap();
+    document(transaction)
+}
+
+/// Sandbox only: the merchant voids the authorization
+#[post("/sandbox/card-authorizations/{id}/cancel")]
+async fn simulate_card_cancel(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let authorization_id = path.into_inner();
+    let mut state = sandbox.state.lock().unwrap();
+    let authorization = match state.authorizations.get(&authorization_id) {
+        Some(a) if a["attributes"]["status"] == "Authorized" => a.clone(),
+        _ => return unit_error(400, "No open authorization with that id"),
+    };
+
+    let held = authorization["attributes"]["amount"].as_i64().unwrap_or(0);
+    let account_id = authorization["relationships"]["account"]["data"]["id"].as_str().unwrap_or_default();
+    if let Some(account) = state.accounts.get_mut(account_id) {
+        account.hold -= held;
+    }
+
+    let authorization = state.authorizations.get_mut(&authorization_id).expect("looked up above");
+    authorization["attributes"]["status"] = json!("Canceled");
+    document(authorization.clone())
+}
+
+// --- Cash & simulations ---
+
+#[post("/cash-deposits/barcode")]
+async fn create_barcode(body: web::Bytes, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let data = match parse(&body) {
+        Ok(data) => data,
+        Err(resp) => return resp,
+    };
+    let mut state = sandbox.state.lock().unwrap();
+    let account_id = data["relationships"]["account"]["data"]["id"].as_str().unwrap_or_default().to_string();
+    if !state.accounts.contains_key(&account_id) {
+        return unit_error(404, "Account not found");
+    }
+
+    let id = state.next_id();
+    document(json!({
+        "type": "cashDepositBarcode",
+        "id": id,
+        "attributes": {
+            "barcodeNumber": format!("8800{:012}", id.parse::<u64>().unwrap_or(0)),
+            "barcodeUrl": format!("https://sandbox.invalid/barcodes/{}.png", id),
+            "expiration": (Utc::now() + chrono::Duration::days(7)).to_rfc3339(),
+            "store": data["attributes"]["store"]
+        }
+    }))
+}
+
+/// Sandbox only: cash paid in at a retailer
+#[post("/sandbox/cash-deposit")]
+async fn simulate_cash_deposit(body: web::Bytes, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let data = match parse(&body) {
+        Ok(data) => data,
+        Err(resp) => return resp,
+    };
+    let mut state = sandbox.state.lock().unwrap();
+    let account_id = data["relationships"]["account"]["data"]["id"].as_str().unwrap_or_default().to_string();
+    let amount = data["attributes"]["amount"].as_i64().unwrap_or(0);
+    if amount <= 0 || !state.accounts.contains_key(&account_id) {
+        return unit_error(400, "A positive amount and an existing account are required");
+    }
+
+    state.post(&account_id, "cashDepositTransaction", "Credit", amount, "Sandbox cash deposit", None);
+    let transaction = state.transactions.last().map(SandboxTransaction::resource).unwrap();
+    document(transaction)
+}
+
+/// Sandbox only: money arrives by ACH from outside (how accounts get funded)
+#[post("/sandbox/received-ach")]
+async fn simulate_received_ach(body: web::Bytes, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let data = match parse(&body) {
+        Ok(data) => data,
+        Err(resp) => return resp,
+    };
+    let mut state = sandbox.state.lock().unwrap();
+    let account_id = data["relationships"]["account"]["data"]["id"].as_str().unwrap_or_default().to_string();
+    let amount = data["attributes"]["amount"].as_i64().unwrap_or(0);
+    if amount <= 0 || !state.accounts.contains_key(&account_id) {
+        return unit_error(400, "A positive amount and an existing account are required");
+    }
+
+    state.post(&account_id, "receivedAchTransaction", "Credit", amount, "Sandbox ACH deposit", None);
+    let transaction = state.transactions.last().map(SandboxTransaction::resource).unwrap();
+    document(transaction)
+}
+
+/// Sandbox only: an ACH payment clears ("Sent")
+#[post("/sandbox/ach-payments/{id}/settle")]
+async fn simulate_ach_settled(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let mut state = sandbox.state.lock().unwrap();
+    let payment = match state.payments.get_mut(&path.into_inner()) {
+        Some(payment) if payment["type"] == "achPayment" => payment,
+        _ => return unit_error(404, "ACH payment not found"),
+    };
+    if payment["attributes"]["status"] != "Pending" {
+        return unit_error(400, "Only pending payments can settle");
+    }
+
+    payment["attributes"]["status"] = json!("Sent");
+    document(payment.clone())
+}
+
+/// Sandbox only: the receiving bank sends an ACH payment back
+#[post("/sandbox/ach-payments/{id}/return")]
+async fn simulate_ach_returned(path: web::Path<String>, sandbox: web::Data<UnitSandbox>) -> impl Responder {
+    let payment_id = path.into_inner();
+    let mut state = sandbox.state.lock().unwrap();
+    let payment = match state.payments.get(&payment_id) {
+        Some(payment) if payment["type"] == "achPayment" => payment.clone(),
+        _ => return unit_error(404, "ACH payment not found"),
+    };
+    if !matches!(payment["attributes"]["status"].as_str(), Some("Pending") | Some("Sent")) {
+        return unit_error(400, "Only pending or sent payments can be returned");
+    }
+
+    // Undo the original movement
+    let account_id = payment["relationships"]["account"]["data"]["id"].as_str().unwrap_or_default().to_string();
+    let amount = payment["attributes"]["amount"].as_i64().unwrap_or(0);
+    let direction = if payment["attributes"]["direction"] == "Credit" { "Credit" } else { "Debit" };
+    state.post(&account_id, "returnedAchTransaction", direction, amount, "Sandbox ACH return", Some(payment_id.clone()));
+
+    let payment = state.payments.get_mut(&payment_id).expect("looked up above");
+    payment["attributes"]["status"] = json!("Returned");
+    payment["attributes"]["reason"] = json!("R01"); // Insufficient funds at the receiving bank
+    document(payment.clone())
+}
+
+// --- Helpers ---
+
+fn parse(body: &[u8]) -> Result<Value, HttpResponse> {
+    serde_json::from_slice::<Value>(body)
+        .map(|doc| doc["data"].clone())
+        .map_err(|e| unit_error(400, &format!("Invalid JSON:API document: {}", e)))
+}
+
+fn replay(state: &UnitState, data: &Value) -> Option<Value> {
+    let key = data["attributes"]["idempotencyKey"].as_str()?;
+    state.idempotency.get(key).cloned()
+}
+
+fn remember(state: &mut UnitState, data: &Value, resource: &Value) {
+    if let Some(key) = data["attributes"]["idempotencyKey"].as_str() {
+        state.idempotency.insert(key.to_string(), resource.clone());
+    }
+}
+
+fn document(data: Value) -> HttpResponse {
+    HttpResponse::Ok().content_type("application/vnd.api+json").json(json!({ "data": data }))
+}
+
+fn found(resource: Option<Value>, what: &str) -> HttpResponse {
+    match resource {
+        Some(resource) => document(resource),
+        None => unit_error(404, &format!("{} not found", what)),
+    }
+}
+
+fn page(items: Vec<Value>, query: &PageQuery) -> HttpResponse {
+    let total = items.len();
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(100);
+    let items: Vec<Value> = items.into_iter().skip(offset).take(limit).collect();
+
+    HttpResponse::Ok()
+        .content_type("application/vnd.api+json")
+        .json(json!({ "data": items, "meta": { "pagination": { "total": total, "limit": limit, "offset": offset } } }))
+}
+
+fn unit_error(status: u16, title: &str) -> HttpResponse {
+    let code = actix_web::http::StatusCode::from_u16(status).unwrap_or(actix_web::http::StatusCode::BAD_REQUEST);
+    HttpResponse::build(code)
+        .content_type("application/vnd.api+json")
+        .json(json!({ "errors": [{ "title": title, "status": status.to_string() }] }))
+}
@@ -0,0 +1,194 @@
+// --- TENANT ONBOARDING, END TO END ---
+// Runs against the sandbox simulators (APP_MODE=sandbox) with the platform
+// accounts seeded the way main.rs seeds them: sign a tenant up (Gusto company,
+// Unit KYB & deposit account, ledger domain), fund its account, then run one
+// billing cycle that books its first invoice into our revenue account.
+// Needs Postgres: `#[sqlx::test]` creates a scratch database per test on
+// DATABASE_URL and runs ./migrations into it.
+
+use patrie_network::core::billing_engine::{BillingEngine, OnChainSettlement};
+use patrie_network::core::fiat_banking::{Address, BusinessApplication, BusinessPerson, FiatAsset, FullName, Phone, UnitClient};
+use patrie_network::core::gusto::{GustoClient, GustoOAuth};
+use patrie_network::core::gusto_tokens::{GustoTokenStore, TokenCipher};
+use patrie_network::core::invoice::{BillingPeriod, Invoice, InvoiceStatus, PaymentMethod};
+use patrie_network::core::metering::MeteringConfig;
+use patrie_network::core::onboarding::{SignupRequest, TenantOnboarding};
+use patrie_network::ledger::client::IrohaClient;
+use patrie_network::sandbox::{self, PlatformAccounts};
+use patrie_network::sandbox::ledger::{SandboxInstruction, SandboxLedger};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+const REVENUE_ACCOUNT: &str = "YOUR_LLC_REVENUE_ACCOUNT_ID";
+const VERIFICATION_ACCOUNT: &str = "platform_verification";
+const CARD_HOLD_ACCOUNT: &str = "card_holds@my_ecosystem";
+const SANDBOX_PORT: u16 = 39_041;
+const ONBOARDING_PORT: u16 = 39_042;
+
+fn platform() -> PlatformAccounts {
+    PlatformAccounts {
+        unit_accounts: vec![REVENUE_ACCOUNT.to_string(), VERIFICATION_ACCOUNT.to_string()],
+        ledger_accounts: vec![CARD_HOLD_ACCOUNT.to_string()],
+    }
+}
+
+/// Sandbox clients on a fresh set of simulators (and the simulators' base URL)
+fn clients(port: u16, pool: &PgPool) -> (String, Arc<GustoClient>, Arc<UnitClient>, Arc<IrohaClient>) {
+    let base_url = sandbox::start_simulators(port, &platform()).expect("simulators start");
+    let tokens = GustoTokenStore::new(pool.clone(), TokenCipher::ephemeral());
+    let gusto = GustoClient::with_base_url("sandbox".to_string(), format!("{}/gusto", base_url), GustoOAuth::from_env(), tokens);
+    let unit = UnitClient::with_base_url("sandbox".to_string(), format!("{}/unit", base_url));
+    let iroha = IrohaClient::sandbox(&platform().ledger_accounts);
+    (base_url, Arc::new(gusto), Arc::new(unit), Arc::new(iroha))
+}
+
+fn signup() -> SignupRequest {
+    SignupRequest {
+        company_name: "Acme Supply, LLC".to_string(),
+        email: "ada@example.com".to_string(),
+        tier: "professional".to_string(),
+        business: business(),
+    }
+}
+
+fn person(first: &str) -> BusinessPerson {
+    BusinessPerson {
+        full_name: FullName { first: first.to_string(), last: "Tester".to_string() },
+        email: format!("{}@example.com", first.to_lowercase()),
+        phone: Phone { country_code: "1".to_string(), number: "5555550100".to_string() },
+        ssn: "000000001".to_string(),
+        date_of_birth: "1990-04-05".to_string(),
+        address: address(),
+        title: Some("CEO".to_string()),
+        percentage: Some(100),
+    }
+}
+
+fn address() -> Address {
+    Address {
+        street: "1 Main St".to_string(),
+        city: "New York".to_string(),
+        state: "NY".to_string(),
+        postal_code: "10001".to_string(),
+        country: "US".to_string(),
+    }
+}
+
+fn business() -> BusinessApplication {
+    BusinessApplication {
+        name: "Acme Supply LLC".to_string(),
+        ein: "123456789".to_string(),
+        entity_type: "LLC".to_string(),
+        state_of_incorporation: "DE".to_string(),
+        phone: Phone { country_code: "1".to_string(), number: "5555550100".to_string() },
+        address: address(),
+        contact: person("Ada"),
+        officer: person("Ada"),
+        beneficial_owners: vec![person("Ada")],
+        idempotency_key: "onboarding-acme".to_string(),
+    }
+}
+
+#[sqlx::test(migrations = "./migrations")]
+async fn onboarding_persists_every_outside_id(pool: PgPool) {
+    let (_, gusto, unit, iroha) = clients(ONBOARDING_PORT, &pool);
+    let onboarding = TenantOnboarding::new(pool.clone(), gusto, unit, iroha.clone());
+
+    let signup = signup();
+    let status = onboarding.sign_up(&signup).await.expect("onboarding");
+    assert!(status.is_complete());
+    assert_eq!(status.iroha_domain, "acme_supply_llc");
+
+    // Everything the webhook processors, payroll and benefits look up is on the row
+    let row = sqlx::query!(
+        r#"
+        SELECT t.status, t.iroha_domain, t.gusto_company_uuid, t.unit_customer_id,
+               t.unit_deposit_account_id, s.tier
+        FROM tenants t JOIN subscription_settings s ON s.tenant_id = t.id
+        WHERE t.id = $1
+        "#,
+        status.tenant_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(row.status, "active");
+    assert_eq!(row.iroha_domain.as_deref(), Some("acme_supply_llc"));
+    assert_eq!(row.gusto_company_uuid, status.gusto_company_uuid);
+    assert!(row.gusto_company_uuid.is_some());
+    assert!(row.unit_customer_id.is_some());
+    assert!(row.unit_deposit_account_id.is_some());
+    assert_eq!(row.tier, "professional");
+    assert!(iroha.has_domain("acme_supply_llc").await.unwrap());
+
+    // Sending the signup again changes nothing; someone else can't take the name
+    let again = onboarding.sign_up(&signup).await.expect("signup is idempotent");
+    assert_eq!(again.tenant_id, status.tenant_id);
+    assert_eq!(again.unit_deposit_account_id, status.unit_deposit_account_id);
+    let squatter = SignupRequest { email: "eve@example.com".to_string(), ..signup.clone() };
+    assert!(onboarding.sign_up(&squatter).await.is_err());
+    let tenants = sqlx::query_scalar!("SELECT COUNT(*) FROM tenants").fetch_one(&pool).await.unwrap();
+    assert_eq!(tenants, Some(1));
+}
+
+#[sqlx::test(migrations = "./migrations")]
+async fn tenant_is_onboarded_and_pays_its_first_invoice(pool: PgPool) {
+    let (base_url, gusto, unit, iroha) = clients(SANDBOX_PORT, &pool);
+
+    // 1. Sign up: Gusto company, KYB (the sandbox approves on the spot), deposit account, domain
+    let onboarding = TenantOnboarding::new(pool.clone(), gusto, unit.clone(), iroha.clone());
+    let status = onboarding.sign_up(&signup()).await.expect("onboarding");
+    assert!(status.is_complete());
+    let account_id = status.unit_deposit_account_id.clone().expect("deposit account");
+
+    // 2. The tenant funds its account by ACH from outside
+    let funded = reqwest::Client::new()
+        .post(format!("{}/unit/sandbox/received-ach", base_url))
+        .json(&serde_json::json!({
+            "data": {
+                "attributes": { "amount": 1_000_000 },
+                "relationships": { "account": { "data": { "type": "depositAccount", "id": account_id } } }
+            }
+        }))
+        .send()
+        .await
+        .expect("received ACH");
+    assert!(funded.status().is_success());
+
+    // 3. One billing cycle, the way the billing job runs it
+    let billing = BillingEngine::new(
+        pool.clone(),
+        unit.clone(),
+        iroha,
+        REVENUE_ACCOUNT.to_string(),
+        OnChainSettlement::from_env(),
+        MeteringConfig::from_env(&FiatAsset::from_env()),
+    );
+    let period = BillingPeriod::current();
+    billing.process_all_tenants(period).await.expect("billing run");
+
+    let invoice = Invoice::find(&pool, status.tenant_id, period)
+        .await
+        .unwrap()
+        .expect("invoice for the period");
+    assert_eq!(invoice.status, InvoiceStatus::Paid);
+    assert_eq!(invoice.payment_method, Some(PaymentMethod::Unit));
+    assert!(invoice.total_cents > 0);
+    assert!(invoice.unit_payment_id.is_some());
+
+    // The money moved from the tenant's account into ours
+    assert_eq!(unit.get_balance(REVENUE_ACCOUNT).await.unwrap().balance_cents, invoice.total_cents);
+    assert_eq!(unit.get_balance(&account_id).await.unwrap().balance_cents, 1_000_000 - invoice.total_cents);
+    assert_eq!(unit.get_balance(VERIFICATION_ACCOUNT).await.unwrap().balance_cents, 0);
+
+    // A second run of the same period charges nothing more
+    billing.process_all_tenants(period).await.expect("billing re-run");
+    assert_eq!(unit.get_balance(REVENUE_ACCOUNT).await.unwrap().balance_cents, invoice.total_cents);
+}
+
+#[test]
+fn platform_ledger_accounts_are_registered() {
+    let ledger = SandboxLedger::with_accounts(&platform().ledger_accounts);
+    let again = ledger.submit(vec![SandboxInstruction::RegisterAccount { account: CARD_HOLD_ACCOUNT }]);
+    assert!(again.is_err(), "the card hold account should already exist");
+}